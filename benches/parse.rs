@@ -0,0 +1,137 @@
+//! Parsing throughput on generated documents, or on the files given as
+//! arguments:
+//!
+//! ```text
+//! cargo bench --bench parse
+//! cargo bench --bench parse -- data/large.json
+//! ```
+//!
+//! Each input is parsed repeatedly for about a second and the best run is
+//! reported, which keeps the numbers steady enough to compare lexer changes.
+
+use std::env;
+use std::fs;
+use std::time::{Duration, Instant};
+
+const RUN_TIME: Duration = Duration::from_secs(1);
+
+fn main() {
+    let files: Vec<String> = env::args()
+        .skip(1)
+        .filter(|x| !x.starts_with("--"))
+        .collect();
+
+    let inputs: Vec<(String, String)> = if files.is_empty() {
+        vec![
+            ("numbers".to_string(), numbers()),
+            ("strings".to_string(), strings()),
+            ("records".to_string(), records()),
+        ]
+    } else {
+        files
+            .iter()
+            .map(|file| match fs::read_to_string(file) {
+                Ok(text) => (file.clone(), text),
+                Err(err) => panic!("{}: {}", file, err),
+            })
+            .collect()
+    };
+
+    for (name, text) in &inputs {
+        if let Err(err) = jsonparser::parse(text) {
+            panic!("{}:{}: {}", name, err.line_no, err.err_msg);
+        }
+
+        let mut best = Duration::MAX;
+        let mut runs = 0;
+        let started = Instant::now();
+        while runs < 3 || started.elapsed() < RUN_TIME {
+            let start = Instant::now();
+            let json = jsonparser::parse(text);
+            best = best.min(start.elapsed());
+            drop(json);
+            runs += 1;
+        }
+
+        let mb = text.len() as f64 / 1e6;
+        println!(
+            "{:<12} {:>8.2} MB  {:>8.2} MB/s  ({} runs)",
+            name,
+            mb,
+            mb / best.as_secs_f64(),
+            runs
+        );
+    }
+}
+
+// A fixed linear congruential generator, so every run sees the same input.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+}
+
+// Telemetry-style samples: mostly floats, some integers and exponents.
+fn numbers() -> String {
+    let mut rng = Rng(1);
+    let values: Vec<String> = (0..200_000)
+        .map(|i| match i % 4 {
+            0 => format!("{}", rng.next() % 100_000),
+            1 => format!("-{}.{:06}", rng.next() % 1000, rng.next() % 1_000_000),
+            2 => format!("{}.{}e-{}", rng.next() % 10, rng.next(), rng.next() % 20),
+            _ => format!("{}", (rng.next() as f64) / 7.0),
+        })
+        .collect();
+
+    format!("[{}]", values.join(","))
+}
+
+// Long strings with the occasional escape and non-ASCII character.
+fn strings() -> String {
+    let mut rng = Rng(2);
+    let words = [
+        "lorem",
+        "ipsum",
+        "dolor",
+        "sit",
+        "amet",
+        "caf\u{e9}",
+        "\\n",
+        "\\\"q\\\"",
+    ];
+    let values: Vec<String> = (0..20_000)
+        .map(|_| {
+            let text: Vec<&str> = (0..20)
+                .map(|_| words[(rng.next() % words.len() as u64) as usize])
+                .collect();
+            format!("\"{}\"", text.join(" "))
+        })
+        .collect();
+
+    format!("[{}]", values.join(",\n"))
+}
+
+// Pretty-printed API records: keys, short strings, literals and nesting.
+fn records() -> String {
+    let mut rng = Rng(3);
+    let records: Vec<String> = (0..20_000)
+        .map(|i| {
+            format!(
+                "  {{\n    \"id\": {},\n    \"name\": \"user{}\",\n    \"active\": {},\n    \"score\": {}.{},\n    \"tags\": [\"a\", \"b\"],\n    \"address\": {{\"city\": \"x\", \"zip\": null}}\n  }}",
+                i,
+                rng.next(),
+                rng.next() & 1 == 0,
+                rng.next() % 100,
+                rng.next() % 100
+            )
+        })
+        .collect();
+
+    format!("[\n{}\n]", records.join(",\n"))
+}
@@ -0,0 +1,546 @@
+//! Generating type declarations for JSON documents.
+//!
+//! A `Model` describes the documents as named structs and string enums
+//! plus the type of the root. It is built from an inferred `Shape` or from
+//! a JSON Schema document, and is then written out as source code with
+//! `Model::to_rust` or `Model::to_typescript`. Identical nested shapes
+//! share one declaration, named after the first member (or `$defs` entry)
+//! that used it.
+//!
+//! Schema support covers `type`, `properties`, `required`,
+//! `additionalProperties`, `items`, string `enum`s, `anyOf`/`oneOf` and
+//! `$ref` within the document. Other keywords are ignored, and recursive
+//! references are rejected.
+
+use crate::infer::Shape;
+use crate::json::JSON;
+use crate::path::pointer_tokens;
+
+use std::collections::{HashMap, HashSet};
+
+mod names;
+mod rust;
+mod typescript;
+
+#[cfg(test)]
+mod tests;
+
+use self::names::{pascal_case, singular, unique};
+
+// Names generated code relies on, which declarations must not shadow.
+const RESERVED: &[&str] = &[
+    "Array",
+    "Box",
+    "ConvertError",
+    "Err",
+    "FromJson",
+    "HashMap",
+    "JSON",
+    "None",
+    "Ok",
+    "Option",
+    "Record",
+    "Result",
+    "Self",
+    "Some",
+    "String",
+    "Vec",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodegenError {
+    pub err_msg: String,
+    /// JSON Pointer to the offending part of the schema.
+    pub keyword_location: String,
+}
+
+impl CodegenError {
+    fn new(err_msg: String, keyword_location: String) -> Self {
+        CodegenError {
+            err_msg,
+            keyword_location,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    /// Any JSON value.
+    Any,
+    Null,
+    Bool,
+    /// A whole number that fits in 64 signed bits.
+    Integer,
+    /// A whole number past `Integer`'s range that fits in 64 unsigned bits.
+    Unsigned,
+    Number,
+    String,
+    Array(Box<Type>),
+    /// An object with arbitrary keys and values of one type.
+    Map(Box<Type>),
+    /// The declaration at this index of `Model::decls`.
+    Named(usize),
+    /// Two or more types, none of them `Any` or a union.
+    Union(Vec<Type>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub key: String,
+    pub ty: Type,
+    /// False if the member may be absent.
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Body {
+    Struct(Vec<Field>),
+    /// A string with one of these values.
+    Enum(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decl {
+    pub name: String,
+    pub body: Body,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Model {
+    /// The name of the root type.
+    pub name: String,
+    pub root: Type,
+    /// Declarations in the order they were made, nested ones first.
+    pub decls: Vec<Decl>,
+}
+
+impl Type {
+    fn union(members: Vec<Type>) -> Type {
+        let mut flat = Vec::new();
+        for member in members {
+            match member {
+                Type::Any => return Type::Any,
+                Type::Union(x) => flat.extend(x),
+                _ => flat.push(member),
+            }
+        }
+
+        let mut members: Vec<Type> = Vec::new();
+        for member in flat {
+            if !members.contains(&member) {
+                members.push(member);
+            }
+        }
+
+        match members.len() {
+            0 => Type::Any,
+            1 => members.pop().unwrap(),
+            _ => Type::Union(members),
+        }
+    }
+}
+
+impl Model {
+    /// A model of the documents an inferred shape was built from. Strings
+    /// that are enum candidates become enums.
+    pub fn from_shape(shape: &Shape, name: &str) -> Model {
+        let mut builder = Builder::new(name);
+        let root = builder.shape_type(shape, name);
+
+        builder.finish(root)
+    }
+
+    pub fn from_json_schema(schema: &JSON, name: &str) -> Result<Model, CodegenError> {
+        let mut builder = Builder::new(name);
+        let mut resolver = Resolver {
+            document: schema,
+            resolving: Vec::new(),
+        };
+        let root = builder.schema_type(&mut resolver, schema, name, "")?;
+
+        Ok(builder.finish(root))
+    }
+}
+
+struct Builder {
+    name: String,
+    decls: Vec<Decl>,
+    taken: HashSet<String>,
+}
+
+struct Resolver<'a> {
+    document: &'a JSON,
+    // The references being expanded, innermost last.
+    resolving: Vec<String>,
+}
+
+impl Builder {
+    fn new(name: &str) -> Self {
+        let mut taken: HashSet<String> = RESERVED.iter().map(|x| x.to_string()).collect();
+        taken.insert(name.to_string());
+
+        Builder {
+            name: name.to_string(),
+            decls: Vec::new(),
+            taken,
+        }
+    }
+
+    // Gives the root declaration the model's name, which nothing else may
+    // take.
+    fn finish(mut self, root: Type) -> Model {
+        if let Type::Named(i) = root {
+            self.decls[i].name = self.name.clone();
+        }
+
+        Model {
+            name: self.name,
+            root,
+            decls: self.decls,
+        }
+    }
+
+    fn declare(&mut self, hint: &str, body: Body) -> Type {
+        if let Some(i) = self.decls.iter().position(|x| x.body == body) {
+            return Type::Named(i);
+        }
+
+        let mut name = pascal_case(hint);
+        if name.is_empty() {
+            name = "Type".to_string();
+        } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+            name.insert(0, 'T');
+        }
+        let name = unique(&name, |x| self.taken.contains(x));
+
+        self.taken.insert(name.clone());
+        self.decls.push(Decl { name, body });
+
+        Type::Named(self.decls.len() - 1)
+    }
+
+    fn shape_type(&mut self, shape: &Shape, hint: &str) -> Type {
+        let mut members = Vec::new();
+
+        if shape.nulls > 0 {
+            members.push(Type::Null);
+        }
+        if shape.booleans > 0 {
+            members.push(Type::Bool);
+        }
+        if let Some(ref numbers) = shape.numbers {
+            // Whole numbers beyond 2^64 are still read as doubles.
+            let ty = if !numbers.integers {
+                Type::Number
+            } else if numbers.min >= -(2f64.powi(63)) && numbers.max < 2f64.powi(63) {
+                Type::Integer
+            } else if numbers.min >= 0.0 && numbers.max < 2f64.powi(64) {
+                Type::Unsigned
+            } else {
+                Type::Number
+            };
+            members.push(ty);
+        }
+        if let Some(ref strings) = shape.strings {
+            // As in the exported JSON Schema, an enum needs every non-null
+            // value to be a string.
+            let only_strings = shape.types().iter().all(|x| *x == "string" || *x == "null");
+            match strings.enum_values().filter(|_| only_strings) {
+                Some(values) => {
+                    let values = values.into_iter().map(|x| x.to_string()).collect();
+                    members.push(self.declare(hint, Body::Enum(values)));
+                }
+                None => members.push(Type::String),
+            }
+        }
+        if let Some(ref arrays) = shape.arrays {
+            let items = if arrays.items.count > 0 {
+                self.shape_type(&arrays.items, &singular(hint))
+            } else {
+                Type::Any
+            };
+            members.push(Type::Array(Box::new(items)));
+        }
+        if let Some(ref objects) = shape.objects {
+            if objects.fields.is_empty() {
+                members.push(Type::Map(Box::new(Type::Any)));
+            } else {
+                let fields = objects
+                    .fields
+                    .iter()
+                    .map(|(key, field)| Field {
+                        key: key.clone(),
+                        ty: self.shape_type(field, key),
+                        required: objects.is_required(key),
+                    })
+                    .collect();
+                members.push(self.declare(hint, Body::Struct(fields)));
+            }
+        }
+
+        Type::union(members)
+    }
+
+    fn schema_type(
+        &mut self,
+        resolver: &mut Resolver,
+        schema: &JSON,
+        hint: &str,
+        location: &str,
+    ) -> Result<Type, CodegenError> {
+        let obj = match *schema {
+            JSON::JSONBool(_) => return Ok(Type::Any),
+            JSON::JSONObject(ref x) => x,
+            _ => {
+                return Err(CodegenError::new(
+                    "Schema must be an object or a boolean".to_string(),
+                    location.to_string(),
+                ))
+            }
+        };
+        let keyword = |name: &str| format!("{}/{}", location, name);
+
+        if let Some(reference) = obj.get("$ref") {
+            return self.reference(resolver, reference, &keyword("$ref"));
+        }
+
+        if let Some(values) = obj.get("enum") {
+            return self.enum_type(values, hint, &keyword("enum"));
+        }
+
+        for name in &["anyOf", "oneOf"] {
+            if let Some(subschemas) = obj.get(*name) {
+                let subschemas = match *subschemas {
+                    JSON::JSONArray(ref x) => x,
+                    _ => {
+                        return Err(CodegenError::new(
+                            format!("`{}` must be an array", name),
+                            keyword(name),
+                        ))
+                    }
+                };
+
+                let mut members = Vec::new();
+                for (i, subschema) in subschemas.iter().enumerate() {
+                    let location = format!("{}/{}", keyword(name), i);
+                    members.push(self.schema_type(resolver, subschema, hint, &location)?);
+                }
+                return Ok(Type::union(members));
+            }
+        }
+
+        let types = match obj.get("type") {
+            Some(JSON::JSONString(x)) => vec![x.as_str()],
+            Some(JSON::JSONArray(x)) => {
+                let mut types = Vec::new();
+                for item in x {
+                    match *item {
+                        JSON::JSONString(ref x) => types.push(x.as_str()),
+                        _ => {
+                            return Err(CodegenError::new(
+                                "`type` must name types as strings".to_string(),
+                                keyword("type"),
+                            ))
+                        }
+                    }
+                }
+                types
+            }
+            Some(_) => {
+                return Err(CodegenError::new(
+                    "`type` must be a string or an array".to_string(),
+                    keyword("type"),
+                ))
+            }
+            None if obj.contains_key("properties") || obj.contains_key("additionalProperties") => {
+                vec!["object"]
+            }
+            None if obj.contains_key("items") => vec!["array"],
+            None => return Ok(Type::Any),
+        };
+
+        let mut members = Vec::new();
+        for name in types {
+            members.push(match name {
+                "null" => Type::Null,
+                "boolean" => Type::Bool,
+                "integer" => Type::Integer,
+                "number" => Type::Number,
+                "string" => Type::String,
+                "array" => {
+                    let items = match obj.get("items") {
+                        Some(items) => {
+                            self.schema_type(resolver, items, &singular(hint), &keyword("items"))?
+                        }
+                        None => Type::Any,
+                    };
+                    Type::Array(Box::new(items))
+                }
+                "object" => self.object_type(resolver, obj, hint, location)?,
+                _ => {
+                    return Err(CodegenError::new(
+                        format!("Unknown type `{}`", name),
+                        keyword("type"),
+                    ))
+                }
+            });
+        }
+
+        Ok(Type::union(members))
+    }
+
+    fn object_type(
+        &mut self,
+        resolver: &mut Resolver,
+        obj: &HashMap<String, JSON>,
+        hint: &str,
+        location: &str,
+    ) -> Result<Type, CodegenError> {
+        let keyword = |name: &str| format!("{}/{}", location, name);
+
+        let required: Vec<&str> = match obj.get("required") {
+            None => Vec::new(),
+            Some(JSON::JSONArray(x)) if x.iter().all(|x| matches!(x, JSON::JSONString(_))) => x
+                .iter()
+                .filter_map(|x| match *x {
+                    JSON::JSONString(ref x) => Some(x.as_str()),
+                    _ => None,
+                })
+                .collect(),
+            Some(_) => {
+                return Err(CodegenError::new(
+                    "`required` must be an array of strings".to_string(),
+                    keyword("required"),
+                ))
+            }
+        };
+
+        let properties = match obj.get("properties") {
+            Some(JSON::JSONObject(x)) if !x.is_empty() => x,
+            Some(JSON::JSONObject(_)) | None => {
+                let values = match obj.get("additionalProperties") {
+                    Some(additional) => self.schema_type(
+                        resolver,
+                        additional,
+                        &singular(hint),
+                        &keyword("additionalProperties"),
+                    )?,
+                    None => Type::Any,
+                };
+                return Ok(Type::Map(Box::new(values)));
+            }
+            Some(_) => {
+                return Err(CodegenError::new(
+                    "`properties` must be an object".to_string(),
+                    keyword("properties"),
+                ))
+            }
+        };
+
+        let mut keys: Vec<&String> = properties.keys().collect();
+        keys.sort();
+
+        let mut fields = Vec::new();
+        for key in keys {
+            let location = format!(
+                "{}/{}",
+                keyword("properties"),
+                key.replace('~', "~0").replace('/', "~1")
+            );
+            fields.push(Field {
+                key: key.clone(),
+                ty: self.schema_type(resolver, &properties[key], key, &location)?,
+                required: required.contains(&key.as_str()),
+            });
+        }
+
+        Ok(self.declare(hint, Body::Struct(fields)))
+    }
+
+    fn enum_type(
+        &mut self,
+        values: &JSON,
+        hint: &str,
+        location: &str,
+    ) -> Result<Type, CodegenError> {
+        let values = match *values {
+            JSON::JSONArray(ref x) => x,
+            _ => {
+                return Err(CodegenError::new(
+                    "`enum` must be an array".to_string(),
+                    location.to_string(),
+                ))
+            }
+        };
+
+        let mut strings = Vec::new();
+        let mut members = Vec::new();
+        for value in values {
+            match *value {
+                JSON::JSONString(ref x) if !strings.contains(x) => strings.push(x.clone()),
+                JSON::JSONString(_) => {}
+                JSON::JSONNull => members.push(Type::Null),
+                // Enums of other values are not worth a declaration.
+                _ => return Ok(Type::Any),
+            }
+        }
+
+        if !strings.is_empty() {
+            strings.sort();
+            members.push(self.declare(hint, Body::Enum(strings)));
+        }
+
+        Ok(Type::union(members))
+    }
+
+    fn reference(
+        &mut self,
+        resolver: &mut Resolver,
+        reference: &JSON,
+        location: &str,
+    ) -> Result<Type, CodegenError> {
+        let error = |err_msg: String| CodegenError::new(err_msg, location.to_string());
+
+        let reference = match *reference {
+            JSON::JSONString(ref x) => x,
+            _ => return Err(error("`$ref` must be a string".to_string())),
+        };
+        let pointer = match reference.strip_prefix('#') {
+            Some(pointer) => pointer,
+            None => {
+                return Err(error(format!(
+                    "Reference `{}` is outside the document",
+                    reference
+                )))
+            }
+        };
+        if resolver.resolving.contains(reference) {
+            return Err(error(format!(
+                "Recursive reference `{}` is not supported",
+                reference
+            )));
+        }
+
+        let tokens = pointer_tokens(pointer)
+            .map_err(|_| error(format!("Invalid reference `{}`", reference)))?;
+        let mut target = resolver.document;
+        for token in &tokens {
+            let next = match *target {
+                JSON::JSONObject(ref x) => x.get(token),
+                JSON::JSONArray(ref x) => token.parse::<usize>().ok().and_then(|i| x.get(i)),
+                _ => None,
+            };
+            target = match next {
+                Some(next) => next,
+                None => return Err(error(format!("Unresolved reference `{}`", reference))),
+            };
+        }
+
+        let hint = tokens.last().map_or("Type", |x| x.as_str()).to_string();
+        resolver.resolving.push(reference.clone());
+        let ty = self.schema_type(resolver, target, &hint, pointer);
+        resolver.resolving.pop();
+
+        ty
+    }
+}
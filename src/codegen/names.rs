@@ -0,0 +1,88 @@
+// Turning JSON member names into identifiers.
+
+// The words of a name: runs of ASCII letters and digits, also split where
+// the case changes (`userID` and `user_id` both give `user`, `id`, and
+// `HTTPServer` gives `http`, `server`).
+pub(crate) fn words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !word.is_empty() {
+                words.push(word.to_ascii_lowercase());
+                word.clear();
+            }
+            continue;
+        }
+
+        if c.is_ascii_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|x| x.is_ascii_lowercase());
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_lower)
+            {
+                words.push(word.to_ascii_lowercase());
+                word.clear();
+            }
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word.to_ascii_lowercase());
+    }
+
+    words
+}
+
+pub(crate) fn pascal_case(name: &str) -> String {
+    let mut out = String::new();
+    for word in words(name) {
+        let mut chars = word.chars();
+        if let Some(c) = chars.next() {
+            out.push(c.to_ascii_uppercase());
+            out.extend(chars);
+        }
+    }
+
+    out
+}
+
+pub(crate) fn snake_case(name: &str) -> String {
+    words(name).join("_")
+}
+
+// A best guess at the singular of an English plural, used to name the items
+// of an array after the array (`orders` holds `Order`s).
+pub(crate) fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        if !stem.is_empty() {
+            return format!("{}y", stem);
+        }
+    }
+    if let Some(stem) = name.strip_suffix('s') {
+        if stem.len() > 1 && !stem.ends_with('s') && !stem.ends_with('u') {
+            return stem.to_string();
+        }
+    }
+
+    format!("{}Item", name)
+}
+
+// `name` if it is not in `taken`, otherwise `name` with the smallest
+// numeric suffix from 2 up that is not.
+pub(crate) fn unique<F>(name: &str, taken: F) -> String
+where
+    F: Fn(&str) -> bool,
+{
+    if !taken(name) {
+        return name.to_string();
+    }
+
+    (2..)
+        .map(|i| format!("{}{}", name, i))
+        .find(|x| !taken(x))
+        .unwrap()
+}
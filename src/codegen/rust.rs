@@ -0,0 +1,203 @@
+use crate::codegen::names::{pascal_case, snake_case, unique};
+use crate::codegen::{Body, Decl, Field, Model, Type};
+
+use std::fmt::Write;
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+impl Model {
+    /// Rust declarations for the model, each with a `FromJson`
+    /// implementation that decodes it.
+    pub fn to_rust(&self) -> String {
+        let mut out = String::new();
+
+        let structs = self.decls.iter().any(|x| matches!(x.body, Body::Struct(_)));
+        if structs {
+            out.push_str("use jsonparser::convert::{self, ConvertError, FromJson};\n");
+        } else {
+            out.push_str("use jsonparser::convert::{ConvertError, FromJson};\n");
+        }
+        out.push_str("use jsonparser::json::JSON;\n");
+        if self.uses_map() {
+            out.push_str("use std::collections::HashMap;\n");
+        }
+
+        if !matches!(self.root, Type::Named(_)) {
+            let root = self.rust_type(&self.root);
+            write!(out, "\npub type {} = {};\n", self.name, root).unwrap();
+        }
+
+        // Outermost first, so the file reads from the root down.
+        for decl in self.decls.iter().rev() {
+            out.push('\n');
+            match decl.body {
+                Body::Struct(ref fields) => self.write_struct(&mut out, decl, fields),
+                Body::Enum(ref values) => write_enum(&mut out, decl, values),
+            }
+        }
+
+        out
+    }
+
+    fn uses_map(&self) -> bool {
+        fn has_map(ty: &Type) -> bool {
+            match *ty {
+                Type::Map(_) => true,
+                Type::Array(ref x) => has_map(x),
+                Type::Union(ref x) => x.iter().any(has_map),
+                _ => false,
+            }
+        }
+
+        has_map(&self.root)
+            || self.decls.iter().any(|decl| match decl.body {
+                Body::Struct(ref fields) => fields.iter().any(|x| has_map(&x.ty)),
+                Body::Enum(_) => false,
+            })
+    }
+
+    fn write_struct(&self, out: &mut String, decl: &Decl, fields: &[Field]) {
+        let mut idents: Vec<String> = Vec::new();
+        for field in fields {
+            let ident = unique(&field_ident(&field.key), |x| idents.iter().any(|y| y == x));
+            idents.push(ident);
+        }
+
+        out.push_str("#[derive(Debug, Clone, PartialEq)]\n");
+        writeln!(out, "pub struct {} {{", decl.name).unwrap();
+        for (field, ident) in fields.iter().zip(&idents) {
+            writeln!(out, "    pub {}: {},", ident, self.field_type(field)).unwrap();
+        }
+        out.push_str("}\n\n");
+
+        writeln!(out, "impl FromJson for {} {{", decl.name).unwrap();
+        out.push_str("    fn from_json(json: &JSON) -> Result<Self, ConvertError> {\n");
+        out.push_str("        let obj = convert::object(json)?;\n");
+        writeln!(out, "        Ok({} {{", decl.name).unwrap();
+        for (field, ident) in fields.iter().zip(&idents) {
+            writeln!(
+                out,
+                "            {}: convert::field(obj, {:?})?,",
+                ident, field.key
+            )
+            .unwrap();
+        }
+        out.push_str("        })\n    }\n}\n");
+    }
+
+    // Absent members decode as `None`, so optional fields are `Option`s
+    // whether or not they can also be `null`.
+    fn field_type(&self, field: &Field) -> String {
+        let ty = self.rust_type(&field.ty);
+        if field.required || ty.starts_with("Option<") {
+            ty
+        } else {
+            format!("Option<{}>", ty)
+        }
+    }
+
+    fn rust_type(&self, ty: &Type) -> String {
+        match *ty {
+            Type::Any => "JSON".to_string(),
+            Type::Null => "Option<JSON>".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::Integer => "i64".to_string(),
+            Type::Unsigned => "u64".to_string(),
+            Type::Number => "f64".to_string(),
+            Type::String => "String".to_string(),
+            Type::Array(ref x) => format!("Vec<{}>", self.rust_type(x)),
+            Type::Map(ref x) => format!("HashMap<String, {}>", self.rust_type(x)),
+            Type::Named(i) => self.decls[i].name.clone(),
+            Type::Union(ref members) => {
+                let nullable = members.contains(&Type::Null);
+                let rest: Vec<&Type> = members.iter().filter(|x| **x != Type::Null).collect();
+
+                let ty = match rest[..] {
+                    [x] => self.rust_type(x),
+                    _ if rest.iter().all(|x| is_numeric(x)) => "f64".to_string(),
+                    // Mixed types have no Rust equivalent short of an enum
+                    // the decoder could not pick a variant for reliably.
+                    _ => return "JSON".to_string(),
+                };
+
+                if nullable {
+                    format!("Option<{}>", ty)
+                } else {
+                    ty
+                }
+            }
+        }
+    }
+}
+
+fn is_numeric(ty: &Type) -> bool {
+    matches!(*ty, Type::Integer | Type::Unsigned | Type::Number)
+}
+
+fn write_enum(out: &mut String, decl: &Decl, values: &[String]) {
+    let mut variants: Vec<String> = Vec::new();
+    for value in values {
+        let variant = unique(&variant_ident(value), |x| variants.iter().any(|y| y == x));
+        variants.push(variant);
+    }
+
+    out.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq)]\n");
+    writeln!(out, "pub enum {} {{", decl.name).unwrap();
+    for variant in &variants {
+        writeln!(out, "    {},", variant).unwrap();
+    }
+    out.push_str("}\n\n");
+
+    writeln!(out, "impl FromJson for {} {{", decl.name).unwrap();
+    out.push_str("    fn from_json(json: &JSON) -> Result<Self, ConvertError> {\n");
+    out.push_str("        match String::from_json(json)?.as_str() {\n");
+    for (value, variant) in values.iter().zip(&variants) {
+        writeln!(
+            out,
+            "            {:?} => Ok({}::{}),",
+            value, decl.name, variant
+        )
+        .unwrap();
+    }
+    out.push_str(
+        "            other => Err(ConvertError::new(format!(\"Unknown variant `{}`\", other))),\n",
+    );
+    out.push_str("        }\n    }\n}\n");
+}
+
+fn field_ident(key: &str) -> String {
+    let ident = snake_case(key);
+
+    if ident.is_empty() {
+        "field".to_string()
+    } else if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("field_{}", ident)
+    } else if ["crate", "self", "super"].contains(&ident.as_str()) {
+        // These cannot be raw identifiers.
+        format!("{}_", ident)
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        format!("r#{}", ident)
+    } else {
+        ident
+    }
+}
+
+fn variant_ident(value: &str) -> String {
+    let ident = pascal_case(value);
+
+    if ident.is_empty() {
+        "Empty".to_string()
+    } else if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("V{}", ident)
+    } else if ident == "Self" {
+        "Self_".to_string()
+    } else {
+        ident
+    }
+}
@@ -0,0 +1,319 @@
+use crate::codegen::names::{pascal_case, singular, snake_case};
+use crate::codegen::{Body, CodegenError, Field, Model, Type};
+use crate::convert::FromJson;
+use crate::infer::infer;
+use crate::json::JSON;
+
+fn from_samples(samples: &[&str]) -> Model {
+    let samples: Vec<JSON> = samples.iter().map(|x| crate::parse(x).unwrap()).collect();
+    Model::from_shape(&infer(&samples), "Root")
+}
+
+fn from_schema(schema: &str) -> Result<Model, CodegenError> {
+    Model::from_json_schema(&crate::parse(schema).unwrap(), "Root")
+}
+
+fn field(key: &str, ty: Type, required: bool) -> Field {
+    Field {
+        key: key.to_string(),
+        ty,
+        required,
+    }
+}
+
+#[test]
+fn test_names() {
+    assert_eq!(snake_case("userID"), "user_id");
+    assert_eq!(snake_case("HTTPServer"), "http_server");
+    assert_eq!(snake_case("display name"), "display_name");
+    assert_eq!(snake_case("--x2y"), "x2y");
+    assert_eq!(pascal_case("line_items"), "LineItems");
+    assert_eq!(pascal_case("café"), "Caf");
+    assert_eq!(singular("orders"), "order");
+    assert_eq!(singular("categories"), "category");
+    assert_eq!(singular("address"), "addressItem");
+    assert_eq!(singular("data"), "dataItem");
+}
+
+#[test]
+fn test_from_shape() {
+    let model = from_samples(&[
+        r#"{"id": 1, "price": 2.5, "tags": ["a"], "note": null}"#,
+        r#"{"id": 2, "price": 3, "tags": [], "note": "x", "extra": {}}"#,
+    ]);
+
+    assert_eq!(model.root, Type::Named(0));
+    assert_eq!(model.decls.len(), 1);
+    assert_eq!(model.decls[0].name, "Root");
+    assert_eq!(
+        model.decls[0].body,
+        Body::Struct(vec![
+            field("extra", Type::Map(Box::new(Type::Any)), false),
+            field("id", Type::Integer, true),
+            field("note", Type::Union(vec![Type::Null, Type::String]), true),
+            field("price", Type::Number, true),
+            field("tags", Type::Array(Box::new(Type::String)), true),
+        ])
+    );
+}
+
+#[test]
+fn test_shared_shapes() {
+    let model =
+        from_samples(&[r#"{"from": {"x": 1, "y": 2}, "to": {"x": 3, "y": 4}, "size": {"w": 1}}"#]);
+    let names: Vec<&str> = model.decls.iter().map(|x| x.name.as_str()).collect();
+
+    assert_eq!(names, vec!["From", "Size", "Root"]);
+}
+
+#[test]
+fn test_enums_and_unions() {
+    let model = from_samples(&[
+        r#"[{"kind": "a"}, {"kind": "b"}, {"kind": "a"}, {"kind": "b"}]"#,
+        r#"[1, "x"]"#,
+    ]);
+    let names: Vec<&str> = model.decls.iter().map(|x| x.name.as_str()).collect();
+
+    assert_eq!(names, vec!["Kind", "RootItem"]);
+    assert_eq!(
+        model.root,
+        Type::Array(Box::new(Type::Union(vec![
+            Type::Integer,
+            Type::String,
+            Type::Named(1)
+        ])))
+    );
+}
+
+#[test]
+fn test_from_json_schema() {
+    let model = from_schema(
+        r##"{
+            "type": "array",
+            "items": {"$ref": "#/$defs/user"},
+            "$defs": {
+                "user": {
+                    "properties": {
+                        "id": {"type": "integer"},
+                        "role": {"enum": ["admin", "user", null]},
+                        "scores": {"additionalProperties": {"type": "number"}},
+                        "friend": {"anyOf": [{"$ref": "#/$defs/ref"}, {"type": "null"}]}
+                    },
+                    "required": ["id"]
+                },
+                "ref": {"type": "object", "properties": {"id": {"type": "integer"}}}
+            }
+        }"##,
+    )
+    .unwrap();
+    let names: Vec<&str> = model.decls.iter().map(|x| x.name.as_str()).collect();
+
+    assert_eq!(names, vec!["Ref", "Role", "User"]);
+    assert_eq!(model.root, Type::Array(Box::new(Type::Named(2))));
+    assert_eq!(
+        model.decls[2].body,
+        Body::Struct(vec![
+            field(
+                "friend",
+                Type::Union(vec![Type::Named(0), Type::Null]),
+                false
+            ),
+            field("id", Type::Integer, true),
+            field("role", Type::Union(vec![Type::Null, Type::Named(1)]), false),
+            field("scores", Type::Map(Box::new(Type::Number)), false),
+        ])
+    );
+}
+
+#[test]
+fn test_schema_errors() {
+    let error = |schema: &str| {
+        let err = from_schema(schema).unwrap_err();
+        (err.keyword_location, err.err_msg)
+    };
+
+    assert_eq!(
+        error(r#"{"properties": {"a": {"type": "text"}}}"#),
+        (
+            "/properties/a/type".to_string(),
+            "Unknown type `text`".to_string()
+        )
+    );
+    assert_eq!(
+        error(r##"{"$defs": {"n": {"items": {"$ref": "#/$defs/n"}}}, "$ref": "#/$defs/n"}"##),
+        (
+            "/$defs/n/items/$ref".to_string(),
+            "Recursive reference `#/$defs/n` is not supported".to_string()
+        )
+    );
+    assert_eq!(
+        error(r##"{"$ref": "#/$defs/missing"}"##),
+        (
+            "/$ref".to_string(),
+            "Unresolved reference `#/$defs/missing`".to_string()
+        )
+    );
+    assert_eq!(
+        error(r#"{"items": 1}"#),
+        (
+            "/items".to_string(),
+            "Schema must be an object or a boolean".to_string()
+        )
+    );
+}
+
+#[test]
+fn test_to_rust() {
+    let model = from_samples(&[
+        r#"{"type": "a", "fooBar": [{"n": 1}], "self": true}"#,
+        r#"{"type": "b"}"#,
+    ]);
+    let expected = r#"use jsonparser::convert::{self, ConvertError, FromJson};
+use jsonparser::json::JSON;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Root {
+    pub foo_bar: Option<Vec<FooBarItem>>,
+    pub self_: Option<bool>,
+    pub r#type: String,
+}
+
+impl FromJson for Root {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        let obj = convert::object(json)?;
+        Ok(Root {
+            foo_bar: convert::field(obj, "fooBar")?,
+            self_: convert::field(obj, "self")?,
+            r#type: convert::field(obj, "type")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FooBarItem {
+    pub n: i64,
+}
+
+impl FromJson for FooBarItem {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        let obj = convert::object(json)?;
+        Ok(FooBarItem {
+            n: convert::field(obj, "n")?,
+        })
+    }
+}
+"#;
+
+    assert_eq!(model.to_rust(), expected);
+}
+
+#[test]
+fn test_integer_ranges() {
+    let samples = [
+        r#"{"small": -3, "large": 1e19, "huge": 1e20, "low": -1e19, "mixed": 1}"#,
+        r#"{"small": 9007199254740993, "large": 3, "huge": 1, "low": 0, "mixed": 1.5}"#,
+    ];
+    let model = from_samples(&samples);
+    let rust = model.to_rust();
+
+    for &(key, ty) in &[
+        ("small", "i64"),
+        ("large", "u64"),
+        ("huge", "f64"),
+        ("low", "f64"),
+        ("mixed", "f64"),
+    ] {
+        let ident = format!("pub {}: {},", key, ty);
+        assert!(rust.contains(&ident), "{} in {}", ident, rust);
+
+        // Each sample must decode with the type chosen for it.
+        for sample in &samples {
+            let value = match crate::parse(sample).unwrap() {
+                JSON::JSONObject(obj) => obj[key].clone(),
+                _ => unreachable!(),
+            };
+            let decoded = match ty {
+                "i64" => i64::from_json(&value).is_ok(),
+                "u64" => u64::from_json(&value).is_ok(),
+                _ => f64::from_json(&value).is_ok(),
+            };
+            assert!(decoded, "{} as {}", value, ty);
+        }
+    }
+}
+
+#[test]
+fn test_to_rust_enum_and_alias() {
+    let model =
+        from_schema(r#"{"type": "array", "items": {"enum": ["in progress", "1", "x", "X"]}}"#)
+            .unwrap();
+    let expected = r#"use jsonparser::convert::{ConvertError, FromJson};
+use jsonparser::json::JSON;
+
+pub type Root = Vec<RootItem>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootItem {
+    V1,
+    X,
+    InProgress,
+    X2,
+}
+
+impl FromJson for RootItem {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        match String::from_json(json)?.as_str() {
+            "1" => Ok(RootItem::V1),
+            "X" => Ok(RootItem::X),
+            "in progress" => Ok(RootItem::InProgress),
+            "x" => Ok(RootItem::X2),
+            other => Err(ConvertError::new(format!("Unknown variant `{}`", other))),
+        }
+    }
+}
+"#;
+
+    assert_eq!(model.to_rust(), expected);
+}
+
+#[test]
+fn test_to_typescript() {
+    let model = from_samples(&[
+        r#"{"id": 1, "display name": "a", "values": [1, "x", null], "kind": "k", "at": null}"#,
+        r#"{"id": 2, "values": [], "kind": "k", "at": "2024-01-01T00:00:00Z", "extra": {"a": 1}}"#,
+    ]);
+    let expected = r#"export interface Root {
+  at: string | null;
+  "display name"?: string;
+  extra?: Extra;
+  id: number;
+  kind: Kind;
+  values: (number | string | null)[];
+}
+
+export type Kind = "k";
+
+export interface Extra {
+  a: number;
+}
+"#;
+
+    assert_eq!(model.to_typescript(), expected);
+}
+
+#[test]
+fn test_to_typescript_alias() {
+    let model = from_schema(
+        r#"{
+            "type": ["array", "null"],
+            "items": {"additionalProperties": {"enum": ["a\"b", "c"]}}
+        }"#,
+    )
+    .unwrap();
+    let expected = r#"export type Root = Record<string, RootItemItem>[] | null;
+
+export type RootItemItem = "a\"b" | "c";
+"#;
+
+    assert_eq!(model.to_typescript(), expected);
+}
@@ -0,0 +1,95 @@
+use crate::codegen::{Body, Decl, Field, Model, Type};
+use crate::serializer::write_string;
+
+use std::fmt::Write;
+
+impl Model {
+    /// TypeScript declarations for the model: an `interface` per struct
+    /// and a union of string literals per enum. Members that may be absent
+    /// are optional properties.
+    pub fn to_typescript(&self) -> String {
+        let mut out = String::new();
+
+        if !matches!(self.root, Type::Named(_)) {
+            let root = self.ts_type(&self.root);
+            writeln!(out, "export type {} = {};", self.name, root).unwrap();
+        }
+
+        for decl in self.decls.iter().rev() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            match decl.body {
+                Body::Struct(ref fields) => self.write_interface(&mut out, decl, fields),
+                Body::Enum(ref values) => {
+                    let values: Vec<String> = values.iter().map(|x| string_literal(x)).collect();
+                    writeln!(out, "export type {} = {};", decl.name, values.join(" | ")).unwrap();
+                }
+            }
+        }
+
+        out
+    }
+
+    fn write_interface(&self, out: &mut String, decl: &Decl, fields: &[Field]) {
+        writeln!(out, "export interface {} {{", decl.name).unwrap();
+        for field in fields {
+            let key = if is_identifier(&field.key) {
+                field.key.clone()
+            } else {
+                string_literal(&field.key)
+            };
+            let optional = if field.required { "" } else { "?" };
+            writeln!(out, "  {}{}: {};", key, optional, self.ts_type(&field.ty)).unwrap();
+        }
+        out.push_str("}\n");
+    }
+
+    fn ts_type(&self, ty: &Type) -> String {
+        match *ty {
+            Type::Any => "unknown".to_string(),
+            Type::Null => "null".to_string(),
+            Type::Bool => "boolean".to_string(),
+            Type::Integer | Type::Unsigned | Type::Number => "number".to_string(),
+            Type::String => "string".to_string(),
+            Type::Array(ref x) => match **x {
+                Type::Union(_) => format!("({})[]", self.ts_type(x)),
+                _ => format!("{}[]", self.ts_type(x)),
+            },
+            Type::Map(ref x) => format!("Record<string, {}>", self.ts_type(x)),
+            Type::Named(i) => self.decls[i].name.clone(),
+            Type::Union(ref members) => {
+                // `null` goes last, as it is usually written.
+                let mut types: Vec<String> = Vec::new();
+                for member in members.iter().filter(|x| **x != Type::Null) {
+                    let ty = self.ts_type(member);
+                    if !types.contains(&ty) {
+                        types.push(ty);
+                    }
+                }
+                if members.contains(&Type::Null) {
+                    types.push("null".to_string());
+                }
+
+                types.join(" | ")
+            }
+        }
+    }
+}
+
+// JSON string syntax is valid JavaScript.
+fn string_literal(text: &str) -> String {
+    let mut out = String::new();
+    write_string(&mut out, text);
+
+    out
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
@@ -0,0 +1,199 @@
+//! Decoding `JSON` values into Rust types.
+//!
+//! `FromJson` is implemented for the scalar types, `String`, `Option`,
+//! `Vec`, `Box`, `HashMap<String, _>` and `JSON` itself. Structs decode
+//! their members with `object` and `field`, which is what the code written
+//! by `codegen` does:
+//!
+//! ```text
+//! impl FromJson for User {
+//!     fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+//!         let obj = convert::object(json)?;
+//!         Ok(User {
+//!             id: convert::field(obj, "id")?,
+//!             email: convert::field(obj, "email")?,
+//!         })
+//!     }
+//! }
+//! ```
+
+use crate::json::JSON;
+
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertError {
+    pub err_msg: String,
+    /// JSON Pointer to the value that could not be decoded.
+    pub path: String,
+}
+
+impl ConvertError {
+    /// An error about the value being decoded; the path is filled in on the
+    /// way back out of the enclosing arrays and objects.
+    pub fn new(err_msg: String) -> Self {
+        ConvertError {
+            err_msg,
+            path: String::new(),
+        }
+    }
+
+    fn within(mut self, token: &str) -> Self {
+        let token = token.replace('~', "~0").replace('/', "~1");
+        self.path = format!("/{}{}", token, self.path);
+        self
+    }
+}
+
+pub trait FromJson: Sized {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError>;
+
+    /// The value of an object member that is absent, if absence is allowed.
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+pub fn from_json<T: FromJson>(json: &JSON) -> Result<T, ConvertError> {
+    T::from_json(json)
+}
+
+pub fn object(json: &JSON) -> Result<&HashMap<String, JSON>, ConvertError> {
+    match *json {
+        JSON::JSONObject(ref x) => Ok(x),
+        _ => Err(mismatch("object", json)),
+    }
+}
+
+/// Decodes the member `key` of `obj`, which may only be missing if `T` is
+/// an `Option`.
+pub fn field<T: FromJson>(obj: &HashMap<String, JSON>, key: &str) -> Result<T, ConvertError> {
+    match obj.get(key) {
+        Some(value) => T::from_json(value).map_err(|err| err.within(key)),
+        None => {
+            T::from_missing().ok_or_else(|| ConvertError::new(format!("Missing field `{}`", key)))
+        }
+    }
+}
+
+fn mismatch(expected: &str, found: &JSON) -> ConvertError {
+    let found = match *found {
+        JSON::JSONNum(_) => "number",
+        JSON::JSONString(_) => "string",
+        JSON::JSONNull => "null",
+        JSON::JSONBool(_) => "boolean",
+        JSON::JSONArray(_) => "array",
+        JSON::JSONObject(_) => "object",
+    };
+
+    ConvertError::new(format!("Expecting {}, found {}", expected, found))
+}
+
+impl FromJson for JSON {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        Ok(json.clone())
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        match *json {
+            JSON::JSONBool(x) => Ok(x),
+            _ => Err(mismatch("boolean", json)),
+        }
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        match *json {
+            JSON::JSONNum(x) => Ok(x),
+            _ => Err(mismatch("number", json)),
+        }
+    }
+}
+
+impl FromJson for i64 {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        match *json {
+            // 2^63 itself is representable as f64 but not as i64.
+            JSON::JSONNum(x) if x.fract() == 0.0 && x >= -(2f64.powi(63)) && x < 2f64.powi(63) => {
+                Ok(x as i64)
+            }
+            JSON::JSONNum(x) => Err(ConvertError::new(format!(
+                "Number {} is not a 64-bit integer",
+                x
+            ))),
+            _ => Err(mismatch("integer", json)),
+        }
+    }
+}
+
+impl FromJson for u64 {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        match *json {
+            JSON::JSONNum(x) if x.fract() == 0.0 && x >= 0.0 && x < 2f64.powi(64) => Ok(x as u64),
+            JSON::JSONNum(x) => Err(ConvertError::new(format!(
+                "Number {} is not an unsigned 64-bit integer",
+                x
+            ))),
+            _ => Err(mismatch("integer", json)),
+        }
+    }
+}
+
+impl FromJson for String {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        match *json {
+            JSON::JSONString(ref x) => Ok(x.clone()),
+            _ => Err(mismatch("string", json)),
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        match *json {
+            JSON::JSONNull => Ok(None),
+            _ => T::from_json(json).map(Some),
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: FromJson> FromJson for Box<T> {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        T::from_json(json).map(Box::new)
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        match *json {
+            JSON::JSONArray(ref x) => x
+                .iter()
+                .enumerate()
+                .map(|(i, item)| T::from_json(item).map_err(|err| err.within(&i.to_string())))
+                .collect(),
+            _ => Err(mismatch("array", json)),
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for HashMap<String, T> {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        object(json)?
+            .iter()
+            .map(|(key, value)| match T::from_json(value) {
+                Ok(value) => Ok((key.clone(), value)),
+                Err(err) => Err(err.within(key)),
+            })
+            .collect()
+    }
+}
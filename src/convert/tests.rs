@@ -0,0 +1,103 @@
+use crate::convert::{self, from_json, ConvertError, FromJson};
+use crate::json::JSON;
+
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq)]
+struct User {
+    id: i64,
+    name: Option<String>,
+    tags: Vec<String>,
+}
+
+impl FromJson for User {
+    fn from_json(json: &JSON) -> Result<Self, ConvertError> {
+        let obj = convert::object(json)?;
+        Ok(User {
+            id: convert::field(obj, "id")?,
+            name: convert::field(obj, "name")?,
+            tags: convert::field(obj, "tags")?,
+        })
+    }
+}
+
+fn decode<T: FromJson>(input: &str) -> Result<T, (String, String)> {
+    from_json(&crate::parse(input).unwrap()).map_err(|err| (err.path, err.err_msg))
+}
+
+fn error(path: &str, err_msg: &str) -> (String, String) {
+    (path.to_string(), err_msg.to_string())
+}
+
+#[test]
+fn test_scalars() {
+    assert_eq!(decode::<bool>("true"), Ok(true));
+    assert_eq!(decode::<f64>("1.5"), Ok(1.5));
+    assert_eq!(decode::<i64>("-3"), Ok(-3));
+    assert_eq!(decode::<u64>("3"), Ok(3));
+    assert_eq!(decode::<String>(r#""x""#), Ok("x".to_string()));
+    assert_eq!(decode::<JSON>("null"), Ok(JSON::JSONNull));
+
+    assert_eq!(
+        decode::<String>("1"),
+        Err(error("", "Expecting string, found number"))
+    );
+    assert_eq!(
+        decode::<i64>("1.5"),
+        Err(error("", "Number 1.5 is not a 64-bit integer"))
+    );
+    assert_eq!(
+        decode::<u64>("-1"),
+        Err(error("", "Number -1 is not an unsigned 64-bit integer"))
+    );
+}
+
+#[test]
+fn test_containers() {
+    assert_eq!(decode::<Option<i64>>("null"), Ok(None));
+    assert_eq!(decode::<Option<i64>>("2"), Ok(Some(2)));
+    assert_eq!(decode::<Vec<bool>>("[true, false]"), Ok(vec![true, false]));
+    assert_eq!(decode::<Box<bool>>("true"), Ok(Box::new(true)));
+
+    let mut expected = HashMap::new();
+    expected.insert("a".to_string(), 1);
+    assert_eq!(decode::<HashMap<String, i64>>(r#"{"a": 1}"#), Ok(expected));
+
+    assert_eq!(
+        decode::<Vec<bool>>("[true, 1]"),
+        Err(error("/1", "Expecting boolean, found number"))
+    );
+    assert_eq!(
+        decode::<HashMap<String, i64>>(r#"{"a/b": "x"}"#),
+        Err(error("/a~1b", "Expecting integer, found string"))
+    );
+}
+
+#[test]
+fn test_struct() {
+    assert_eq!(
+        decode::<User>(r#"{"id": 1, "tags": ["a"]}"#),
+        Ok(User {
+            id: 1,
+            name: None,
+            tags: vec!["a".to_string()],
+        })
+    );
+    assert_eq!(
+        decode::<User>(r#"{"id": 1, "name": null, "tags": []}"#).map(|x| x.name),
+        Ok(None)
+    );
+
+    assert_eq!(
+        decode::<User>(r#"{"name": "x", "tags": []}"#),
+        Err(error("", "Missing field `id`"))
+    );
+    assert_eq!(
+        decode::<Vec<User>>(r#"[{"id": 1, "tags": [null]}]"#),
+        Err(error("/0/tags/0", "Expecting string, found null"))
+    );
+    assert_eq!(
+        decode::<User>("[]"),
+        Err(error("", "Expecting object, found array"))
+    );
+}
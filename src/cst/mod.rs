@@ -0,0 +1,237 @@
+//! A lossless concrete syntax tree.
+//!
+//! Every token keeps its original text together with the whitespace and
+//! comments in front of it, so printing a `Document` gives back the input
+//! byte for byte. This is the layer to use when a file has to be changed
+//! without disturbing its formatting; `to_json` gives the plain value.
+//! `to_string_pretty` reindents a document but keeps every token as
+//! written, so members stay in source order and numbers keep their digits.
+
+use crate::json::JSON;
+use crate::lexer::Dialect;
+use crate::parser::ParseError;
+
+use std::collections::HashMap;
+use std::fmt;
+
+mod parser;
+
+#[cfg(test)]
+mod tests;
+
+use self::parser::CstParser;
+
+/// A token's source text and the trivia (whitespace and comments) that
+/// precede it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tok {
+    pub leading: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scalar {
+    pub tok: Tok,
+    pub value: JSON,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Scalar(Scalar),
+    Array(Array),
+    Object(Object),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Array {
+    pub open: Tok,
+    pub elements: Vec<Element>,
+    pub close: Tok,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    pub value: Value,
+    pub comma: Option<Tok>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object {
+    pub open: Tok,
+    pub members: Vec<Member>,
+    pub close: Tok,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    /// The key as written, quotes included.
+    pub key: Tok,
+    /// The key's decoded name.
+    pub name: String,
+    pub colon: Tok,
+    pub value: Value,
+    pub comma: Option<Tok>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub dialect: Dialect,
+    pub value: Value,
+    /// Whitespace and comments after the root value.
+    pub trailing: String,
+}
+
+pub fn parse(input: &str) -> Result<Document, ParseError> {
+    parse_with(input, Dialect::Json)
+}
+
+pub fn parse_with(input: &str, dialect: Dialect) -> Result<Document, ParseError> {
+    CstParser::new(input, dialect).parse()
+}
+
+impl Document {
+    pub fn to_json(&self) -> JSON {
+        self.value.to_json()
+    }
+
+    /// The document laid out as `serializer::to_string_pretty` would, with
+    /// trivia dropped. Only a strict JSON document comes out as JSON, since
+    /// tokens such as JSON5's single-quoted strings are copied unchanged.
+    pub fn to_string_pretty(&self, indent: usize) -> String {
+        let mut out = String::new();
+        write_pretty(&mut out, &self.value, indent, 0);
+
+        out
+    }
+}
+
+fn write_pretty(out: &mut String, value: &Value, indent: usize, level: usize) {
+    match *value {
+        Value::Array(ref x) if !x.elements.is_empty() => {
+            out.push('[');
+            for (i, elem) in x.elements.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                write_newline(out, indent, level + 1);
+                write_pretty(out, &elem.value, indent, level + 1);
+            }
+            write_newline(out, indent, level);
+            out.push(']');
+        }
+        Value::Array(_) => out.push_str("[]"),
+        Value::Object(ref x) if !x.members.is_empty() => {
+            out.push('{');
+            for (i, member) in x.members.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                write_newline(out, indent, level + 1);
+                out.push_str(&member.key.text);
+                out.push_str(": ");
+                write_pretty(out, &member.value, indent, level + 1);
+            }
+            write_newline(out, indent, level);
+            out.push('}');
+        }
+        Value::Object(_) => out.push_str("{}"),
+        Value::Scalar(ref x) => out.push_str(&x.tok.text),
+    }
+}
+
+fn write_newline(out: &mut String, indent: usize, level: usize) {
+    out.push('\n');
+    for _ in 0..indent * level {
+        out.push(' ');
+    }
+}
+
+impl Value {
+    pub fn to_json(&self) -> JSON {
+        match *self {
+            Value::Scalar(ref x) => x.value.clone(),
+            Value::Array(ref x) => {
+                JSON::JSONArray(x.elements.iter().map(|e| e.value.to_json()).collect())
+            }
+            Value::Object(ref x) => {
+                let mut obj = HashMap::new();
+                for member in &x.members {
+                    obj.insert(member.name.clone(), member.value.to_json());
+                }
+                JSON::JSONObject(obj)
+            }
+        }
+    }
+
+    /// The first token of the value, which carries its leading trivia.
+    pub fn first_tok(&self) -> &Tok {
+        match *self {
+            Value::Scalar(ref x) => &x.tok,
+            Value::Array(ref x) => &x.open,
+            Value::Object(ref x) => &x.open,
+        }
+    }
+
+    pub fn first_tok_mut(&mut self) -> &mut Tok {
+        match *self {
+            Value::Scalar(ref mut x) => &mut x.tok,
+            Value::Array(ref mut x) => &mut x.open,
+            Value::Object(ref mut x) => &mut x.open,
+        }
+    }
+}
+
+impl Object {
+    /// The last member with the given name, which is the one whose value
+    /// wins when the object is read as `JSON`.
+    pub fn get(&self, name: &str) -> Option<&Member> {
+        self.members.iter().rev().find(|m| m.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Member> {
+        self.members.iter_mut().rev().find(|m| m.name == name)
+    }
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.leading, self.text)
+    }
+}
+
+fn write_comma(f: &mut fmt::Formatter, comma: &Option<Tok>) -> fmt::Result {
+    match *comma {
+        Some(ref comma) => write!(f, "{}", comma),
+        None => Ok(()),
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Scalar(ref x) => write!(f, "{}", x.tok),
+            Value::Array(ref x) => {
+                write!(f, "{}", x.open)?;
+                for elem in &x.elements {
+                    write!(f, "{}", elem.value)?;
+                    write_comma(f, &elem.comma)?;
+                }
+                write!(f, "{}", x.close)
+            }
+            Value::Object(ref x) => {
+                write!(f, "{}", x.open)?;
+                for member in &x.members {
+                    write!(f, "{}{}{}", member.key, member.colon, member.value)?;
+                    write_comma(f, &member.comma)?;
+                }
+                write!(f, "{}", x.close)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.trailing)
+    }
+}
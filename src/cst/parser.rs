@@ -0,0 +1,195 @@
+use super::{Array, Document, Element, Member, Object, Scalar, Tok, Value};
+use crate::json::JSON;
+use crate::lexer::{Dialect, Lexer, Token, TokenVal};
+use crate::parser::ParseError;
+
+pub struct CstParser<'a> {
+    input: &'a str,
+    lexer: Lexer<'a>,
+    curr_token: Option<Token>,
+    // End of the last token taken, where the next token's trivia starts.
+    prev_end: usize,
+}
+
+impl<'a> CstParser<'a> {
+    pub fn new(input: &'a str, dialect: Dialect) -> Self {
+        CstParser {
+            input,
+            lexer: Lexer::with_dialect(input, dialect),
+            curr_token: None,
+            prev_end: 0,
+        }
+    }
+
+    fn cont(&mut self) -> Result<(), ParseError> {
+        match self.lexer.next_token() {
+            Ok(token) => {
+                self.curr_token = Some(token);
+                Ok(())
+            }
+            Err(err) => Err(ParseError::at(err.err_msg, err.line_no, err.column)),
+        }
+    }
+
+    fn peek(&self) -> &TokenVal {
+        &self.curr_token.as_ref().unwrap().value
+    }
+
+    fn error<T>(&self, err_msg: &str) -> Result<T, ParseError> {
+        let token = self.curr_token.as_ref().unwrap();
+        let column = self.lexer.column(token.start);
+
+        Err(ParseError::at(err_msg.to_string(), token.line_no, column))
+    }
+
+    // Takes the current token along with the trivia in front of it.
+    fn take(&mut self) -> Result<(TokenVal, Tok), ParseError> {
+        let token = self.curr_token.take().unwrap();
+        let tok = Tok {
+            leading: self.input[self.prev_end..token.start].to_string(),
+            text: self.input[token.start..token.end].to_string(),
+        };
+        self.prev_end = token.end;
+        self.cont()?;
+
+        Ok((token.value, tok))
+    }
+
+    fn expect(&mut self, value: TokenVal, err_msg: &str) -> Result<Tok, ParseError> {
+        if *self.peek() != value {
+            return self.error(err_msg);
+        }
+
+        Ok(self.take()?.1)
+    }
+
+    fn trailing_comma(&self, close: TokenVal) -> bool {
+        self.lexer.dialect().allows_trailing_commas() && *self.peek() == close
+    }
+
+    pub fn parse(&mut self) -> Result<Document, ParseError> {
+        self.cont()?;
+
+        let value = self.parse_value()?;
+        if *self.peek() != TokenVal::Eof {
+            return self.error("Expecting EOF");
+        }
+
+        Ok(Document {
+            dialect: self.lexer.dialect(),
+            value,
+            trailing: self.input[self.prev_end..].to_string(),
+        })
+    }
+
+    fn parse_value(&mut self) -> Result<Value, ParseError> {
+        let token = self.curr_token.as_ref().unwrap();
+        let (line_no, column) = (token.line_no, self.lexer.column(token.start));
+        let (value, tok) = self.take()?;
+
+        let value = match value {
+            TokenVal::LBrace => return self.parse_object(tok),
+            TokenVal::LBrack => return self.parse_array(tok),
+            TokenVal::True => JSON::JSONBool(true),
+            TokenVal::False => JSON::JSONBool(false),
+            TokenVal::Null => JSON::JSONNull,
+            TokenVal::JString(x) => JSON::JSONString(x),
+            TokenVal::JNumber(x) => JSON::JSONNum(x),
+            TokenVal::Ident(x) => match x.as_str() {
+                "true" => JSON::JSONBool(true),
+                "false" => JSON::JSONBool(false),
+                "null" => JSON::JSONNull,
+                "Infinity" => JSON::JSONNum(f64::INFINITY),
+                "NaN" => JSON::JSONNum(f64::NAN),
+                _ => {
+                    return Err(ParseError::at(
+                        format!("Unexpected identifier: {}", x),
+                        line_no,
+                        column,
+                    ))
+                }
+            },
+            _ => {
+                return Err(ParseError::at(
+                    "Unexpected token".to_string(),
+                    line_no,
+                    column,
+                ))
+            }
+        };
+
+        Ok(Value::Scalar(Scalar { tok, value }))
+    }
+
+    fn parse_object(&mut self, open: Tok) -> Result<Value, ParseError> {
+        let mut members = Vec::new();
+
+        if *self.peek() != TokenVal::RBrace {
+            loop {
+                let name = match self.peek() {
+                    TokenVal::JString(x) | TokenVal::Ident(x) => x.clone(),
+                    _ => return self.error("Expecting string"),
+                };
+                let key = self.take()?.1;
+                let colon = self.expect(TokenVal::Colon, "Expecting colon after key")?;
+                let value = self.parse_value()?;
+
+                let comma = if *self.peek() == TokenVal::Comma {
+                    Some(self.take()?.1)
+                } else {
+                    None
+                };
+                let done = comma.is_none() || self.trailing_comma(TokenVal::RBrace);
+
+                members.push(Member {
+                    key,
+                    name,
+                    colon,
+                    value,
+                    comma,
+                });
+                if done {
+                    break;
+                }
+            }
+        }
+
+        let close = self.expect(TokenVal::RBrace, "Expecting right brace at end of object")?;
+
+        Ok(Value::Object(Object {
+            open,
+            members,
+            close,
+        }))
+    }
+
+    fn parse_array(&mut self, open: Tok) -> Result<Value, ParseError> {
+        let mut elements = Vec::new();
+
+        if *self.peek() != TokenVal::RBrack {
+            loop {
+                let value = self.parse_value()?;
+
+                let comma = if *self.peek() == TokenVal::Comma {
+                    Some(self.take()?.1)
+                } else {
+                    None
+                };
+                let done = comma.is_none() || self.trailing_comma(TokenVal::RBrack);
+
+                elements.push(Element { value, comma });
+                if done {
+                    break;
+                }
+            }
+        }
+
+        let close = self.expect(TokenVal::RBrack, "Expecting right bracket at end of array")?;
+
+        Ok(Value::Array(Array {
+            open,
+            elements,
+            close,
+        }))
+    }
+}
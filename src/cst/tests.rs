@@ -0,0 +1,70 @@
+use crate::cst::{parse, parse_with, Value};
+use crate::json::JSON;
+use crate::lexer::Dialect;
+
+#[test]
+fn test_lossless_json() {
+    let input = "  {\n    \"a\" :[1.50, -0e0 ,\"\\u0041\"],\n\t\"b\":{ }\r\n}\n\n";
+    let doc = parse(input).unwrap();
+
+    assert_eq!(doc.to_string(), input);
+    assert_eq!(doc.to_json(), crate::parse(input).unwrap());
+}
+
+#[test]
+fn test_lossless_jsonc() {
+    let input = "// header\n{\n  /* flag */ \"on\": true, // trailing\n  \"list\": [1, 2,],\n}\n// footer\n";
+    let doc = parse_with(input, Dialect::Jsonc).unwrap();
+
+    assert_eq!(doc.to_string(), input);
+    assert_eq!(
+        doc.to_json(),
+        crate::parse_with(input, Dialect::Jsonc).unwrap()
+    );
+}
+
+#[test]
+fn test_lossless_json5() {
+    let input = "{unquoted: 'single', hex: 0xFF, inf: -Infinity, trailing: [.5,],}";
+    let doc = parse_with(input, Dialect::Json5).unwrap();
+
+    assert_eq!(doc.to_string(), input);
+}
+
+#[test]
+fn test_trivia() {
+    let input = "{ /* c */ \"k\" : 1 }";
+    let doc = parse_with(input, Dialect::Jsonc).unwrap();
+
+    let obj = match doc.value {
+        Value::Object(ref x) => x,
+        _ => panic!("expected an object"),
+    };
+    let member = obj.get("k").unwrap();
+
+    assert_eq!(member.key.leading, " /* c */ ");
+    assert_eq!(member.key.text, "\"k\"");
+    assert_eq!(member.colon.leading, " ");
+    assert_eq!(member.value.first_tok().text, "1");
+    assert_eq!(member.value.to_json(), JSON::JSONNum(1.0));
+    assert_eq!(obj.close.leading, " ");
+}
+
+#[test]
+fn test_pretty() {
+    let input = "{\"z\":1,\"id\":12345678901234567891,\"a\":[ ],\"z\":[1.50,\"\\u0041\"]}";
+    let doc = parse(input).unwrap();
+    let expected = "{\n  \"z\": 1,\n  \"id\": 12345678901234567891,\n  \"a\": [],\n  \"z\": [\n    1.50,\n    \"\\u0041\"\n  ]\n}";
+
+    assert_eq!(doc.to_string_pretty(2), expected);
+    assert_eq!(parse(expected).unwrap().to_string_pretty(2), expected);
+}
+
+#[test]
+fn test_errors() {
+    assert!(parse("{\"a\": 1,}").is_err());
+    assert!(parse("[1 2]").is_err());
+    assert!(parse("{\"a\" 1}").is_err());
+    assert!(parse("1 2").is_err());
+    assert_eq!(parse("[\n\n}").unwrap_err().line_no, 3);
+}
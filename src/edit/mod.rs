@@ -0,0 +1,634 @@
+//! Format-preserving edits of JSON source.
+//!
+//! An `Editor` holds the lossless tree from `cst` and changes only the
+//! tokens an operation touches; everything else is printed back exactly as
+//! it was read. Targets are JSON Pointers. New members and elements copy
+//! the line breaks, indentation and comma style of their siblings, and new
+//! container values are laid out with the indentation unit found in the
+//! document.
+
+use crate::cst::{self, Document, Element, Member, Tok, Value};
+use crate::json::JSON;
+use crate::lexer::Dialect;
+use crate::parser::ParseError;
+use crate::path::{parse_index, pointer_tokens};
+use crate::serializer;
+
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditError {
+    pub err_msg: String,
+    pub pointer: String,
+}
+
+impl EditError {
+    fn new(err_msg: String, pointer: &str) -> Self {
+        EditError {
+            err_msg,
+            pointer: pointer.to_string(),
+        }
+    }
+}
+
+pub struct Editor {
+    doc: Document,
+    indent_unit: String,
+}
+
+// Where a located value sits: the indentation of the line it starts on, and
+// whether its container puts one item per line.
+struct Layout {
+    indent: String,
+    multiline: bool,
+}
+
+// A member of an object or an element of an array.
+trait Item {
+    fn lead(&mut self) -> &mut Tok;
+    fn comma(&mut self) -> &mut Option<Tok>;
+}
+
+impl Item for Member {
+    fn lead(&mut self) -> &mut Tok {
+        &mut self.key
+    }
+
+    fn comma(&mut self) -> &mut Option<Tok> {
+        &mut self.comma
+    }
+}
+
+impl Item for Element {
+    fn lead(&mut self) -> &mut Tok {
+        self.value.first_tok_mut()
+    }
+
+    fn comma(&mut self) -> &mut Option<Tok> {
+        &mut self.comma
+    }
+}
+
+impl Editor {
+    pub fn new(source: &str) -> Result<Self, ParseError> {
+        Editor::with_dialect(source, Dialect::Json)
+    }
+
+    pub fn with_dialect(source: &str, dialect: Dialect) -> Result<Self, ParseError> {
+        let doc = cst::parse_with(source, dialect)?;
+        let indent_unit = infer_indent_unit(&doc.value).unwrap_or_else(|| "  ".to_string());
+
+        Ok(Editor { doc, indent_unit })
+    }
+
+    pub fn document(&self) -> &Document {
+        &self.doc
+    }
+
+    /// Replaces the value at `pointer`, or adds it if the pointer names a
+    /// missing member of an existing object or the end (`-`) of an array.
+    pub fn set(&mut self, pointer: &str, value: &JSON) -> Result<(), EditError> {
+        let tokens = self.tokens(pointer)?;
+        let (last, parents) = match tokens.split_last() {
+            Some(split) => split,
+            None => {
+                let layout = Layout {
+                    indent: String::new(),
+                    multiline: self.doc.value.to_string().contains('\n'),
+                };
+                let new = self.render(value, &layout);
+                replace_value(&mut self.doc.value, new);
+                return Ok(());
+            }
+        };
+
+        let exists = match self
+            .locate(parents)
+            .map_err(|e| EditError::new(e, pointer))?
+            .0
+        {
+            Value::Object(ref x) => x.get(last).is_some(),
+            Value::Array(ref x) => parse_index(last).is_some_and(|i| i < x.elements.len()),
+            Value::Scalar(_) => false,
+        };
+
+        if !exists {
+            return self.insert(pointer, value);
+        }
+
+        let unit = self.indent_unit.clone();
+        let dialect = self.doc.dialect;
+        let (target, layout) = self
+            .locate(&tokens)
+            .map_err(|e| EditError::new(e, pointer))?;
+        let new = render(value, &layout, &unit, dialect);
+        replace_value(target, new);
+
+        Ok(())
+    }
+
+    /// Adds a member to an object, failing if it already exists, or inserts
+    /// an element into an array before the given index (`-` appends).
+    pub fn insert(&mut self, pointer: &str, value: &JSON) -> Result<(), EditError> {
+        let tokens = self.tokens(pointer)?;
+        let (last, parents) = match tokens.split_last() {
+            Some(split) => split,
+            None => {
+                return Err(EditError::new(
+                    "Cannot insert at the root".to_string(),
+                    pointer,
+                ))
+            }
+        };
+
+        let unit = self.indent_unit.clone();
+        let dialect = self.doc.dialect;
+        let (parent, layout) = self
+            .locate(parents)
+            .map_err(|e| EditError::new(e, pointer))?;
+
+        match *parent {
+            Value::Object(ref mut obj) => {
+                if obj.get(last).is_some() {
+                    return Err(EditError::new(
+                        format!("Member `{}` already exists", last),
+                        pointer,
+                    ));
+                }
+
+                let item_layout = item_layout(&mut obj.members, &layout, &unit);
+                let (colon, value_lead) = match obj.members.first() {
+                    Some(m) => (m.colon.leading.clone(), m.value.first_tok().leading.clone()),
+                    None => (String::new(), " ".to_string()),
+                };
+
+                let mut new_value = render(value, &item_layout, &unit, dialect);
+                new_value.first_tok_mut().leading = value_lead;
+
+                let mut key = String::new();
+                serializer::write_string(&mut key, last);
+                let member = Member {
+                    key: Tok {
+                        leading: String::new(),
+                        text: key,
+                    },
+                    name: last.clone(),
+                    colon: Tok {
+                        leading: colon,
+                        text: ":".to_string(),
+                    },
+                    value: new_value,
+                    comma: None,
+                };
+
+                let index = obj.members.len();
+                insert_item(
+                    &mut obj.members,
+                    &mut obj.close,
+                    index,
+                    member,
+                    &layout,
+                    &unit,
+                );
+            }
+            Value::Array(ref mut arr) => {
+                let len = arr.elements.len();
+                let index = if last == "-" {
+                    len
+                } else {
+                    match parse_index(last) {
+                        Some(i) if i <= len => i,
+                        _ => {
+                            return Err(EditError::new(
+                                format!("Index `{}` is out of bounds", last),
+                                pointer,
+                            ))
+                        }
+                    }
+                };
+
+                let item_layout = item_layout(&mut arr.elements, &layout, &unit);
+                let element = Element {
+                    value: render(value, &item_layout, &unit, dialect),
+                    comma: None,
+                };
+
+                insert_item(
+                    &mut arr.elements,
+                    &mut arr.close,
+                    index,
+                    element,
+                    &layout,
+                    &unit,
+                );
+            }
+            Value::Scalar(_) => {
+                return Err(EditError::new(
+                    "Parent is not an object or array".to_string(),
+                    pointer,
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, pointer: &str) -> Result<(), EditError> {
+        let tokens = self.tokens(pointer)?;
+        let (last, parents) = match tokens.split_last() {
+            Some(split) => split,
+            None => {
+                return Err(EditError::new(
+                    "Cannot remove the root".to_string(),
+                    pointer,
+                ))
+            }
+        };
+
+        let not_found = || EditError::new("No value at this location".to_string(), pointer);
+        let (parent, _) = self
+            .locate(parents)
+            .map_err(|e| EditError::new(e, pointer))?;
+
+        match *parent {
+            Value::Object(ref mut obj) => {
+                let index = obj
+                    .members
+                    .iter()
+                    .rposition(|m| &m.name == last)
+                    .ok_or_else(not_found)?;
+                remove_item(&mut obj.members, &mut obj.close, index);
+            }
+            Value::Array(ref mut arr) => {
+                let index = parse_index(last)
+                    .filter(|&i| i < arr.elements.len())
+                    .ok_or_else(not_found)?;
+                remove_item(&mut arr.elements, &mut arr.close, index);
+            }
+            Value::Scalar(_) => return Err(not_found()),
+        }
+
+        Ok(())
+    }
+
+    pub fn rename_key(&mut self, pointer: &str, new_name: &str) -> Result<(), EditError> {
+        let tokens = self.tokens(pointer)?;
+        let (last, parents) = match tokens.split_last() {
+            Some(split) => split,
+            None => return Err(EditError::new("The root has no key".to_string(), pointer)),
+        };
+
+        let dialect = self.doc.dialect;
+        let (parent, _) = self
+            .locate(parents)
+            .map_err(|e| EditError::new(e, pointer))?;
+        let obj = match *parent {
+            Value::Object(ref mut obj) => obj,
+            _ => {
+                return Err(EditError::new(
+                    "Parent is not an object".to_string(),
+                    pointer,
+                ))
+            }
+        };
+
+        if new_name != last && obj.get(new_name).is_some() {
+            return Err(EditError::new(
+                format!("Member `{}` already exists", new_name),
+                pointer,
+            ));
+        }
+
+        let member = obj
+            .get_mut(last)
+            .ok_or_else(|| EditError::new("No value at this location".to_string(), pointer))?;
+
+        // JSON5 keys written without quotes stay that way when they can.
+        let unquoted = dialect == Dialect::Json5
+            && !member.key.text.starts_with('"')
+            && !member.key.text.starts_with('\'')
+            && is_identifier(new_name);
+
+        member.key.text = if unquoted {
+            new_name.to_string()
+        } else {
+            let mut key = String::new();
+            serializer::write_string(&mut key, new_name);
+            key
+        };
+        member.name = new_name.to_string();
+
+        Ok(())
+    }
+
+    fn tokens(&self, pointer: &str) -> Result<Vec<String>, EditError> {
+        pointer_tokens(pointer).map_err(|err| EditError::new(err.err_msg, pointer))
+    }
+
+    fn locate(&mut self, tokens: &[String]) -> Result<(&mut Value, Layout), String> {
+        let layout = Layout {
+            indent: String::new(),
+            multiline: self.doc.value.to_string().contains('\n'),
+        };
+
+        locate(&mut self.doc.value, tokens, layout)
+    }
+
+    fn render(&self, value: &JSON, layout: &Layout) -> Value {
+        render(value, layout, &self.indent_unit, self.doc.dialect)
+    }
+}
+
+impl fmt::Display for Editor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.doc)
+    }
+}
+
+fn locate<'a>(
+    value: &'a mut Value,
+    tokens: &[String],
+    layout: Layout,
+) -> Result<(&'a mut Value, Layout), String> {
+    let (first, rest) = match tokens.split_first() {
+        Some(split) => split,
+        None => return Ok((value, layout)),
+    };
+
+    let (lead, child) = match *value {
+        Value::Object(ref mut obj) => {
+            let member = obj
+                .get_mut(first)
+                .ok_or_else(|| format!("No member `{}`", first))?;
+            (member.key.leading.clone(), &mut member.value)
+        }
+        Value::Array(ref mut arr) => {
+            let len = arr.elements.len();
+            let elem = match parse_index(first) {
+                Some(i) if i < len => &mut arr.elements[i],
+                _ => return Err(format!("No element `{}` in an array of {}", first, len)),
+            };
+            (elem.value.first_tok().leading.clone(), &mut elem.value)
+        }
+        Value::Scalar(_) => return Err(format!("Cannot look up `{}` in a scalar", first)),
+    };
+
+    let child_layout = match line_indent(&lead) {
+        Some(indent) => Layout {
+            indent,
+            multiline: true,
+        },
+        None => Layout {
+            indent: layout.indent,
+            multiline: false,
+        },
+    };
+
+    locate(child, rest, child_layout)
+}
+
+// Keeps the trivia in front of the old value.
+fn replace_value(old: &mut Value, mut new: Value) {
+    new.first_tok_mut().leading = old.first_tok().leading.clone();
+    *old = new;
+}
+
+// Lays out a new value: pretty-printed with the document's indentation
+// inside one-item-per-line containers, compact otherwise.
+fn render(value: &JSON, layout: &Layout, unit: &str, dialect: Dialect) -> Value {
+    let text = if layout.multiline {
+        let pretty = serializer::to_string_pretty(value, 1);
+        let mut text = String::new();
+
+        for (i, line) in pretty.lines().enumerate() {
+            if i != 0 {
+                text.push('\n');
+                text.push_str(&layout.indent);
+            }
+            let depth = line.len() - line.trim_start_matches(' ').len();
+            text.push_str(&unit.repeat(depth));
+            text.push_str(&line[depth..]);
+        }
+
+        text
+    } else {
+        serializer::to_string(value)
+    };
+
+    cst::parse_with(&text, dialect).unwrap().value
+}
+
+// The layout a new item of a container will have, judged from its siblings.
+fn item_layout<T: Item>(items: &mut [T], container: &Layout, unit: &str) -> Layout {
+    if items.is_empty() {
+        return Layout {
+            indent: format!("{}{}", container.indent, unit),
+            multiline: container.multiline,
+        };
+    }
+
+    let probe = if items.len() >= 2 { 1 } else { 0 };
+    match line_indent(&items[probe].lead().leading) {
+        Some(indent) => Layout {
+            indent,
+            multiline: true,
+        },
+        None => Layout {
+            indent: container.indent.clone(),
+            multiline: false,
+        },
+    }
+}
+
+fn comma_tok() -> Tok {
+    Tok {
+        leading: String::new(),
+        text: ",".to_string(),
+    }
+}
+
+// The indentation of the last line of some trivia, if it spans lines.
+fn line_indent(leading: &str) -> Option<String> {
+    let start = leading.rfind('\n')? + 1;
+    let indent = leading[start..]
+        .chars()
+        .take_while(|&c| c == ' ' || c == '\t')
+        .collect();
+
+    Some(indent)
+}
+
+// Splits trivia into the part still on the previous token's line (often a
+// trailing comment that belongs with that token) and the rest.
+fn split_same_line(leading: &str) -> (String, String) {
+    match leading.find('\n') {
+        Some(i) => (leading[..i].to_string(), leading[i..].to_string()),
+        None => (String::new(), leading.to_string()),
+    }
+}
+
+// What goes in front of an item that isn't first: a line break and
+// indentation, or a single space.
+fn separator<T: Item>(items: &mut [T]) -> String {
+    let probe = if items.len() >= 2 { 1 } else { 0 };
+
+    match line_indent(&items[probe].lead().leading) {
+        Some(indent) => format!("\n{}", indent),
+        None => " ".to_string(),
+    }
+}
+
+// Moves `holder`'s same-line trivia in front of a new item that goes just
+// before it, returning the new item's trivia.
+fn split_holder(holder: &mut String, sep: &str) -> String {
+    if !sep.starts_with('\n') || !holder.contains('\n') {
+        return sep.to_string();
+    }
+
+    let (same_line, rest) = split_same_line(holder);
+    *holder = rest;
+
+    same_line + sep
+}
+
+fn insert_item<T: Item>(
+    items: &mut Vec<T>,
+    close: &mut Tok,
+    index: usize,
+    mut new: T,
+    container: &Layout,
+    unit: &str,
+) {
+    let len = items.len();
+
+    if len == 0 {
+        if container.multiline {
+            new.lead().leading = format!("\n{}{}", container.indent, unit);
+            if close.leading.trim().is_empty() {
+                close.leading = format!("\n{}", container.indent);
+            }
+        }
+        items.push(new);
+        return;
+    }
+
+    let sep = separator(items);
+
+    if index == 0 {
+        let first = items[0].lead();
+        if sep.starts_with('\n') && first.leading.contains('\n') {
+            let (same_line, rest) = split_same_line(&first.leading);
+            let indent = line_indent(&rest).unwrap_or_default();
+            new.lead().leading = format!("{}\n{}", same_line, indent);
+            first.leading = rest;
+        } else {
+            new.lead().leading = first.leading.clone();
+            if first.leading.trim().is_empty() {
+                first.leading = sep;
+            }
+        }
+        *new.comma() = Some(comma_tok());
+    } else if index < len {
+        new.lead().leading = split_holder(&mut items[index].lead().leading, &sep);
+        *new.comma() = Some(comma_tok());
+    } else {
+        new.lead().leading = split_holder(&mut close.leading, &sep);
+
+        // A trailing comma after the last item stays trailing.
+        let last = items[len - 1].comma();
+        if last.is_some() {
+            *new.comma() = Some(comma_tok());
+        } else {
+            *last = Some(comma_tok());
+        }
+    }
+
+    items.insert(index, new);
+}
+
+fn remove_item<T: Item>(items: &mut Vec<T>, close: &mut Tok, index: usize) {
+    let mut removed = items.remove(index);
+    let removed_lead = removed.lead().leading.clone();
+    let (same_line, _) = split_same_line(&removed_lead);
+    let len = items.len();
+
+    if len == 0 {
+        let (_, rest) = split_same_line(&close.leading);
+        close.leading = if rest.trim().is_empty() {
+            String::new()
+        } else {
+            rest
+        };
+    } else if index < len {
+        // The next item's same-line trivia was the removed item's trailing
+        // comment; what was on the line before it stays.
+        let holder = items[index].lead();
+        if holder.leading.contains('\n') {
+            let (_, rest) = split_same_line(&holder.leading);
+            holder.leading = same_line + &rest;
+        } else {
+            holder.leading = removed_lead;
+        }
+    } else {
+        *items[len - 1].comma() = removed.comma().take();
+        if close.leading.contains('\n') {
+            let (_, rest) = split_same_line(&close.leading);
+            close.leading = same_line + &rest;
+        }
+    }
+}
+
+// The smallest indentation step between an item and the line of its
+// container, or `None` if the document keeps everything on one line.
+fn infer_indent_unit(value: &Value) -> Option<String> {
+    fn walk(value: &Value, indent: &str, best: &mut Option<String>) {
+        let leads: Vec<(&str, &Value)> = match *value {
+            Value::Object(ref x) => x
+                .members
+                .iter()
+                .map(|m| (m.key.leading.as_str(), &m.value))
+                .collect(),
+            Value::Array(ref x) => x
+                .elements
+                .iter()
+                .map(|e| (e.value.first_tok().leading.as_str(), &e.value))
+                .collect(),
+            Value::Scalar(_) => return,
+        };
+
+        for (lead, child) in leads {
+            let child_indent = match line_indent(lead) {
+                Some(child_indent) => {
+                    if let Some(step) = child_indent.strip_prefix(indent) {
+                        let shorter = best.as_ref().is_none_or(|b| step.len() < b.len());
+                        if !step.is_empty() && shorter {
+                            *best = Some(step.to_string());
+                        }
+                    }
+                    child_indent
+                }
+                None => indent.to_string(),
+            };
+            walk(child, &child_indent, best);
+        }
+    }
+
+    let mut best = None;
+    walk(value, "", &mut best);
+
+    best
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        }
+        _ => false,
+    }
+}
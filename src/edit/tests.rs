@@ -0,0 +1,156 @@
+use crate::edit::Editor;
+use crate::json::JSON;
+use crate::lexer::Dialect;
+
+const PACKAGE: &str = r#"{
+    "name": "demo",
+    "version": "1.0.0",
+    "scripts": {},
+    "files": [
+        "src", // sources
+        "docs"
+    ]
+}
+"#;
+
+fn package() -> Editor {
+    Editor::with_dialect(PACKAGE, Dialect::Jsonc).unwrap()
+}
+
+fn string(x: &str) -> JSON {
+    JSON::JSONString(x.to_string())
+}
+
+#[test]
+fn test_set_existing() {
+    let mut editor = package();
+    editor.set("/version", &string("1.0.1")).unwrap();
+
+    assert_eq!(
+        editor.to_string(),
+        PACKAGE.replace("\"1.0.0\"", "\"1.0.1\"")
+    );
+}
+
+#[test]
+fn test_set_adds_member() {
+    let mut editor = package();
+    editor.set("/private", &JSON::JSONBool(true)).unwrap();
+    editor.set("/scripts/test", &string("cargo test")).unwrap();
+
+    let expected = r#"{
+    "name": "demo",
+    "version": "1.0.0",
+    "scripts": {
+        "test": "cargo test"
+    },
+    "files": [
+        "src", // sources
+        "docs"
+    ],
+    "private": true
+}
+"#;
+    assert_eq!(editor.to_string(), expected);
+}
+
+#[test]
+fn test_set_container() {
+    let mut editor = package();
+    let value = crate::parse(r#"{"a": [1]}"#).unwrap();
+    editor.set("/name", &value).unwrap();
+
+    let expected = PACKAGE.replace(
+        "\"name\": \"demo\"",
+        "\"name\": {\n        \"a\": [\n            1\n        ]\n    }",
+    );
+    assert_eq!(editor.to_string(), expected);
+}
+
+#[test]
+fn test_insert_into_array() {
+    let mut editor = package();
+    editor.insert("/files/1", &string("lib")).unwrap();
+    editor.insert("/files/0", &string("bin")).unwrap();
+    editor.insert("/files/-", &string("tests")).unwrap();
+
+    let expected = PACKAGE.replace(
+        "        \"src\", // sources\n        \"docs\"\n",
+        "        \"bin\",\n        \"src\", // sources\n        \"lib\",\n        \"docs\",\n        \"tests\"\n",
+    );
+    assert_eq!(editor.to_string(), expected);
+}
+
+#[test]
+fn test_inline() {
+    let mut editor = Editor::new(r#"{"a": [1, 2], "b": {}}"#).unwrap();
+    editor.insert("/a/0", &JSON::JSONNum(0.0)).unwrap();
+    editor
+        .set("/b/c", &crate::parse("[true]").unwrap())
+        .unwrap();
+    editor.remove("/a/2").unwrap();
+
+    assert_eq!(editor.to_string(), r#"{"a": [0, 1], "b": {"c": [true]}}"#);
+}
+
+#[test]
+fn test_remove() {
+    let mut editor = package();
+    editor.remove("/files/1").unwrap();
+    editor.remove("/name").unwrap();
+    editor.remove("/scripts").unwrap();
+
+    let expected = r#"{
+    "version": "1.0.0",
+    "files": [
+        "src" // sources
+    ]
+}
+"#;
+    assert_eq!(editor.to_string(), expected);
+
+    editor.remove("/files/0").unwrap();
+    assert!(editor.to_string().contains("\"files\": []"));
+}
+
+#[test]
+fn test_rename_key() {
+    let mut editor = package();
+    editor.rename_key("/scripts", "tasks").unwrap();
+
+    assert_eq!(
+        editor.to_string(),
+        PACKAGE.replace("\"scripts\"", "\"tasks\"")
+    );
+    assert!(editor.rename_key("/tasks", "name").is_err());
+
+    let mut editor = Editor::with_dialect("{a: 1, 'b': 2}", Dialect::Json5).unwrap();
+    editor.rename_key("/a", "x").unwrap();
+    editor.rename_key("/b", "y").unwrap();
+    assert_eq!(editor.to_string(), "{x: 1, \"y\": 2}");
+}
+
+#[test]
+fn test_trailing_commas_and_comments() {
+    let input = "{\n\t// keep me\n\t\"a\": 1,\n}\n";
+    let mut editor = Editor::with_dialect(input, Dialect::Jsonc).unwrap();
+    editor.insert("/b", &JSON::JSONNull).unwrap();
+
+    assert_eq!(
+        editor.to_string(),
+        "{\n\t// keep me\n\t\"a\": 1,\n\t\"b\": null,\n}\n"
+    );
+}
+
+#[test]
+fn test_errors() {
+    let mut editor = package();
+
+    assert!(editor.insert("/name", &JSON::JSONNull).is_err());
+    assert!(editor.insert("/files/5", &JSON::JSONNull).is_err());
+    assert!(editor.remove("/missing").is_err());
+    assert!(editor.remove("").is_err());
+    assert!(editor.set("/name/x", &JSON::JSONNull).is_err());
+    assert!(editor.set("no-slash", &JSON::JSONNull).is_err());
+    assert_eq!(editor.to_string(), PACKAGE);
+}
@@ -0,0 +1,226 @@
+//! Decoding JSON text from bytes.
+//!
+//! `decode` works out the encoding from a byte-order mark or, failing
+//! that, from where the null bytes fall in the first four bytes, which
+//! [RFC 4627](https://www.rfc-editor.org/rfc/rfc4627#section-3) notes is
+//! enough because JSON text starts with two ASCII characters. The text is
+//! transcoded to a `String` without its BOM, and invalid input is reported
+//! with the byte offset where it starts. `encode` goes the other way, so a
+//! rewritten file can keep the encoding it was read in.
+
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded {
+    pub text: String,
+    pub encoding: Encoding,
+    /// Whether the input started with a byte-order mark.
+    pub bom: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub err_msg: String,
+    /// Offset of the first invalid byte in the input, BOM included.
+    pub offset: usize,
+    /// Line of the invalid sequence, counted in the text before it.
+    pub line_no: u64,
+}
+
+impl DecodeError {
+    fn new(err_msg: String, offset: usize, before: &str) -> Self {
+        DecodeError {
+            err_msg,
+            offset,
+            line_no: 1 + before.matches('\n').count() as u64,
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Utf32Le => "UTF-32LE",
+            Encoding::Utf32Be => "UTF-32BE",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The encoding of `bytes` and the length of its byte-order mark, if any.
+pub fn detect(bytes: &[u8]) -> (Encoding, usize) {
+    // UTF-32LE's mark starts with UTF-16LE's, so it is tried first.
+    let boms: [(&[u8], Encoding); 5] = [
+        (&[0xef, 0xbb, 0xbf], Encoding::Utf8),
+        (&[0xff, 0xfe, 0, 0], Encoding::Utf32Le),
+        (&[0, 0, 0xfe, 0xff], Encoding::Utf32Be),
+        (&[0xff, 0xfe], Encoding::Utf16Le),
+        (&[0xfe, 0xff], Encoding::Utf16Be),
+    ];
+    for (bom, encoding) in boms.iter() {
+        if bytes.starts_with(bom) {
+            return (*encoding, bom.len());
+        }
+    }
+
+    let nulls: Vec<bool> = bytes.iter().take(4).map(|x| *x == 0).collect();
+    let encoding = match nulls[..] {
+        [true, true, true, false] => Encoding::Utf32Be,
+        [false, true, true, true] => Encoding::Utf32Le,
+        [true, false, ..] => Encoding::Utf16Be,
+        [false, true, ..] => Encoding::Utf16Le,
+        _ => Encoding::Utf8,
+    };
+
+    (encoding, 0)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Decoded, DecodeError> {
+    let (encoding, bom) = detect(bytes);
+    let body = &bytes[bom..];
+
+    let text = match encoding {
+        Encoding::Utf8 => decode_utf8(body, bom)?,
+        Encoding::Utf16Le => decode_utf16(body, bom, u16::from_le_bytes)?,
+        Encoding::Utf16Be => decode_utf16(body, bom, u16::from_be_bytes)?,
+        Encoding::Utf32Le => decode_utf32(body, bom, u32::from_le_bytes)?,
+        Encoding::Utf32Be => decode_utf32(body, bom, u32::from_be_bytes)?,
+    };
+
+    Ok(Decoded {
+        text,
+        encoding,
+        bom: bom > 0,
+    })
+}
+
+/// `text` in `encoding`, preceded by that encoding's byte-order mark if
+/// `bom` is set.
+pub fn encode(text: &str, encoding: Encoding, bom: bool) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let text = if bom {
+        format!("\u{feff}{}", text)
+    } else {
+        text.to_string()
+    };
+
+    match encoding {
+        Encoding::Utf8 => bytes.extend(text.as_bytes()),
+        Encoding::Utf16Le => text
+            .encode_utf16()
+            .for_each(|x| bytes.extend(x.to_le_bytes())),
+        Encoding::Utf16Be => text
+            .encode_utf16()
+            .for_each(|x| bytes.extend(x.to_be_bytes())),
+        Encoding::Utf32Le => text
+            .chars()
+            .for_each(|x| bytes.extend((x as u32).to_le_bytes())),
+        Encoding::Utf32Be => text
+            .chars()
+            .for_each(|x| bytes.extend((x as u32).to_be_bytes())),
+    }
+
+    bytes
+}
+
+fn decode_utf8(bytes: &[u8], base: usize) -> Result<String, DecodeError> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Ok(text.to_string()),
+        Err(err) => {
+            let valid = err.valid_up_to();
+            let before = std::str::from_utf8(&bytes[..valid]).unwrap();
+            let err_msg = match err.error_len() {
+                Some(_) => "Invalid UTF-8 sequence",
+                None => "Truncated UTF-8 sequence",
+            };
+            Err(DecodeError::new(err_msg.to_string(), base + valid, before))
+        }
+    }
+}
+
+fn decode_utf16(
+    bytes: &[u8],
+    base: usize,
+    unit: fn([u8; 2]) -> u16,
+) -> Result<String, DecodeError> {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|x| unit([x[0], x[1]])).collect();
+    let mut text = String::new();
+
+    let mut i = 0;
+    while i < units.len() {
+        let offset = base + 2 * i;
+        let high = units[i] as u32;
+        i += 1;
+
+        let code_pt = match high {
+            0xd800..=0xdbff => match units.get(i).map(|x| *x as u32) {
+                Some(low @ 0xdc00..=0xdfff) => {
+                    i += 1;
+                    0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                }
+                Some(_) => high,
+                None => {
+                    let err_msg = "Truncated UTF-16 surrogate pair".to_string();
+                    return Err(DecodeError::new(err_msg, offset, &text));
+                }
+            },
+            _ => high,
+        };
+
+        match std::char::from_u32(code_pt) {
+            Some(c) => text.push(c),
+            None => {
+                let err_msg = "Unpaired UTF-16 surrogate".to_string();
+                return Err(DecodeError::new(err_msg, offset, &text));
+            }
+        }
+    }
+
+    if 2 * units.len() < bytes.len() {
+        let err_msg = "Truncated UTF-16 code unit".to_string();
+        return Err(DecodeError::new(err_msg, base + bytes.len() - 1, &text));
+    }
+
+    Ok(text)
+}
+
+fn decode_utf32(
+    bytes: &[u8],
+    base: usize,
+    unit: fn([u8; 4]) -> u32,
+) -> Result<String, DecodeError> {
+    let mut text = String::new();
+
+    for (i, x) in bytes.chunks(4).enumerate() {
+        let offset = base + 4 * i;
+        if x.len() < 4 {
+            let err_msg = "Truncated UTF-32 code unit".to_string();
+            return Err(DecodeError::new(err_msg, offset, &text));
+        }
+
+        match std::char::from_u32(unit([x[0], x[1], x[2], x[3]])) {
+            Some(c) => text.push(c),
+            None => {
+                let err_msg = "Invalid UTF-32 code point".to_string();
+                return Err(DecodeError::new(err_msg, offset, &text));
+            }
+        }
+    }
+
+    Ok(text)
+}
@@ -0,0 +1,167 @@
+use crate::encoding::{decode, detect, encode, DecodeError, Encoding};
+use crate::json::JSON;
+use crate::parser::ParseError;
+
+fn utf16le(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn utf16be(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(|x| x.to_be_bytes()).collect()
+}
+
+fn utf32le(text: &str) -> Vec<u8> {
+    text.chars()
+        .flat_map(|x| (x as u32).to_le_bytes())
+        .collect()
+}
+
+fn utf32be(text: &str) -> Vec<u8> {
+    text.chars()
+        .flat_map(|x| (x as u32).to_be_bytes())
+        .collect()
+}
+
+fn with_bom(bom: &[u8], bytes: Vec<u8>) -> Vec<u8> {
+    bom.iter().cloned().chain(bytes).collect()
+}
+
+fn error(err_msg: &str, offset: usize, line_no: u64) -> Result<String, DecodeError> {
+    Err(DecodeError {
+        err_msg: err_msg.to_string(),
+        offset,
+        line_no,
+    })
+}
+
+fn text(bytes: &[u8]) -> Result<String, DecodeError> {
+    decode(bytes).map(|x| x.text)
+}
+
+#[test]
+fn test_detect_by_nulls() {
+    let text = "{\"a\": \"\u{e9}\u{1f600}\"}";
+
+    assert_eq!(detect(text.as_bytes()), (Encoding::Utf8, 0));
+    assert_eq!(detect(&utf16le(text)), (Encoding::Utf16Le, 0));
+    assert_eq!(detect(&utf16be(text)), (Encoding::Utf16Be, 0));
+    assert_eq!(detect(&utf32le(text)), (Encoding::Utf32Le, 0));
+    assert_eq!(detect(&utf32be(text)), (Encoding::Utf32Be, 0));
+    assert_eq!(detect(&utf16le("1")), (Encoding::Utf16Le, 0));
+    assert_eq!(detect(b"1"), (Encoding::Utf8, 0));
+    assert_eq!(detect(b""), (Encoding::Utf8, 0));
+
+    for bytes in &[utf16le(text), utf16be(text), utf32le(text), utf32be(text)] {
+        let decoded = decode(bytes).unwrap();
+        assert_eq!(decoded.text, text);
+        assert!(!decoded.bom);
+    }
+}
+
+#[test]
+fn test_detect_by_bom() {
+    let cases = vec![
+        (
+            with_bom(&[0xef, 0xbb, 0xbf], b"[1]".to_vec()),
+            Encoding::Utf8,
+        ),
+        (with_bom(&[0xff, 0xfe], utf16le("[1]")), Encoding::Utf16Le),
+        (with_bom(&[0xfe, 0xff], utf16be("[1]")), Encoding::Utf16Be),
+        (
+            with_bom(&[0xff, 0xfe, 0, 0], utf32le("[1]")),
+            Encoding::Utf32Le,
+        ),
+        (
+            with_bom(&[0, 0, 0xfe, 0xff], utf32be("[1]")),
+            Encoding::Utf32Be,
+        ),
+    ];
+
+    for (bytes, encoding) in cases {
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.encoding, encoding);
+        assert_eq!(decoded.text, "[1]");
+        assert!(decoded.bom);
+    }
+}
+
+#[test]
+fn test_invalid_sequences() {
+    assert_eq!(
+        text(b"[\"a\",\n\"\xff\"]"),
+        error("Invalid UTF-8 sequence", 7, 2)
+    );
+    assert_eq!(text(b"\"\xe2\x82"), error("Truncated UTF-8 sequence", 1, 1));
+    assert_eq!(
+        text(&with_bom(&[0xef, 0xbb, 0xbf], b"1\xc0".to_vec())),
+        error("Invalid UTF-8 sequence", 4, 1)
+    );
+
+    let mut bytes = utf16le("[\"");
+    bytes.extend(&[0x00, 0xdc, b'"', 0]);
+    assert_eq!(text(&bytes), error("Unpaired UTF-16 surrogate", 4, 1));
+
+    let mut bytes = utf16le("\"");
+    bytes.extend(&[0x3d, 0xd8]);
+    assert_eq!(text(&bytes), error("Truncated UTF-16 surrogate pair", 2, 1));
+
+    let mut bytes = with_bom(&[0xfe, 0xff], utf16be("1"));
+    bytes.push(0);
+    assert_eq!(text(&bytes), error("Truncated UTF-16 code unit", 4, 1));
+
+    let mut bytes = utf32be("[\n");
+    bytes.extend(&[0, 0x11, 0, 0]);
+    assert_eq!(text(&bytes), error("Invalid UTF-32 code point", 8, 2));
+    let bytes = with_bom(&[0xff, 0xfe, 0, 0], utf32le("1")[..3].to_vec());
+    assert_eq!(text(&bytes), error("Truncated UTF-32 code unit", 4, 1));
+}
+
+#[test]
+fn test_encode() {
+    assert_eq!(
+        encode("[\"é\"]", Encoding::Utf8, false),
+        "[\"é\"]".as_bytes()
+    );
+    assert_eq!(
+        encode("1", Encoding::Utf16Le, true),
+        with_bom(&[0xff, 0xfe], utf16le("1"))
+    );
+
+    let encodings = [
+        Encoding::Utf8,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+        Encoding::Utf32Le,
+        Encoding::Utf32Be,
+    ];
+    for &encoding in encodings.iter() {
+        for &bom in [false, true].iter() {
+            let text = "{\"a\": \"\u{1f600}\"}\n";
+            let decoded = decode(&encode(text, encoding, bom)).unwrap();
+            assert_eq!(
+                (decoded.text.as_str(), decoded.encoding, decoded.bom),
+                (text, encoding, bom)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_parse_bytes() {
+    let bytes = with_bom(&[0xff, 0xfe], utf16le("{\"a\": [true]}"));
+    let mut expected = std::collections::HashMap::new();
+    expected.insert("a".to_string(), JSON::JSONArray(vec![JSON::JSONBool(true)]));
+
+    assert_eq!(crate::parse_bytes(&bytes), Ok(JSON::JSONObject(expected)));
+    assert_eq!(
+        crate::parse_bytes(b"[1,\n\xff]"),
+        Err(ParseError::new(
+            "Invalid UTF-8 sequence at byte 4".to_string(),
+            2
+        ))
+    );
+    assert_eq!(
+        crate::parse_bytes_with(b"\xef\xbb\xbf[1,]", crate::Dialect::Jsonc),
+        Ok(JSON::JSONArray(vec![JSON::JSONNum(1.0)]))
+    );
+}
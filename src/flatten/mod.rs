@@ -0,0 +1,255 @@
+//! Flattening nested values into `("a.b.0.c", leaf)` pairs and back, for
+//! stores that only understand flat keys.
+//!
+//! Leaves are scalars and empty arrays or objects. Object members are
+//! visited in key order, so the output is stable. With an escape character
+//! configured, separators, brackets and the escape character itself are
+//! escaped inside keys, as are object keys that would otherwise read back
+//! as array indices, and an empty key is written as the escape character
+//! followed by `"`; without one, keys are written as they are and may not
+//! survive a round trip. `unflatten` pads arrays with `null` up to each
+//! index it sees and rejects indices above 1,000,000.
+
+use crate::json::JSON;
+use crate::path::{assign, PathElem};
+use crate::serializer::sorted_entries;
+
+use std::fmt::Write;
+
+#[cfg(test)]
+mod tests;
+
+/// How array indices appear in flat keys.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndexStyle {
+    /// `a.b.0`, indices are ordinary segments.
+    Separator,
+    /// `a.b[0]`, indices are bracketed.
+    Brackets,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub separator: String,
+    pub escape: Option<char>,
+    pub index_style: IndexStyle,
+}
+
+// Follows the escape character to stand for an empty key, which would
+// otherwise leave nothing in the flat key.
+const EMPTY_KEY: char = '"';
+
+impl Options {
+    /// Fails if keys written with `separator` couldn't be read back: it
+    /// must be non-empty, can't contain the escape character and can't
+    /// start with the empty-key marker.
+    pub fn new(separator: &str) -> Result<Self, FlattenError> {
+        let escape = '\\';
+        let err_msg = if separator.is_empty() {
+            "The separator can't be empty".to_string()
+        } else if separator.contains(escape) {
+            format!(
+                "The separator can't contain the escape character `{}`",
+                escape
+            )
+        } else if separator.starts_with(EMPTY_KEY) {
+            format!("The separator can't start with `{}`", EMPTY_KEY)
+        } else {
+            return Ok(Options {
+                separator: separator.to_string(),
+                escape: Some(escape),
+                index_style: IndexStyle::Separator,
+            });
+        };
+
+        Err(FlattenError::new(err_msg, ""))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlattenError {
+    pub err_msg: String,
+    /// The flat key that couldn't be read, empty for bad `Options`.
+    pub key: String,
+}
+
+impl FlattenError {
+    fn new(err_msg: String, key: &str) -> Self {
+        FlattenError {
+            err_msg,
+            key: key.to_string(),
+        }
+    }
+}
+
+pub fn flatten(json: &JSON, separator: &str) -> Result<Vec<(String, JSON)>, FlattenError> {
+    Ok(flatten_with(json, &Options::new(separator)?))
+}
+
+pub fn flatten_with(json: &JSON, options: &Options) -> Vec<(String, JSON)> {
+    let mut out = Vec::new();
+    let mut prefix = String::new();
+    walk(json, &mut prefix, options, &mut out);
+
+    out
+}
+
+fn walk(json: &JSON, prefix: &mut String, options: &Options, out: &mut Vec<(String, JSON)>) {
+    match *json {
+        JSON::JSONArray(ref x) if !x.is_empty() => {
+            for (i, elem) in x.iter().enumerate() {
+                let len = prefix.len();
+                match options.index_style {
+                    IndexStyle::Separator => {
+                        if len != 0 {
+                            prefix.push_str(&options.separator);
+                        }
+                        write!(prefix, "{}", i).unwrap();
+                    }
+                    IndexStyle::Brackets => write!(prefix, "[{}]", i).unwrap(),
+                }
+                walk(elem, prefix, options, out);
+                prefix.truncate(len);
+            }
+        }
+        JSON::JSONObject(ref x) if !x.is_empty() => {
+            for (key, val) in sorted_entries(x) {
+                let len = prefix.len();
+                if len != 0 {
+                    prefix.push_str(&options.separator);
+                }
+                escape_key(prefix, key, options);
+                walk(val, prefix, options, out);
+                prefix.truncate(len);
+            }
+        }
+        _ => out.push((prefix.clone(), json.clone())),
+    }
+}
+
+fn escape_key(out: &mut String, key: &str, options: &Options) {
+    let escape = match options.escape {
+        Some(escape) => escape,
+        None => {
+            out.push_str(key);
+            return;
+        }
+    };
+
+    if key.is_empty() {
+        out.push(escape);
+        out.push(EMPTY_KEY);
+    }
+    if options.index_style == IndexStyle::Separator && is_index(key) {
+        out.push(escape);
+    }
+
+    for (i, c) in key.char_indices() {
+        let special = c == escape
+            || key[i..].starts_with(&options.separator)
+            || (options.index_style == IndexStyle::Brackets && c == '[');
+        if special {
+            out.push(escape);
+        }
+        out.push(c);
+    }
+}
+
+fn is_index(segment: &str) -> bool {
+    !segment.is_empty()
+        && segment.bytes().all(|b| b.is_ascii_digit())
+        && (segment == "0" || !segment.starts_with('0'))
+}
+
+pub fn unflatten(pairs: &[(String, JSON)], separator: &str) -> Result<JSON, FlattenError> {
+    unflatten_with(pairs, &Options::new(separator)?)
+}
+
+pub fn unflatten_with(pairs: &[(String, JSON)], options: &Options) -> Result<JSON, FlattenError> {
+    let mut root = None;
+
+    for (key, value) in pairs {
+        let path = parse_key(key, options).map_err(|err_msg| FlattenError::new(err_msg, key))?;
+        assign(&mut root, &path, value.clone())
+            .map_err(|err_msg| FlattenError::new(err_msg, key))?;
+    }
+
+    Ok(root.unwrap_or(JSON::JSONNull))
+}
+
+fn parse_key(key: &str, options: &Options) -> Result<Vec<PathElem>, String> {
+    let mut path = Vec::new();
+    if key.is_empty() {
+        return Ok(path);
+    }
+
+    let mut segment = String::new();
+    // An escaped segment is always an object key, even if it is all digits.
+    let mut escaped = false;
+    // Set right after `[n]`, where the next separator doesn't end a segment.
+    let mut after_index = false;
+    let mut chars = key.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if Some(c) == options.escape {
+            match chars.next() {
+                Some((_, EMPTY_KEY)) => (),
+                Some((_, c)) => segment.push(c),
+                None => return Err("Key ends with an escape character".to_string()),
+            }
+            escaped = true;
+            after_index = false;
+        } else if key[i..].starts_with(&options.separator) {
+            if !after_index {
+                path.push(finish_segment(&mut segment, escaped, options));
+            }
+            escaped = false;
+            after_index = false;
+            for _ in 1..options.separator.chars().count() {
+                chars.next();
+            }
+        } else if c == '[' && options.index_style == IndexStyle::Brackets {
+            if !after_index && (!segment.is_empty() || escaped || !path.is_empty()) {
+                path.push(finish_segment(&mut segment, escaped, options));
+            }
+
+            let mut digits = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, ']')) => break,
+                    Some((_, d)) => digits.push(d),
+                    None => return Err("Expecting `]` after index".to_string()),
+                }
+            }
+            match digits.parse() {
+                Ok(i) if is_index(&digits) => path.push(PathElem::Index(i)),
+                _ => return Err(format!("Invalid array index `{}`", digits)),
+            }
+            escaped = false;
+            after_index = true;
+        } else {
+            if after_index {
+                return Err("Expecting a separator after `]`".to_string());
+            }
+            segment.push(c);
+        }
+    }
+
+    if !after_index {
+        path.push(finish_segment(&mut segment, escaped, options));
+    }
+
+    Ok(path)
+}
+
+fn finish_segment(segment: &mut String, escaped: bool, options: &Options) -> PathElem {
+    let segment = std::mem::take(segment);
+
+    if !escaped && options.index_style == IndexStyle::Separator && is_index(&segment) {
+        if let Ok(i) = segment.parse() {
+            return PathElem::Index(i);
+        }
+    }
+
+    PathElem::Key(segment)
+}
@@ -0,0 +1,95 @@
+use crate::flatten::{flatten, flatten_with, unflatten, unflatten_with, IndexStyle, Options};
+use crate::json::JSON;
+
+fn doc() -> JSON {
+    crate::parse(r#"{"a":{"b":[{"c":1},"x"],"e":[],"f":{}},"g":null}"#).unwrap()
+}
+
+fn keys(pairs: &[(String, JSON)]) -> Vec<&str> {
+    pairs.iter().map(|(key, _)| key.as_str()).collect()
+}
+
+#[test]
+fn test_flatten() {
+    let pairs = flatten(&doc(), ".").unwrap();
+
+    assert_eq!(keys(&pairs), vec!["a.b.0.c", "a.b.1", "a.e", "a.f", "g"]);
+    assert_eq!(pairs[0].1, JSON::JSONNum(1.0));
+    assert_eq!(pairs[2].1, JSON::JSONArray(Vec::new()));
+}
+
+#[test]
+fn test_brackets() {
+    let mut options = Options::new("__").unwrap();
+    options.index_style = IndexStyle::Brackets;
+    let pairs = flatten_with(&doc(), &options);
+
+    assert_eq!(
+        keys(&pairs),
+        vec!["a__b[0]__c", "a__b[1]", "a__e", "a__f", "g"]
+    );
+    assert_eq!(unflatten_with(&pairs, &options).unwrap(), doc());
+}
+
+#[test]
+fn test_escaping() {
+    let json = crate::parse(r#"{"a.b":{"0":"zero","c\\d":[true]}}"#).unwrap();
+    let pairs = flatten(&json, ".").unwrap();
+
+    assert_eq!(keys(&pairs), vec![r"a\.b.\0", r"a\.b.c\\d.0"]);
+    assert_eq!(unflatten(&pairs, ".").unwrap(), json);
+}
+
+#[test]
+fn test_round_trip() {
+    let json = doc();
+
+    assert_eq!(unflatten(&flatten(&json, ".").unwrap(), ".").unwrap(), json);
+    assert_eq!(
+        unflatten(&flatten(&JSON::JSONBool(true), ".").unwrap(), ".").unwrap(),
+        JSON::JSONBool(true)
+    );
+}
+
+#[test]
+fn test_empty_keys() {
+    let json = crate::parse(r#"{"": {"": 1, "\"": [{"": 2}]}}"#).unwrap();
+    let pairs = flatten(&json, ".").unwrap();
+
+    assert_eq!(keys(&pairs), vec![r#"\".\""#, r#"\".".0.\""#]);
+    assert_eq!(unflatten(&pairs, ".").unwrap(), json);
+}
+
+#[test]
+fn test_bad_separators() {
+    for separator in ["", "\\", "a\\b", "\""] {
+        assert!(Options::new(separator).is_err(), "{:?}", separator);
+        assert!(flatten(&doc(), separator).is_err(), "{:?}", separator);
+    }
+}
+
+#[test]
+fn test_unflatten_errors() {
+    let conflict = vec![
+        ("a".to_string(), JSON::JSONNum(1.0)),
+        ("a.b".to_string(), JSON::JSONNum(2.0)),
+    ];
+    let err = unflatten(&conflict, ".").unwrap_err();
+    assert_eq!(err.key, "a.b");
+
+    let dangling = vec![("a\\".to_string(), JSON::JSONNull)];
+    assert!(unflatten(&dangling, ".").is_err());
+
+    let mut options = Options::new(".").unwrap();
+    options.index_style = IndexStyle::Brackets;
+    let bad_index = vec![("a[x]".to_string(), JSON::JSONNull)];
+    assert!(unflatten_with(&bad_index, &options).is_err());
+
+    let huge_index = vec![("a.99999999999".to_string(), JSON::JSONNum(1.0))];
+    let err = unflatten(&huge_index, ".").unwrap_err();
+    assert_eq!(err.key, "a.99999999999");
+    assert_eq!(
+        err.err_msg,
+        "Index 99999999999 is too large, the limit is 1000000"
+    );
+}
@@ -0,0 +1,173 @@
+//! [gron](https://github.com/tomnomnom/gron)-style flattening of a `JSON`
+//! value into one assignment per line:
+//!
+//! ```text
+//! json = {};
+//! json.items = [];
+//! json.items[0] = {};
+//! json.items[0]["display name"] = "x";
+//! ```
+//!
+//! `gron` writes the lines and `ungron` rebuilds the value from them, so the
+//! output can be filtered with grep or edited with sed in between. Missing
+//! array elements become `null`, and indices above 1,000,000 are rejected
+//! rather than padded out.
+
+use crate::json::JSON;
+use crate::parser::{ParseError, ParseResult};
+use crate::path::{assign, PathElem};
+use crate::serializer::{sorted_entries, write_string, write_value};
+
+use std::fmt::Write;
+
+#[cfg(test)]
+mod tests;
+
+const ROOT: &str = "json";
+
+pub fn gron(json: &JSON) -> String {
+    let mut out = String::new();
+    let mut path = String::from(ROOT);
+    write_lines(&mut out, &mut path, json);
+
+    out
+}
+
+fn write_lines(out: &mut String, path: &mut String, json: &JSON) {
+    out.push_str(path);
+    out.push_str(" = ");
+
+    match *json {
+        JSON::JSONArray(ref x) => {
+            out.push_str("[];\n");
+            for (i, elem) in x.iter().enumerate() {
+                let len = path.len();
+                write!(path, "[{}]", i).unwrap();
+                write_lines(out, path, elem);
+                path.truncate(len);
+            }
+        }
+        JSON::JSONObject(ref x) => {
+            out.push_str("{};\n");
+            for (key, val) in sorted_entries(x) {
+                let len = path.len();
+                if is_identifier(key) {
+                    path.push('.');
+                    path.push_str(key);
+                } else {
+                    path.push('[');
+                    write_string(path, key);
+                    path.push(']');
+                }
+                write_lines(out, path, val);
+                path.truncate(len);
+            }
+        }
+        _ => {
+            write_value(out, json);
+            out.push_str(";\n");
+        }
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        }
+        _ => false,
+    }
+}
+
+/// Rebuilds a value from `gron` output. Lines may come in any order.
+pub fn ungron(input: &str) -> ParseResult {
+    let mut root = None;
+
+    for (i, line) in input.lines().enumerate() {
+        let line_no = i as u64 + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (path, value) =
+            parse_line(line).map_err(|err_msg| ParseError::new(err_msg, line_no))?;
+        assign(&mut root, &path, value).map_err(|err_msg| ParseError::new(err_msg, line_no))?;
+    }
+
+    match root {
+        Some(root) => Ok(root),
+        None => Err(ParseError::new("No assignments found".to_string(), 1)),
+    }
+}
+
+fn parse_line(line: &str) -> Result<(Vec<PathElem>, JSON), String> {
+    if !line.starts_with(ROOT) {
+        return Err(format!("Expecting a line starting with `{}`", ROOT));
+    }
+
+    let mut rest = &line[ROOT.len()..];
+    let mut path = Vec::new();
+
+    loop {
+        if let Some(after) = rest.strip_prefix('.') {
+            let len = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+                .unwrap_or(after.len());
+            if len == 0 {
+                return Err("Expecting a key after `.`".to_string());
+            }
+            path.push(PathElem::Key(after[..len].to_string()));
+            rest = &after[len..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let close = if after.starts_with('"') {
+                string_end(after).ok_or("Unterminated string in path")?
+            } else {
+                after.find(']').ok_or("Expecting `]` in path")?
+            };
+
+            path.push(match crate::parse(&after[..close]) {
+                Ok(JSON::JSONString(key)) => PathElem::Key(key),
+                Ok(JSON::JSONNum(x)) if x >= 0.0 && x.fract() == 0.0 => PathElem::Index(x as usize),
+                _ => return Err(format!("Invalid path element `{}`", &after[..close])),
+            });
+
+            rest = after[close..]
+                .strip_prefix(']')
+                .ok_or("Expecting `]` in path")?;
+        } else {
+            break;
+        }
+    }
+
+    let rest = rest
+        .trim_start()
+        .strip_prefix('=')
+        .ok_or("Expecting `=` after path")?;
+    let rest = rest.trim();
+    let rest = rest.strip_suffix(';').unwrap_or(rest);
+
+    match crate::parse(rest) {
+        Ok(value) => Ok((path, value)),
+        Err(err) => Err(format!("Invalid value: {}", err.err_msg)),
+    }
+}
+
+// Byte offset just past the closing quote of the JSON string at the start
+// of `text`.
+fn string_end(text: &str) -> Option<usize> {
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(i + 1),
+            _ => (),
+        }
+    }
+
+    None
+}
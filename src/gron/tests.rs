@@ -0,0 +1,63 @@
+use crate::gron::{gron, ungron};
+use crate::json::JSON;
+
+#[test]
+fn test_gron() {
+    let json = crate::parse(r#"{"items":[{"name":"x","tags":[]}],"a b":1.5,"ok":true}"#).unwrap();
+    let actual = gron(&json);
+    let expected = r#"json = {};
+json["a b"] = 1.5;
+json.items = [];
+json.items[0] = {};
+json.items[0].name = "x";
+json.items[0].tags = [];
+json.ok = true;
+"#;
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_scalar_root() {
+    assert_eq!(gron(&JSON::JSONNull), "json = null;\n");
+    assert_eq!(
+        ungron("json = \"x\";").unwrap(),
+        JSON::JSONString("x".to_string())
+    );
+}
+
+#[test]
+fn test_round_trip() {
+    let input = r#"{"a":[1,[2,{"b":null}],"x\"]y"],"c":{"":{},"d.e":"f = g;"}}"#;
+    let json = crate::parse(input).unwrap();
+    let actual = ungron(&gron(&json)).unwrap();
+
+    assert_eq!(actual, json);
+}
+
+#[test]
+fn test_ungron_out_of_order() {
+    let input = "json.list[2] = \"c\";\njson.list[0] = \"a\";\njson.obj.x = 1;\njson.obj = {};\n";
+    let actual = ungron(input).unwrap();
+    let expected = crate::parse(r#"{"list":["a",null,"c"],"obj":{"x":1}}"#).unwrap();
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_ungron_errors() {
+    let err = ungron("json = {};\njson.a = ;\n").unwrap_err();
+    assert_eq!(err.line_no, 2);
+
+    assert!(ungron("").is_err());
+    assert!(ungron("other.a = 1;").is_err());
+    assert!(ungron("json.a = 1;\njson.a.b = 2;").is_err());
+    assert!(ungron("json[\"a] = 1;").is_err());
+
+    let err = ungron("json = [];\njson[99999999999] = 1;").unwrap_err();
+    assert_eq!(err.line_no, 2);
+    assert_eq!(
+        err.err_msg,
+        "Index 99999999999 is too large, the limit is 1000000"
+    );
+}
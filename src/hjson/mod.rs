@@ -0,0 +1,22 @@
+//! [Hjson](https://hjson.github.io/syntax.html), a relaxed JSON syntax meant
+//! for hand-edited configuration.
+//!
+//! `parse` reads Hjson (quoteless strings and keys, optional commas, `#`,
+//! `//` and `/* */` comments, `'''` multi-line strings and an optional pair
+//! of braces around the root object) into a `JSON` value. `to_string`
+//! writes a value back out as idiomatic Hjson that `parse` reads back to the
+//! same value.
+
+use crate::parser::ParseResult;
+
+mod reader;
+mod writer;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::writer::to_string;
+
+pub fn parse(input: &str) -> ParseResult {
+    reader::Reader::new(input).parse()
+}
@@ -0,0 +1,428 @@
+use crate::json::JSON;
+use crate::parser::{ParseError, ParseResult};
+
+use std::collections::HashMap;
+
+// Characters that can never start a quoteless string or appear in a
+// quoteless key.
+const PUNCTUATORS: &str = "{}[],:";
+
+pub struct Reader {
+    chars: Vec<char>,
+    pos: usize,
+    line_no: u64,
+}
+
+impl Reader {
+    pub fn new(input: &str) -> Self {
+        Reader {
+            chars: input.chars().collect(),
+            pos: 0,
+            line_no: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).cloned()
+    }
+
+    fn cont(&mut self) {
+        if self.peek() == Some('\n') {
+            self.line_no += 1;
+        }
+        self.pos += 1;
+    }
+
+    fn looking_at(&self, text: &str) -> bool {
+        text.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    // The 1-based column of the current position within its line.
+    fn column(&self) -> u64 {
+        let pos = self.pos.min(self.chars.len());
+        let line_start = self.chars[..pos]
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |i| i + 1);
+
+        (pos - line_start) as u64 + 1
+    }
+
+    fn throw<T>(&self, err_msg: String) -> Result<T, ParseError> {
+        Err(ParseError::at(err_msg, self.line_no, self.column()))
+    }
+
+    fn at_comment(&self) -> bool {
+        self.looking_at("#") || self.looking_at("//") || self.looking_at("/*")
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => self.cont(),
+                Some('#') => self.skip_line(),
+                Some('/') if self.peek_at(1) == Some('/') => self.skip_line(),
+                Some('/') if self.peek_at(1) == Some('*') => {
+                    let (line_no, column) = (self.line_no, self.column());
+                    self.pos += 2;
+                    while !self.looking_at("*/") {
+                        if self.peek().is_none() {
+                            return Err(ParseError::at(
+                                "Unterminated block comment".to_string(),
+                                line_no,
+                                column,
+                            ));
+                        }
+                        self.cont();
+                    }
+                    self.pos += 2;
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+            self.cont();
+        }
+    }
+
+    // Like `skip_whitespace`, but stops at the end of the line so a
+    // following comma can be told apart from one on the next line.
+    fn skip_spaces(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '\n' || c == '\r' || !c.is_whitespace() {
+                break;
+            }
+            self.cont();
+        }
+    }
+
+    pub fn parse(&mut self) -> ParseResult {
+        self.skip_whitespace()?;
+
+        let value = match self.peek() {
+            None => return Ok(JSON::JSONObject(HashMap::new())),
+            Some('{') | Some('[') => self.parse_value()?,
+            _ => {
+                // The root may be an object without braces; anything that
+                // doesn't read as one is taken as a single value.
+                let start = (self.pos, self.line_no);
+                match self.parse_members(false) {
+                    Ok(obj) => obj,
+                    Err(err) => {
+                        self.pos = start.0;
+                        self.line_no = start.1;
+                        match self.parse_value() {
+                            Ok(value) => value,
+                            Err(_) => return Err(err),
+                        }
+                    }
+                }
+            }
+        };
+
+        self.skip_whitespace()?;
+        if self.peek().is_some() {
+            return self.throw("Expecting EOF".to_string());
+        }
+
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> ParseResult {
+        self.skip_whitespace()?;
+
+        match self.peek() {
+            Some('{') => {
+                self.cont();
+                self.parse_members(true)
+            }
+            Some('[') => {
+                self.cont();
+                self.parse_array()
+            }
+            Some('\'') if self.looking_at("'''") => self.parse_multiline(),
+            Some('"') | Some('\'') => Ok(JSON::JSONString(self.parse_quoted()?)),
+            Some(c) if PUNCTUATORS.contains(c) => {
+                self.throw(format!("Unexpected '{}' where a value was expected", c))
+            }
+            Some(_) => self.parse_quoteless(),
+            None => self.throw("Unexpected EOF while parsing value".to_string()),
+        }
+    }
+
+    fn parse_members(&mut self, braced: bool) -> ParseResult {
+        let mut obj = HashMap::new();
+
+        loop {
+            self.skip_whitespace()?;
+
+            match self.peek() {
+                Some('}') if braced => {
+                    self.cont();
+                    return Ok(JSON::JSONObject(obj));
+                }
+                None if braced => {
+                    return self.throw("Expecting right brace at end of object".to_string())
+                }
+                None => return Ok(JSON::JSONObject(obj)),
+                _ => (),
+            }
+
+            let key = self.parse_key()?;
+            self.skip_whitespace()?;
+            if self.peek() != Some(':') {
+                return self.throw("Expecting colon after key".to_string());
+            }
+            self.cont();
+
+            let val = self.parse_value()?;
+            obj.insert(key, val);
+
+            self.skip_separator()?;
+        }
+    }
+
+    fn parse_array(&mut self) -> ParseResult {
+        let mut arr = Vec::new();
+
+        loop {
+            self.skip_whitespace()?;
+
+            match self.peek() {
+                Some(']') => {
+                    self.cont();
+                    return Ok(JSON::JSONArray(arr));
+                }
+                None => return self.throw("Expecting right bracket at end of array".to_string()),
+                _ => (),
+            }
+
+            let elem = self.parse_value()?;
+            arr.push(elem);
+
+            self.skip_separator()?;
+        }
+    }
+
+    // Members and elements are separated by a comma, a newline or both.
+    fn skip_separator(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace()?;
+        if self.peek() == Some(',') {
+            self.cont();
+        }
+
+        Ok(())
+    }
+
+    fn parse_key(&mut self) -> Result<String, ParseError> {
+        if let Some('"') | Some('\'') = self.peek() {
+            return self.parse_quoted();
+        }
+
+        let mut key = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || PUNCTUATORS.contains(c) {
+                break;
+            }
+            key.push(c);
+            self.cont();
+        }
+
+        if key.is_empty() {
+            return match self.peek() {
+                Some(c) => self.throw(format!("Unexpected '{}' where a key was expected", c)),
+                None => self.throw("Unexpected EOF while parsing key".to_string()),
+            };
+        }
+
+        Ok(key)
+    }
+
+    fn parse_quoted(&mut self) -> Result<String, ParseError> {
+        let quote = self.peek().unwrap();
+        self.cont(); // eat the opening quote
+
+        let mut val = String::new();
+
+        while let Some(c) = self.peek() {
+            self.cont();
+
+            match c {
+                '\n' | '\r' => {
+                    return self.throw(format!("Invalid character in string: {}", c as u8))
+                }
+                '\\' => self.parse_escape(&mut val)?,
+                _ if c == quote => return Ok(val),
+                _ => val.push(c),
+            }
+        }
+
+        self.throw("Unexpected EOF while parsing string".to_string())
+    }
+
+    fn parse_escape(&mut self, string: &mut String) -> Result<(), ParseError> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return self.throw("Unexpected EOF while parsing string".to_string()),
+        };
+        self.cont();
+
+        match c {
+            '\\' | '"' | '\'' | '/' => string.push(c),
+            'b' => string.push('\x08'),
+            'f' => string.push('\x0c'),
+            'n' => string.push('\n'),
+            'r' => string.push('\r'),
+            't' => string.push('\t'),
+            'u' => {
+                let mut code_pt = self.parse_hex()?;
+
+                // A high surrogate must be followed by an escaped low one.
+                if (0xd800..0xdc00).contains(&code_pt) && self.looking_at("\\u") {
+                    self.pos += 2;
+                    let low = self.parse_hex()?;
+                    if (0xdc00..0xe000).contains(&low) {
+                        code_pt = 0x10000 + ((code_pt - 0xd800) << 10) + (low - 0xdc00);
+                    }
+                }
+
+                match std::char::from_u32(code_pt) {
+                    Some(x) => string.push(x),
+                    None => return self.throw("Invalid escape character".to_string()),
+                }
+            }
+            _ => return self.throw("Invalid escape character".to_string()),
+        }
+
+        Ok(())
+    }
+
+    fn parse_hex(&mut self) -> Result<u32, ParseError> {
+        let mut code_pt = 0;
+
+        for _ in 0..4 {
+            match self.peek().and_then(|c| c.to_digit(16)) {
+                Some(digit) => code_pt = code_pt * 16 + digit,
+                None => return self.throw("Invalid escape character".to_string()),
+            }
+            self.cont();
+        }
+
+        Ok(code_pt)
+    }
+
+    // The body of a `'''` string has the indentation of the opening quotes
+    // stripped from every line. A line break straight after the opening
+    // quotes and the one before the closing quotes are not part of the
+    // string.
+    fn parse_multiline(&mut self) -> ParseResult {
+        let line_start = self.chars[..self.pos]
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |i| i + 1);
+        let indent = self.pos - line_start;
+
+        self.pos += 3;
+        self.skip_spaces();
+        if self.peek() == Some('\r') {
+            self.cont();
+        }
+        if self.peek() == Some('\n') {
+            self.cont();
+            self.skip_indent(indent);
+        }
+
+        let mut val = String::new();
+
+        loop {
+            match self.peek() {
+                None => return self.throw("Unexpected EOF while parsing string".to_string()),
+                Some('\'') if self.looking_at("'''") => {
+                    self.pos += 3;
+                    break;
+                }
+                Some('\r') => self.cont(),
+                Some('\n') => {
+                    val.push('\n');
+                    self.cont();
+                    self.skip_indent(indent);
+                }
+                Some(c) => {
+                    val.push(c);
+                    self.cont();
+                }
+            }
+        }
+
+        if val.ends_with('\n') {
+            val.pop();
+        }
+
+        Ok(JSON::JSONString(val))
+    }
+
+    fn skip_indent(&mut self, indent: usize) {
+        for _ in 0..indent {
+            match self.peek() {
+                Some(c) if c != '\n' && c.is_whitespace() => self.cont(),
+                _ => break,
+            }
+        }
+    }
+
+    // Numbers and `true`/`false`/`null` are only recognised when nothing
+    // but a separator or comment follows them on the line. Anything else
+    // is a quoteless string that runs to the end of the line.
+    fn parse_quoteless(&mut self) -> ParseResult {
+        let start = self.pos;
+
+        let mut literal = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || ",]}".contains(c) || self.at_comment() {
+                break;
+            }
+            literal.push(c);
+            self.cont();
+        }
+
+        let end = self.pos;
+        self.skip_spaces();
+        let terminated = match self.peek() {
+            None | Some('\n') | Some('\r') | Some(',') | Some(']') | Some('}') => true,
+            _ => self.at_comment(),
+        };
+
+        if terminated {
+            if let Ok(value) = crate::parse(&literal) {
+                if let JSON::JSONNum(_) | JSON::JSONBool(_) | JSON::JSONNull = value {
+                    self.pos = end;
+                    return Ok(value);
+                }
+            }
+        }
+
+        self.pos = start;
+        let mut val = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\n' || c == '\r' {
+                break;
+            }
+            val.push(c);
+            self.cont();
+        }
+
+        Ok(JSON::JSONString(val.trim_end().to_string()))
+    }
+}
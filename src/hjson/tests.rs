@@ -0,0 +1,130 @@
+use crate::hjson::{parse, to_string};
+use crate::json::JSON;
+
+use std::collections::HashMap;
+
+fn object(entries: Vec<(&str, JSON)>) -> JSON {
+    let mut obj = HashMap::new();
+    for (key, val) in entries {
+        obj.insert(key.to_string(), val);
+    }
+
+    JSON::JSONObject(obj)
+}
+
+fn string(x: &str) -> JSON {
+    JSON::JSONString(x.to_string())
+}
+
+#[test]
+fn test_quoteless() {
+    let input = r#"{
+        # a comment
+        name: look ma, no quotes!
+        rate: 1000 // another comment
+        flag: true
+        path: C:\temp\  /* block */
+        "quoted key": 'single'
+    }"#;
+    let actual = parse(input).unwrap();
+    let expected = object(vec![
+        ("name", string("look ma, no quotes!")),
+        ("rate", JSON::JSONNum(1000.0)),
+        ("flag", JSON::JSONBool(true)),
+        ("path", string(r"C:\temp\  /* block */")),
+        ("quoted key", string("single")),
+    ]);
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_literal_followed_by_text() {
+    let actual = parse("a: 3 apples\nb: true story\nc: null, d: 1.5e3").unwrap();
+    let expected = object(vec![
+        ("a", string("3 apples")),
+        ("b", string("true story")),
+        ("c", JSON::JSONNull),
+        ("d", JSON::JSONNum(1500.0)),
+    ]);
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_optional_commas() {
+    let actual = parse("[\n  1\n  2,\n  three\n  [4, 5,]\n]").unwrap();
+    let expected = JSON::JSONArray(vec![
+        JSON::JSONNum(1.0),
+        JSON::JSONNum(2.0),
+        string("three"),
+        JSON::JSONArray(vec![JSON::JSONNum(4.0), JSON::JSONNum(5.0)]),
+    ]);
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_multiline() {
+    let input = "text:\n  '''\n  first\n    indented\n\n  last\n  '''\n";
+    let actual = parse(input).unwrap();
+    let expected = object(vec![("text", string("first\n  indented\n\nlast"))]);
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_root_values() {
+    assert_eq!(parse("").unwrap(), object(vec![]));
+    assert_eq!(parse("  42  ").unwrap(), JSON::JSONNum(42.0));
+    assert_eq!(parse("hello world").unwrap(), string("hello world"));
+}
+
+#[test]
+fn test_errors() {
+    assert!(parse("{ a: 1 ").is_err());
+    assert!(parse("{ a }").is_err());
+    assert!(parse("[ , ]").is_err());
+    assert!(parse("{ a: '''never closed }").is_err());
+    assert!(parse("{ a: 1 /* never closed").is_err());
+
+    let err = parse("{\n  a: 1\n  [\n}").unwrap_err();
+    assert_eq!((err.line_no, err.column), (3, 3));
+}
+
+#[test]
+fn test_write() {
+    let json = object(vec![
+        ("name", string("demo")),
+        ("port", JSON::JSONNum(8080.0)),
+        ("tags", JSON::JSONArray(vec![string("a b"), string("true")])),
+        ("empty", object(vec![])),
+    ]);
+    let actual = to_string(&json);
+    let expected =
+        "{\n  empty: {}\n  name: demo\n  port: 8080\n  tags:\n  [\n    a b\n    \"true\"\n  ]\n}\n";
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_round_trip() {
+    let json = object(vec![
+        ("multi", string("line one\n  line two\n")),
+        ("number-like", string("12")),
+        ("padded", string(" x ")),
+        ("comment-like", string("# not a comment")),
+        ("with, comma", string("a, b")),
+        ("", string("")),
+        (
+            "nested",
+            object(vec![(
+                "list",
+                JSON::JSONArray(vec![JSON::JSONNull, string("x\ny")]),
+            )]),
+        ),
+    ]);
+    let actual = parse(&to_string(&json)).unwrap();
+
+    assert_eq!(actual, json);
+}
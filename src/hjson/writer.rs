@@ -0,0 +1,146 @@
+use super::reader::Reader;
+use crate::json::JSON;
+use crate::serializer::{sorted_entries, write_number, write_string};
+
+const INDENT: &str = "  ";
+
+pub fn to_string(json: &JSON) -> String {
+    let mut out = String::new();
+    write_value(&mut out, json, 0);
+    out.push('\n');
+
+    out
+}
+
+fn write_indent(out: &mut String, level: usize) {
+    for _ in 0..level {
+        out.push_str(INDENT);
+    }
+}
+
+fn is_container(json: &JSON) -> bool {
+    match *json {
+        JSON::JSONArray(ref x) => !x.is_empty(),
+        JSON::JSONObject(ref x) => !x.is_empty(),
+        _ => false,
+    }
+}
+
+// Writes `json` starting at the current position, with any following lines
+// indented to `level`.
+fn write_value(out: &mut String, json: &JSON, level: usize) {
+    match *json {
+        JSON::JSONNum(x) => write_number(out, x),
+        JSON::JSONString(ref x) => write_hjson_string(out, x, level),
+        JSON::JSONNull => out.push_str("null"),
+        JSON::JSONBool(x) => out.push_str(if x { "true" } else { "false" }),
+        JSON::JSONArray(ref x) if x.is_empty() => out.push_str("[]"),
+        JSON::JSONArray(ref x) => {
+            out.push('[');
+            for elem in x {
+                out.push('\n');
+                write_indent(out, level + 1);
+                write_value(out, elem, level + 1);
+            }
+            out.push('\n');
+            write_indent(out, level);
+            out.push(']');
+        }
+        JSON::JSONObject(ref x) if x.is_empty() => out.push_str("{}"),
+        JSON::JSONObject(ref x) => {
+            out.push('{');
+            for (key, val) in sorted_entries(x) {
+                out.push('\n');
+                write_indent(out, level + 1);
+                write_key(out, key);
+                out.push(':');
+
+                // Nested containers and multi-line strings open on their own
+                // line, in the style of the reference implementation.
+                if is_container(val) || is_multiline(val) {
+                    out.push('\n');
+                    write_indent(out, level + 1);
+                } else {
+                    out.push(' ');
+                }
+                write_value(out, val, level + 1);
+            }
+            out.push('\n');
+            write_indent(out, level);
+            out.push('}');
+        }
+    }
+}
+
+fn write_key(out: &mut String, key: &str) {
+    let quoteless = !key.is_empty()
+        && !key.starts_with('#')
+        && !key.starts_with("//")
+        && !key.starts_with("/*")
+        && !key
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || "{}[],:\"'".contains(c));
+
+    if quoteless {
+        out.push_str(key);
+    } else {
+        write_string(out, key);
+    }
+}
+
+fn is_multiline(json: &JSON) -> bool {
+    match *json {
+        JSON::JSONString(ref x) => can_write_multiline(x),
+        _ => false,
+    }
+}
+
+fn can_write_multiline(string: &str) -> bool {
+    string.contains('\n')
+        && !string.contains("'''")
+        && !string.contains('\r')
+        && !string
+            .chars()
+            .any(|c| c != '\n' && c != '\t' && c.is_control())
+}
+
+fn write_hjson_string(out: &mut String, string: &str, level: usize) {
+    if can_write_multiline(string) {
+        out.push_str("'''");
+        for line in string.split('\n') {
+            out.push('\n');
+            if !line.is_empty() {
+                write_indent(out, level);
+                out.push_str(line);
+            }
+        }
+        out.push('\n');
+        write_indent(out, level);
+        out.push_str("'''");
+    } else if reads_back_quoteless(string) {
+        out.push_str(string);
+    } else {
+        write_string(out, string);
+    }
+}
+
+// A string can go without quotes only if the reader would give back exactly
+// the same string, which rules out text that looks like a number, keyword,
+// comment or punctuation, and surrounding whitespace.
+fn reads_back_quoteless(string: &str) -> bool {
+    if string.is_empty()
+        || string.starts_with(|c: char| c.is_whitespace() || "{}[],:\"'".contains(c))
+        || string.ends_with(char::is_whitespace)
+        || string.starts_with('#')
+        || string.starts_with("//")
+        || string.starts_with("/*")
+        || string.chars().any(char::is_control)
+    {
+        return false;
+    }
+
+    match Reader::new(string).parse() {
+        Ok(JSON::JSONString(x)) => x == string,
+        _ => false,
+    }
+}
@@ -0,0 +1,221 @@
+//! Checking documents against [I-JSON](https://www.rfc-editor.org/rfc/rfc7493),
+//! the Internet JSON profile.
+//!
+//! `check` reads strict RFC 8259 JSON, failing with the parser's own error
+//! where the parser would, and then goes over the lexer's tokens to report
+//! every place where the document falls outside I-JSON: duplicate member
+//! names, noncharacters in strings, and numbers that an IEEE 754 double
+//! can't hold exactly or at all. Lone surrogates are already syntax errors.
+//! `Options` adds a requirement on the kind of the top-level value.
+
+use crate::lexer::{Lexer, Token, TokenVal};
+use crate::parser::ParseError;
+use crate::path::{to_pointer, PathElem};
+
+use std::collections::HashSet;
+
+#[cfg(test)]
+mod tests;
+
+/// One way a document breaks I-JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub err_msg: String,
+    pub line_no: u64,
+    /// JSON Pointer to the offending value, or to the member for a problem
+    /// with its name.
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TopLevel {
+    Any,
+    /// An object or an array, as RFC 7493 recommends for new protocols.
+    Container,
+    Object,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub top_level: TopLevel,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            top_level: TopLevel::Any,
+        }
+    }
+}
+
+// RFC 7493 section 2.2: integers outside +/-(2^53 - 1) may not survive
+// other implementations.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+pub fn check(input: &str) -> Result<Vec<Violation>, ParseError> {
+    check_with(input, &Options::default())
+}
+
+pub fn check_with(input: &str, options: &Options) -> Result<Vec<Violation>, ParseError> {
+    // The parser decides what is JSON, so that a document is never valid
+    // for one of `parse` and `check` and not the other.
+    crate::parse(input)?;
+
+    let mut checker = Checker {
+        input,
+        lexer: Lexer::new(input),
+        path: Vec::new(),
+        violations: Vec::new(),
+    };
+
+    let token = checker.next()?;
+    let line_no = token.line_no;
+    let err_msg = match (options.top_level, &token.value) {
+        (TopLevel::Object, TokenVal::LBrace) => None,
+        (TopLevel::Object, _) => Some("Top-level value must be an object"),
+        (TopLevel::Container, TokenVal::LBrace) | (TopLevel::Container, TokenVal::LBrack) => None,
+        (TopLevel::Container, _) => Some("Top-level value must be an object or an array"),
+        (TopLevel::Any, _) => None,
+    };
+    checker.check_value(token)?;
+
+    if let Some(err_msg) = err_msg {
+        checker.violations.insert(
+            0,
+            Violation {
+                err_msg: err_msg.to_string(),
+                line_no,
+                path: String::new(),
+            },
+        );
+    }
+
+    Ok(checker.violations)
+}
+
+// Walks the tokens of a document the parser has already accepted.
+struct Checker<'a> {
+    input: &'a str,
+    lexer: Lexer<'a>,
+    path: Vec<PathElem>,
+    violations: Vec<Violation>,
+}
+
+impl<'a> Checker<'a> {
+    fn next(&mut self) -> Result<Token, ParseError> {
+        self.lexer
+            .next_token()
+            .map_err(|err| ParseError::at(err.err_msg, err.line_no, err.column))
+    }
+
+    fn report(&mut self, err_msg: String, line_no: u64) {
+        self.violations.push(Violation {
+            err_msg,
+            line_no,
+            path: to_pointer(&self.path),
+        });
+    }
+
+    fn check_value(&mut self, token: Token) -> Result<(), ParseError> {
+        match token.value {
+            TokenVal::LBrace => self.check_object(),
+            TokenVal::LBrack => self.check_array(),
+            TokenVal::JString(ref x) => {
+                self.check_string(x, token.line_no);
+                Ok(())
+            }
+            TokenVal::JNumber(x) => {
+                self.check_number(&self.input[token.start..token.end], x, token.line_no);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_object(&mut self) -> Result<(), ParseError> {
+        let mut keys = HashSet::new();
+
+        loop {
+            let token = self.next()?;
+            let key = match token.value {
+                TokenVal::JString(x) => x,
+                _ => return Ok(()), // the right brace
+            };
+
+            self.path.push(PathElem::Key(key.clone()));
+            self.check_string(&key, token.line_no);
+            if !keys.insert(key.clone()) {
+                self.report(format!("Duplicate key `{}`", key), token.line_no);
+            }
+            self.next()?; // the colon
+            let token = self.next()?;
+            self.check_value(token)?;
+            self.path.pop();
+
+            if self.next()?.value == TokenVal::RBrace {
+                return Ok(());
+            }
+        }
+    }
+
+    fn check_array(&mut self) -> Result<(), ParseError> {
+        let mut token = self.next()?;
+        if token.value == TokenVal::RBrack {
+            return Ok(());
+        }
+
+        let mut index = 0;
+        loop {
+            self.path.push(PathElem::Index(index));
+            self.check_value(token)?;
+            self.path.pop();
+            index += 1;
+
+            if self.next()?.value == TokenVal::RBrack {
+                return Ok(());
+            }
+            token = self.next()?;
+        }
+    }
+
+    fn check_string(&mut self, string: &str, line_no: u64) {
+        for c in string.chars() {
+            let code_pt = c as u32;
+            if (0xfdd0..=0xfdef).contains(&code_pt) || code_pt & 0xfffe == 0xfffe {
+                self.report(format!("Noncharacter U+{:04X} in string", code_pt), line_no);
+            }
+        }
+    }
+
+    fn check_number(&mut self, literal: &str, val: f64, line_no: u64) {
+        let integer = !literal.contains(['.', 'e', 'E']);
+
+        if val.is_infinite() || (val == 0.0 && significant_digits(literal) != "0") {
+            self.report(format!("Number `{}` is out of range", literal), line_no);
+        } else if significant_digits(literal) != significant_digits(&format!("{:e}", val)) {
+            self.report(
+                format!("Number `{}` has more precision than a double", literal),
+                line_no,
+            );
+        } else if integer && val.abs() > MAX_SAFE_INTEGER {
+            self.report(
+                format!("Integer `{}` is outside the interoperable range", literal),
+                line_no,
+            );
+        }
+    }
+}
+
+// The digits of a number's mantissa without leading or trailing zeros, so
+// that `0.50`, `5e-1` and `500e-3` all give `5`. Zero gives `0`.
+fn significant_digits(literal: &str) -> String {
+    let mantissa = literal.split(['e', 'E']).next().unwrap();
+    let digits: String = mantissa.chars().filter(|c| c.is_ascii_digit()).collect();
+    let digits = digits.trim_start_matches('0').trim_end_matches('0');
+
+    if digits.is_empty() {
+        "0".to_string()
+    } else {
+        digits.to_string()
+    }
+}
@@ -0,0 +1,121 @@
+use crate::ijson::{check, check_with, Options, TopLevel};
+use crate::parser::ParseError;
+
+fn violations(input: &str) -> Vec<(u64, String, String)> {
+    check(input)
+        .unwrap()
+        .into_iter()
+        .map(|x| (x.line_no, x.path, x.err_msg))
+        .collect()
+}
+
+fn violation(line_no: u64, path: &str, err_msg: &str) -> (u64, String, String) {
+    (line_no, path.to_string(), err_msg.to_string())
+}
+
+#[test]
+fn test_conforming() {
+    let input =
+        r#"{"a": [1, -0.5, 1e300, 9007199254740991], "b": "\ud83d\ude00 \u00e9", "c": null}"#;
+
+    assert!(violations(input).is_empty());
+    assert!(violations("0.1").is_empty());
+    assert!(violations("5e-324").is_empty());
+}
+
+#[test]
+fn test_duplicate_keys() {
+    let input = "{\"a\": 1,\n \"b\": {\"x\": 1, \"x\": 2},\n \"\\u0061\": 3}";
+
+    assert_eq!(
+        violations(input),
+        vec![
+            violation(2, "/b/x", "Duplicate key `x`"),
+            violation(3, "/a", "Duplicate key `a`"),
+        ]
+    );
+}
+
+#[test]
+fn test_strings() {
+    // Rejected by the parser, so not a violation but a syntax error.
+    assert_eq!(
+        check(r#"["\ud800"]"#).unwrap_err().err_msg,
+        "Lone surrogate in string"
+    );
+    assert_eq!(
+        violations("{\"\\ufdd0\": \"\u{fffe}\", \"k\": \"\\udbff\\udfff\"}"),
+        vec![
+            violation(1, "/\u{fdd0}", "Noncharacter U+FDD0 in string"),
+            violation(1, "/\u{fdd0}", "Noncharacter U+FFFE in string"),
+            violation(1, "/k", "Noncharacter U+10FFFF in string"),
+        ]
+    );
+}
+
+#[test]
+fn test_numbers() {
+    assert_eq!(
+        violations("[1e400, -1e400, 1e-400, 0e-400, 0.30000000000000001, 9007199254740993, 9007199254740992, 9007199254740992.0]"),
+        vec![
+            violation(1, "/0", "Number `1e400` is out of range"),
+            violation(1, "/1", "Number `-1e400` is out of range"),
+            violation(1, "/2", "Number `1e-400` is out of range"),
+            violation(1, "/4", "Number `0.30000000000000001` has more precision than a double"),
+            violation(1, "/5", "Number `9007199254740993` has more precision than a double"),
+            violation(1, "/6", "Integer `9007199254740992` is outside the interoperable range"),
+        ]
+    );
+}
+
+#[test]
+fn test_top_level() {
+    let object = Options {
+        top_level: TopLevel::Object,
+    };
+    let container = Options {
+        top_level: TopLevel::Container,
+    };
+    let top_level = |input: &str, options: &Options| {
+        check_with(input, options)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.err_msg)
+            .collect::<Vec<String>>()
+    };
+
+    assert!(top_level("{}", &object).is_empty());
+    assert_eq!(
+        top_level("[]", &object),
+        vec!["Top-level value must be an object"]
+    );
+    assert!(top_level(" []", &container).is_empty());
+    assert_eq!(
+        top_level("\"\\ufdd0\"", &container),
+        vec![
+            "Top-level value must be an object or an array",
+            "Noncharacter U+FDD0 in string"
+        ]
+    );
+}
+
+#[test]
+fn test_syntax_errors() {
+    // The same errors, columns included, as the parser gives.
+    for input in ["{\"a\": 1,}", "[1\n 2]", "01", "\"\\x\"", "", "[1, 01]"] {
+        assert_eq!(
+            check(input),
+            Err(crate::parse(input).unwrap_err()),
+            "{}",
+            input
+        );
+    }
+    assert_eq!(
+        check("[1\n 2]"),
+        Err(ParseError::at(
+            "Expecting right bracket at end of array".to_string(),
+            2,
+            2
+        ))
+    );
+}
@@ -0,0 +1,399 @@
+//! Inferring a schema from sample documents.
+//!
+//! A `Shape` summarizes every value seen at one place in a set of samples:
+//! how often each type occurred, the range of the numbers, the format and
+//! distinct values of the strings, and the shapes of array items and object
+//! fields. Fold samples in with `Shape::add` (or `infer` for a whole set,
+//! such as the records of an NDJSON file) and export the result with
+//! `Shape::to_json_schema`.
+
+use crate::json::JSON;
+use crate::jtd::is_timestamp;
+use crate::path::{to_normalized_path, PathElem};
+
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(test)]
+mod tests;
+
+/// Strings stop being enum candidates once this many distinct values are seen.
+const MAX_ENUM_VALUES: usize = 10;
+
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Shape {
+    /// Number of values seen here, `null` included.
+    pub count: usize,
+    pub nulls: usize,
+    pub booleans: usize,
+    pub numbers: Option<Numbers>,
+    pub strings: Option<Strings>,
+    pub arrays: Option<Arrays>,
+    pub objects: Option<Objects>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Numbers {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    /// True if every number seen was a whole number.
+    pub integers: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strings {
+    pub count: usize,
+    /// The format every string seen matched, if any.
+    pub format: Option<Format>,
+    /// Occurrences of each distinct string, until there are too many to be
+    /// an enum.
+    pub values: Option<BTreeMap<String, usize>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arrays {
+    pub count: usize,
+    pub min_len: usize,
+    pub max_len: usize,
+    /// The items of every array seen, merged.
+    pub items: Box<Shape>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Objects {
+    pub count: usize,
+    pub fields: BTreeMap<String, Shape>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    DateTime,
+    Email,
+    Uuid,
+}
+
+pub fn infer<'a, I>(samples: I) -> Shape
+where
+    I: IntoIterator<Item = &'a JSON>,
+{
+    let mut shape = Shape::new();
+    for sample in samples {
+        shape.add(sample);
+    }
+
+    shape
+}
+
+impl Shape {
+    pub fn new() -> Self {
+        Shape::default()
+    }
+
+    pub fn add(&mut self, value: &JSON) {
+        self.count += 1;
+
+        match *value {
+            JSON::JSONNull => self.nulls += 1,
+            JSON::JSONBool(_) => self.booleans += 1,
+            JSON::JSONNum(x) => match self.numbers {
+                Some(ref mut numbers) => numbers.add(x),
+                None => {
+                    self.numbers = Some(Numbers {
+                        count: 1,
+                        min: x,
+                        max: x,
+                        integers: is_integer(x),
+                    })
+                }
+            },
+            JSON::JSONString(ref x) => self
+                .strings
+                .get_or_insert_with(|| Strings {
+                    count: 0,
+                    format: None,
+                    values: Some(BTreeMap::new()),
+                })
+                .add(x),
+            JSON::JSONArray(ref x) => {
+                let arrays = self.arrays.get_or_insert_with(|| Arrays {
+                    count: 0,
+                    min_len: x.len(),
+                    max_len: x.len(),
+                    items: Box::new(Shape::new()),
+                });
+                arrays.count += 1;
+                arrays.min_len = arrays.min_len.min(x.len());
+                arrays.max_len = arrays.max_len.max(x.len());
+                for item in x {
+                    arrays.items.add(item);
+                }
+            }
+            JSON::JSONObject(ref x) => {
+                let objects = self.objects.get_or_insert_with(|| Objects {
+                    count: 0,
+                    fields: BTreeMap::new(),
+                });
+                objects.count += 1;
+                for (key, value) in x {
+                    objects.fields.entry(key.clone()).or_default().add(value);
+                }
+            }
+        }
+    }
+
+    pub fn is_nullable(&self) -> bool {
+        self.nulls > 0
+    }
+
+    /// The JSON Schema names of the types seen, `integer` standing in for
+    /// `number` when every number was whole.
+    pub fn types(&self) -> Vec<&'static str> {
+        let mut types = Vec::new();
+
+        if self.nulls > 0 {
+            types.push("null");
+        }
+        if self.booleans > 0 {
+            types.push("boolean");
+        }
+        if let Some(ref numbers) = self.numbers {
+            types.push(if numbers.integers {
+                "integer"
+            } else {
+                "number"
+            });
+        }
+        if self.strings.is_some() {
+            types.push("string");
+        }
+        if self.arrays.is_some() {
+            types.push("array");
+        }
+        if self.objects.is_some() {
+            types.push("object");
+        }
+
+        types
+    }
+
+    /// Every place below this one that held a value, as a JSONPath
+    /// expression (`$['items'][*]['id']`) with the shape found there.
+    pub fn paths(&self) -> Vec<(String, &Shape)> {
+        let mut paths = Vec::new();
+        self.collect_paths(String::from("$"), &mut paths);
+
+        paths
+    }
+
+    fn collect_paths<'a>(&'a self, path: String, paths: &mut Vec<(String, &'a Shape)>) {
+        paths.push((path.clone(), self));
+
+        if let Some(ref arrays) = self.arrays {
+            if arrays.items.count > 0 {
+                arrays.items.collect_paths(format!("{}[*]", path), paths);
+            }
+        }
+        if let Some(ref objects) = self.objects {
+            for (key, field) in &objects.fields {
+                // The normalized path syntax for the key, minus its `$`.
+                let segment = to_normalized_path(&[PathElem::Key(key.clone())]);
+                field.collect_paths(format!("{}{}", path, &segment[1..]), paths);
+            }
+        }
+    }
+
+    /// The shape as a JSON Schema (draft 2020-12) document.
+    pub fn to_json_schema(&self) -> JSON {
+        let mut schema = self.schema();
+        if let JSON::JSONObject(ref mut obj) = schema {
+            obj.insert(
+                "$schema".to_string(),
+                JSON::JSONString(SCHEMA_DIALECT.to_string()),
+            );
+        }
+
+        schema
+    }
+
+    fn schema(&self) -> JSON {
+        let mut obj = HashMap::new();
+        let types = self.types();
+
+        match types.len() {
+            0 => return JSON::JSONObject(obj),
+            1 => {
+                obj.insert("type".to_string(), JSON::JSONString(types[0].to_string()));
+            }
+            _ => {
+                let types = types
+                    .iter()
+                    .map(|x| JSON::JSONString(x.to_string()))
+                    .collect();
+                obj.insert("type".to_string(), JSON::JSONArray(types));
+            }
+        }
+
+        if let Some(ref numbers) = self.numbers {
+            obj.insert("minimum".to_string(), JSON::JSONNum(numbers.min));
+            obj.insert("maximum".to_string(), JSON::JSONNum(numbers.max));
+        }
+
+        if let Some(ref strings) = self.strings {
+            if let Some(format) = strings.format {
+                obj.insert(
+                    "format".to_string(),
+                    JSON::JSONString(format.name().to_string()),
+                );
+            }
+
+            // An enum would reject the other types, so only strings (and
+            // `null`) qualify.
+            let only_strings = types.iter().all(|x| *x == "string" || *x == "null");
+            if let Some(values) = strings.enum_values().filter(|_| only_strings) {
+                let mut values: Vec<JSON> = values
+                    .into_iter()
+                    .map(|x| JSON::JSONString(x.to_string()))
+                    .collect();
+                if self.is_nullable() {
+                    values.push(JSON::JSONNull);
+                }
+                obj.insert("enum".to_string(), JSON::JSONArray(values));
+            }
+        }
+
+        if let Some(ref arrays) = self.arrays {
+            if arrays.items.count > 0 {
+                obj.insert("items".to_string(), arrays.items.schema());
+            }
+        }
+
+        if let Some(ref objects) = self.objects {
+            let properties = objects
+                .fields
+                .iter()
+                .map(|(key, field)| (key.clone(), field.schema()))
+                .collect();
+            obj.insert("properties".to_string(), JSON::JSONObject(properties));
+
+            let required: Vec<JSON> = objects
+                .required()
+                .into_iter()
+                .map(|x| JSON::JSONString(x.to_string()))
+                .collect();
+            if !required.is_empty() {
+                obj.insert("required".to_string(), JSON::JSONArray(required));
+            }
+        }
+
+        JSON::JSONObject(obj)
+    }
+}
+
+impl Numbers {
+    fn add(&mut self, x: f64) {
+        self.count += 1;
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+        self.integers = self.integers && is_integer(x);
+    }
+}
+
+impl Strings {
+    fn add(&mut self, x: &str) {
+        let format = Format::detect(x);
+        if self.count == 0 {
+            self.format = format;
+        } else if self.format != format {
+            self.format = None;
+        }
+        self.count += 1;
+
+        if let Some(ref mut values) = self.values {
+            *values.entry(x.to_string()).or_insert(0) += 1;
+            if values.len() > MAX_ENUM_VALUES {
+                self.values = None;
+            }
+        }
+    }
+
+    /// The distinct strings seen, if there are few of them and each was
+    /// seen at least twice on average. Formatted strings never qualify.
+    pub fn enum_values(&self) -> Option<Vec<&str>> {
+        let values = self.values.as_ref()?;
+        if self.format.is_some() || self.count < 2 * values.len() {
+            return None;
+        }
+
+        Some(values.keys().map(|x| x.as_str()).collect())
+    }
+}
+
+impl Objects {
+    /// The keys present in every object seen, sorted.
+    pub fn required(&self) -> Vec<&str> {
+        self.fields
+            .iter()
+            .filter(|(_, field)| field.count == self.count)
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+
+    pub fn is_required(&self, key: &str) -> bool {
+        self.fields.get(key).is_some_and(|x| x.count == self.count)
+    }
+}
+
+impl Format {
+    /// The name of the format in JSON Schema's `format` keyword.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Format::DateTime => "date-time",
+            Format::Email => "email",
+            Format::Uuid => "uuid",
+        }
+    }
+
+    fn detect(text: &str) -> Option<Format> {
+        if is_timestamp(text) {
+            Some(Format::DateTime)
+        } else if is_uuid(text) {
+            Some(Format::Uuid)
+        } else if is_email(text) {
+            Some(Format::Email)
+        } else {
+            None
+        }
+    }
+}
+
+fn is_integer(x: f64) -> bool {
+    x.is_finite() && x.fract() == 0.0
+}
+
+// Hex digits in the 8-4-4-4-12 grouping, in either case.
+fn is_uuid(text: &str) -> bool {
+    let bytes = text.as_bytes();
+
+    bytes.len() == 36
+        && bytes.iter().enumerate().all(|(i, &b)| match i {
+            8 | 13 | 18 | 23 => b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        })
+}
+
+// A deliberately loose check: one `@` between a local part and a dotted
+// domain, and no whitespace.
+fn is_email(text: &str) -> bool {
+    let (local, domain) = match text.split_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+
+    !local.is_empty()
+        && !domain.contains('@')
+        && !text.chars().any(char::is_whitespace)
+        && domain.split('.').count() > 1
+        && domain.split('.').all(|x| !x.is_empty())
+}
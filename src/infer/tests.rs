@@ -0,0 +1,179 @@
+use crate::infer::{infer, Format, Shape};
+use crate::json::JSON;
+use crate::schema::Schema;
+
+fn shape(samples: &[&str]) -> Shape {
+    let samples: Vec<JSON> = samples.iter().map(|x| crate::parse(x).unwrap()).collect();
+    infer(&samples)
+}
+
+fn field<'a>(shape: &'a Shape, key: &str) -> &'a Shape {
+    &shape.objects.as_ref().unwrap().fields[key]
+}
+
+#[test]
+fn test_types() {
+    let s = shape(&["1", "2.5", "null"]);
+    assert_eq!(s.count, 3);
+    assert_eq!(s.types(), vec!["null", "number"]);
+    assert!(s.is_nullable());
+
+    let s = shape(&["1", "-4", "true"]);
+    assert_eq!(s.types(), vec!["boolean", "integer"]);
+    assert!(!s.is_nullable());
+
+    assert!(shape(&[]).types().is_empty());
+}
+
+#[test]
+fn test_numbers() {
+    let s = shape(&["3", "-1", "12"]);
+    let numbers = s.numbers.unwrap();
+    assert_eq!((numbers.min, numbers.max), (-1.0, 12.0));
+    assert!(numbers.integers);
+
+    let s = shape(&["3", "0.5"]);
+    assert!(!s.numbers.unwrap().integers);
+}
+
+#[test]
+fn test_required_and_optional() {
+    let s = shape(&[
+        r#"{"id": 1, "name": "a", "tag": null}"#,
+        r#"{"id": 2, "tag": "x"}"#,
+    ]);
+    let objects = s.objects.as_ref().unwrap();
+    assert_eq!(objects.count, 2);
+    assert_eq!(objects.required(), vec!["id", "tag"]);
+    assert!(!objects.is_required("name"));
+    assert!(!objects.is_required("missing"));
+    assert!(field(&s, "tag").is_nullable());
+    assert_eq!(field(&s, "name").count, 1);
+}
+
+#[test]
+fn test_arrays() {
+    let s = shape(&["[1, 2]", "[]", r#"["a"]"#]);
+    let arrays = s.arrays.unwrap();
+    assert_eq!((arrays.count, arrays.min_len, arrays.max_len), (3, 0, 2));
+    assert_eq!(arrays.items.types(), vec!["integer", "string"]);
+}
+
+#[test]
+fn test_formats() {
+    let format = |samples: &[&str]| shape(samples).strings.unwrap().format;
+
+    assert_eq!(
+        format(&[
+            r#""2024-01-31T12:00:00Z""#,
+            r#""1990-06-15T08:30:00.5+02:00""#
+        ]),
+        Some(Format::DateTime)
+    );
+    assert_eq!(format(&[r#""a.b@example.com""#]), Some(Format::Email));
+    assert_eq!(
+        format(&[r#""123e4567-E89B-12d3-a456-426614174000""#]),
+        Some(Format::Uuid)
+    );
+    assert_eq!(format(&[r#""a@example.com""#, r#""b""#]), None);
+    assert_eq!(format(&[r#""b""#, r#""a@example.com""#]), None);
+    assert_eq!(format(&[r#""a@b""#]), None);
+    assert_eq!(format(&[r#""a b@example.com""#]), None);
+    assert_eq!(format(&[r#""2024-13-01T00:00:00Z""#]), None);
+}
+
+#[test]
+fn test_enum_values() {
+    let s = shape(&[r#""red""#, r#""blue""#, r#""red""#, r#""blue""#]);
+    assert_eq!(s.strings.unwrap().enum_values(), Some(vec!["blue", "red"]));
+
+    // Every value seen once is not evidence of a closed set.
+    let s = shape(&[r#""red""#, r#""blue""#]);
+    assert_eq!(s.strings.unwrap().enum_values(), None);
+
+    let samples: Vec<String> = (0..30).map(|i| format!("\"v{}\"", i % 15)).collect();
+    let samples: Vec<&str> = samples.iter().map(|x| x.as_str()).collect();
+    let strings = shape(&samples).strings.unwrap();
+    assert_eq!(strings.values, None);
+    assert_eq!(strings.enum_values(), None);
+}
+
+#[test]
+fn test_paths() {
+    let s = shape(&[r#"{"items": [{"id": 1}], "display name": "x"}"#]);
+    let paths: Vec<(String, Vec<&str>)> = s
+        .paths()
+        .into_iter()
+        .map(|(path, shape)| (path, shape.types()))
+        .collect();
+
+    assert_eq!(
+        paths,
+        vec![
+            ("$".to_string(), vec!["object"]),
+            ("$['display name']".to_string(), vec!["string"]),
+            ("$['items']".to_string(), vec!["array"]),
+            ("$['items'][*]".to_string(), vec!["object"]),
+            ("$['items'][*]['id']".to_string(), vec!["integer"]),
+        ]
+    );
+}
+
+#[test]
+fn test_to_json_schema() {
+    let s = shape(&[
+        r#"{"id": 1, "status": "open", "tags": ["a"], "note": null}"#,
+        r#"{"id": 7, "status": "closed", "tags": [], "note": "x"}"#,
+        r#"{"id": 3, "status": "open", "tags": []}"#,
+        r#"{"id": 4, "status": "closed", "tags": []}"#,
+    ]);
+    let expected = crate::parse(
+        r#"{
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer", "minimum": 1, "maximum": 7},
+                "status": {"type": "string", "enum": ["closed", "open"]},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "note": {"type": ["null", "string"]}
+            },
+            "required": ["id", "status", "tags"]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(s.to_json_schema(), expected);
+}
+
+#[test]
+fn test_to_json_schema_nullable_enum() {
+    let s = shape(&[r#""a""#, r#""a""#, "null", r#""b""#, r#""b""#]);
+    let expected = crate::parse(
+        r#"{
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": ["null", "string"],
+            "enum": ["a", "b", null]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(s.to_json_schema(), expected);
+}
+
+#[test]
+fn test_samples_validate_against_schema() {
+    let samples = [
+        r#"{"at": "2024-01-31T12:00:00Z", "n": 1.5, "kind": "x", "list": [1, "a"]}"#,
+        r#"{"at": "2024-02-01T00:00:00Z", "n": -2, "kind": "x", "extra": {}}"#,
+        r#"{"at": null, "n": 0, "kind": "y", "list": []}"#,
+        r#"{"n": 9, "kind": "y"}"#,
+    ];
+    let s = shape(&samples);
+    let schema = Schema::compile(&s.to_json_schema()).unwrap();
+
+    for sample in &samples {
+        assert!(schema.is_valid(&crate::parse(sample).unwrap()));
+    }
+    assert!(!schema.is_valid(&crate::parse(r#"{"n": 10, "kind": "y"}"#).unwrap()));
+    assert!(!schema.is_valid(&crate::parse(r#"{"n": 1, "kind": "z"}"#).unwrap()));
+}
@@ -0,0 +1,490 @@
+use super::parser::{Ast, Comparator};
+use super::{ErrorKind, JmesError};
+use crate::json::JSON;
+use crate::serializer;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+type SearchResult = Result<JSON, JmesError>;
+
+fn type_error<T>(err_msg: String) -> Result<T, JmesError> {
+    Err(JmesError::new(ErrorKind::InvalidType, err_msg))
+}
+
+pub fn truthy(json: &JSON) -> bool {
+    match *json {
+        JSON::JSONNull | JSON::JSONBool(false) => false,
+        JSON::JSONString(ref x) => !x.is_empty(),
+        JSON::JSONArray(ref x) => !x.is_empty(),
+        JSON::JSONObject(ref x) => !x.is_empty(),
+        _ => true,
+    }
+}
+
+fn type_name(json: &JSON) -> &'static str {
+    match *json {
+        JSON::JSONNum(_) => "number",
+        JSON::JSONString(_) => "string",
+        JSON::JSONNull => "null",
+        JSON::JSONBool(_) => "boolean",
+        JSON::JSONArray(_) => "array",
+        JSON::JSONObject(_) => "object",
+    }
+}
+
+fn resolve_index(idx: i64, len: usize) -> Option<usize> {
+    let idx = if idx < 0 { idx + len as i64 } else { idx };
+
+    if idx >= 0 && (idx as usize) < len {
+        Some(idx as usize)
+    } else {
+        None
+    }
+}
+
+fn slice(arr: &[JSON], start: Option<i64>, stop: Option<i64>, step: Option<i64>) -> Vec<JSON> {
+    let len = arr.len() as i64;
+    let step = step.unwrap_or(1);
+
+    let clamp = |x: i64| -> i64 {
+        if x < 0 {
+            let x = x + len;
+            if x < 0 {
+                if step < 0 {
+                    -1
+                } else {
+                    0
+                }
+            } else {
+                x
+            }
+        } else if x >= len {
+            if step < 0 {
+                len - 1
+            } else {
+                len
+            }
+        } else {
+            x
+        }
+    };
+
+    let (mut i, stop) = if step > 0 {
+        (start.map_or(0, clamp), stop.map_or(len, clamp))
+    } else {
+        (start.map_or(len - 1, clamp), stop.map_or(-1, clamp))
+    };
+
+    let mut result = Vec::new();
+    while (step > 0 && i < stop) || (step < 0 && i > stop) {
+        result.push(arr[i as usize].clone());
+        i += step;
+    }
+
+    result
+}
+
+fn compare(op: Comparator, lhs: &JSON, rhs: &JSON) -> JSON {
+    match op {
+        Comparator::Eq => JSON::JSONBool(lhs == rhs),
+        Comparator::Ne => JSON::JSONBool(lhs != rhs),
+        _ => match (lhs, rhs) {
+            (&JSON::JSONNum(x), &JSON::JSONNum(y)) => JSON::JSONBool(match op {
+                Comparator::Lt => x < y,
+                Comparator::Le => x <= y,
+                Comparator::Gt => x > y,
+                _ => x >= y,
+            }),
+            _ => JSON::JSONNull,
+        },
+    }
+}
+
+pub struct Interpreter;
+
+impl Interpreter {
+    pub fn search(&self, ast: &Ast, input: &JSON) -> SearchResult {
+        match *ast {
+            Ast::Identity => Ok(input.clone()),
+            Ast::Field(ref name) => match *input {
+                JSON::JSONObject(ref obj) => Ok(obj.get(name).cloned().unwrap_or(JSON::JSONNull)),
+                _ => Ok(JSON::JSONNull),
+            },
+            Ast::Subexpr(ref lhs, ref rhs) => {
+                let base = self.search(lhs, input)?;
+                self.search(rhs, &base)
+            }
+            Ast::Index(idx) => match *input {
+                JSON::JSONArray(ref arr) => {
+                    Ok(resolve_index(idx, arr.len()).map_or(JSON::JSONNull, |i| arr[i].clone()))
+                }
+                _ => Ok(JSON::JSONNull),
+            },
+            Ast::Slice(start, stop, step) => match *input {
+                JSON::JSONArray(ref arr) => Ok(JSON::JSONArray(slice(arr, start, stop, step))),
+                _ => Ok(JSON::JSONNull),
+            },
+            Ast::Projection(ref lhs, ref rhs) => match self.search(lhs, input)? {
+                JSON::JSONArray(arr) => self.project(arr, rhs),
+                _ => Ok(JSON::JSONNull),
+            },
+            Ast::ValueProjection(ref lhs, ref rhs) => match self.search(lhs, input)? {
+                JSON::JSONObject(obj) => {
+                    let mut entries: Vec<(String, JSON)> = obj.into_iter().collect();
+                    entries.sort_by(|a, b| a.0.cmp(&b.0));
+                    self.project(entries.into_iter().map(|(_, v)| v).collect(), rhs)
+                }
+                _ => Ok(JSON::JSONNull),
+            },
+            Ast::FilterProjection(ref lhs, ref rhs, ref cond) => match self.search(lhs, input)? {
+                JSON::JSONArray(arr) => {
+                    let mut kept = Vec::new();
+                    for elem in arr {
+                        if truthy(&self.search(cond, &elem)?) {
+                            kept.push(elem);
+                        }
+                    }
+                    self.project(kept, rhs)
+                }
+                _ => Ok(JSON::JSONNull),
+            },
+            Ast::Flatten(ref inner) => match self.search(inner, input)? {
+                JSON::JSONArray(arr) => {
+                    let mut flat = Vec::new();
+                    for elem in arr {
+                        match elem {
+                            JSON::JSONArray(inner) => flat.extend(inner),
+                            _ => flat.push(elem),
+                        }
+                    }
+                    Ok(JSON::JSONArray(flat))
+                }
+                _ => Ok(JSON::JSONNull),
+            },
+            Ast::Literal(ref x) => Ok(x.clone()),
+            Ast::MultiList(ref items) => {
+                if *input == JSON::JSONNull {
+                    return Ok(JSON::JSONNull);
+                }
+                let mut result = Vec::new();
+                for item in items {
+                    result.push(self.search(item, input)?);
+                }
+                Ok(JSON::JSONArray(result))
+            }
+            Ast::MultiHash(ref entries) => {
+                if *input == JSON::JSONNull {
+                    return Ok(JSON::JSONNull);
+                }
+                let mut result = HashMap::new();
+                for (key, value) in entries {
+                    result.insert(key.clone(), self.search(value, input)?);
+                }
+                Ok(JSON::JSONObject(result))
+            }
+            Ast::Or(ref lhs, ref rhs) => {
+                let left = self.search(lhs, input)?;
+                if truthy(&left) {
+                    Ok(left)
+                } else {
+                    self.search(rhs, input)
+                }
+            }
+            Ast::And(ref lhs, ref rhs) => {
+                let left = self.search(lhs, input)?;
+                if truthy(&left) {
+                    self.search(rhs, input)
+                } else {
+                    Ok(left)
+                }
+            }
+            Ast::Not(ref operand) => Ok(JSON::JSONBool(!truthy(&self.search(operand, input)?))),
+            Ast::Compare(op, ref lhs, ref rhs) => {
+                let left = self.search(lhs, input)?;
+                let right = self.search(rhs, input)?;
+                Ok(compare(op, &left, &right))
+            }
+            Ast::Pipe(ref lhs, ref rhs) => {
+                let left = self.search(lhs, input)?;
+                self.search(rhs, &left)
+            }
+            Ast::Function(ref name, ref args) => self.call(name, args, input),
+            Ast::Expref(_) => {
+                type_error("Expression references can only be function arguments".to_string())
+            }
+        }
+    }
+
+    fn project(&self, elems: Vec<JSON>, rhs: &Ast) -> SearchResult {
+        let mut result = Vec::new();
+
+        for elem in elems {
+            let val = self.search(rhs, &elem)?;
+            if val != JSON::JSONNull {
+                result.push(val);
+            }
+        }
+
+        Ok(JSON::JSONArray(result))
+    }
+
+    fn call(&self, name: &str, args: &[Ast], input: &JSON) -> SearchResult {
+        let arity = match name {
+            "abs" | "avg" | "ceil" | "floor" | "keys" | "length" | "max" | "min" | "reverse"
+            | "sort" | "sum" | "to_array" | "to_string" | "to_number" | "type" | "values" => {
+                Some(1)
+            }
+            "contains" | "ends_with" | "join" | "map" | "max_by" | "min_by" | "sort_by"
+            | "starts_with" => Some(2),
+            "merge" | "not_null" => None,
+            _ => {
+                return Err(JmesError::new(
+                    ErrorKind::UnknownFunction,
+                    format!("Unknown function: {}()", name),
+                ))
+            }
+        };
+
+        match arity {
+            Some(n) if n != args.len() => {
+                return Err(JmesError::new(
+                    ErrorKind::InvalidArity,
+                    format!(
+                        "{}() takes {} arguments but {} were given",
+                        name,
+                        n,
+                        args.len()
+                    ),
+                ))
+            }
+            None if args.is_empty() => {
+                return Err(JmesError::new(
+                    ErrorKind::InvalidArity,
+                    format!("{}() takes at least 1 argument", name),
+                ))
+            }
+            _ => (),
+        }
+
+        // Functions taking an expression reference evaluate it per element,
+        // so only the remaining arguments are resolved up front.
+        if let "map" | "max_by" | "min_by" | "sort_by" = name {
+            // `map` takes the expression first, the others take the array first
+            let (expr, arr) = if name == "map" {
+                (&args[0], &args[1])
+            } else {
+                (&args[1], &args[0])
+            };
+            let expr = match *expr {
+                Ast::Expref(ref expr) => expr,
+                _ => return type_error(format!("{}() expects an expression reference", name)),
+            };
+            let arr = match self.search(arr, input)? {
+                JSON::JSONArray(arr) => arr,
+                other => {
+                    return type_error(format!(
+                        "{}() expects an array, got {}",
+                        name,
+                        type_name(&other)
+                    ))
+                }
+            };
+            return self.call_by(name, expr, arr);
+        }
+
+        let mut vals = Vec::new();
+        for arg in args {
+            vals.push(self.search(arg, input)?);
+        }
+
+        functions(name, vals)
+    }
+
+    fn call_by(&self, name: &str, expr: &Ast, arr: Vec<JSON>) -> SearchResult {
+        if name == "map" {
+            let mut result = Vec::new();
+            for elem in &arr {
+                result.push(self.search(expr, elem)?);
+            }
+            return Ok(JSON::JSONArray(result));
+        }
+
+        let mut keyed = Vec::new();
+        for elem in arr {
+            let key = self.search(expr, &elem)?;
+            match key {
+                JSON::JSONNum(_) | JSON::JSONString(_) => keyed.push((key, elem)),
+                _ => {
+                    return type_error(format!(
+                        "{}() expression must return a number or string, got {}",
+                        name,
+                        type_name(&key)
+                    ))
+                }
+            }
+        }
+
+        if let Some(first) = keyed.first() {
+            let kind = type_name(&first.0);
+            if keyed.iter().any(|pair| type_name(&pair.0) != kind) {
+                return type_error(format!("{}() keys must all have the same type", name));
+            }
+        }
+        keyed.sort_by(|a, b| order(&a.0, &b.0));
+
+        match name {
+            "sort_by" => Ok(JSON::JSONArray(keyed.into_iter().map(|(_, v)| v).collect())),
+            "min_by" => Ok(keyed.into_iter().next().map_or(JSON::JSONNull, |(_, v)| v)),
+            _ => Ok(keyed.pop().map_or(JSON::JSONNull, |(_, v)| v)),
+        }
+    }
+}
+
+fn order(a: &JSON, b: &JSON) -> Ordering {
+    match (a, b) {
+        (&JSON::JSONNum(x), &JSON::JSONNum(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+        (JSON::JSONString(x), JSON::JSONString(y)) => x.cmp(y),
+        _ => Ordering::Equal,
+    }
+}
+
+fn numbers(name: &str, arr: &[JSON]) -> Result<Vec<f64>, JmesError> {
+    arr.iter()
+        .map(|x| match *x {
+            JSON::JSONNum(x) => Ok(x),
+            _ => type_error(format!("{}() expects an array of numbers", name)),
+        })
+        .collect()
+}
+
+fn functions(name: &str, mut vals: Vec<JSON>) -> SearchResult {
+    let arg = vals[0].clone();
+    let invalid = |arg: &JSON| {
+        type_error(format!(
+            "{}() got an invalid argument of type {}",
+            name,
+            type_name(arg)
+        ))
+    };
+
+    match (name, &arg) {
+        ("abs", &JSON::JSONNum(x)) => Ok(JSON::JSONNum(x.abs())),
+        ("ceil", &JSON::JSONNum(x)) => Ok(JSON::JSONNum(x.ceil())),
+        ("floor", &JSON::JSONNum(x)) => Ok(JSON::JSONNum(x.floor())),
+        ("avg", JSON::JSONArray(arr)) => {
+            let nums = numbers(name, arr)?;
+            if nums.is_empty() {
+                Ok(JSON::JSONNull)
+            } else {
+                Ok(JSON::JSONNum(nums.iter().sum::<f64>() / nums.len() as f64))
+            }
+        }
+        ("sum", JSON::JSONArray(arr)) => Ok(JSON::JSONNum(numbers(name, arr)?.iter().sum())),
+        ("contains", JSON::JSONArray(arr)) => Ok(JSON::JSONBool(arr.contains(&vals[1]))),
+        ("contains", JSON::JSONString(s)) => match vals[1] {
+            JSON::JSONString(ref sub) => Ok(JSON::JSONBool(s.contains(sub.as_str()))),
+            _ => Ok(JSON::JSONBool(false)),
+        },
+        ("starts_with", &JSON::JSONString(ref s)) | ("ends_with", &JSON::JSONString(ref s)) => {
+            match vals[1] {
+                JSON::JSONString(ref affix) => Ok(JSON::JSONBool(if name == "starts_with" {
+                    s.starts_with(affix.as_str())
+                } else {
+                    s.ends_with(affix.as_str())
+                })),
+                ref other => invalid(other),
+            }
+        }
+        ("join", JSON::JSONString(sep)) => match vals[1] {
+            JSON::JSONArray(ref arr) => {
+                let mut parts = Vec::new();
+                for elem in arr {
+                    match *elem {
+                        JSON::JSONString(ref x) => parts.push(x.as_str()),
+                        _ => return type_error("join() expects an array of strings".to_string()),
+                    }
+                }
+                Ok(JSON::JSONString(parts.join(sep)))
+            }
+            ref other => invalid(other),
+        },
+        ("keys", JSON::JSONObject(obj)) => {
+            let mut keys: Vec<&String> = obj.keys().collect();
+            keys.sort();
+            Ok(JSON::JSONArray(
+                keys.into_iter()
+                    .map(|k| JSON::JSONString(k.clone()))
+                    .collect(),
+            ))
+        }
+        ("values", JSON::JSONObject(obj)) => Ok(JSON::JSONArray(
+            serializer::sorted_entries(obj)
+                .into_iter()
+                .map(|(_, v)| v.clone())
+                .collect(),
+        )),
+        ("length", JSON::JSONString(s)) => Ok(JSON::JSONNum(s.chars().count() as f64)),
+        ("length", JSON::JSONArray(arr)) => Ok(JSON::JSONNum(arr.len() as f64)),
+        ("length", JSON::JSONObject(obj)) => Ok(JSON::JSONNum(obj.len() as f64)),
+        ("max", &JSON::JSONArray(ref arr)) | ("min", &JSON::JSONArray(ref arr)) => {
+            if arr.is_empty() {
+                return Ok(JSON::JSONNull);
+            }
+            let kind = type_name(&arr[0]);
+            if (kind != "number" && kind != "string") || arr.iter().any(|x| type_name(x) != kind) {
+                return type_error(format!("{}() expects an array of numbers or strings", name));
+            }
+            let mut sorted = arr.clone();
+            sorted.sort_by(order);
+            if name == "min" {
+                Ok(sorted.swap_remove(0))
+            } else {
+                Ok(sorted.pop().unwrap())
+            }
+        }
+        ("merge", _) => {
+            let mut result = HashMap::new();
+            for val in vals {
+                match val {
+                    JSON::JSONObject(obj) => result.extend(obj),
+                    other => return invalid(&other),
+                }
+            }
+            Ok(JSON::JSONObject(result))
+        }
+        ("not_null", _) => Ok(vals
+            .into_iter()
+            .find(|x| *x != JSON::JSONNull)
+            .unwrap_or(JSON::JSONNull)),
+        ("reverse", JSON::JSONString(s)) => Ok(JSON::JSONString(s.chars().rev().collect())),
+        ("reverse", JSON::JSONArray(arr)) => {
+            Ok(JSON::JSONArray(arr.iter().rev().cloned().collect()))
+        }
+        ("sort", JSON::JSONArray(arr)) => {
+            if let Some(first) = arr.first() {
+                let kind = type_name(first);
+                if (kind != "number" && kind != "string")
+                    || arr.iter().any(|x| type_name(x) != kind)
+                {
+                    return type_error("sort() expects an array of numbers or strings".to_string());
+                }
+            }
+            let mut sorted = arr.clone();
+            sorted.sort_by(order);
+            Ok(JSON::JSONArray(sorted))
+        }
+        ("to_array", &JSON::JSONArray(_)) => Ok(arg),
+        ("to_array", _) => Ok(JSON::JSONArray(vec![vals.remove(0)])),
+        ("to_string", &JSON::JSONString(_)) => Ok(arg),
+        ("to_string", _) => Ok(JSON::JSONString(serializer::to_string(&arg))),
+        ("to_number", &JSON::JSONNum(_)) => Ok(arg),
+        ("to_number", JSON::JSONString(s)) => match crate::parse(s) {
+            Ok(JSON::JSONNum(x)) => Ok(JSON::JSONNum(x)),
+            _ => Ok(JSON::JSONNull),
+        },
+        ("to_number", _) => Ok(JSON::JSONNull),
+        ("type", _) => Ok(JSON::JSONString(type_name(&arg).to_string())),
+        _ => invalid(&arg),
+    }
+}
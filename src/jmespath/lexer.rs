@@ -0,0 +1,252 @@
+use super::{ErrorKind, JmesError};
+use crate::json::JSON;
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tok {
+    Eof,
+    Identifier(String),
+    QuotedIdentifier(String),
+    Literal(JSON),
+    Number(i64),
+    Dot,
+    Star,
+    LBrack,
+    RBrack,
+    Flatten,
+    Filter,
+    LBrace,
+    RBrace,
+    LParen,
+    RParen,
+    Comma,
+    Colon,
+    Pipe,
+    Or,
+    And,
+    Not,
+    Expref,
+    Current,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Tok {
+    // Binding powers follow the reference implementation of the spec.
+    pub fn binding_power(&self) -> u8 {
+        match *self {
+            Tok::Pipe => 1,
+            Tok::Or => 2,
+            Tok::And => 3,
+            Tok::Eq | Tok::Ne | Tok::Lt | Tok::Le | Tok::Gt | Tok::Ge => 5,
+            Tok::Flatten => 9,
+            Tok::Star => 20,
+            Tok::Filter => 21,
+            Tok::Dot => 40,
+            Tok::Not => 45,
+            Tok::LBrace => 50,
+            Tok::LBrack => 55,
+            Tok::LParen => 60,
+            _ => 0,
+        }
+    }
+}
+
+pub struct Lexer<'a> {
+    source: &'a str,
+    input: Peekable<CharIndices<'a>>,
+}
+
+fn syntax_error<T>(err_msg: String) -> Result<T, JmesError> {
+    Err(JmesError::new(ErrorKind::Syntax, err_msg))
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Lexer {
+            source,
+            input: source.char_indices().peekable(),
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.input.peek().map(|&(_, c)| c)
+    }
+
+    fn next_if(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.input.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn offset(&mut self) -> usize {
+        self.input.peek().map_or(self.source.len(), |&(i, _)| i)
+    }
+
+    // Scans up to the closing delimiter, keeping escape sequences intact so
+    // that the caller can decode them. Returns the text between the
+    // delimiters.
+    fn delimited(&mut self, delim: char) -> Result<&'a str, JmesError> {
+        let start = self.offset();
+
+        while let Some((i, c)) = self.input.next() {
+            if c == '\\' {
+                self.input.next();
+            } else if c == delim {
+                return Ok(&self.source[start..i]);
+            }
+        }
+
+        syntax_error(format!("Unterminated {} delimited token", delim))
+    }
+
+    fn get_number(&mut self, first: char) -> Result<Tok, JmesError> {
+        let mut val = first.to_string();
+
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() {
+                val.push(c);
+                self.input.next();
+            } else {
+                break;
+            }
+        }
+
+        match val.parse() {
+            Ok(x) => Ok(Tok::Number(x)),
+            Err(_) => syntax_error(format!("Invalid number: {}", val)),
+        }
+    }
+
+    pub fn next_token(&mut self) -> Result<Tok, JmesError> {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.input.next();
+            } else {
+                break;
+            }
+        }
+
+        let c = match self.input.next() {
+            Some((_, c)) => c,
+            None => return Ok(Tok::Eof),
+        };
+
+        let tok = match c {
+            '.' => Tok::Dot,
+            '*' => Tok::Star,
+            ']' => Tok::RBrack,
+            '{' => Tok::LBrace,
+            '}' => Tok::RBrace,
+            '(' => Tok::LParen,
+            ')' => Tok::RParen,
+            ',' => Tok::Comma,
+            ':' => Tok::Colon,
+            '@' => Tok::Current,
+            '[' => {
+                if self.next_if(']') {
+                    Tok::Flatten
+                } else if self.next_if('?') {
+                    Tok::Filter
+                } else {
+                    Tok::LBrack
+                }
+            }
+            '|' => {
+                if self.next_if('|') {
+                    Tok::Or
+                } else {
+                    Tok::Pipe
+                }
+            }
+            '&' => {
+                if self.next_if('&') {
+                    Tok::And
+                } else {
+                    Tok::Expref
+                }
+            }
+            '!' => {
+                if self.next_if('=') {
+                    Tok::Ne
+                } else {
+                    Tok::Not
+                }
+            }
+            '=' => {
+                if self.next_if('=') {
+                    Tok::Eq
+                } else {
+                    return syntax_error("Expecting `==`".to_string());
+                }
+            }
+            '<' => {
+                if self.next_if('=') {
+                    Tok::Le
+                } else {
+                    Tok::Lt
+                }
+            }
+            '>' => {
+                if self.next_if('=') {
+                    Tok::Ge
+                } else {
+                    Tok::Gt
+                }
+            }
+            '\"' => {
+                let text = self.delimited('\"')?;
+                match crate::parse(&format!("\"{}\"", text)) {
+                    Ok(JSON::JSONString(x)) => Tok::QuotedIdentifier(x),
+                    _ => return syntax_error(format!("Invalid quoted identifier: {}", text)),
+                }
+            }
+            '\'' => {
+                let text = self.delimited('\'')?;
+                Tok::Literal(JSON::JSONString(text.replace("\\'", "'")))
+            }
+            '`' => {
+                let text = self.delimited('`')?.replace("\\`", "`");
+                match crate::parse(&text) {
+                    Ok(x) => Tok::Literal(x),
+                    Err(_) => return syntax_error(format!("Invalid JSON literal: {}", text)),
+                }
+            }
+            '-' => match self.input.next() {
+                Some((_, d)) if d.is_ascii_digit() => {
+                    let tok = self.get_number(d)?;
+                    match tok {
+                        Tok::Number(x) => Tok::Number(-x),
+                        _ => tok,
+                    }
+                }
+                _ => return syntax_error("Expecting digit after `-`".to_string()),
+            },
+            _ if c.is_ascii_digit() => self.get_number(c)?,
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(c) = self.peek() {
+                    if c.is_ascii_alphanumeric() || c == '_' {
+                        ident.push(c);
+                        self.input.next();
+                    } else {
+                        break;
+                    }
+                }
+                Tok::Identifier(ident)
+            }
+            _ => return syntax_error(format!("Unexpected character: {}", c)),
+        };
+
+        Ok(tok)
+    }
+}
@@ -0,0 +1,60 @@
+//! [JMESPath](https://jmespath.org/specification.html) expressions over
+//! `JSON` values.
+//!
+//! An expression is compiled once with `compile` and can then be searched
+//! against any number of documents. Every search produces exactly one
+//! value, which is `JSONNull` when nothing matched.
+
+use crate::json::JSON;
+
+mod eval;
+mod lexer;
+mod parser;
+
+#[cfg(test)]
+mod tests;
+
+use self::eval::Interpreter;
+use self::parser::{Ast, Parser};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    Syntax,
+    InvalidType,
+    InvalidValue,
+    InvalidArity,
+    UnknownFunction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JmesError {
+    pub kind: ErrorKind,
+    pub err_msg: String,
+}
+
+impl JmesError {
+    fn new(kind: ErrorKind, err_msg: String) -> Self {
+        JmesError { kind, err_msg }
+    }
+}
+
+pub struct Expression {
+    ast: Ast,
+}
+
+impl Expression {
+    pub fn search(&self, input: &JSON) -> Result<JSON, JmesError> {
+        Interpreter.search(&self.ast, input)
+    }
+}
+
+pub fn compile(expression: &str) -> Result<Expression, JmesError> {
+    let mut parser = Parser::new(expression)?;
+    let ast = parser.parse()?;
+
+    Ok(Expression { ast })
+}
+
+pub fn search(expression: &str, input: &JSON) -> Result<JSON, JmesError> {
+    compile(expression)?.search(input)
+}
@@ -0,0 +1,369 @@
+use super::lexer::{Lexer, Tok};
+use super::{ErrorKind, JmesError};
+use crate::json::JSON;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ast {
+    Identity,
+    Field(String),
+    Subexpr(Box<Ast>, Box<Ast>),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Projection(Box<Ast>, Box<Ast>),
+    ValueProjection(Box<Ast>, Box<Ast>),
+    FilterProjection(Box<Ast>, Box<Ast>, Box<Ast>),
+    Flatten(Box<Ast>),
+    Literal(JSON),
+    MultiList(Vec<Ast>),
+    MultiHash(Vec<(String, Ast)>),
+    Or(Box<Ast>, Box<Ast>),
+    And(Box<Ast>, Box<Ast>),
+    Not(Box<Ast>),
+    Compare(Comparator, Box<Ast>, Box<Ast>),
+    Pipe(Box<Ast>, Box<Ast>),
+    Function(String, Vec<Ast>),
+    Expref(Box<Ast>),
+}
+
+// Tokens that bind more loosely than this end the right-hand side of a
+// projection, so `a[*].b | c` stops projecting at the pipe.
+const PROJECTION_STOP: u8 = 10;
+
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
+    curr_token: Tok,
+    next_token: Tok,
+}
+
+fn syntax_error<T>(err_msg: String) -> Result<T, JmesError> {
+    Err(JmesError::new(ErrorKind::Syntax, err_msg))
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Result<Self, JmesError> {
+        let mut lexer = Lexer::new(input);
+        let curr_token = lexer.next_token()?;
+        let next_token = lexer.next_token()?;
+
+        Ok(Parser {
+            lexer,
+            curr_token,
+            next_token,
+        })
+    }
+
+    fn cont(&mut self) -> Result<Tok, JmesError> {
+        let after = self.lexer.next_token()?;
+        let next = std::mem::replace(&mut self.next_token, after);
+
+        Ok(std::mem::replace(&mut self.curr_token, next))
+    }
+
+    fn expect(&mut self, tok: Tok) -> Result<(), JmesError> {
+        if self.curr_token == tok {
+            self.cont()?;
+            Ok(())
+        } else {
+            syntax_error(format!("Expecting {:?}, found {:?}", tok, self.curr_token))
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<Ast, JmesError> {
+        let ast = self.expr(0)?;
+
+        if self.curr_token != Tok::Eof {
+            return syntax_error(format!("Unexpected token {:?}", self.curr_token));
+        }
+
+        Ok(ast)
+    }
+
+    fn expr(&mut self, binding_power: u8) -> Result<Ast, JmesError> {
+        let token = self.cont()?;
+        let mut left = self.nud(token)?;
+
+        while binding_power < self.curr_token.binding_power() {
+            let token = self.cont()?;
+            left = self.led(token, left)?;
+        }
+
+        Ok(left)
+    }
+
+    fn nud(&mut self, token: Tok) -> Result<Ast, JmesError> {
+        match token {
+            Tok::Literal(x) => Ok(Ast::Literal(x)),
+            Tok::Identifier(x) => Ok(Ast::Field(x)),
+            Tok::QuotedIdentifier(x) => {
+                if self.curr_token == Tok::LParen {
+                    return syntax_error("Quoted identifiers cannot be function names".to_string());
+                }
+                Ok(Ast::Field(x))
+            }
+            Tok::Star => {
+                let rhs = self.projection_rhs(Tok::Star.binding_power())?;
+                Ok(Ast::ValueProjection(Box::new(Ast::Identity), Box::new(rhs)))
+            }
+            Tok::Filter => self.filter(Ast::Identity),
+            Tok::LBrace => self.multi_hash(),
+            Tok::LParen => {
+                let inner = self.expr(0)?;
+                self.expect(Tok::RParen)?;
+                Ok(inner)
+            }
+            Tok::Flatten => {
+                let rhs = self.projection_rhs(Tok::Flatten.binding_power())?;
+                Ok(Ast::Projection(
+                    Box::new(Ast::Flatten(Box::new(Ast::Identity))),
+                    Box::new(rhs),
+                ))
+            }
+            Tok::Not => {
+                let operand = self.expr(Tok::Not.binding_power())?;
+                Ok(Ast::Not(Box::new(operand)))
+            }
+            Tok::LBrack => match self.curr_token {
+                Tok::Number(_) | Tok::Colon => {
+                    let index = self.index_expr()?;
+                    self.project_if_slice(Ast::Identity, index)
+                }
+                Tok::Star if self.next_token == Tok::RBrack => {
+                    self.cont()?;
+                    self.cont()?;
+                    let rhs = self.projection_rhs(Tok::Star.binding_power())?;
+                    Ok(Ast::Projection(Box::new(Ast::Identity), Box::new(rhs)))
+                }
+                _ => self.multi_list(),
+            },
+            Tok::Current => Ok(Ast::Identity),
+            Tok::Expref => {
+                let inner = self.expr(0)?;
+                Ok(Ast::Expref(Box::new(inner)))
+            }
+            Tok::Eof => syntax_error("Unexpected end of expression".to_string()),
+            _ => syntax_error(format!("Unexpected token {:?}", token)),
+        }
+    }
+
+    fn led(&mut self, token: Tok, left: Ast) -> Result<Ast, JmesError> {
+        let binding_power = token.binding_power();
+
+        match token {
+            Tok::Dot => {
+                if self.curr_token == Tok::Star {
+                    self.cont()?;
+                    let rhs = self.projection_rhs(binding_power)?;
+                    Ok(Ast::ValueProjection(Box::new(left), Box::new(rhs)))
+                } else {
+                    let rhs = self.dot_rhs(binding_power)?;
+                    Ok(Ast::Subexpr(Box::new(left), Box::new(rhs)))
+                }
+            }
+            Tok::Pipe => {
+                let rhs = self.expr(binding_power)?;
+                Ok(Ast::Pipe(Box::new(left), Box::new(rhs)))
+            }
+            Tok::Or => {
+                let rhs = self.expr(binding_power)?;
+                Ok(Ast::Or(Box::new(left), Box::new(rhs)))
+            }
+            Tok::And => {
+                let rhs = self.expr(binding_power)?;
+                Ok(Ast::And(Box::new(left), Box::new(rhs)))
+            }
+            Tok::LParen => {
+                let name = match left {
+                    Ast::Field(name) => name,
+                    _ => return syntax_error("Invalid function name".to_string()),
+                };
+
+                let mut args = Vec::new();
+                while self.curr_token != Tok::RParen {
+                    args.push(self.expr(0)?);
+                    if self.curr_token == Tok::Comma {
+                        self.cont()?;
+                    } else if self.curr_token != Tok::RParen {
+                        return syntax_error("Expecting `,` between arguments".to_string());
+                    }
+                }
+                self.expect(Tok::RParen)?;
+
+                Ok(Ast::Function(name, args))
+            }
+            Tok::Filter => self.filter(left),
+            Tok::Flatten => {
+                let rhs = self.projection_rhs(binding_power)?;
+                Ok(Ast::Projection(
+                    Box::new(Ast::Flatten(Box::new(left))),
+                    Box::new(rhs),
+                ))
+            }
+            Tok::LBrack => match self.curr_token {
+                Tok::Number(_) | Tok::Colon => {
+                    let index = self.index_expr()?;
+                    self.project_if_slice(left, index)
+                }
+                _ => {
+                    self.expect(Tok::Star)?;
+                    self.expect(Tok::RBrack)?;
+                    let rhs = self.projection_rhs(Tok::Star.binding_power())?;
+                    Ok(Ast::Projection(Box::new(left), Box::new(rhs)))
+                }
+            },
+            Tok::Eq | Tok::Ne | Tok::Lt | Tok::Le | Tok::Gt | Tok::Ge => {
+                let op = match token {
+                    Tok::Eq => Comparator::Eq,
+                    Tok::Ne => Comparator::Ne,
+                    Tok::Lt => Comparator::Lt,
+                    Tok::Le => Comparator::Le,
+                    Tok::Gt => Comparator::Gt,
+                    _ => Comparator::Ge,
+                };
+                let rhs = self.expr(binding_power)?;
+                Ok(Ast::Compare(op, Box::new(left), Box::new(rhs)))
+            }
+            _ => syntax_error(format!("Unexpected token {:?}", token)),
+        }
+    }
+
+    fn project_if_slice(&mut self, left: Ast, index: Ast) -> Result<Ast, JmesError> {
+        let is_slice = matches!(index, Ast::Slice(..));
+        let indexed = match left {
+            Ast::Identity => index,
+            _ => Ast::Subexpr(Box::new(left), Box::new(index)),
+        };
+
+        if is_slice {
+            let rhs = self.projection_rhs(Tok::Star.binding_power())?;
+            Ok(Ast::Projection(Box::new(indexed), Box::new(rhs)))
+        } else {
+            Ok(indexed)
+        }
+    }
+
+    // Parses `[n]` or `[start:stop:step]`, with the opening bracket already
+    // consumed.
+    fn index_expr(&mut self) -> Result<Ast, JmesError> {
+        let mut parts = [None, None, None];
+        let mut pos = 0;
+
+        loop {
+            match self.cont()? {
+                Tok::Number(x) => {
+                    if parts[pos].is_some() {
+                        return syntax_error("Expecting `:` in slice".to_string());
+                    }
+                    parts[pos] = Some(x);
+                }
+                Tok::Colon if pos < 2 => pos += 1,
+                Tok::RBrack => break,
+                token => return syntax_error(format!("Unexpected token {:?} in index", token)),
+            }
+        }
+
+        if pos == 0 {
+            match parts[0] {
+                Some(x) => Ok(Ast::Index(x)),
+                None => syntax_error("Expecting index".to_string()),
+            }
+        } else {
+            if parts[2] == Some(0) {
+                return Err(JmesError::new(
+                    ErrorKind::InvalidValue,
+                    "Slice step cannot be 0".to_string(),
+                ));
+            }
+            Ok(Ast::Slice(parts[0], parts[1], parts[2]))
+        }
+    }
+
+    fn projection_rhs(&mut self, binding_power: u8) -> Result<Ast, JmesError> {
+        if self.curr_token.binding_power() < PROJECTION_STOP {
+            return Ok(Ast::Identity);
+        }
+
+        match self.curr_token {
+            Tok::LBrack | Tok::Filter => self.expr(binding_power),
+            Tok::Dot => {
+                self.cont()?;
+                self.dot_rhs(binding_power)
+            }
+            _ => syntax_error(format!("Unexpected token {:?}", self.curr_token)),
+        }
+    }
+
+    fn dot_rhs(&mut self, binding_power: u8) -> Result<Ast, JmesError> {
+        match self.curr_token {
+            Tok::Identifier(_) | Tok::QuotedIdentifier(_) | Tok::Star => self.expr(binding_power),
+            Tok::LBrack => {
+                self.cont()?;
+                self.multi_list()
+            }
+            Tok::LBrace => {
+                self.cont()?;
+                self.multi_hash()
+            }
+            _ => syntax_error(format!("Unexpected token {:?} after `.`", self.curr_token)),
+        }
+    }
+
+    fn filter(&mut self, left: Ast) -> Result<Ast, JmesError> {
+        let cond = self.expr(0)?;
+        self.expect(Tok::RBrack)?;
+
+        let rhs = self.projection_rhs(Tok::Filter.binding_power())?;
+
+        Ok(Ast::FilterProjection(
+            Box::new(left),
+            Box::new(rhs),
+            Box::new(cond),
+        ))
+    }
+
+    fn multi_list(&mut self) -> Result<Ast, JmesError> {
+        let mut items = vec![self.expr(0)?];
+
+        while self.curr_token == Tok::Comma {
+            self.cont()?;
+            items.push(self.expr(0)?);
+        }
+        self.expect(Tok::RBrack)?;
+
+        Ok(Ast::MultiList(items))
+    }
+
+    fn multi_hash(&mut self) -> Result<Ast, JmesError> {
+        let mut entries = Vec::new();
+
+        loop {
+            let key = match self.cont()? {
+                Tok::Identifier(x) | Tok::QuotedIdentifier(x) => x,
+                token => {
+                    return syntax_error(format!("Invalid key {:?} in multi-select hash", token))
+                }
+            };
+            self.expect(Tok::Colon)?;
+            let value = self.expr(0)?;
+            entries.push((key, value));
+
+            if self.curr_token == Tok::Comma {
+                self.cont()?;
+            } else {
+                break;
+            }
+        }
+        self.expect(Tok::RBrace)?;
+
+        Ok(Ast::MultiHash(entries))
+    }
+}
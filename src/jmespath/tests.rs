@@ -0,0 +1,146 @@
+use crate::jmespath::{compile, search, ErrorKind};
+use crate::json::JSON;
+
+fn jp(expression: &str, input: &str) -> String {
+    let input = crate::parse(input).unwrap();
+    let result = search(expression, &input).unwrap();
+
+    crate::serializer::to_string(&result)
+}
+
+#[test]
+fn test_subexpressions() {
+    let input = r#"{"a": {"b": {"c": [1, 2, 3]}}, "d-e": 4}"#;
+
+    assert_eq!(jp("a.b.c", input), "[1,2,3]");
+    assert_eq!(jp("a.b.c[-1]", input), "3");
+    assert_eq!(jp("a.x.c", input), "null");
+    assert_eq!(jp(r#""d-e""#, input), "4");
+}
+
+#[test]
+fn test_slices() {
+    let input = "[0, 1, 2, 3, 4, 5]";
+
+    assert_eq!(jp("[1:3]", input), "[1,2]");
+    assert_eq!(jp("[::2]", input), "[0,2,4]");
+    assert_eq!(jp("[::-1]", input), "[5,4,3,2,1,0]");
+    assert_eq!(jp("[-2:]", input), "[4,5]");
+}
+
+#[test]
+fn test_projections() {
+    let input = r#"{"people": [{"name": "a", "age": 1}, {"name": "b"}, {"age": 3}]}"#;
+
+    assert_eq!(jp("people[*].name", input), r#"["a","b"]"#);
+    assert_eq!(jp("people[*].age | [0]", input), "1");
+
+    let input = r#"{"ops": {"x": {"n": 1}, "y": {"n": 2}, "z": {}}}"#;
+    assert_eq!(jp("ops.*.n", input), "[1,2]");
+}
+
+#[test]
+fn test_flatten() {
+    let input =
+        r#"{"reservations": [{"instances": [{"id": 1}, {"id": 2}]}, {"instances": [{"id": 3}]}]}"#;
+
+    assert_eq!(jp("reservations[].instances[].id", input), "[1,2,3]");
+    assert_eq!(jp("[]", "[[1, 2], 3, [4]]"), "[1,2,3,4]");
+}
+
+#[test]
+fn test_filters() {
+    let input = r#"{"machines": [
+        {"name": "a", "state": "running", "cpus": 4},
+        {"name": "b", "state": "stopped", "cpus": 8},
+        {"name": "c", "state": "running", "cpus": 16}
+    ]}"#;
+
+    assert_eq!(
+        jp("machines[?state == 'running'].name", input),
+        r#"["a","c"]"#
+    );
+    assert_eq!(
+        jp("machines[?cpus > `4` && state != 'stopped'].name", input),
+        r#"["c"]"#
+    );
+    assert_eq!(jp("machines[?!(cpus < `8`)] | length(@)", input), "2");
+}
+
+#[test]
+fn test_multiselect() {
+    let input = r#"{"a": 1, "b": {"c": 2}, "items": [{"k": "x", "v": 1}, {"k": "y", "v": 2}]}"#;
+
+    assert_eq!(jp("[a, b.c]", input), "[1,2]");
+    assert_eq!(
+        jp("{first: a, second: b.c}", input),
+        r#"{"first":1,"second":2}"#
+    );
+    assert_eq!(jp("items[*].[k, v]", input), r#"[["x",1],["y",2]]"#);
+    assert_eq!(
+        jp("items[*].{key: k}", input),
+        r#"[{"key":"x"},{"key":"y"}]"#
+    );
+}
+
+#[test]
+fn test_or_and_not() {
+    assert_eq!(jp("a || b", r#"{"a": [], "b": 2}"#), "2");
+    assert_eq!(jp("a && b", r#"{"a": "", "b": 2}"#), r#""""#);
+    assert_eq!(jp("!a", r#"{"a": {}}"#), "true");
+}
+
+#[test]
+fn test_functions() {
+    let input =
+        r#"{"people": [{"name": "b", "age": 30}, {"name": "a", "age": 20}], "tags": ["x", "y"]}"#;
+
+    assert_eq!(jp("sort_by(people, &age)[0].name", input), r#""a""#);
+    assert_eq!(jp("max_by(people, &age).name", input), r#""b""#);
+    assert_eq!(jp("map(&name, people)", input), r#"["b","a"]"#);
+    assert_eq!(jp("join(', ', tags)", input), r#""x, y""#);
+    assert_eq!(jp("sum(people[*].age)", input), "50");
+    assert_eq!(jp("avg(people[*].age)", input), "25");
+    assert_eq!(jp("keys(@)", input), r#"["people","tags"]"#);
+    assert_eq!(jp("length(people)", input), "2");
+    assert_eq!(jp("contains(tags, 'y')", input), "true");
+    assert_eq!(jp("not_null(missing, tags[0])", input), r#""x""#);
+    assert_eq!(
+        jp("merge(`{\"a\": 1}`, `{\"b\": 2}`)", input),
+        r#"{"a":1,"b":2}"#
+    );
+    assert_eq!(jp("to_string(tags)", input), r#""[\"x\",\"y\"]""#);
+    assert_eq!(jp("type(people[0].age)", input), r#""number""#);
+}
+
+#[test]
+fn test_pipes_stop_projections() {
+    let input = r#"{"a": [{"b": 1}, {"b": 2}]}"#;
+
+    assert_eq!(jp("a[*].b | [0]", input), "1");
+    assert_eq!(jp("a[*].b[0]", input), "[]");
+}
+
+#[test]
+fn test_errors() {
+    let input = JSON::JSONNull;
+
+    assert_eq!(compile("a.").err().unwrap().kind, ErrorKind::Syntax);
+    assert_eq!(compile("[1").err().unwrap().kind, ErrorKind::Syntax);
+    assert_eq!(
+        compile("[::0]").err().unwrap().kind,
+        ErrorKind::InvalidValue
+    );
+    assert_eq!(
+        search("nope(@)", &input).unwrap_err().kind,
+        ErrorKind::UnknownFunction
+    );
+    assert_eq!(
+        search("length(@, @)", &input).unwrap_err().kind,
+        ErrorKind::InvalidArity
+    );
+    assert_eq!(
+        search("abs('x')", &input).unwrap_err().kind,
+        ErrorKind::InvalidType
+    );
+}
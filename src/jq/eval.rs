@@ -0,0 +1,1663 @@
+use super::parser::{AssignOp, Ast, BinOp, FuncDef, StrAst};
+use super::JqError;
+use crate::json::JSON;
+use crate::path::MAX_INDEX;
+use crate::serializer;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+type Outputs = Result<Vec<JSON>, JqError>;
+type Paths = Result<Vec<(Vec<JSON>, JSON)>, JqError>;
+
+// Filters hand each output to a sink as soon as it is produced. Both the
+// sink and the evaluator return `Ok(false)` once no more outputs are
+// wanted, so generators such as `range(infinite)` can be cut short.
+type Flow = Result<bool, JqError>;
+type Sink<'s> = dyn FnMut(JSON) -> Flow + 's;
+
+// How much stack a program may use before evaluation fails. Recursion is
+// bounded in bytes rather than calls because the frames between two levels
+// differ in size from one builtin to the next and between builds.
+const MAX_STACK: usize = 1 << 20;
+
+// Builtins that are easier to express in jq itself. They are compiled
+// ahead of every program, so user definitions can shadow them.
+pub const PRELUDE: &str = r#"
+def map(f): [.[] | f];
+def select(f): if f then . else empty end;
+def recurse(f): def r: ., (f | r); r;
+def recurse(f; cond): def r: ., (f | select(cond) | r); r;
+def recurse: recurse(.[]?);
+def values: select(. != null);
+def nulls: select(. == null);
+def booleans: select(type == "boolean");
+def numbers: select(type == "number");
+def strings: select(type == "string");
+def arrays: select(type == "array");
+def objects: select(type == "object");
+def iterables: select(type == "array" or type == "object");
+def scalars: select(type != "array" and type != "object");
+def with_entries(f): to_entries | map(f) | from_entries;
+def any: reduce .[] as $x (false; . or $x);
+def all: reduce .[] as $x (true; . and $x);
+def any(f): reduce (.[] | f) as $x (false; . or $x);
+def all(f): reduce (.[] | f) as $x (true; . and $x);
+def in(xs): . as $x | xs | has($x);
+def inside(xs): . as $x | xs | contains($x);
+def first: .[0];
+def last: .[-1];
+def nth($n): .[$n];
+def paths(node_filter): . as $dot | paths | select(. as $p | $dot | getpath($p) | node_filter);
+def leaf_paths: paths(scalars);
+def walk(f): def w: if type == "object" then map_values(w) elif type == "array" then map(w) else . end | f; w;
+def until(cond; update): def _until: if cond then . else (update | _until) end; _until;
+def while(cond; update): def _while: if cond then ., (update | _while) else empty end; _while;
+def isempty(g): first((g | false), true);
+def ascii: [.] | implode;
+def repeat(f): def _repeat: ., (f | _repeat); _repeat;
+"#;
+
+enum Env<'a> {
+    Root,
+    Var(&'a str, JSON, Rc<Env<'a>>),
+    Func(&'a FuncDef, Rc<Env<'a>>),
+    Closure(&'a str, &'a Ast, Rc<Env<'a>>, Rc<Env<'a>>),
+}
+
+enum Callable<'a> {
+    Func(&'a FuncDef, Rc<Env<'a>>),
+    Closure(&'a Ast, Rc<Env<'a>>),
+    Native,
+}
+
+// Strings built by `*` are capped so that a filter can't exhaust memory.
+const MAX_STRING: usize = 1 << 28;
+
+fn error<T>(err_msg: String) -> Result<T, JqError> {
+    Err(JqError::new(err_msg))
+}
+
+fn parent<'a, 'e>(env: &'e Env<'a>) -> Option<&'e Rc<Env<'a>>> {
+    match *env {
+        Env::Root => None,
+        Env::Var(_, _, ref parent) => Some(parent),
+        Env::Func(_, ref parent) => Some(parent),
+        Env::Closure(_, _, _, ref parent) => Some(parent),
+    }
+}
+
+fn lookup_var<'a>(env: &Rc<Env<'a>>, name: &str) -> Result<JSON, JqError> {
+    let mut curr = env;
+
+    loop {
+        if let Env::Var(var, ref val, _) = **curr {
+            if var == name {
+                return Ok(val.clone());
+            }
+        }
+
+        match parent(curr) {
+            Some(p) => curr = p,
+            None => return error(format!("${} is not defined", name)),
+        }
+    }
+}
+
+fn lookup_func<'a>(env: &Rc<Env<'a>>, name: &str, arity: usize) -> Callable<'a> {
+    let mut curr = env;
+
+    loop {
+        match **curr {
+            Env::Func(def, _) if def.name == name && def.params.len() == arity => {
+                return Callable::Func(def, curr.clone());
+            }
+            Env::Closure(param, ast, ref closure_env, _) if param == name && arity == 0 => {
+                return Callable::Closure(ast, closure_env.clone());
+            }
+            _ => (),
+        }
+
+        match parent(curr) {
+            Some(p) => curr = p,
+            None => return Callable::Native,
+        }
+    }
+}
+
+pub fn type_name(json: &JSON) -> &'static str {
+    match *json {
+        JSON::JSONNum(_) => "number",
+        JSON::JSONString(_) => "string",
+        JSON::JSONNull => "null",
+        JSON::JSONBool(_) => "boolean",
+        JSON::JSONArray(_) => "array",
+        JSON::JSONObject(_) => "object",
+    }
+}
+
+fn describe(json: &JSON) -> String {
+    let mut text = serializer::to_string(json);
+    if text.len() > 11 {
+        let mut end = 10;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("...");
+    }
+
+    format!("{} ({})", type_name(json), text)
+}
+
+pub fn truthy(json: &JSON) -> bool {
+    !matches!(*json, JSON::JSONNull | JSON::JSONBool(false))
+}
+
+fn type_rank(json: &JSON) -> u8 {
+    match *json {
+        JSON::JSONNull => 0,
+        JSON::JSONBool(false) => 1,
+        JSON::JSONBool(true) => 2,
+        JSON::JSONNum(_) => 3,
+        JSON::JSONString(_) => 4,
+        JSON::JSONArray(_) => 5,
+        JSON::JSONObject(_) => 6,
+    }
+}
+
+fn sorted_keys(obj: &HashMap<String, JSON>) -> Vec<&String> {
+    let mut keys: Vec<&String> = obj.keys().collect();
+    keys.sort();
+
+    keys
+}
+
+/// Orders values the way jq does: null < false < true < numbers < strings
+/// < arrays < objects.
+pub fn compare(a: &JSON, b: &JSON) -> Ordering {
+    match (a, b) {
+        (&JSON::JSONNum(x), &JSON::JSONNum(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Less),
+        (JSON::JSONString(x), JSON::JSONString(y)) => x.cmp(y),
+        (JSON::JSONArray(x), JSON::JSONArray(y)) => {
+            for (x, y) in x.iter().zip(y.iter()) {
+                let ord = compare(x, y);
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            x.len().cmp(&y.len())
+        }
+        (JSON::JSONObject(x), JSON::JSONObject(y)) => {
+            let keys_x = sorted_keys(x);
+            let keys_y = sorted_keys(y);
+            if keys_x != keys_y {
+                return keys_x.cmp(&keys_y);
+            }
+            for key in keys_x {
+                let ord = compare(&x[key], &y[key]);
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            Ordering::Equal
+        }
+        _ => type_rank(a).cmp(&type_rank(b)),
+    }
+}
+
+fn as_index(x: f64, len: usize) -> Option<usize> {
+    let idx = x.floor();
+    let idx = if idx < 0.0 { idx + len as f64 } else { idx };
+
+    if idx >= 0.0 && idx < len as f64 {
+        Some(idx as usize)
+    } else {
+        None
+    }
+}
+
+fn index_value(target: &JSON, key: &JSON) -> Result<JSON, JqError> {
+    match (target, key) {
+        (&JSON::JSONNull, &JSON::JSONString(_)) | (&JSON::JSONNull, &JSON::JSONNum(_)) => {
+            Ok(JSON::JSONNull)
+        }
+        (JSON::JSONObject(obj), JSON::JSONString(key)) => {
+            Ok(obj.get(key).cloned().unwrap_or(JSON::JSONNull))
+        }
+        (JSON::JSONArray(arr), &JSON::JSONNum(x)) => Ok(as_index(x, arr.len())
+            .map(|i| arr[i].clone())
+            .unwrap_or(JSON::JSONNull)),
+        (&JSON::JSONObject(_), _) | (&JSON::JSONArray(_), _) => error(format!(
+            "Cannot index {} with {}",
+            type_name(target),
+            type_name(key)
+        )),
+        (_, JSON::JSONString(key)) => error(format!(
+            "Cannot index {} with \"{}\"",
+            type_name(target),
+            key
+        )),
+        _ => error(format!(
+            "Cannot index {} with {}",
+            type_name(target),
+            type_name(key)
+        )),
+    }
+}
+
+fn slice_bounds(from: &JSON, to: &JSON, len: usize) -> Result<(usize, usize), JqError> {
+    let clamp = |json: &JSON, default: usize| -> Result<usize, JqError> {
+        match *json {
+            JSON::JSONNull => Ok(default),
+            JSON::JSONNum(x) => {
+                let x = if x < 0.0 { x + len as f64 } else { x };
+                Ok(x.floor().max(0.0).min(len as f64) as usize)
+            }
+            _ => error("Start and end indices of a slice must be numbers".to_string()),
+        }
+    };
+
+    let from = clamp(from, 0)?;
+    let to = clamp(to, len)?;
+
+    Ok((from, to.max(from)))
+}
+
+fn slice_value(target: &JSON, from: &JSON, to: &JSON) -> Result<JSON, JqError> {
+    match *target {
+        JSON::JSONNull => Ok(JSON::JSONNull),
+        JSON::JSONArray(ref arr) => {
+            let (from, to) = slice_bounds(from, to, arr.len())?;
+            Ok(JSON::JSONArray(arr[from..to].to_vec()))
+        }
+        JSON::JSONString(ref string) => {
+            let chars: Vec<char> = string.chars().collect();
+            let (from, to) = slice_bounds(from, to, chars.len())?;
+            Ok(JSON::JSONString(chars[from..to].iter().collect()))
+        }
+        _ => error(format!("Cannot index {} with object", type_name(target))),
+    }
+}
+
+fn iterate(target: &JSON) -> Outputs {
+    match *target {
+        JSON::JSONArray(ref arr) => Ok(arr.clone()),
+        JSON::JSONObject(ref obj) => Ok(sorted_keys(obj)
+            .into_iter()
+            .map(|k| obj[k].clone())
+            .collect()),
+        _ => error(format!("Cannot iterate over {}", describe(target))),
+    }
+}
+
+fn deep_merge(mut a: HashMap<String, JSON>, b: HashMap<String, JSON>) -> HashMap<String, JSON> {
+    for (key, val) in b {
+        let merged = match (a.remove(&key), val) {
+            (Some(JSON::JSONObject(x)), JSON::JSONObject(y)) => JSON::JSONObject(deep_merge(x, y)),
+            (_, val) => val,
+        };
+        a.insert(key, merged);
+    }
+
+    a
+}
+
+fn arith(op: BinOp, lhs: JSON, rhs: JSON) -> Result<JSON, JqError> {
+    use crate::json::JSON::*;
+
+    let fail = |verb: &str, lhs: &JSON, rhs: &JSON| {
+        error(format!(
+            "{} and {} cannot be {}",
+            describe(lhs),
+            describe(rhs),
+            verb
+        ))
+    };
+
+    match op {
+        BinOp::Add => match (lhs, rhs) {
+            (JSONNull, x) | (x, JSONNull) => Ok(x),
+            (JSONNum(x), JSONNum(y)) => Ok(JSONNum(x + y)),
+            (JSONString(x), JSONString(y)) => Ok(JSONString(x + &y)),
+            (JSONArray(mut x), JSONArray(y)) => {
+                x.extend(y);
+                Ok(JSONArray(x))
+            }
+            (JSONObject(mut x), JSONObject(y)) => {
+                x.extend(y);
+                Ok(JSONObject(x))
+            }
+            (x, y) => fail("added", &x, &y),
+        },
+        BinOp::Sub => match (lhs, rhs) {
+            (JSONNum(x), JSONNum(y)) => Ok(JSONNum(x - y)),
+            (JSONArray(x), JSONArray(y)) => Ok(JSONArray(
+                x.into_iter().filter(|elem| !y.contains(elem)).collect(),
+            )),
+            (x, y) => fail("subtracted", &x, &y),
+        },
+        BinOp::Mul => match (lhs, rhs) {
+            (JSONNum(x), JSONNum(y)) => Ok(JSONNum(x * y)),
+            (JSONString(s), JSONNum(n)) | (JSONNum(n), JSONString(s)) => {
+                if n <= 0.0 {
+                    Ok(JSONNull)
+                } else if s.len() as f64 * n.ceil() > MAX_STRING as f64 {
+                    error(format!(
+                        "Repeating a string {} times is too long, the limit is {} bytes",
+                        n.ceil(),
+                        MAX_STRING
+                    ))
+                } else {
+                    Ok(JSONString(s.repeat(n.ceil() as usize)))
+                }
+            }
+            (JSONObject(x), JSONObject(y)) => Ok(JSONObject(deep_merge(x, y))),
+            (x, y) => fail("multiplied", &x, &y),
+        },
+        BinOp::Div => match (lhs, rhs) {
+            (JSONNum(x), JSONNum(y)) => {
+                if y == 0.0 {
+                    fail(
+                        "divided because the divisor is zero",
+                        &JSONNum(x),
+                        &JSONNum(y),
+                    )
+                } else {
+                    Ok(JSONNum(x / y))
+                }
+            }
+            (JSONString(x), JSONString(y)) => Ok(split(&x, &y)),
+            (x, y) => fail("divided", &x, &y),
+        },
+        BinOp::Mod => match (lhs, rhs) {
+            (JSONNum(x), JSONNum(y)) => {
+                let (x, y) = (x.trunc() as i64, y.trunc() as i64);
+                if y == 0 {
+                    fail(
+                        "divided because the divisor is zero",
+                        &JSONNum(x as f64),
+                        &JSONNum(y as f64),
+                    )
+                } else {
+                    Ok(JSONNum((x % y.abs()) as f64))
+                }
+            }
+            (x, y) => fail("divided", &x, &y),
+        },
+        BinOp::Eq => Ok(JSONBool(compare(&lhs, &rhs) == Ordering::Equal)),
+        BinOp::Ne => Ok(JSONBool(compare(&lhs, &rhs) != Ordering::Equal)),
+        BinOp::Lt => Ok(JSONBool(compare(&lhs, &rhs) == Ordering::Less)),
+        BinOp::Le => Ok(JSONBool(compare(&lhs, &rhs) != Ordering::Greater)),
+        BinOp::Gt => Ok(JSONBool(compare(&lhs, &rhs) == Ordering::Greater)),
+        BinOp::Ge => Ok(JSONBool(compare(&lhs, &rhs) != Ordering::Less)),
+    }
+}
+
+fn split(string: &str, sep: &str) -> JSON {
+    if string.is_empty() {
+        return JSON::JSONArray(Vec::new());
+    }
+
+    let parts: Vec<JSON> = if sep.is_empty() {
+        string
+            .chars()
+            .map(|c| JSON::JSONString(c.to_string()))
+            .collect()
+    } else {
+        string
+            .split(sep)
+            .map(|s| JSON::JSONString(s.to_string()))
+            .collect()
+    };
+
+    JSON::JSONArray(parts)
+}
+
+fn contains(a: &JSON, b: &JSON) -> Result<bool, JqError> {
+    match (a, b) {
+        (JSON::JSONObject(x), JSON::JSONObject(y)) => {
+            for (key, val) in y {
+                match x.get(key) {
+                    Some(elem) if contains(elem, val)? => (),
+                    _ => return Ok(false),
+                }
+            }
+            Ok(true)
+        }
+        (JSON::JSONArray(x), JSON::JSONArray(y)) => {
+            for val in y {
+                let mut found = false;
+                for elem in x {
+                    if contains(elem, val)? {
+                        found = true;
+                        break;
+                    }
+                }
+                if !found {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        (JSON::JSONString(x), JSON::JSONString(y)) => Ok(x.contains(y.as_str())),
+        _ if type_name(a) == type_name(b) => Ok(a == b),
+        _ => error(format!(
+            "{} and {} cannot have their containment checked",
+            describe(a),
+            describe(b)
+        )),
+    }
+}
+
+fn collect_paths(json: &JSON, prefix: &mut Vec<JSON>, out: &mut Vec<JSON>) {
+    match *json {
+        JSON::JSONArray(ref arr) => {
+            for (i, elem) in arr.iter().enumerate() {
+                prefix.push(JSON::JSONNum(i as f64));
+                out.push(JSON::JSONArray(prefix.clone()));
+                collect_paths(elem, prefix, out);
+                prefix.pop();
+            }
+        }
+        JSON::JSONObject(ref obj) => {
+            for key in sorted_keys(obj) {
+                prefix.push(JSON::JSONString(key.clone()));
+                out.push(JSON::JSONArray(prefix.clone()));
+                collect_paths(&obj[key], prefix, out);
+                prefix.pop();
+            }
+        }
+        _ => (),
+    }
+}
+
+fn getpath(json: &JSON, path: &[JSON]) -> Result<JSON, JqError> {
+    match path.split_first() {
+        None => Ok(json.clone()),
+        Some((key, rest)) => {
+            let child = index_value(json, key)?;
+            if child == JSON::JSONNull {
+                Ok(JSON::JSONNull)
+            } else {
+                getpath(&child, rest)
+            }
+        }
+    }
+}
+
+fn setpath(json: JSON, path: &[JSON], value: JSON) -> Result<JSON, JqError> {
+    let (key, rest) = match path.split_first() {
+        None => return Ok(value),
+        Some(x) => x,
+    };
+
+    match (json, key) {
+        (JSON::JSONNull, JSON::JSONString(k)) => {
+            let mut obj = HashMap::new();
+            obj.insert(k.clone(), setpath(JSON::JSONNull, rest, value)?);
+            Ok(JSON::JSONObject(obj))
+        }
+        (JSON::JSONObject(mut obj), JSON::JSONString(k)) => {
+            let child = obj.remove(k).unwrap_or(JSON::JSONNull);
+            obj.insert(k.clone(), setpath(child, rest, value)?);
+            Ok(JSON::JSONObject(obj))
+        }
+        (JSON::JSONNull, &JSON::JSONNum(x)) => setpath(JSON::JSONArray(Vec::new()), path, value),
+        (JSON::JSONArray(mut arr), &JSON::JSONNum(x)) => {
+            let idx = if x < 0.0 { x + arr.len() as f64 } else { x };
+            if idx < 0.0 {
+                return error("Out of bounds negative array index".to_string());
+            }
+            if idx > MAX_INDEX as f64 {
+                return error(format!(
+                    "Array index {} is too large, the limit is {}",
+                    idx, MAX_INDEX
+                ));
+            }
+            let idx = idx as usize;
+            if idx >= arr.len() {
+                arr.resize(idx + 1, JSON::JSONNull);
+            }
+            let child = std::mem::replace(&mut arr[idx], JSON::JSONNull);
+            arr[idx] = setpath(child, rest, value)?;
+            Ok(JSON::JSONArray(arr))
+        }
+        (json, key) => error(format!(
+            "Cannot index {} with {}",
+            type_name(&json),
+            type_name(key)
+        )),
+    }
+}
+
+fn delpath(json: JSON, path: &[JSON]) -> Result<JSON, JqError> {
+    let (key, rest) = match path.split_first() {
+        None => return Ok(JSON::JSONNull),
+        Some(x) => x,
+    };
+
+    match (json, key) {
+        (JSON::JSONNull, _) => Ok(JSON::JSONNull),
+        (JSON::JSONObject(mut obj), JSON::JSONString(k)) => {
+            if rest.is_empty() {
+                obj.remove(k);
+            } else if let Some(child) = obj.remove(k) {
+                obj.insert(k.clone(), delpath(child, rest)?);
+            }
+            Ok(JSON::JSONObject(obj))
+        }
+        (JSON::JSONArray(mut arr), &JSON::JSONNum(x)) => {
+            if let Some(i) = as_index(x, arr.len()) {
+                if rest.is_empty() {
+                    arr.remove(i);
+                } else {
+                    let child = std::mem::replace(&mut arr[i], JSON::JSONNull);
+                    arr[i] = delpath(child, rest)?;
+                }
+            }
+            Ok(JSON::JSONArray(arr))
+        }
+        (json, key) => error(format!(
+            "Cannot delete field at {} index of {}",
+            type_name(key),
+            type_name(&json)
+        )),
+    }
+}
+
+// Deletes from the back so that removing one array element does not shift
+// the indices of the others still waiting to be removed.
+fn delpaths(mut json: JSON, mut paths: Vec<Vec<JSON>>) -> Result<JSON, JqError> {
+    paths.sort_by(|a, b| compare(&JSON::JSONArray(b.clone()), &JSON::JSONArray(a.clone())));
+    paths.dedup();
+
+    for path in paths {
+        json = delpath(json, &path)?;
+    }
+
+    Ok(json)
+}
+
+fn to_number(json: &JSON) -> Result<f64, JqError> {
+    match *json {
+        JSON::JSONNum(x) => Ok(x),
+        _ => error(format!("{} number required", describe(json))),
+    }
+}
+
+fn to_str(json: &JSON) -> Result<&str, JqError> {
+    match *json {
+        JSON::JSONString(ref x) => Ok(x),
+        _ => error(format!("{} cannot be used as a string", describe(json))),
+    }
+}
+
+// Seconds since the Unix epoch as an ISO 8601 UTC time, dropping any
+// fraction of a second, for years 0 to 9999.
+fn todate(secs: f64) -> Result<String, JqError> {
+    if !(-62_167_219_200.0..253_402_300_800.0).contains(&secs) {
+        return error(format!("{} is out of range for a date", secs));
+    }
+    let secs = secs.floor() as i64;
+    let (days, time) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+
+    // Howard Hinnant's days-to-civil algorithm, with eras of 400 years
+    // starting on March 1st.
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    Ok(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    ))
+}
+
+fn tostring(json: &JSON) -> String {
+    match *json {
+        JSON::JSONString(ref x) => x.clone(),
+        _ => serializer::to_string(json),
+    }
+}
+
+fn length(json: &JSON) -> Result<JSON, JqError> {
+    let len = match *json {
+        JSON::JSONNull => 0.0,
+        JSON::JSONBool(_) => return error(format!("{} has no length", describe(json))),
+        JSON::JSONNum(x) => x.abs(),
+        JSON::JSONString(ref x) => x.chars().count() as f64,
+        JSON::JSONArray(ref x) => x.len() as f64,
+        JSON::JSONObject(ref x) => x.len() as f64,
+    };
+
+    Ok(JSON::JSONNum(len))
+}
+
+fn keys(json: &JSON) -> Result<JSON, JqError> {
+    match *json {
+        JSON::JSONObject(ref obj) => Ok(JSON::JSONArray(
+            sorted_keys(obj)
+                .into_iter()
+                .map(|k| JSON::JSONString(k.clone()))
+                .collect(),
+        )),
+        JSON::JSONArray(ref arr) => Ok(JSON::JSONArray(
+            (0..arr.len()).map(|i| JSON::JSONNum(i as f64)).collect(),
+        )),
+        _ => error(format!("{} has no keys", describe(json))),
+    }
+}
+
+fn to_entries(json: &JSON) -> Result<JSON, JqError> {
+    match *json {
+        JSON::JSONObject(ref obj) => Ok(JSON::JSONArray(
+            sorted_keys(obj)
+                .into_iter()
+                .map(|k| {
+                    let mut entry = HashMap::new();
+                    entry.insert("key".to_string(), JSON::JSONString(k.clone()));
+                    entry.insert("value".to_string(), obj[k].clone());
+                    JSON::JSONObject(entry)
+                })
+                .collect(),
+        )),
+        _ => error(format!("{} has no keys", describe(json))),
+    }
+}
+
+fn from_entries(json: &JSON) -> Result<JSON, JqError> {
+    let mut obj = HashMap::new();
+
+    for entry in iterate(json)? {
+        let field = |names: &[&str]| -> JSON {
+            for name in names {
+                if let Ok(val) = index_value(&entry, &JSON::JSONString(name.to_string())) {
+                    if truthy(&val) {
+                        return val;
+                    }
+                }
+            }
+            JSON::JSONNull
+        };
+
+        let key = match field(&["key", "k", "name", "Name", "Key", "K"]) {
+            JSON::JSONString(x) => x,
+            JSON::JSONNull => return error("Cannot use null as object key".to_string()),
+            other => tostring(&other),
+        };
+        let value = match index_value(&entry, &JSON::JSONString("value".to_string()))? {
+            JSON::JSONNull => field(&["v", "Value", "V"]),
+            val => val,
+        };
+
+        obj.insert(key, value);
+    }
+
+    Ok(JSON::JSONObject(obj))
+}
+
+fn flatten(json: &JSON, depth: f64, out: &mut Vec<JSON>) -> Result<(), JqError> {
+    for elem in iterate(json)? {
+        match elem {
+            JSON::JSONArray(_) if depth > 0.0 => flatten(&elem, depth - 1.0, out)?,
+            _ => out.push(elem),
+        }
+    }
+
+    Ok(())
+}
+
+fn sorted(mut arr: Vec<JSON>) -> Vec<JSON> {
+    arr.sort_by(compare);
+    arr
+}
+
+fn math(name: &str) -> Option<fn(f64) -> f64> {
+    let f: fn(f64) -> f64 = match name {
+        "floor" => f64::floor,
+        "ceil" => f64::ceil,
+        "round" => f64::round,
+        "sqrt" => f64::sqrt,
+        "fabs" | "abs" => f64::abs,
+        "log" => f64::ln,
+        "log2" => f64::log2,
+        "log10" => f64::log10,
+        "exp" => f64::exp,
+        "exp10" => |x| 10f64.powf(x),
+        "trunc" => f64::trunc,
+        _ => return None,
+    };
+
+    Some(f)
+}
+
+// Emits a number range one value at a time, so `range(infinite)` can be
+// cut short by `limit` or `first`.
+fn range(from: f64, upto: f64, out: &mut Sink) -> Flow {
+    let mut x = from;
+    while x < upto {
+        if !out(JSON::JSONNum(x))? {
+            return Ok(false);
+        }
+        x += 1.0;
+    }
+
+    Ok(true)
+}
+
+fn emit_all(vals: Vec<JSON>, out: &mut Sink) -> Flow {
+    for val in vals {
+        if !out(val)? {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+// Runs `f` with a sink that forwards to `out`, but keeps errors raised by
+// `out` apart from those raised while producing the outputs. `try` and `//`
+// suppress only the latter, so a failure further down the pipe still gets
+// through them.
+fn guarded<F>(out: &mut Sink, f: F) -> Result<Flow, JqError>
+where
+    F: FnOnce(&mut Sink) -> Flow,
+{
+    let mut downstream = None;
+    let flow = f(&mut |x| match out(x) {
+        Err(err) => {
+            downstream = Some(err);
+            Ok(false)
+        }
+        flow => flow,
+    });
+
+    match downstream {
+        Some(err) => Err(err),
+        None => Ok(flow),
+    }
+}
+
+fn stack_address() -> usize {
+    let probe = 0u8;
+    &probe as *const u8 as usize
+}
+
+pub struct Interpreter {
+    base: usize,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Interpreter {
+            base: stack_address(),
+        }
+    }
+
+    fn check_depth(&self) -> Result<(), JqError> {
+        if self.base.abs_diff(stack_address()) > MAX_STACK {
+            return error("Maximum recursion depth exceeded".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn collect<'a>(&self, ast: &'a Ast, input: &JSON, env: &Rc<Env<'a>>) -> Outputs {
+        let mut vals = Vec::new();
+        self.eval(ast, input, env, &mut |x| {
+            vals.push(x);
+            Ok(true)
+        })?;
+
+        Ok(vals)
+    }
+
+    fn first<'a>(
+        &self,
+        ast: &'a Ast,
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+    ) -> Result<Option<JSON>, JqError> {
+        let mut first = None;
+        self.eval(ast, input, env, &mut |x| {
+            first = Some(x);
+            Ok(false)
+        })?;
+
+        Ok(first)
+    }
+
+    fn last<'a>(
+        &self,
+        ast: &'a Ast,
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+    ) -> Result<Option<JSON>, JqError> {
+        let mut last = None;
+        self.eval(ast, input, env, &mut |x| {
+            last = Some(x);
+            Ok(true)
+        })?;
+
+        Ok(last)
+    }
+
+    // Feeds each output of `ast` to `out` as soon as it is produced and
+    // stops once `out` returns `false`.
+    fn eval<'a>(&self, ast: &'a Ast, input: &JSON, env: &Rc<Env<'a>>, out: &mut Sink) -> Flow {
+        self.check_depth()?;
+
+        // The larger arms live in their own methods, which keeps this
+        // frame small since it appears once per level of nesting.
+
+        match *ast {
+            Ast::Identity => out(input.clone()),
+            // `..` is just `recurse`, so look it up like any other call
+            Ast::RecurseAll => self.call_named("recurse", &[], input, env, out),
+            Ast::Literal(ref x) => out(x.clone()),
+            Ast::Str(ref parts) => self.eval_str(parts, String::new(), input, env, out),
+            Ast::Var(ref name) => out(lookup_var(env, name)?),
+            Ast::Index(ref target, ref key) => self.eval(target, input, env, &mut |t| {
+                self.eval(key, input, env, &mut |k| out(index_value(&t, &k)?))
+            }),
+            Ast::Slice(ref target, ref from, ref to) => {
+                self.eval_slice(target, from, to, input, env, out)
+            }
+            Ast::Iterate(ref target) => {
+                self.eval(target, input, env, &mut |t| emit_all(iterate(&t)?, out))
+            }
+            Ast::Try(ref body, ref catch) => self.eval_try(body, catch, input, env, out),
+            Ast::Array(ref inner) => match *inner {
+                Some(ref inner) => out(JSON::JSONArray(self.collect(inner, input, env)?)),
+                None => out(JSON::JSONArray(Vec::new())),
+            },
+            Ast::Object(ref entries) => self.eval_object(entries, HashMap::new(), input, env, out),
+            Ast::Neg(ref operand) => self.eval(operand, input, env, &mut |val| match val {
+                JSON::JSONNum(x) => out(JSON::JSONNum(-x)),
+                _ => error(format!("{} cannot be negated", describe(&val))),
+            }),
+            Ast::Binary(op, ref lhs, ref rhs) => self.eval(rhs, input, env, &mut |r| {
+                self.eval(lhs, input, env, &mut |l| out(arith(op, l, r.clone())?))
+            }),
+            Ast::And(ref lhs, ref rhs) | Ast::Or(ref lhs, ref rhs) => {
+                let is_and = matches!(*ast, Ast::And(_, _));
+                self.eval(lhs, input, env, &mut |l| {
+                    if truthy(&l) != is_and {
+                        return out(JSON::JSONBool(!is_and));
+                    }
+                    self.eval(rhs, input, env, &mut |r| out(JSON::JSONBool(truthy(&r))))
+                })
+            }
+            Ast::Alt(ref lhs, ref rhs) => self.eval_alt(lhs, rhs, input, env, out),
+            Ast::Assign(op, ref lhs, ref rhs) => self.eval_assign(op, lhs, rhs, input, env, out),
+            Ast::Pipe(ref lhs, ref rhs) => {
+                self.eval(lhs, input, env, &mut |val| self.eval(rhs, &val, env, out))
+            }
+            Ast::Comma(ref lhs, ref rhs) => {
+                if !self.eval(lhs, input, env, out)? {
+                    return Ok(false);
+                }
+                self.eval(rhs, input, env, out)
+            }
+            Ast::If(ref cond, ref then, ref otherwise) => self.eval(cond, input, env, &mut |c| {
+                let branch = if truthy(&c) { then } else { otherwise };
+                self.eval(branch, input, env, out)
+            }),
+            Ast::Reduce(ref source, ref name, ref init, ref update) => {
+                self.eval_reduce(source, name, init, update, input, env, out)
+            }
+            Ast::Foreach(ref source, ref name, ref init, ref update, ref extract) => {
+                self.eval_foreach(source, name, init, update, extract, input, env, out)
+            }
+            Ast::Bind(ref source, ref name, ref body) => {
+                self.eval(source, input, env, &mut |val| {
+                    let env = Rc::new(Env::Var(name, val, env.clone()));
+                    self.eval(body, input, &env, out)
+                })
+            }
+            Ast::FuncDef(ref def, ref rest) => {
+                let env = Rc::new(Env::Func(def, env.clone()));
+                self.eval(rest, input, &env, out)
+            }
+            Ast::Call(ref name, ref args) => self.call_named(name, args, input, env, out),
+        }
+    }
+
+    // Interpolated strings take the cartesian product of their parts, in
+    // the order the parts appear.
+    fn eval_str<'a>(
+        &self,
+        parts: &'a [StrAst],
+        prefix: String,
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+        out: &mut Sink,
+    ) -> Flow {
+        match parts.split_first() {
+            None => out(JSON::JSONString(prefix)),
+            Some((StrAst::Lit(x), rest)) => self.eval_str(rest, prefix + x, input, env, out),
+            Some((StrAst::Interp(x), rest)) => self.eval(x, input, env, &mut |val| {
+                let prefix = format!("{}{}", prefix, tostring(&val));
+                self.eval_str(rest, prefix, input, env, out)
+            }),
+        }
+    }
+
+    fn eval_object<'a>(
+        &self,
+        entries: &'a [(Ast, Ast)],
+        obj: HashMap<String, JSON>,
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+        out: &mut Sink,
+    ) -> Flow {
+        let (key, value) = match entries.split_first() {
+            None => return out(JSON::JSONObject(obj)),
+            Some(((key, value), _)) => (key, value),
+        };
+        self.eval(key, input, env, &mut |k| {
+            let k = match k {
+                JSON::JSONString(k) => k,
+                _ => {
+                    return error(format!(
+                        "Object keys must be strings, not {}",
+                        type_name(&k)
+                    ))
+                }
+            };
+            self.eval(value, input, env, &mut |v| {
+                let mut obj = obj.clone();
+                obj.insert(k.clone(), v);
+                self.eval_object(&entries[1..], obj, input, env, out)
+            })
+        })
+    }
+
+    fn eval_slice<'a>(
+        &self,
+        target: &'a Ast,
+        from: &'a Option<Box<Ast>>,
+        to: &'a Option<Box<Ast>>,
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+        out: &mut Sink,
+    ) -> Flow {
+        let bound = |b: &'a Option<Box<Ast>>, out: &mut Sink| match *b {
+            Some(ref b) => self.eval(b, input, env, out),
+            None => out(JSON::JSONNull),
+        };
+        self.eval(target, input, env, &mut |t| {
+            bound(to, &mut |to| {
+                bound(from, &mut |from| out(slice_value(&t, &from, &to)?))
+            })
+        })
+    }
+
+    fn eval_try<'a>(
+        &self,
+        body: &'a Ast,
+        catch: &'a Option<Box<Ast>>,
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+        out: &mut Sink,
+    ) -> Flow {
+        match guarded(out, |out| self.eval(body, input, env, out))? {
+            Err(err) => match *catch {
+                Some(ref catch) => self.eval(catch, &err.value, env, out),
+                None => Ok(true),
+            },
+            flow => flow,
+        }
+    }
+
+    fn eval_alt<'a>(
+        &self,
+        lhs: &'a Ast,
+        rhs: &'a Ast,
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+        out: &mut Sink,
+    ) -> Flow {
+        let mut any = false;
+        let flow = guarded(out, |out| {
+            self.eval(lhs, input, env, &mut |x| {
+                if !truthy(&x) {
+                    return Ok(true);
+                }
+                any = true;
+                out(x)
+            })
+        })?;
+        match flow {
+            Ok(false) => Ok(false),
+            _ if any => Ok(true),
+            _ => self.eval(rhs, input, env, out),
+        }
+    }
+
+    fn eval_assign<'a>(
+        &self,
+        op: AssignOp,
+        lhs: &'a Ast,
+        rhs: &'a Ast,
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+        out: &mut Sink,
+    ) -> Flow {
+        let paths = self.eval_paths(lhs, &[], input, env)?;
+        if let AssignOp::Update = op {
+            let mut result = input.clone();
+            for (path, _) in paths {
+                let old = getpath(&result, &path)?;
+                result = match self.first(rhs, &old, env)? {
+                    Some(new) => setpath(result, &path, new)?,
+                    None => delpaths(result, vec![path])?,
+                };
+            }
+            return out(result);
+        }
+        self.eval(rhs, input, env, &mut |val| {
+            let mut result = input.clone();
+            for (path, _) in &paths {
+                let old = getpath(&result, path)?;
+                let new = match op {
+                    AssignOp::Arith(op) => arith(op, old, val.clone())?,
+                    AssignOp::Alt if truthy(&old) => old,
+                    _ => val.clone(),
+                };
+                result = setpath(result, path, new)?;
+            }
+            out(result)
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn eval_reduce<'a>(
+        &self,
+        source: &'a Ast,
+        name: &'a str,
+        init: &'a Ast,
+        update: &'a Ast,
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+        out: &mut Sink,
+    ) -> Flow {
+        self.eval(init, input, env, &mut |mut acc| {
+            self.eval(source, input, env, &mut |item| {
+                let env = Rc::new(Env::Var(name, item, env.clone()));
+                acc = self.last(update, &acc, &env)?.unwrap_or(JSON::JSONNull);
+                Ok(true)
+            })?;
+            out(acc)
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn eval_foreach<'a>(
+        &self,
+        source: &'a Ast,
+        name: &'a str,
+        init: &'a Ast,
+        update: &'a Ast,
+        extract: &'a Option<Box<Ast>>,
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+        out: &mut Sink,
+    ) -> Flow {
+        self.eval(init, input, env, &mut |acc| {
+            let mut states = vec![acc];
+            self.eval(source, input, env, &mut |item| {
+                let env = Rc::new(Env::Var(name, item, env.clone()));
+                let mut next = Vec::new();
+                for state in &states {
+                    let go = self.eval(update, state, &env, &mut |new_state| {
+                        let go = match *extract {
+                            Some(ref extract) => self.eval(extract, &new_state, &env, out)?,
+                            None => out(new_state.clone())?,
+                        };
+                        next.push(new_state);
+                        Ok(go)
+                    })?;
+                    if !go {
+                        return Ok(false);
+                    }
+                }
+                states = next;
+                Ok(true)
+            })
+        })
+    }
+
+    fn call_env<'a>(
+        &self,
+        def: &'a FuncDef,
+        args: &'a [Ast],
+        def_env: Rc<Env<'a>>,
+        env: &Rc<Env<'a>>,
+    ) -> Rc<Env<'a>> {
+        let mut call_env = def_env;
+        for (param, arg) in def.params.iter().zip(args.iter()) {
+            call_env = Rc::new(Env::Closure(param, arg, env.clone(), call_env));
+        }
+
+        call_env
+    }
+
+    fn call_named<'a>(
+        &self,
+        name: &str,
+        args: &'a [Ast],
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+        out: &mut Sink,
+    ) -> Flow {
+        match lookup_func(env, name, args.len()) {
+            Callable::Func(def, def_env) => {
+                let call_env = self.call_env(def, args, def_env, env);
+                self.eval(&def.body, input, &call_env, out)
+            }
+            Callable::Closure(ast, closure_env) => self.eval(ast, input, &closure_env, out),
+            Callable::Native => self.call_native(name, args, input, env, out),
+        }
+    }
+
+    // Evaluates `ast` as a path expression, such as the left-hand side of
+    // an assignment or the argument to `path` and `del`. Each output is the
+    // path that was followed along with the value found there.
+    fn eval_paths<'a>(
+        &self,
+        ast: &'a Ast,
+        path: &[JSON],
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+    ) -> Paths {
+        self.check_depth()?;
+
+        match *ast {
+            Ast::Identity => Ok(vec![(path.to_vec(), input.clone())]),
+            Ast::RecurseAll => self.call_paths("recurse", &[], path, input, env),
+            Ast::Index(ref target, ref key) => {
+                let keys = self.collect(key, input, env)?;
+                let mut out = Vec::new();
+                for (p, v) in self.eval_paths(target, path, input, env)? {
+                    for k in &keys {
+                        let child = index_value(&v, k)?;
+                        let mut p = p.clone();
+                        p.push(k.clone());
+                        out.push((p, child));
+                    }
+                }
+                Ok(out)
+            }
+            Ast::Iterate(ref target) => {
+                let mut out = Vec::new();
+                for (p, v) in self.eval_paths(target, path, input, env)? {
+                    match v {
+                        JSON::JSONNull => (),
+                        JSON::JSONArray(arr) => {
+                            for (i, elem) in arr.into_iter().enumerate() {
+                                let mut p = p.clone();
+                                p.push(JSON::JSONNum(i as f64));
+                                out.push((p, elem));
+                            }
+                        }
+                        JSON::JSONObject(ref obj) => {
+                            for key in sorted_keys(obj) {
+                                let mut p = p.clone();
+                                p.push(JSON::JSONString(key.clone()));
+                                out.push((p, obj[key].clone()));
+                            }
+                        }
+                        _ => return error(format!("Cannot iterate over {}", describe(&v))),
+                    }
+                }
+                Ok(out)
+            }
+            Ast::Try(ref body, _) => Ok(self
+                .eval_paths(body, path, input, env)
+                .unwrap_or_else(|_| Vec::new())),
+            Ast::Pipe(ref lhs, ref rhs) => {
+                let mut out = Vec::new();
+                for (p, v) in self.eval_paths(lhs, path, input, env)? {
+                    out.extend(self.eval_paths(rhs, &p, &v, env)?);
+                }
+                Ok(out)
+            }
+            Ast::Comma(ref lhs, ref rhs) => {
+                let mut out = self.eval_paths(lhs, path, input, env)?;
+                out.extend(self.eval_paths(rhs, path, input, env)?);
+                Ok(out)
+            }
+            Ast::If(ref cond, ref then, ref otherwise) => {
+                let mut out = Vec::new();
+                for c in self.collect(cond, input, env)? {
+                    let branch = if truthy(&c) { then } else { otherwise };
+                    out.extend(self.eval_paths(branch, path, input, env)?);
+                }
+                Ok(out)
+            }
+            Ast::Alt(ref lhs, ref rhs) => {
+                let paths: Vec<(Vec<JSON>, JSON)> = match self.eval_paths(lhs, path, input, env) {
+                    Ok(paths) => paths.into_iter().filter(|p| truthy(&p.1)).collect(),
+                    Err(_) => Vec::new(),
+                };
+                if paths.is_empty() {
+                    self.eval_paths(rhs, path, input, env)
+                } else {
+                    Ok(paths)
+                }
+            }
+            Ast::Bind(ref source, ref name, ref body) => {
+                let mut out = Vec::new();
+                for val in self.collect(source, input, env)? {
+                    let env = Rc::new(Env::Var(name, val, env.clone()));
+                    out.extend(self.eval_paths(body, path, input, &env)?);
+                }
+                Ok(out)
+            }
+            Ast::FuncDef(ref def, ref rest) => {
+                let env = Rc::new(Env::Func(def, env.clone()));
+                self.eval_paths(rest, path, input, &env)
+            }
+            Ast::Call(ref name, ref args) => self.call_paths(name, args, path, input, env),
+            _ => match self.first(ast, input, env)? {
+                Some(val) => error(format!(
+                    "Invalid path expression with result {}",
+                    describe(&val)
+                )),
+                None => Ok(Vec::new()),
+            },
+        }
+    }
+
+    fn call_paths<'a>(
+        &self,
+        name: &str,
+        args: &'a [Ast],
+        path: &[JSON],
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+    ) -> Paths {
+        match lookup_func(env, name, args.len()) {
+            Callable::Func(def, def_env) => {
+                let call_env = self.call_env(def, args, def_env, env);
+                self.eval_paths(&def.body, path, input, &call_env)
+            }
+            Callable::Closure(ast, closure_env) => self.eval_paths(ast, path, input, &closure_env),
+            Callable::Native => match (name, args.len()) {
+                ("empty", 0) => Ok(Vec::new()),
+                ("getpath", 1) => {
+                    let mut out = Vec::new();
+                    for p in self.collect(&args[0], input, env)? {
+                        let p = iterate(&p)?;
+                        let val = getpath(input, &p).unwrap_or(JSON::JSONNull);
+                        let mut full = path.to_vec();
+                        full.extend(p);
+                        out.push((full, val));
+                    }
+                    Ok(out)
+                }
+                ("first", 1) => Ok(self
+                    .eval_paths(&args[0], path, input, env)?
+                    .into_iter()
+                    .take(1)
+                    .collect()),
+                ("last", 1) => Ok(self
+                    .eval_paths(&args[0], path, input, env)?
+                    .pop()
+                    .into_iter()
+                    .collect()),
+                _ => {
+                    let mut first = None;
+                    self.call_native(name, args, input, env, &mut |x| {
+                        first = Some(x);
+                        Ok(false)
+                    })?;
+                    match first {
+                        Some(val) => error(format!(
+                            "Invalid path expression with result {}",
+                            describe(&val)
+                        )),
+                        None => Ok(Vec::new()),
+                    }
+                }
+            },
+        }
+    }
+
+    // Evaluates a single-argument builtin once for each output of its
+    // argument, which is how jq treats `$`-style parameters.
+    fn each_arg<'a, F>(
+        &self,
+        arg: &'a Ast,
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+        out: &mut Sink,
+        f: F,
+    ) -> Flow
+    where
+        F: Fn(&JSON) -> Result<JSON, JqError>,
+    {
+        self.eval(arg, input, env, &mut |val| out(f(&val)?))
+    }
+
+    fn by_key<'a>(
+        &self,
+        f: &'a Ast,
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+    ) -> Result<Vec<(JSON, JSON)>, JqError> {
+        let mut pairs = Vec::new();
+        for elem in iterate(input)? {
+            let key = JSON::JSONArray(self.collect(f, &elem, env)?);
+            pairs.push((key, elem));
+        }
+        pairs.sort_by(|a, b| compare(&a.0, &b.0));
+
+        Ok(pairs)
+    }
+
+    fn call_native<'a>(
+        &self,
+        name: &str,
+        args: &'a [Ast],
+        input: &JSON,
+        env: &Rc<Env<'a>>,
+        out: &mut Sink,
+    ) -> Flow {
+        match (name, args.len()) {
+            ("empty", 0) => Ok(true),
+            ("not", 0) => out(JSON::JSONBool(!truthy(input))),
+            ("length", 0) => out(length(input)?),
+            ("utf8bytelength", 0) => out(JSON::JSONNum(to_str(input)?.len() as f64)),
+            ("keys", 0) | ("keys_unsorted", 0) => out(keys(input)?),
+            ("type", 0) => out(JSON::JSONString(type_name(input).to_string())),
+            ("error", 0) => Err(JqError::from_value(input.clone())),
+            ("error", 1) => match self.first(&args[0], input, env)? {
+                Some(val) => Err(JqError::from_value(val)),
+                None => Ok(true),
+            },
+            ("add", 0) => {
+                let mut acc = JSON::JSONNull;
+                for elem in iterate(input)? {
+                    acc = arith(BinOp::Add, acc, elem)?;
+                }
+                out(acc)
+            }
+            ("range", 1) => self.eval(&args[0], input, env, &mut |upto| {
+                range(0.0, to_number(&upto)?, out)
+            }),
+            ("range", 2) => self.eval(&args[0], input, env, &mut |from| {
+                self.eval(&args[1], input, env, &mut |upto| {
+                    range(to_number(&from)?, to_number(&upto)?, out)
+                })
+            }),
+            ("tostring", 0) => out(JSON::JSONString(tostring(input))),
+            ("todate", 0) => out(JSON::JSONString(todate(to_number(input)?)?)),
+            ("tojson", 0) => out(JSON::JSONString(serializer::to_string(input))),
+            ("fromjson", 0) => match crate::parse(to_str(input)?) {
+                Ok(x) => out(x),
+                Err(err) => error(format!("{:?} cannot be parsed as JSON", err)),
+            },
+            ("tonumber", 0) => match *input {
+                JSON::JSONNum(_) => out(input.clone()),
+                JSON::JSONString(ref x) => match x.trim().parse() {
+                    Ok(x) => out(JSON::JSONNum(x)),
+                    Err(_) => error(format!("Cannot parse '{}' as a number", x)),
+                },
+                _ => error(format!("{} cannot be parsed as a number", describe(input))),
+            },
+            ("ascii_downcase", 0) => out(JSON::JSONString(to_str(input)?.to_ascii_lowercase())),
+            ("ascii_upcase", 0) => out(JSON::JSONString(to_str(input)?.to_ascii_uppercase())),
+            ("explode", 0) => out(JSON::JSONArray(
+                to_str(input)?
+                    .chars()
+                    .map(|c| JSON::JSONNum(f64::from(c as u32)))
+                    .collect(),
+            )),
+            ("implode", 0) => {
+                let mut string = String::new();
+                for elem in iterate(input)? {
+                    let code_pt = to_number(&elem)? as u32;
+                    match std::char::from_u32(code_pt) {
+                        Some(c) => string.push(c),
+                        None => return error(format!("Invalid codepoint: {}", code_pt)),
+                    }
+                }
+                out(JSON::JSONString(string))
+            }
+            ("sort", 0) => out(JSON::JSONArray(sorted(iterate(input)?))),
+            ("reverse", 0) => match *input {
+                JSON::JSONString(ref x) => out(JSON::JSONString(x.chars().rev().collect())),
+                JSON::JSONNull => out(JSON::JSONArray(Vec::new())),
+                _ => {
+                    let mut arr = iterate(input)?;
+                    arr.reverse();
+                    out(JSON::JSONArray(arr))
+                }
+            },
+            ("unique", 0) => {
+                let mut arr = sorted(iterate(input)?);
+                arr.dedup_by(|a, b| compare(a, b) == Ordering::Equal);
+                out(JSON::JSONArray(arr))
+            }
+            ("min", 0) => out(sorted(iterate(input)?)
+                .into_iter()
+                .next()
+                .unwrap_or(JSON::JSONNull)),
+            ("max", 0) => out(sorted(iterate(input)?).pop().unwrap_or(JSON::JSONNull)),
+            ("to_entries", 0) => out(to_entries(input)?),
+            ("from_entries", 0) => out(from_entries(input)?),
+            ("flatten", 0) => {
+                let mut flat = Vec::new();
+                flatten(input, 1e9, &mut flat)?;
+                out(JSON::JSONArray(flat))
+            }
+            ("flatten", 1) => self.eval(&args[0], input, env, &mut |depth| {
+                let depth = to_number(&depth)?;
+                if depth < 0.0 {
+                    return error("flatten depth must not be negative".to_string());
+                }
+                let mut flat = Vec::new();
+                flatten(input, depth, &mut flat)?;
+                out(JSON::JSONArray(flat))
+            }),
+            ("path", 1) => emit_all(
+                self.eval_paths(&args[0], &[], input, env)?
+                    .into_iter()
+                    .map(|(p, _)| JSON::JSONArray(p))
+                    .collect(),
+                out,
+            ),
+            ("del", 1) => {
+                let paths = self.eval_paths(&args[0], &[], input, env)?;
+                out(delpaths(
+                    input.clone(),
+                    paths.into_iter().map(|(p, _)| p).collect(),
+                )?)
+            }
+            ("delpaths", 1) => self.each_arg(&args[0], input, env, out, |paths| {
+                let mut list = Vec::new();
+                for p in iterate(paths)? {
+                    list.push(iterate(&p)?);
+                }
+                delpaths(input.clone(), list)
+            }),
+            ("paths", 0) => {
+                let mut paths = Vec::new();
+                collect_paths(input, &mut Vec::new(), &mut paths);
+                emit_all(paths, out)
+            }
+            ("getpath", 1) => self.each_arg(&args[0], input, env, out, |path| {
+                getpath(input, &iterate(path)?).or(Ok(JSON::JSONNull))
+            }),
+            ("setpath", 2) => self.eval(&args[1], input, env, &mut |value| {
+                self.eval(&args[0], input, env, &mut |path| {
+                    out(setpath(input.clone(), &iterate(&path)?, value.clone())?)
+                })
+            }),
+            ("has", 1) => self.each_arg(&args[0], input, env, out, |key| match (input, key) {
+                (JSON::JSONObject(obj), JSON::JSONString(k)) => {
+                    Ok(JSON::JSONBool(obj.contains_key(k)))
+                }
+                (JSON::JSONArray(arr), &JSON::JSONNum(x)) => {
+                    Ok(JSON::JSONBool(x >= 0.0 && x < arr.len() as f64))
+                }
+                _ => error(format!(
+                    "Cannot check whether {} has a {} key",
+                    type_name(input),
+                    type_name(key)
+                )),
+            }),
+            ("contains", 1) => self.each_arg(&args[0], input, env, out, |b| {
+                Ok(JSON::JSONBool(contains(input, b)?))
+            }),
+            ("startswith", 1) => self.each_arg(&args[0], input, env, out, |b| {
+                Ok(JSON::JSONBool(to_str(input)?.starts_with(to_str(b)?)))
+            }),
+            ("endswith", 1) => self.each_arg(&args[0], input, env, out, |b| {
+                Ok(JSON::JSONBool(to_str(input)?.ends_with(to_str(b)?)))
+            }),
+            ("ltrimstr", 1) => self.each_arg(&args[0], input, env, out, |b| match (input, b) {
+                (JSON::JSONString(x), JSON::JSONString(y)) if x.starts_with(y.as_str()) => {
+                    Ok(JSON::JSONString(x[y.len()..].to_string()))
+                }
+                _ => Ok(input.clone()),
+            }),
+            ("rtrimstr", 1) => self.each_arg(&args[0], input, env, out, |b| match (input, b) {
+                (JSON::JSONString(x), JSON::JSONString(y)) if x.ends_with(y.as_str()) => {
+                    Ok(JSON::JSONString(x[..x.len() - y.len()].to_string()))
+                }
+                _ => Ok(input.clone()),
+            }),
+            ("split", 1) => self.each_arg(&args[0], input, env, out, |sep| {
+                Ok(split(to_str(input)?, to_str(sep)?))
+            }),
+            ("join", 1) => self.each_arg(&args[0], input, env, out, |sep| {
+                let sep = to_str(sep)?;
+                let mut parts = Vec::new();
+                for elem in iterate(input)? {
+                    parts.push(match elem {
+                        JSON::JSONNull => String::new(),
+                        JSON::JSONString(x) => x,
+                        JSON::JSONNum(_) | JSON::JSONBool(_) => tostring(&elem),
+                        _ => return error(format!("Cannot join with {}", type_name(&elem))),
+                    });
+                }
+                Ok(JSON::JSONString(parts.join(sep)))
+            }),
+            ("map_values", 1) => match *input {
+                JSON::JSONObject(ref obj) => {
+                    let mut result = HashMap::new();
+                    for (key, val) in obj {
+                        if let Some(x) = self.first(&args[0], val, env)? {
+                            result.insert(key.clone(), x);
+                        }
+                    }
+                    out(JSON::JSONObject(result))
+                }
+                _ => {
+                    let mut result = Vec::new();
+                    for val in iterate(input)? {
+                        if let Some(x) = self.first(&args[0], &val, env)? {
+                            result.push(x);
+                        }
+                    }
+                    out(JSON::JSONArray(result))
+                }
+            },
+            ("sort_by", 1) => out(JSON::JSONArray(
+                self.by_key(&args[0], input, env)?
+                    .into_iter()
+                    .map(|(_, v)| v)
+                    .collect(),
+            )),
+            ("group_by", 1) | ("unique_by", 1) => {
+                let mut groups: Vec<(JSON, Vec<JSON>)> = Vec::new();
+                for (key, val) in self.by_key(&args[0], input, env)? {
+                    match groups.last_mut() {
+                        Some(&mut (ref k, ref mut group)) if *k == key => group.push(val),
+                        _ => groups.push((key, vec![val])),
+                    }
+                }
+                let result = groups.into_iter().map(|(_, group)| {
+                    if name == "group_by" {
+                        JSON::JSONArray(group)
+                    } else {
+                        group.into_iter().next().unwrap()
+                    }
+                });
+                out(JSON::JSONArray(result.collect()))
+            }
+            ("min_by", 1) => out(self
+                .by_key(&args[0], input, env)?
+                .into_iter()
+                .next()
+                .map_or(JSON::JSONNull, |(_, v)| v)),
+            ("max_by", 1) => out(self
+                .by_key(&args[0], input, env)?
+                .pop()
+                .map_or(JSON::JSONNull, |(_, v)| v)),
+            ("first", 1) => match self.first(&args[0], input, env)? {
+                Some(x) => out(x),
+                None => Ok(true),
+            },
+            ("last", 1) => match self.last(&args[0], input, env)? {
+                Some(x) => out(x),
+                None => Ok(true),
+            },
+            // Stops pulling from the generator once `n` outputs have been
+            // taken, which is what makes `limit(1; repeat(1))` terminate.
+            ("limit", 2) => self.eval(&args[0], input, env, &mut |n| {
+                let n = to_number(&n)?.max(0.0) as usize;
+                if n == 0 {
+                    return Ok(true);
+                }
+                let (mut taken, mut go) = (0, true);
+                self.eval(&args[1], input, env, &mut |x| {
+                    go = out(x)?;
+                    taken += 1;
+                    Ok(go && taken < n)
+                })?;
+                Ok(go)
+            }),
+            ("pow", 2) => self.eval(&args[1], input, env, &mut |b| {
+                self.eval(&args[0], input, env, &mut |a| {
+                    out(JSON::JSONNum(to_number(&a)?.powf(to_number(&b)?)))
+                })
+            }),
+            ("infinite", 0) => out(JSON::JSONNum(f64::INFINITY)),
+            ("nan", 0) => out(JSON::JSONNum(f64::NAN)),
+            ("isnan", 0) => out(JSON::JSONBool(to_number(input)?.is_nan())),
+            ("isinfinite", 0) => out(JSON::JSONBool(to_number(input)?.is_infinite())),
+            (name, 0) => match math(name) {
+                Some(f) => out(JSON::JSONNum(f(to_number(input)?))),
+                None => error(format!("{}/0 is not defined", name)),
+            },
+            (name, arity) => error(format!("{}/{} is not defined", name, arity)),
+        }
+    }
+
+    pub fn run(&self, ast: &Ast, input: &JSON) -> Outputs {
+        let env = Rc::new(Env::Root);
+        self.collect(ast, input, &env)
+    }
+}
@@ -0,0 +1,361 @@
+use super::JqError;
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StrPart {
+    Lit(String),
+    Interp(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tok {
+    Eof,
+    Dot,
+    DotDot,
+    Field(String),
+    Ident(String),
+    Keyword(&'static str),
+    Var(String),
+    Num(f64),
+    Str(Vec<StrPart>),
+    LParen,
+    RParen,
+    LBrack,
+    RBrack,
+    LBrace,
+    RBrace,
+    Pipe,
+    Comma,
+    Colon,
+    Semi,
+    Question,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Alt,
+    Assign(&'static str),
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+const KEYWORDS: [&str; 13] = [
+    "as", "def", "if", "then", "elif", "else", "end", "reduce", "foreach", "try", "catch", "and",
+    "or",
+];
+
+pub struct Lexer<'a> {
+    input: Peekable<Chars<'a>>,
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer {
+            input: input.chars().peekable(),
+        }
+    }
+
+    fn error(&self, err_msg: String) -> Result<Tok, JqError> {
+        Err(JqError::new(err_msg))
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.input.peek().cloned()
+    }
+
+    fn next_if(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.input.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                for c in self.input.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            } else if c.is_whitespace() {
+                self.input.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn get_ident(&mut self) -> String {
+        let mut ident = String::new();
+
+        while let Some(c) = self.peek() {
+            if is_ident_char(c) {
+                ident.push(c);
+                self.input.next();
+            } else if c == ':' {
+                // namespaced names like `foo::bar`
+                let mut ahead = self.input.clone();
+                ahead.next();
+                if ahead.peek() == Some(&':') {
+                    ident.push_str("::");
+                    self.input.next();
+                    self.input.next();
+                } else {
+                    break;
+                }
+            } else {
+                break;
+            }
+        }
+
+        ident
+    }
+
+    fn get_number(&mut self, first: char) -> Result<Tok, JqError> {
+        let mut val = first.to_string();
+
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || c == '.' {
+                val.push(c);
+                self.input.next();
+            } else if c == 'e' || c == 'E' {
+                val.push(c);
+                self.input.next();
+                if let Some(sign) = self.peek() {
+                    if sign == '+' || sign == '-' {
+                        val.push(sign);
+                        self.input.next();
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+
+        match val.parse() {
+            Ok(x) => Ok(Tok::Num(x)),
+            Err(_) => self.error(format!("Invalid number literal: {}", val)),
+        }
+    }
+
+    fn get_escape_char(&mut self, string: &mut String) -> Result<(), JqError> {
+        let err = || JqError::new("Invalid escape in string literal".to_string());
+
+        match self.input.next() {
+            Some('\\') => string.push('\\'),
+            Some('\"') => string.push('\"'),
+            Some('/') => string.push('/'),
+            Some('b') => string.push('\x08'),
+            Some('f') => string.push('\x0c'),
+            Some('n') => string.push('\n'),
+            Some('r') => string.push('\r'),
+            Some('t') => string.push('\t'),
+            Some('u') => {
+                let mut code_pt = String::new();
+                for _ in 0..4 {
+                    match self.input.next() {
+                        Some(c) if c.is_ascii_hexdigit() => code_pt.push(c),
+                        _ => return Err(err()),
+                    }
+                }
+
+                let code_pt = u32::from_str_radix(&code_pt, 16).map_err(|_| err())?;
+                string.push(std::char::from_u32(code_pt).ok_or_else(err)?);
+            }
+            _ => return Err(err()),
+        }
+
+        Ok(())
+    }
+
+    // Collects the source of a `\(...)` interpolation, stopping at the
+    // parenthesis that closes it. Nested strings may contain parentheses of
+    // their own, so they are skipped over whole.
+    fn get_interp(&mut self) -> Result<String, JqError> {
+        let mut src = String::new();
+        let mut depth = 0;
+        let mut in_string = false;
+
+        while let Some(c) = self.input.next() {
+            if in_string {
+                src.push(c);
+                if c == '\\' {
+                    if let Some(c) = self.input.next() {
+                        src.push(c);
+                    }
+                } else if c == '\"' {
+                    in_string = false;
+                }
+                continue;
+            }
+
+            match c {
+                '\"' => in_string = true,
+                '(' => depth += 1,
+                ')' if depth == 0 => return Ok(src),
+                ')' => depth -= 1,
+                _ => (),
+            }
+            src.push(c);
+        }
+
+        Err(JqError::new(
+            "Unterminated string interpolation".to_string(),
+        ))
+    }
+
+    fn get_string(&mut self) -> Result<Tok, JqError> {
+        let mut parts = Vec::new();
+        let mut val = String::new();
+
+        while let Some(c) = self.input.next() {
+            match c {
+                '\"' => {
+                    if !val.is_empty() || parts.is_empty() {
+                        parts.push(StrPart::Lit(val));
+                    }
+                    return Ok(Tok::Str(parts));
+                }
+                '\\' => {
+                    if self.next_if('(') {
+                        if !val.is_empty() {
+                            parts.push(StrPart::Lit(val));
+                            val = String::new();
+                        }
+                        parts.push(StrPart::Interp(self.get_interp()?));
+                    } else {
+                        self.get_escape_char(&mut val)?;
+                    }
+                }
+                _ => val.push(c),
+            }
+        }
+
+        self.error("Unterminated string literal".to_string())
+    }
+
+    pub fn next_token(&mut self) -> Result<Tok, JqError> {
+        self.skip_spaces();
+
+        let c = match self.input.next() {
+            Some(c) => c,
+            None => return Ok(Tok::Eof),
+        };
+
+        let tok = match c {
+            '.' => {
+                if self.next_if('.') {
+                    Tok::DotDot
+                } else if self.peek().is_some_and(is_ident_start) {
+                    Tok::Field(self.get_ident())
+                } else {
+                    Tok::Dot
+                }
+            }
+            '$' => {
+                if self.peek().is_some_and(is_ident_start) {
+                    Tok::Var(self.get_ident())
+                } else {
+                    return self.error("Expecting variable name after $".to_string());
+                }
+            }
+            '\"' => return self.get_string(),
+            '(' => Tok::LParen,
+            ')' => Tok::RParen,
+            '[' => Tok::LBrack,
+            ']' => Tok::RBrack,
+            '{' => Tok::LBrace,
+            '}' => Tok::RBrace,
+            '|' => {
+                if self.next_if('=') {
+                    Tok::Assign("|=")
+                } else {
+                    Tok::Pipe
+                }
+            }
+            ',' => Tok::Comma,
+            ':' => Tok::Colon,
+            ';' => Tok::Semi,
+            '?' => Tok::Question,
+            '+' | '-' | '*' | '%' if self.peek() == Some('=') => {
+                self.input.next();
+                match c {
+                    '+' => Tok::Assign("+="),
+                    '-' => Tok::Assign("-="),
+                    '*' => Tok::Assign("*="),
+                    _ => Tok::Assign("%="),
+                }
+            }
+            '+' => Tok::Plus,
+            '-' => Tok::Minus,
+            '*' => Tok::Star,
+            '%' => Tok::Percent,
+            '/' => {
+                if self.next_if('/') {
+                    if self.next_if('=') {
+                        Tok::Assign("//=")
+                    } else {
+                        Tok::Alt
+                    }
+                } else if self.next_if('=') {
+                    Tok::Assign("/=")
+                } else {
+                    Tok::Slash
+                }
+            }
+            '=' => {
+                if self.next_if('=') {
+                    Tok::Eq
+                } else {
+                    Tok::Assign("=")
+                }
+            }
+            '!' if self.next_if('=') => Tok::Ne,
+            '<' => {
+                if self.next_if('=') {
+                    Tok::Le
+                } else {
+                    Tok::Lt
+                }
+            }
+            '>' => {
+                if self.next_if('=') {
+                    Tok::Ge
+                } else {
+                    Tok::Gt
+                }
+            }
+            _ if c.is_ascii_digit() => return self.get_number(c),
+            _ if is_ident_start(c) => {
+                let mut ident = c.to_string();
+                ident.push_str(&self.get_ident());
+
+                match KEYWORDS.iter().find(|k| **k == ident) {
+                    Some(keyword) => Tok::Keyword(keyword),
+                    None => Tok::Ident(ident),
+                }
+            }
+            _ => return self.error(format!("Unexpected character: {}", c)),
+        };
+
+        Ok(tok)
+    }
+}
@@ -0,0 +1,70 @@
+//! A subset of the [jq](https://jqlang.github.io/jq/manual/) language,
+//! evaluated directly over `JSON` values.
+//!
+//! Programs are compiled once with `compile` and can then be run against
+//! any number of inputs. Every filter produces zero or more outputs, which
+//! are generated one at a time, so `limit` and `first` stop pulling from
+//! their argument early, and the outputs of the whole program are returned
+//! as a `Vec`. Recursion that runs too deep fails with an error rather than
+//! overflowing the stack.
+
+use crate::json::JSON;
+use crate::serializer;
+
+mod eval;
+mod lexer;
+mod parser;
+
+#[cfg(test)]
+mod tests;
+
+use self::eval::Interpreter;
+use self::parser::{Ast, Parser};
+
+pub use self::eval::compare;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JqError {
+    pub err_msg: String,
+    pub value: JSON,
+}
+
+impl JqError {
+    fn new(err_msg: String) -> Self {
+        JqError {
+            value: JSON::JSONString(err_msg.clone()),
+            err_msg,
+        }
+    }
+
+    fn from_value(value: JSON) -> Self {
+        let err_msg = match value {
+            JSON::JSONString(ref x) => x.clone(),
+            _ => format!("{} (not a string)", serializer::to_string(&value)),
+        };
+
+        JqError { err_msg, value }
+    }
+}
+
+pub struct Filter {
+    ast: Ast,
+}
+
+impl Filter {
+    pub fn run(&self, input: &JSON) -> Result<Vec<JSON>, JqError> {
+        Interpreter::new().run(&self.ast, input)
+    }
+}
+
+pub fn compile(program: &str) -> Result<Filter, JqError> {
+    let source = format!("{}{}", eval::PRELUDE, program);
+    let mut parser = Parser::new(&source)?;
+    let ast = parser.parse()?;
+
+    Ok(Filter { ast })
+}
+
+pub fn run(program: &str, input: &JSON) -> Result<Vec<JSON>, JqError> {
+    compile(program)?.run(input)
+}
@@ -0,0 +1,625 @@
+use super::lexer::{Lexer, StrPart, Tok};
+use super::JqError;
+use crate::json::JSON;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssignOp {
+    Set,
+    Update,
+    Arith(BinOp),
+    Alt,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StrAst {
+    Lit(String),
+    Interp(Ast),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuncDef {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Ast,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ast {
+    Identity,
+    RecurseAll,
+    Literal(JSON),
+    Str(Vec<StrAst>),
+    Var(String),
+    Index(Box<Ast>, Box<Ast>),
+    Slice(Box<Ast>, Option<Box<Ast>>, Option<Box<Ast>>),
+    Iterate(Box<Ast>),
+    Try(Box<Ast>, Option<Box<Ast>>),
+    Array(Option<Box<Ast>>),
+    Object(Vec<(Ast, Ast)>),
+    Neg(Box<Ast>),
+    Binary(BinOp, Box<Ast>, Box<Ast>),
+    And(Box<Ast>, Box<Ast>),
+    Or(Box<Ast>, Box<Ast>),
+    Alt(Box<Ast>, Box<Ast>),
+    Assign(AssignOp, Box<Ast>, Box<Ast>),
+    Pipe(Box<Ast>, Box<Ast>),
+    Comma(Box<Ast>, Box<Ast>),
+    If(Box<Ast>, Box<Ast>, Box<Ast>),
+    Reduce(Box<Ast>, String, Box<Ast>, Box<Ast>),
+    Foreach(Box<Ast>, String, Box<Ast>, Box<Ast>, Option<Box<Ast>>),
+    Bind(Box<Ast>, String, Box<Ast>),
+    FuncDef(Box<FuncDef>, Box<Ast>),
+    Call(String, Vec<Ast>),
+}
+
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
+    curr_token: Tok,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Result<Self, JqError> {
+        let mut lexer = Lexer::new(input);
+        let curr_token = lexer.next_token()?;
+
+        Ok(Parser { lexer, curr_token })
+    }
+
+    fn cont(&mut self) -> Result<Tok, JqError> {
+        let next = self.lexer.next_token()?;
+
+        Ok(std::mem::replace(&mut self.curr_token, next))
+    }
+
+    fn matches(&mut self, tok: &Tok) -> Result<bool, JqError> {
+        if self.curr_token == *tok {
+            self.cont()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, tok: Tok, err_msg: &str) -> Result<(), JqError> {
+        if self.curr_token == tok {
+            self.cont()?;
+            Ok(())
+        } else {
+            Err(self.unexpected(err_msg))
+        }
+    }
+
+    fn unexpected(&self, err_msg: &str) -> JqError {
+        JqError::new(format!("{}, found {:?}", err_msg, self.curr_token))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<bool, JqError> {
+        match self.curr_token {
+            Tok::Keyword(k) if k == keyword => {
+                self.cont()?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), JqError> {
+        if self.keyword(keyword)? {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("Expecting `{}`", keyword)))
+        }
+    }
+
+    fn get_var(&mut self) -> Result<String, JqError> {
+        match self.cont()? {
+            Tok::Var(name) => Ok(name),
+            _ => Err(JqError::new("Expecting variable after `as`".to_string())),
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<Ast, JqError> {
+        let ast = self.parse_pipe()?;
+
+        if self.curr_token != Tok::Eof {
+            return Err(self.unexpected("Expecting end of program"));
+        }
+
+        Ok(ast)
+    }
+
+    fn parse_def(&mut self) -> Result<FuncDef, JqError> {
+        let name = match self.cont()? {
+            Tok::Ident(name) => name,
+            Tok::Keyword(name) => name.to_string(),
+            _ => return Err(JqError::new("Expecting function name".to_string())),
+        };
+
+        let mut params = Vec::new();
+        if self.matches(&Tok::LParen)? {
+            loop {
+                match self.cont()? {
+                    Tok::Ident(param) => params.push(param),
+                    Tok::Var(param) => params.push(format!("${}", param)),
+                    _ => return Err(JqError::new("Expecting parameter name".to_string())),
+                }
+
+                if !self.matches(&Tok::Semi)? {
+                    break;
+                }
+            }
+            self.expect(Tok::RParen, "Expecting `)` after parameters")?;
+        }
+
+        self.expect(Tok::Colon, "Expecting `:` after function name")?;
+        let mut body = self.parse_pipe()?;
+        self.expect(Tok::Semi, "Expecting `;` after function body")?;
+
+        // `def f($a): body` is sugar for `def f(a): a as $a | body`
+        for param in params.iter_mut().rev() {
+            if param.starts_with('$') {
+                param.remove(0);
+                body = Ast::Bind(
+                    Box::new(Ast::Call(param.clone(), Vec::new())),
+                    param.clone(),
+                    Box::new(body),
+                );
+            }
+        }
+
+        Ok(FuncDef { name, params, body })
+    }
+
+    fn parse_pipe(&mut self) -> Result<Ast, JqError> {
+        if self.keyword("def")? {
+            let def = self.parse_def()?;
+            let rest = self.parse_pipe()?;
+
+            return Ok(Ast::FuncDef(Box::new(def), Box::new(rest)));
+        }
+
+        let lhs = self.parse_comma()?;
+
+        if self.matches(&Tok::Pipe)? {
+            let rhs = self.parse_pipe()?;
+            Ok(Ast::Pipe(Box::new(lhs), Box::new(rhs)))
+        } else {
+            Ok(lhs)
+        }
+    }
+
+    fn parse_comma(&mut self) -> Result<Ast, JqError> {
+        let mut lhs = self.parse_alt()?;
+
+        while self.matches(&Tok::Comma)? {
+            let rhs = self.parse_alt()?;
+            lhs = Ast::Comma(Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_alt(&mut self) -> Result<Ast, JqError> {
+        let lhs = self.parse_assign()?;
+
+        if self.matches(&Tok::Alt)? {
+            let rhs = self.parse_alt()?;
+            Ok(Ast::Alt(Box::new(lhs), Box::new(rhs)))
+        } else {
+            Ok(lhs)
+        }
+    }
+
+    fn parse_assign(&mut self) -> Result<Ast, JqError> {
+        let lhs = self.parse_or()?;
+
+        let op = match self.curr_token {
+            Tok::Assign("=") => AssignOp::Set,
+            Tok::Assign("|=") => AssignOp::Update,
+            Tok::Assign("+=") => AssignOp::Arith(BinOp::Add),
+            Tok::Assign("-=") => AssignOp::Arith(BinOp::Sub),
+            Tok::Assign("*=") => AssignOp::Arith(BinOp::Mul),
+            Tok::Assign("/=") => AssignOp::Arith(BinOp::Div),
+            Tok::Assign("%=") => AssignOp::Arith(BinOp::Mod),
+            Tok::Assign("//=") => AssignOp::Alt,
+            _ => return Ok(lhs),
+        };
+        self.cont()?;
+
+        let rhs = self.parse_or()?;
+        Ok(Ast::Assign(op, Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn parse_or(&mut self) -> Result<Ast, JqError> {
+        let mut lhs = self.parse_and()?;
+
+        while self.keyword("or")? {
+            let rhs = self.parse_and()?;
+            lhs = Ast::Or(Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Ast, JqError> {
+        let mut lhs = self.parse_comparison()?;
+
+        while self.keyword("and")? {
+            let rhs = self.parse_comparison()?;
+            lhs = Ast::And(Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Ast, JqError> {
+        let lhs = self.parse_additive()?;
+
+        let op = match self.curr_token {
+            Tok::Eq => BinOp::Eq,
+            Tok::Ne => BinOp::Ne,
+            Tok::Lt => BinOp::Lt,
+            Tok::Le => BinOp::Le,
+            Tok::Gt => BinOp::Gt,
+            Tok::Ge => BinOp::Ge,
+            _ => return Ok(lhs),
+        };
+        self.cont()?;
+
+        let rhs = self.parse_additive()?;
+        Ok(Ast::Binary(op, Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn parse_additive(&mut self) -> Result<Ast, JqError> {
+        let mut lhs = self.parse_multiplicative()?;
+
+        loop {
+            let op = match self.curr_token {
+                Tok::Plus => BinOp::Add,
+                Tok::Minus => BinOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.cont()?;
+
+            let rhs = self.parse_multiplicative()?;
+            lhs = Ast::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Ast, JqError> {
+        let mut lhs = self.parse_unary()?;
+
+        loop {
+            let op = match self.curr_token {
+                Tok::Star => BinOp::Mul,
+                Tok::Slash => BinOp::Div,
+                Tok::Percent => BinOp::Mod,
+                _ => return Ok(lhs),
+            };
+            self.cont()?;
+
+            let rhs = self.parse_unary()?;
+            lhs = Ast::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Ast, JqError> {
+        if self.matches(&Tok::Minus)? {
+            let operand = self.parse_unary()?;
+            Ok(Ast::Neg(Box::new(operand)))
+        } else {
+            self.parse_postfix(true)
+        }
+    }
+
+    fn parse_postfix(&mut self, allow_bind: bool) -> Result<Ast, JqError> {
+        let mut term = self.parse_term()?;
+
+        loop {
+            match self.curr_token {
+                Tok::Field(_) => {
+                    if let Tok::Field(name) = self.cont()? {
+                        term = index(term, Ast::Literal(JSON::JSONString(name)));
+                    }
+                }
+                Tok::Dot => {
+                    self.cont()?;
+                    match self.curr_token {
+                        Tok::Str(_) => {
+                            let key = self.parse_term()?;
+                            term = index(term, key);
+                        }
+                        Tok::LBrack => term = self.parse_bracket_suffix(term)?,
+                        _ => return Err(self.unexpected("Expecting field name after `.`")),
+                    }
+                }
+                Tok::LBrack => term = self.parse_bracket_suffix(term)?,
+                Tok::Question => {
+                    self.cont()?;
+                    term = Ast::Try(Box::new(term), None);
+                }
+                Tok::Keyword("as") if allow_bind => {
+                    self.cont()?;
+                    let name = self.get_var()?;
+                    self.expect(Tok::Pipe, "Expecting `|` after variable binding")?;
+                    let body = self.parse_pipe()?;
+
+                    return Ok(Ast::Bind(Box::new(term), name, Box::new(body)));
+                }
+                _ => return Ok(term),
+            }
+        }
+    }
+
+    fn parse_bracket_suffix(&mut self, term: Ast) -> Result<Ast, JqError> {
+        self.expect(Tok::LBrack, "Expecting `[`")?;
+
+        if self.matches(&Tok::RBrack)? {
+            return Ok(Ast::Iterate(Box::new(term)));
+        }
+
+        if self.matches(&Tok::Colon)? {
+            let to = self.parse_pipe()?;
+            self.expect(Tok::RBrack, "Expecting `]` after slice")?;
+
+            return Ok(Ast::Slice(Box::new(term), None, Some(Box::new(to))));
+        }
+
+        let idx = self.parse_pipe()?;
+
+        if self.matches(&Tok::Colon)? {
+            let to = if self.curr_token == Tok::RBrack {
+                None
+            } else {
+                Some(Box::new(self.parse_pipe()?))
+            };
+            self.expect(Tok::RBrack, "Expecting `]` after slice")?;
+
+            return Ok(Ast::Slice(Box::new(term), Some(Box::new(idx)), to));
+        }
+
+        self.expect(Tok::RBrack, "Expecting `]` after index")?;
+
+        Ok(index(term, idx))
+    }
+
+    fn parse_string(&mut self, parts: Vec<StrPart>) -> Result<Ast, JqError> {
+        let mut result = Vec::new();
+
+        for part in parts {
+            match part {
+                StrPart::Lit(x) => result.push(StrAst::Lit(x)),
+                StrPart::Interp(src) => {
+                    let mut parser = Parser::new(&src)?;
+                    result.push(StrAst::Interp(parser.parse()?));
+                }
+            }
+        }
+
+        if let [StrAst::Lit(ref x)] = result[..] {
+            return Ok(Ast::Literal(JSON::JSONString(x.clone())));
+        }
+
+        Ok(Ast::Str(result))
+    }
+
+    fn parse_object(&mut self) -> Result<Ast, JqError> {
+        let mut entries = Vec::new();
+
+        if self.matches(&Tok::RBrace)? {
+            return Ok(Ast::Object(entries));
+        }
+
+        loop {
+            let (key, shorthand) = match self.cont()? {
+                Tok::Ident(name) => {
+                    let key = Ast::Literal(JSON::JSONString(name.clone()));
+                    (
+                        key,
+                        index(Ast::Identity, Ast::Literal(JSON::JSONString(name))),
+                    )
+                }
+                Tok::Keyword(name) => {
+                    let key = Ast::Literal(JSON::JSONString(name.to_string()));
+                    let value = Ast::Literal(JSON::JSONString(name.to_string()));
+                    (key, index(Ast::Identity, value))
+                }
+                Tok::Var(name) => {
+                    let key = Ast::Literal(JSON::JSONString(name.clone()));
+                    (key, Ast::Var(name))
+                }
+                Tok::Str(parts) => {
+                    let key = self.parse_string(parts)?;
+                    (key.clone(), index(Ast::Identity, key))
+                }
+                Tok::LParen => {
+                    let key = self.parse_pipe()?;
+                    self.expect(Tok::RParen, "Expecting `)` after object key")?;
+                    (key.clone(), index(Ast::Identity, key))
+                }
+                _ => return Err(JqError::new("Invalid object key".to_string())),
+            };
+
+            let value = if self.matches(&Tok::Colon)? {
+                self.parse_object_value()?
+            } else {
+                shorthand
+            };
+            entries.push((key, value));
+
+            if !self.matches(&Tok::Comma)? {
+                break;
+            }
+        }
+
+        self.expect(Tok::RBrace, "Expecting `}` at end of object")?;
+
+        Ok(Ast::Object(entries))
+    }
+
+    // Object values stop at a comma, so `{a: 1, b: 2}` has two entries. A
+    // pipe is still allowed, as in `{a: .x | length}`.
+    fn parse_object_value(&mut self) -> Result<Ast, JqError> {
+        let lhs = self.parse_alt()?;
+
+        if self.matches(&Tok::Pipe)? {
+            let rhs = self.parse_object_value()?;
+            Ok(Ast::Pipe(Box::new(lhs), Box::new(rhs)))
+        } else {
+            Ok(lhs)
+        }
+    }
+
+    fn parse_if(&mut self) -> Result<Ast, JqError> {
+        let cond = self.parse_pipe()?;
+        self.expect_keyword("then")?;
+        let then = self.parse_pipe()?;
+
+        let otherwise = if self.keyword("elif")? {
+            self.parse_if()?
+        } else if self.keyword("else")? {
+            let otherwise = self.parse_pipe()?;
+            self.expect_keyword("end")?;
+            otherwise
+        } else {
+            self.expect_keyword("end")?;
+            Ast::Identity
+        };
+
+        Ok(Ast::If(Box::new(cond), Box::new(then), Box::new(otherwise)))
+    }
+
+    fn parse_args(&mut self) -> Result<Vec<Ast>, JqError> {
+        let mut args = Vec::new();
+
+        if self.matches(&Tok::LParen)? {
+            args.push(self.parse_pipe()?);
+            while self.matches(&Tok::Semi)? {
+                args.push(self.parse_pipe()?);
+            }
+            self.expect(Tok::RParen, "Expecting `)` after arguments")?;
+        }
+
+        Ok(args)
+    }
+
+    fn parse_term(&mut self) -> Result<Ast, JqError> {
+        let token = self.cont()?;
+
+        match token {
+            Tok::Dot => match self.curr_token {
+                Tok::Str(_) => {
+                    let key = self.parse_term()?;
+                    Ok(index(Ast::Identity, key))
+                }
+                Tok::LBrack => self.parse_bracket_suffix(Ast::Identity),
+                _ => Ok(Ast::Identity),
+            },
+            Tok::DotDot => Ok(Ast::RecurseAll),
+            Tok::Field(name) => Ok(index(Ast::Identity, Ast::Literal(JSON::JSONString(name)))),
+            Tok::Num(x) => Ok(Ast::Literal(JSON::JSONNum(x))),
+            Tok::Str(parts) => self.parse_string(parts),
+            Tok::Var(name) => Ok(Ast::Var(name)),
+            Tok::LParen => {
+                let inner = self.parse_pipe()?;
+                self.expect(Tok::RParen, "Expecting `)`")?;
+                Ok(inner)
+            }
+            Tok::LBrack => {
+                if self.matches(&Tok::RBrack)? {
+                    return Ok(Ast::Array(None));
+                }
+
+                let inner = self.parse_pipe()?;
+                self.expect(Tok::RBrack, "Expecting `]` at end of array")?;
+                Ok(Ast::Array(Some(Box::new(inner))))
+            }
+            Tok::LBrace => self.parse_object(),
+            Tok::Keyword("if") => self.parse_if(),
+            Tok::Keyword("try") => {
+                let body = self.parse_postfix(false)?;
+                let catch = if self.keyword("catch")? {
+                    Some(Box::new(self.parse_postfix(false)?))
+                } else {
+                    None
+                };
+                Ok(Ast::Try(Box::new(body), catch))
+            }
+            Tok::Keyword("reduce") => {
+                let source = self.parse_postfix(false)?;
+                self.expect_keyword("as")?;
+                let name = self.get_var()?;
+                self.expect(Tok::LParen, "Expecting `(` after reduce binding")?;
+                let init = self.parse_pipe()?;
+                self.expect(Tok::Semi, "Expecting `;` in reduce")?;
+                let update = self.parse_pipe()?;
+                self.expect(Tok::RParen, "Expecting `)` at end of reduce")?;
+
+                Ok(Ast::Reduce(
+                    Box::new(source),
+                    name,
+                    Box::new(init),
+                    Box::new(update),
+                ))
+            }
+            Tok::Keyword("foreach") => {
+                let source = self.parse_postfix(false)?;
+                self.expect_keyword("as")?;
+                let name = self.get_var()?;
+                self.expect(Tok::LParen, "Expecting `(` after foreach binding")?;
+                let init = self.parse_pipe()?;
+                self.expect(Tok::Semi, "Expecting `;` in foreach")?;
+                let update = self.parse_pipe()?;
+                let extract = if self.matches(&Tok::Semi)? {
+                    Some(Box::new(self.parse_pipe()?))
+                } else {
+                    None
+                };
+                self.expect(Tok::RParen, "Expecting `)` at end of foreach")?;
+
+                Ok(Ast::Foreach(
+                    Box::new(source),
+                    name,
+                    Box::new(init),
+                    Box::new(update),
+                    extract,
+                ))
+            }
+            Tok::Keyword("def") => {
+                let def = self.parse_def()?;
+                let rest = self.parse_pipe()?;
+                Ok(Ast::FuncDef(Box::new(def), Box::new(rest)))
+            }
+            Tok::Ident(name) => {
+                let args = self.parse_args()?;
+
+                match (name.as_str(), args.len()) {
+                    ("true", 0) => Ok(Ast::Literal(JSON::JSONBool(true))),
+                    ("false", 0) => Ok(Ast::Literal(JSON::JSONBool(false))),
+                    ("null", 0) => Ok(Ast::Literal(JSON::JSONNull)),
+                    _ => Ok(Ast::Call(name, args)),
+                }
+            }
+            _ => Err(JqError::new(format!("Unexpected token {:?}", token))),
+        }
+    }
+}
+
+fn index(term: Ast, key: Ast) -> Ast {
+    Ast::Index(Box::new(term), Box::new(key))
+}
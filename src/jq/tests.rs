@@ -0,0 +1,215 @@
+use crate::jq::{compile, run, JqError};
+use crate::json::JSON;
+
+fn jq(program: &str, input: &str) -> Vec<String> {
+    let input = crate::parse(input).unwrap();
+    let outputs = run(program, &input).unwrap();
+
+    outputs.iter().map(crate::serializer::to_string).collect()
+}
+
+#[test]
+fn test_paths() {
+    let input = r#"{"a": {"b": [10, 20, 30]}, "c": null}"#;
+
+    assert_eq!(jq(".a.b[1]", input), vec!["20"]);
+    assert_eq!(jq(".a.b[-1]", input), vec!["30"]);
+    assert_eq!(jq(r#".["a"].b[1:]"#, input), vec!["[20,30]"]);
+    assert_eq!(jq(".c.d", input), vec!["null"]);
+    assert_eq!(jq(".a.b[]", input), vec!["10", "20", "30"]);
+}
+
+#[test]
+fn test_pipe_and_comma() {
+    let input = r#"[{"name": "x", "n": 1}, {"name": "y", "n": 2}]"#;
+    let actual = jq(".[] | .name, .n", input);
+    let expected = vec![r#""x""#, "1", r#""y""#, "2"];
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_map_select() {
+    let input = "[1, 2, 3, 4, 5, 6]";
+    let actual = jq("map(select(. % 2 == 0) | . * 10)", input);
+
+    assert_eq!(actual, vec!["[20,40,60]"]);
+}
+
+#[test]
+fn test_keys_and_entries() {
+    let input = r#"{"b": 2, "a": 1}"#;
+
+    assert_eq!(jq("keys", input), vec![r#"["a","b"]"#]);
+    assert_eq!(
+        jq("to_entries", input),
+        vec![r#"[{"key":"a","value":1},{"key":"b","value":2}]"#]
+    );
+}
+
+#[test]
+fn test_with_entries() {
+    let input = r#"{"b": 2, "a": 1}"#;
+    let actual = jq("with_entries(.value = 0) ", input);
+
+    assert_eq!(actual, vec![r#"{"a":0,"b":0}"#]);
+}
+
+#[test]
+fn test_assignment() {
+    let input = r#"{"a": {"b": 1}, "c": [1, 2, 3]}"#;
+
+    assert_eq!(jq(".a.b = 5 | .a", input), vec![r#"{"b":5}"#]);
+    assert_eq!(jq(".c[] |= . * 2 | .c", input), vec!["[2,4,6]"]);
+    assert_eq!(jq(".a.b += 1 | .a.b", input), vec!["2"]);
+    assert_eq!(jq(".d //= 7 | .d", input), vec!["7"]);
+    assert_eq!(jq("del(.c[0, 2]) | .c", input), vec!["[2]"]);
+    assert_eq!(jq("[paths(type == \"number\")] | length", input), vec!["4"]);
+}
+
+#[test]
+fn test_construction() {
+    let input = r#"{"user": "ann", "titles": ["a", "b"]}"#;
+    let actual = jq("{user, title: .titles[]}", input);
+    let expected = vec![
+        r#"{"title":"a","user":"ann"}"#,
+        r#"{"title":"b","user":"ann"}"#,
+    ];
+
+    assert_eq!(actual, expected);
+    assert_eq!(
+        jq("[.titles[] | ascii_upcase]", input),
+        vec![r#"["A","B"]"#]
+    );
+    assert_eq!(jq(r#"{(.user): 1}"#, input), vec![r#"{"ann":1}"#]);
+}
+
+#[test]
+fn test_reduce() {
+    let actual = jq("reduce .[] as $x (0; . + $x)", "[1, 2, 3, 4]");
+
+    assert_eq!(actual, vec!["10"]);
+}
+
+#[test]
+fn test_arithmetic() {
+    assert_eq!(jq("(.a + 1) * 2 - 3 / 3", r#"{"a": 4}"#), vec!["9"]);
+    assert_eq!(
+        jq(r#". + {"b": 2}"#, r#"{"a": 1}"#),
+        vec![r#"{"a":1,"b":2}"#]
+    );
+    assert_eq!(jq(". - [2]", "[1, 2, 3]"), vec!["[1,3]"]);
+    assert_eq!(jq(r#""a,b" / ",""#, "null"), vec![r#"["a","b"]"#]);
+}
+
+#[test]
+fn test_string_interpolation() {
+    let actual = jq(
+        r#""\(.name) is \(.age + 1)""#,
+        r#"{"name": "ann", "age": 30}"#,
+    );
+
+    assert_eq!(actual, vec![r#""ann is 31""#]);
+}
+
+#[test]
+fn test_alternative() {
+    assert_eq!(jq(".a // 5", "{}"), vec!["5"]);
+    assert_eq!(jq(".a // 5", r#"{"a": false}"#), vec!["5"]);
+    assert_eq!(jq(".a // 5", r#"{"a": 0}"#), vec!["0"]);
+    assert_eq!(jq("(.a | error) // 1", r#"{"a": "x"}"#), vec!["1"]);
+}
+
+#[test]
+fn test_user_functions() {
+    let program =
+        "def inc(f): f + 1; def fac: if . <= 1 then 1 else . * (. - 1 | fac) end; [inc(.), fac]";
+
+    assert_eq!(jq(program, "5"), vec!["[6,120]"]);
+    assert_eq!(jq("def add($a; $b): $a + $b; add(1; 2)", "null"), vec!["3"]);
+}
+
+#[test]
+fn test_conditionals() {
+    let program = r#".[] | if . > 2 then "big" elif . > 1 then "mid" else "small" end"#;
+    let actual = jq(program, "[1, 2, 3]");
+
+    assert_eq!(actual, vec![r#""small""#, r#""mid""#, r#""big""#]);
+}
+
+#[test]
+fn test_try_catch() {
+    assert_eq!(
+        jq(r#"try error("boom") catch ."#, "null"),
+        vec![r#""boom""#]
+    );
+    assert_eq!(jq("[.[] | .a?]", r#"[1, {"a": 2}]"#), vec!["[2]"]);
+}
+
+#[test]
+fn test_runtime_error() {
+    let input = JSON::JSONNum(1.0);
+    let actual = run(".[]", &input);
+    let expected = Err(JqError::new("Cannot iterate over number (1)".to_string()));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_syntax_error() {
+    assert!(compile(".a |").is_err());
+    assert!(compile("[1, 2").is_err());
+    assert!(compile(r#""\(.a""#).is_err());
+}
+
+#[test]
+fn test_lazy_generators() {
+    assert_eq!(jq("[limit(3; repeat(. * 2))]", "1"), vec!["[1,2,4]"]);
+    assert_eq!(jq("first(range(infinite))", "null"), vec!["0"]);
+    assert_eq!(
+        jq("[limit(2; range(10) | select(. > 4))]", "null"),
+        vec!["[5,6]"]
+    );
+    assert_eq!(jq("isempty(repeat(1))", "null"), vec!["false"]);
+    assert_eq!(
+        jq("[try (1, error(\"x\"), 3) catch .]", "null"),
+        vec![r#"[1,"x"]"#]
+    );
+}
+
+#[test]
+fn test_recursion_limit() {
+    let fac = "def fac: if . <= 1 then 1 else . * (. - 1 | fac) end; fac";
+    let actual = run(fac, &JSON::JSONNum(5000.0));
+    let expected = Err(JqError::new("Maximum recursion depth exceeded".to_string()));
+
+    assert_eq!(actual, expected);
+    assert_eq!(jq(fac, "20"), vec!["2432902008176640000"]);
+    assert!(run("def f: first(f); f", &JSON::JSONNull).is_err());
+}
+
+#[test]
+fn test_resource_limits() {
+    for program in [
+        ".[1e30] = 1",
+        ".[1e9] = 1",
+        r#""x" * 1e300"#,
+        r#""x" * 1e10"#,
+    ] {
+        assert!(run(program, &JSON::JSONNull).is_err(), "{}", program);
+    }
+    assert_eq!(jq(".[2] = 1", "null"), vec!["[null,null,1]"]);
+    assert_eq!(jq(r#""ab" * 2.5"#, "null"), vec![r#""ababab""#]);
+}
+
+#[test]
+fn test_todate() {
+    assert_eq!(
+        jq("todate", "1700000000"),
+        vec![r#""2023-11-14T22:13:20Z""#]
+    );
+    assert_eq!(jq("todate", "0.5"), vec![r#""1970-01-01T00:00:00Z""#]);
+    assert_eq!(jq("todate", "-1"), vec![r#""1969-12-31T23:59:59Z""#]);
+    assert_eq!(jq("todate", "951782400"), vec![r#""2000-02-29T00:00:00Z""#]);
+    assert!(run("todate", &JSON::JSONNum(1e20)).is_err());
+}
@@ -1,7 +1,9 @@
+use crate::serializer::write_number;
+
 use std::collections::HashMap;
 use std::fmt;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum JSON {
     JSONNum(f64),
     JSONString(String),
@@ -13,12 +15,16 @@ pub enum JSON {
 
 impl fmt::Display for JSON {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            &JSON::JSONNum(ref x) => write!(f, "{}", x),
-            &JSON::JSONString(ref x) => write!(f, "\"{}\"", x),
-            &JSON::JSONNull => write!(f, "null"),
-            &JSON::JSONBool(ref x) => write!(f, "{}", x),
-            &JSON::JSONArray(ref x) => {
+        match *self {
+            JSON::JSONNum(x) => {
+                let mut text = String::new();
+                write_number(&mut text, x);
+                write!(f, "{}", text)
+            }
+            JSON::JSONString(ref x) => write!(f, "\"{}\"", x),
+            JSON::JSONNull => write!(f, "null"),
+            JSON::JSONBool(ref x) => write!(f, "{}", x),
+            JSON::JSONArray(ref x) => {
                 write!(f, "[").unwrap();
                 for (i, elem) in x.iter().enumerate() {
                     if i != 0 {
@@ -28,7 +34,7 @@ impl fmt::Display for JSON {
                 }
                 write!(f, "]")
             }
-            &JSON::JSONObject(ref x) => {
+            JSON::JSONObject(ref x) => {
                 write!(f, "{{").unwrap();
                 for (i, (key, val)) in x.iter().enumerate() {
                     if i != 0 {
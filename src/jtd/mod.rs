@@ -0,0 +1,731 @@
+//! [JSON Type Definition](https://www.rfc-editor.org/rfc/rfc8927) schemas.
+//!
+//! `Schema::compile` reads and checks a JTD schema; `Schema::validate`
+//! reports every place an instance departs from it as a pair of paths, one
+//! into the instance and one into the schema, as the RFC specifies.
+
+use crate::json::JSON;
+
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub err_msg: String,
+    /// Where in the schema the problem is, one token per step.
+    pub schema_path: Vec<String>,
+}
+
+impl SchemaError {
+    fn new(err_msg: String, schema_path: &[&str]) -> Self {
+        SchemaError {
+            err_msg,
+            schema_path: schema_path.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub instance_path: Vec<String>,
+    pub schema_path: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Type {
+    Boolean,
+    String,
+    Timestamp,
+    Float32,
+    Float64,
+    Int8,
+    Uint8,
+    Int16,
+    Uint16,
+    Int32,
+    Uint32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Form {
+    Empty,
+    Ref(String),
+    Type(Type),
+    Enum(Vec<String>),
+    Elements(Box<Schema>),
+    Properties {
+        required: HashMap<String, Schema>,
+        optional: HashMap<String, Schema>,
+        additional: bool,
+    },
+    Values(Box<Schema>),
+    Discriminator {
+        tag: String,
+        mapping: HashMap<String, Schema>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    /// Only ever set on the root schema.
+    pub definitions: HashMap<String, Schema>,
+    pub nullable: bool,
+    pub form: Form,
+}
+
+/// Limits on validation: `max_depth` bounds how many `ref`s may be
+/// followed in a row without moving into the instance, which only a cycle
+/// of definitions can exceed, and `max_errors` stops validation once that
+/// many errors are found. Zero means no limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    pub max_depth: usize,
+    pub max_errors: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            max_depth: 64,
+            max_errors: 0,
+        }
+    }
+}
+
+const KEYWORDS: [&str; 13] = [
+    "definitions",
+    "nullable",
+    "metadata",
+    "ref",
+    "type",
+    "enum",
+    "elements",
+    "properties",
+    "optionalProperties",
+    "additionalProperties",
+    "values",
+    "discriminator",
+    "mapping",
+];
+
+impl Type {
+    fn from_name(name: &str) -> Option<Type> {
+        Some(match name {
+            "boolean" => Type::Boolean,
+            "string" => Type::String,
+            "timestamp" => Type::Timestamp,
+            "float32" => Type::Float32,
+            "float64" => Type::Float64,
+            "int8" => Type::Int8,
+            "uint8" => Type::Uint8,
+            "int16" => Type::Int16,
+            "uint16" => Type::Uint16,
+            "int32" => Type::Int32,
+            "uint32" => Type::Uint32,
+            _ => return None,
+        })
+    }
+
+    fn matches(self, instance: &JSON) -> bool {
+        let (lo, hi) = match (self, instance) {
+            (Type::Boolean, JSON::JSONBool(_)) => return true,
+            (Type::String, JSON::JSONString(_)) => return true,
+            (Type::Timestamp, JSON::JSONString(x)) => return is_timestamp(x),
+            (Type::Float32, JSON::JSONNum(_)) | (Type::Float64, JSON::JSONNum(_)) => return true,
+            (Type::Int8, _) => (-128.0, 127.0),
+            (Type::Uint8, _) => (0.0, 255.0),
+            (Type::Int16, _) => (-32768.0, 32767.0),
+            (Type::Uint16, _) => (0.0, 65535.0),
+            (Type::Int32, _) => (-2147483648.0, 2147483647.0),
+            (Type::Uint32, _) => (0.0, 4294967295.0),
+            _ => return false,
+        };
+
+        match *instance {
+            JSON::JSONNum(x) => x.fract() == 0.0 && lo <= x && x <= hi,
+            _ => false,
+        }
+    }
+}
+
+impl Schema {
+    pub fn compile(input: &JSON) -> Result<Schema, SchemaError> {
+        let schema = parse_schema(input, true, &mut Vec::new())?;
+        schema.check_refs(&schema.definitions, &mut Vec::new())?;
+        for (name, def) in &schema.definitions {
+            def.check_refs(&schema.definitions, &mut vec!["definitions", name])?;
+        }
+
+        Ok(schema)
+    }
+
+    fn check_refs<'a>(
+        &'a self,
+        definitions: &HashMap<String, Schema>,
+        path: &mut Vec<&'a str>,
+    ) -> Result<(), SchemaError> {
+        let mut check = |key: &'a str, name: Option<&'a str>, schema: &'a Schema| {
+            path.push(key);
+            path.extend(name);
+            let result = schema.check_refs(definitions, path);
+            path.truncate(path.len() - 1 - name.iter().count());
+            result
+        };
+
+        match self.form {
+            Form::Ref(ref name) if !definitions.contains_key(name) => {
+                path.push("ref");
+                Err(SchemaError::new(
+                    format!("No definition named `{}`", name),
+                    path,
+                ))
+            }
+            Form::Elements(ref schema) => check("elements", None, schema),
+            Form::Values(ref schema) => check("values", None, schema),
+            Form::Properties {
+                ref required,
+                ref optional,
+                ..
+            } => {
+                for (name, schema) in required {
+                    check("properties", Some(name), schema)?;
+                }
+                for (name, schema) in optional {
+                    check("optionalProperties", Some(name), schema)?;
+                }
+                Ok(())
+            }
+            Form::Discriminator { ref mapping, .. } => {
+                for (name, schema) in mapping {
+                    check("mapping", Some(name), schema)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails only when `max_depth` is exceeded, with the path to the `ref`
+    /// that gave up.
+    pub fn validate(&self, instance: &JSON) -> Result<Vec<ValidationError>, SchemaError> {
+        self.validate_with(instance, &Options::default())
+    }
+
+    pub fn validate_with(
+        &self,
+        instance: &JSON,
+        options: &Options,
+    ) -> Result<Vec<ValidationError>, SchemaError> {
+        let mut validator = Validator {
+            definitions: &self.definitions,
+            options,
+            instance_path: Vec::new(),
+            schema_path: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        match validator.validate(self, instance, None, 0) {
+            Ok(()) | Err(Stop::MaxErrors) => Ok(validator.errors),
+            Err(Stop::MaxDepth) => Err(SchemaError::new(
+                "Maximum depth exceeded".to_string(),
+                &validator
+                    .schema_path
+                    .iter()
+                    .map(|x| x.as_str())
+                    .collect::<Vec<_>>(),
+            )),
+        }
+    }
+}
+
+fn parse_schema<'a>(
+    input: &'a JSON,
+    root: bool,
+    path: &mut Vec<&'a str>,
+) -> Result<Schema, SchemaError> {
+    let obj = match *input {
+        JSON::JSONObject(ref x) => x,
+        _ => {
+            return Err(SchemaError::new(
+                "A schema must be an object".to_string(),
+                path,
+            ))
+        }
+    };
+
+    let error = |path: &[&str], key: &str, err_msg: &str| {
+        let mut path = path.to_vec();
+        path.push(key);
+        Err(SchemaError::new(err_msg.to_string(), &path))
+    };
+
+    for key in obj.keys() {
+        if !KEYWORDS.contains(&key.as_str()) {
+            return error(path, key, "Unknown keyword");
+        }
+    }
+
+    let mut definitions = HashMap::new();
+    if let Some(value) = obj.get("definitions") {
+        if !root {
+            return error(
+                path,
+                "definitions",
+                "Only the root schema may have definitions",
+            );
+        }
+        path.push("definitions");
+        definitions = parse_map(value, path)?;
+        path.pop();
+    }
+
+    let nullable = match obj.get("nullable") {
+        Some(JSON::JSONBool(x)) => *x,
+        Some(_) => return error(path, "nullable", "Expecting a boolean"),
+        None => false,
+    };
+    if let Some(value) = obj.get("metadata") {
+        if !matches!(value, JSON::JSONObject(_)) {
+            return error(path, "metadata", "Expecting an object");
+        }
+    }
+
+    let has = |key: &str| obj.contains_key(key);
+    let forms = ["ref", "type", "enum", "elements", "values", "discriminator"]
+        .iter()
+        .filter(|&&key| has(key))
+        .count()
+        + (has("properties") || has("optionalProperties")) as usize;
+    if forms > 1 {
+        return Err(SchemaError::new(
+            "A schema may only have one form".to_string(),
+            path,
+        ));
+    }
+    if has("mapping") && !has("discriminator") {
+        return error(path, "mapping", "Only allowed with `discriminator`");
+    }
+    if has("additionalProperties") && !has("properties") && !has("optionalProperties") {
+        return error(
+            path,
+            "additionalProperties",
+            "Only allowed with `properties` or `optionalProperties`",
+        );
+    }
+
+    let form = if let Some(value) = obj.get("ref") {
+        match *value {
+            JSON::JSONString(ref x) => Form::Ref(x.clone()),
+            _ => return error(path, "ref", "Expecting a string"),
+        }
+    } else if let Some(value) = obj.get("type") {
+        match *value {
+            JSON::JSONString(ref x) => match Type::from_name(x) {
+                Some(x) => Form::Type(x),
+                None => return error(path, "type", "Unknown type"),
+            },
+            _ => return error(path, "type", "Expecting a string"),
+        }
+    } else if let Some(value) = obj.get("enum") {
+        let mut values: Vec<String> = Vec::new();
+        match *value {
+            JSON::JSONArray(ref x) if !x.is_empty() => {
+                for elem in x {
+                    match *elem {
+                        JSON::JSONString(ref x) if !values.contains(x) => values.push(x.clone()),
+                        _ => return error(path, "enum", "Expecting distinct strings"),
+                    }
+                }
+            }
+            _ => return error(path, "enum", "Expecting a non-empty array"),
+        }
+        Form::Enum(values)
+    } else if let Some(value) = obj.get("elements") {
+        path.push("elements");
+        let schema = parse_schema(value, false, path)?;
+        path.pop();
+        Form::Elements(Box::new(schema))
+    } else if has("properties") || has("optionalProperties") {
+        let mut required = HashMap::new();
+        let mut optional = HashMap::new();
+        if let Some(value) = obj.get("properties") {
+            path.push("properties");
+            required = parse_map(value, path)?;
+            path.pop();
+        }
+        if let Some(value) = obj.get("optionalProperties") {
+            path.push("optionalProperties");
+            optional = parse_map(value, path)?;
+            path.pop();
+        }
+        if let Some(name) = required.keys().find(|&key| optional.contains_key(key)) {
+            let mut path = path.clone();
+            path.push("optionalProperties");
+            path.push(name);
+            return Err(SchemaError::new(
+                "Property is both required and optional".to_string(),
+                &path,
+            ));
+        }
+
+        let additional = match obj.get("additionalProperties") {
+            Some(JSON::JSONBool(x)) => *x,
+            Some(_) => return error(path, "additionalProperties", "Expecting a boolean"),
+            None => false,
+        };
+        Form::Properties {
+            required,
+            optional,
+            additional,
+        }
+    } else if let Some(value) = obj.get("values") {
+        path.push("values");
+        let schema = parse_schema(value, false, path)?;
+        path.pop();
+        Form::Values(Box::new(schema))
+    } else if let Some(value) = obj.get("discriminator") {
+        parse_discriminator(value, obj, path)?
+    } else {
+        Form::Empty
+    };
+
+    Ok(Schema {
+        definitions,
+        nullable,
+        form,
+    })
+}
+
+fn parse_discriminator<'a>(
+    value: &'a JSON,
+    obj: &'a HashMap<String, JSON>,
+    path: &mut Vec<&'a str>,
+) -> Result<Form, SchemaError> {
+    let tag = match *value {
+        JSON::JSONString(ref x) => x.clone(),
+        _ => {
+            path.push("discriminator");
+            return Err(SchemaError::new("Expecting a string".to_string(), path));
+        }
+    };
+
+    path.push("mapping");
+    let mapping = match obj.get("mapping") {
+        Some(value) => parse_map(value, path)?,
+        None => return Err(SchemaError::new("Missing mapping".to_string(), path)),
+    };
+
+    // Each mapping must describe the rest of the object, without the tag.
+    for (name, schema) in &mapping {
+        let valid = match schema.form {
+            Form::Properties {
+                ref required,
+                ref optional,
+                ..
+            } => !schema.nullable && !required.contains_key(&tag) && !optional.contains_key(&tag),
+            _ => false,
+        };
+        if !valid {
+            let mut path = path.clone();
+            path.push(name);
+            return Err(SchemaError::new(
+                "A mapping must be a non-nullable properties schema without the tag".to_string(),
+                &path,
+            ));
+        }
+    }
+    path.pop();
+
+    Ok(Form::Discriminator { tag, mapping })
+}
+
+fn parse_map<'a>(
+    value: &'a JSON,
+    path: &mut Vec<&'a str>,
+) -> Result<HashMap<String, Schema>, SchemaError> {
+    let obj = match *value {
+        JSON::JSONObject(ref x) => x,
+        _ => return Err(SchemaError::new("Expecting an object".to_string(), path)),
+    };
+
+    let mut schemas = HashMap::new();
+    for (name, schema) in obj {
+        path.push(name);
+        schemas.insert(name.clone(), parse_schema(schema, false, path)?);
+        path.pop();
+    }
+
+    Ok(schemas)
+}
+
+enum Stop {
+    MaxDepth,
+    MaxErrors,
+}
+
+struct Validator<'a> {
+    definitions: &'a HashMap<String, Schema>,
+    options: &'a Options,
+    instance_path: Vec<String>,
+    schema_path: Vec<String>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'a> Validator<'a> {
+    fn error(&mut self, schema_key: &[&str]) -> Result<(), Stop> {
+        let mut schema_path = self.schema_path.clone();
+        schema_path.extend(schema_key.iter().map(|x| x.to_string()));
+
+        self.errors.push(ValidationError {
+            instance_path: self.instance_path.clone(),
+            schema_path,
+        });
+
+        if self.options.max_errors != 0 && self.errors.len() >= self.options.max_errors {
+            return Err(Stop::MaxErrors);
+        }
+        Ok(())
+    }
+
+    // Validates a child of the instance against a subschema, `schema_key`
+    // being the path from the current schema to the subschema.
+    fn descend(
+        &mut self,
+        schema: &Schema,
+        instance: &JSON,
+        instance_key: Option<&str>,
+        schema_key: &[&str],
+    ) -> Result<(), Stop> {
+        let mark = (self.instance_path.len(), self.schema_path.len());
+        self.instance_path.extend(instance_key.map(str::to_string));
+        self.schema_path
+            .extend(schema_key.iter().map(|x| x.to_string()));
+
+        self.validate(schema, instance, None, 0)?;
+
+        self.instance_path.truncate(mark.0);
+        self.schema_path.truncate(mark.1);
+        Ok(())
+    }
+
+    // `tag` is the discriminator property a mapping schema should ignore.
+    fn validate(
+        &mut self,
+        schema: &Schema,
+        instance: &JSON,
+        tag: Option<&str>,
+        depth: usize,
+    ) -> Result<(), Stop> {
+        if schema.nullable && *instance == JSON::JSONNull {
+            return Ok(());
+        }
+
+        match schema.form {
+            Form::Empty => (),
+            Form::Ref(ref name) => {
+                if self.options.max_depth != 0 && depth >= self.options.max_depth {
+                    self.schema_path.push("ref".to_string());
+                    return Err(Stop::MaxDepth);
+                }
+
+                // The schema path restarts at the definition.
+                let saved = std::mem::replace(
+                    &mut self.schema_path,
+                    vec!["definitions".to_string(), name.clone()],
+                );
+                let result = self.validate(&self.definitions[name], instance, None, depth + 1);
+                // Giving up leaves the path where it happened.
+                if let Err(Stop::MaxDepth) = result {
+                    return result;
+                }
+                self.schema_path = saved;
+                result?;
+            }
+            Form::Type(x) => {
+                if !x.matches(instance) {
+                    self.error(&["type"])?;
+                }
+            }
+            Form::Enum(ref values) => match *instance {
+                JSON::JSONString(ref x) if values.contains(x) => (),
+                _ => self.error(&["enum"])?,
+            },
+            Form::Elements(ref schema) => match *instance {
+                JSON::JSONArray(ref x) => {
+                    for (i, elem) in x.iter().enumerate() {
+                        self.descend(schema, elem, Some(&i.to_string()), &["elements"])?;
+                    }
+                }
+                _ => self.error(&["elements"])?,
+            },
+            Form::Properties {
+                ref required,
+                ref optional,
+                additional,
+            } => {
+                let obj = match *instance {
+                    JSON::JSONObject(ref x) => x,
+                    _ if required.is_empty() && !optional.is_empty() => {
+                        return self.error(&["optionalProperties"])
+                    }
+                    _ => return self.error(&["properties"]),
+                };
+
+                for (name, schema) in sorted(required) {
+                    match obj.get(name) {
+                        Some(value) => {
+                            self.descend(schema, value, Some(name), &["properties", name])?
+                        }
+                        None => self.error(&["properties", name])?,
+                    }
+                }
+                for (name, schema) in sorted(optional) {
+                    if let Some(value) = obj.get(name) {
+                        self.descend(schema, value, Some(name), &["optionalProperties", name])?;
+                    }
+                }
+
+                if !additional {
+                    let mut keys: Vec<&String> = obj.keys().collect();
+                    keys.sort();
+                    for key in keys {
+                        let known = required.contains_key(key)
+                            || optional.contains_key(key)
+                            || tag == Some(key.as_str());
+                        if !known {
+                            self.instance_path.push(key.clone());
+                            let result = self.error(&[]);
+                            self.instance_path.pop();
+                            result?;
+                        }
+                    }
+                }
+            }
+            Form::Values(ref schema) => match *instance {
+                JSON::JSONObject(ref x) => {
+                    let mut keys: Vec<&String> = x.keys().collect();
+                    keys.sort();
+                    for key in keys {
+                        self.descend(schema, &x[key], Some(key), &["values"])?;
+                    }
+                }
+                _ => self.error(&["values"])?,
+            },
+            Form::Discriminator {
+                ref tag,
+                ref mapping,
+            } => {
+                let obj = match *instance {
+                    JSON::JSONObject(ref x) => x,
+                    _ => return self.error(&["discriminator"]),
+                };
+
+                self.instance_path.push(tag.clone());
+                let result = match obj.get(tag) {
+                    None => {
+                        self.instance_path.pop();
+                        let result = self.error(&["discriminator"]);
+                        self.instance_path.push(tag.clone());
+                        result
+                    }
+                    Some(JSON::JSONString(x)) => match mapping.get(x) {
+                        Some(schema) => {
+                            self.instance_path.pop();
+                            self.schema_path.push("mapping".to_string());
+                            self.schema_path.push(x.clone());
+                            let result = self.validate(schema, instance, Some(tag), depth);
+                            if let Err(Stop::MaxDepth) = result {
+                                return result;
+                            }
+                            self.schema_path.truncate(self.schema_path.len() - 2);
+                            self.instance_path.push(tag.clone());
+                            result
+                        }
+                        None => self.error(&["mapping"]),
+                    },
+                    Some(_) => self.error(&["discriminator"]),
+                };
+                self.instance_path.pop();
+                result?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn sorted(map: &HashMap<String, Schema>) -> Vec<(&String, &Schema)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+/// Whether `text` is an RFC 3339 date-time, e.g. `1985-04-12T23:20:50.52Z`.
+pub(crate) fn is_timestamp(text: &str) -> bool {
+    let b = text.as_bytes();
+    let digits = |range: std::ops::Range<usize>| -> Option<u32> {
+        let part = text.get(range)?;
+        if part.bytes().all(|c| c.is_ascii_digit()) {
+            part.parse().ok()
+        } else {
+            None
+        }
+    };
+
+    let fields = (|| {
+        if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || b[13] != b':' || b[16] != b':' {
+            return None;
+        }
+        if !matches!(b[10], b'T' | b't' | b' ') {
+            return None;
+        }
+        Some((
+            digits(0..4)?,
+            digits(5..7)?,
+            digits(8..10)?,
+            digits(11..13)?,
+            digits(14..16)?,
+            digits(17..19)?,
+        ))
+    })();
+    let (year, month, day, hour, minute, second) = match fields {
+        Some(x) => x,
+        None => return false,
+    };
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    if day == 0 || day > days || hour > 23 || minute > 59 || second > 60 {
+        return false;
+    }
+
+    let mut rest = &text[19..];
+    if let Some(frac) = rest.strip_prefix('.') {
+        let len = frac.bytes().take_while(|c| c.is_ascii_digit()).count();
+        if len == 0 {
+            return false;
+        }
+        rest = &frac[len..];
+    }
+
+    match rest.as_bytes() {
+        [b'Z'] | [b'z'] => true,
+        [sign, h1, h2, b':', m1, m2] if *sign == b'+' || *sign == b'-' => {
+            let hours = (*h1 as char).to_digit(10).zip((*h2 as char).to_digit(10));
+            let minutes = (*m1 as char).to_digit(10).zip((*m2 as char).to_digit(10));
+            match (hours, minutes) {
+                (Some((a, b)), Some((c, d))) => a * 10 + b < 24 && c * 10 + d < 60,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
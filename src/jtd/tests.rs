@@ -0,0 +1,216 @@
+use crate::jtd::{is_timestamp, Options, Schema, ValidationError};
+
+fn schema(input: &str) -> Schema {
+    Schema::compile(&crate::parse(input).unwrap()).unwrap()
+}
+
+fn errors(schema: &Schema, instance: &str) -> Vec<(String, String)> {
+    schema
+        .validate(&crate::parse(instance).unwrap())
+        .unwrap()
+        .into_iter()
+        .map(
+            |ValidationError {
+                 instance_path,
+                 schema_path,
+             }| { (instance_path.join("/"), schema_path.join("/")) },
+        )
+        .collect()
+}
+
+fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+    expected
+        .iter()
+        .map(|&(a, b)| (a.to_string(), b.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_types() {
+    let s = schema(r#"{"elements": {"type": "uint8"}}"#);
+    assert!(errors(&s, "[0, 255, 3.0]").is_empty());
+    assert_eq!(
+        errors(&s, r#"[256, -1, 1.5, "1"]"#),
+        pairs(&[
+            ("0", "elements/type"),
+            ("1", "elements/type"),
+            ("2", "elements/type"),
+            ("3", "elements/type"),
+        ])
+    );
+
+    let s = schema(r#"{"type": "timestamp", "nullable": true}"#);
+    assert!(errors(&s, r#""1985-04-12T23:20:50.52Z""#).is_empty());
+    assert!(errors(&s, "null").is_empty());
+    assert_eq!(errors(&s, r#""1985-04-12""#), pairs(&[("", "type")]));
+
+    let s = schema(r#"{"enum": ["a", "b"]}"#);
+    assert!(errors(&s, r#""a""#).is_empty());
+    assert_eq!(errors(&s, r#""c""#), pairs(&[("", "enum")]));
+    assert_eq!(errors(&s, "1"), pairs(&[("", "enum")]));
+}
+
+#[test]
+fn test_properties() {
+    let s = schema(
+        r#"{
+            "properties": {"id": {"type": "string"}, "tags": {"values": {"type": "boolean"}}},
+            "optionalProperties": {"note": {"type": "string"}}
+        }"#,
+    );
+
+    assert!(errors(&s, r#"{"id": "x", "tags": {}}"#).is_empty());
+    assert_eq!(
+        errors(&s, r#"{"tags": {"a": 1}, "note": 2, "extra": null}"#),
+        pairs(&[
+            ("", "properties/id"),
+            ("tags/a", "properties/tags/values/type"),
+            ("note", "optionalProperties/note/type"),
+            ("extra", ""),
+        ])
+    );
+    assert_eq!(errors(&s, "[]"), pairs(&[("", "properties")]));
+
+    let s = schema(r#"{"optionalProperties": {"a": {}}, "additionalProperties": true}"#);
+    assert!(errors(&s, r#"{"b": 1}"#).is_empty());
+    assert_eq!(errors(&s, "1"), pairs(&[("", "optionalProperties")]));
+}
+
+#[test]
+fn test_discriminator() {
+    let s = schema(
+        r#"{
+            "discriminator": "kind",
+            "mapping": {
+                "circle": {"properties": {"r": {"type": "float64"}}},
+                "square": {"properties": {"side": {"type": "float64"}}}
+            }
+        }"#,
+    );
+
+    assert!(errors(&s, r#"{"kind": "circle", "r": 1.5}"#).is_empty());
+    assert_eq!(
+        errors(&s, r#"{"kind": "square", "r": 1}"#),
+        pairs(&[
+            ("", "mapping/square/properties/side"),
+            ("r", "mapping/square")
+        ])
+    );
+    assert_eq!(
+        errors(&s, r#"{"kind": "oval"}"#),
+        pairs(&[("kind", "mapping")])
+    );
+    assert_eq!(
+        errors(&s, r#"{"kind": 1}"#),
+        pairs(&[("kind", "discriminator")])
+    );
+    assert_eq!(errors(&s, "{}"), pairs(&[("", "discriminator")]));
+    assert_eq!(errors(&s, "[]"), pairs(&[("", "discriminator")]));
+}
+
+#[test]
+fn test_refs() {
+    let s = schema(
+        r#"{
+            "definitions": {
+                "node": {
+                    "properties": {"value": {"type": "int32"}},
+                    "optionalProperties": {"next": {"ref": "node"}}
+                }
+            },
+            "ref": "node"
+        }"#,
+    );
+
+    assert!(errors(&s, r#"{"value": 1, "next": {"value": 2}}"#).is_empty());
+    assert_eq!(
+        errors(&s, r#"{"value": 1, "next": {"value": "2"}}"#),
+        pairs(&[("next/value", "definitions/node/properties/value/type")])
+    );
+
+    // Only refs in a row count towards the depth, not nesting in the data.
+    let deep = format!(
+        "{}{{\"value\": \"bad\"}}{}",
+        r#"{"value": 1, "next": "#.repeat(80),
+        "}".repeat(80)
+    );
+    assert_eq!(
+        errors(&s, &deep),
+        pairs(&[(
+            &format!("{}value", "next/".repeat(80)),
+            "definitions/node/properties/value/type"
+        )])
+    );
+
+    let s = schema(
+        r#"{
+            "definitions": {"loop": {"ref": "loop"}},
+            "properties": {"a": {"ref": "loop"}}
+        }"#,
+    );
+    let err = s
+        .validate(&crate::parse(r#"{"a": 1}"#).unwrap())
+        .unwrap_err();
+    assert_eq!(err.err_msg, "Maximum depth exceeded");
+    assert_eq!(err.schema_path, ["definitions", "loop", "ref"]);
+
+    let s = schema(r#"{"elements": {"type": "string"}}"#);
+    let options = Options {
+        max_depth: 0,
+        max_errors: 2,
+    };
+    let found = s
+        .validate_with(&crate::parse("[1, 2, 3]").unwrap(), &options)
+        .unwrap();
+    assert_eq!(found.len(), 2);
+}
+
+#[test]
+fn test_invalid_schemas() {
+    for input in [
+        "[]",
+        r#"{"type": "int64"}"#,
+        r#"{"type": "string", "enum": ["a"]}"#,
+        r#"{"enum": []}"#,
+        r#"{"enum": ["a", "a"]}"#,
+        r#"{"ref": "missing"}"#,
+        r#"{"elements": {"definitions": {}}}"#,
+        r#"{"properties": {"a": {}}, "optionalProperties": {"a": {}}}"#,
+        r#"{"additionalProperties": true}"#,
+        r#"{"discriminator": "k", "mapping": {"x": {"type": "string"}}}"#,
+        r#"{"discriminator": "k", "mapping": {"x": {"properties": {"k": {}}}}}"#,
+        r#"{"nullable": 1}"#,
+        r#"{"unknown": 1}"#,
+    ] {
+        assert!(
+            Schema::compile(&crate::parse(input).unwrap()).is_err(),
+            "{}",
+            input
+        );
+    }
+
+    let err = Schema::compile(&crate::parse(r#"{"values": {"ref": "x"}}"#).unwrap()).unwrap_err();
+    assert_eq!(err.schema_path, vec!["values", "ref"]);
+}
+
+#[test]
+fn test_timestamps() {
+    for text in [
+        "2024-02-29T12:00:00Z",
+        "1990-12-31T23:59:60Z",
+        "2000-01-01t00:00:00.123456+05:30",
+        "2000-01-01T00:00:00-00:00",
+    ] {
+        assert!(is_timestamp(text), "{}", text);
+    }
+    for text in [
+        "2023-02-29T12:00:00Z",
+        "2000-13-01T00:00:00Z",
+        "2000-01-01T24:00:00Z",
+        "2000-01-01T00:00:00",
+        "2000-01-01T00:00:00.Z",
+        "2000-01-01T00:00:00+0530",
+    ] {
+        assert!(!is_timestamp(text), "{}", text);
+    }
+}
@@ -0,0 +1,386 @@
+//! Correctly rounded conversion of decimal number literals to `f64`.
+//!
+//! Most literals take Clinger's fast path, where the digits and the power
+//! of ten are both exact doubles. The rest go through the Eisel-Lemire
+//! algorithm, which multiplies the first 19 significant digits by a 128-bit
+//! power of five. In the rare cases where that can't decide the rounding,
+//! because the product is too close to a halfway point or digits were cut
+//! off, the nearest double is found by comparing the literal with halfway
+//! points exactly in big-integer arithmetic.
+
+use super::pow5::{LARGEST_POWER_OF_FIVE, POWERS_OF_FIVE, SMALLEST_POWER_OF_FIVE};
+
+use std::cmp::Ordering;
+
+// Powers of ten that are exact in a double.
+const POW10: [f64; 23] = [
+    1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+    1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+];
+
+// The largest mantissa that converts to a double exactly.
+const MAX_EXACT_MANTISSA: u64 = 1 << 53;
+
+// More significant digits than this can't change which double is nearest,
+// except to break what would otherwise be a tie.
+const MAX_DIGITS: usize = 768;
+
+const MANTISSA_BITS: u32 = 52;
+const INFINITE_POWER: i32 = 0x7ff;
+
+/// The value of the unsigned literal with the given integer and fraction
+/// digits, times `10^exponent`, rounded to the nearest double with ties to
+/// even.
+pub(crate) fn to_f64(integer: &[u8], fraction: &[u8], exponent: i64) -> f64 {
+    // The first 19 significant digits fit in a `u64`; each one dropped
+    // after them scales the mantissa by ten.
+    let mut mantissa: u64 = 0;
+    let mut exp10 = exponent - fraction.len() as i64;
+    let mut digits = 0;
+    let mut truncated = false;
+
+    for &b in integer.iter().chain(fraction) {
+        if digits == 0 && b == b'0' {
+            continue;
+        }
+        if digits < 19 {
+            mantissa = mantissa * 10 + u64::from(b - b'0');
+        } else {
+            truncated |= b != b'0';
+            exp10 += 1;
+        }
+        digits += 1;
+    }
+
+    if mantissa == 0 {
+        return 0.0;
+    }
+    if !truncated {
+        if let Some(val) = clinger(mantissa, exp10) {
+            return val;
+        }
+    }
+
+    // A truncated literal lies between `mantissa` and `mantissa + 1`, so
+    // it rounds the same way as both when they agree.
+    let (val, exact) = eisel_lemire(mantissa, exp10);
+    if exact && (!truncated || eisel_lemire(mantissa + 1, exp10) == (val, true)) {
+        return val;
+    }
+
+    nearest(integer, fraction, exponent, val)
+}
+
+fn clinger(mantissa: u64, exp10: i64) -> Option<f64> {
+    if mantissa > MAX_EXACT_MANTISSA {
+        return None;
+    }
+
+    match exp10 {
+        0..=22 => Some(mantissa as f64 * POW10[exp10 as usize]),
+        -22..=-1 => Some(mantissa as f64 / POW10[-exp10 as usize]),
+        // Some of a larger power of ten can go into the mantissa when it
+        // has digits to spare, as in `1e30`.
+        23..=37 => {
+            let mantissa = mantissa.checked_mul(10u64.pow(exp10 as u32 - 22))?;
+            if mantissa > MAX_EXACT_MANTISSA {
+                return None;
+            }
+            Some(mantissa as f64 * POW10[22])
+        }
+        _ => None,
+    }
+}
+
+// `mantissa * 10^exp10` rounded to a double, and whether the rounding is
+// certain. When it isn't, the result is still within one unit in the last
+// place.
+fn eisel_lemire(mantissa: u64, exp10: i64) -> (f64, bool) {
+    if exp10 < SMALLEST_POWER_OF_FIVE {
+        return (0.0, true);
+    }
+    if exp10 > LARGEST_POWER_OF_FIVE {
+        return (f64::INFINITY, true);
+    }
+
+    let zeros = mantissa.leading_zeros();
+    let (lo, hi) = product_approx(mantissa << zeros, exp10, MANTISSA_BITS + 3);
+
+    // All ones in the low word means the truncated power of five may have
+    // lost a carry, except for powers small enough to be exact.
+    let exact = lo != u64::MAX || (-27..=55).contains(&exp10);
+
+    let upper_bit = (hi >> 63) as i32;
+    let mut bits = hi >> (upper_bit + 64 - MANTISSA_BITS as i32 - 3);
+    let mut power2 = power(exp10 as i32) + upper_bit - zeros as i32 + 1023;
+
+    if power2 <= 0 {
+        if -power2 + 1 >= 64 {
+            return (0.0, true);
+        }
+        // A subnormal, which may round up to the smallest normal.
+        bits >>= -power2 + 1;
+        bits += bits & 1;
+        bits >>= 1;
+        power2 = (bits >= 1 << MANTISSA_BITS) as i32;
+        return (from_parts(bits, power2), exact);
+    }
+
+    // An exact product that lies halfway rounds down to an even mantissa
+    // rather than up, which can only happen for small powers.
+    if lo <= 1
+        && (-4..=23).contains(&exp10)
+        && bits & 3 == 1
+        && bits << (upper_bit + 64 - MANTISSA_BITS as i32 - 3) == hi
+    {
+        bits &= !1;
+    }
+
+    bits += bits & 1;
+    bits >>= 1;
+    if bits >= 2 << MANTISSA_BITS {
+        bits = 1 << MANTISSA_BITS;
+        power2 += 1;
+    }
+
+    if power2 >= INFINITE_POWER {
+        return (f64::INFINITY, exact);
+    }
+
+    (from_parts(bits, power2), exact)
+}
+
+// The high 128 bits of `mantissa * 5^exp10`, with the mantissa normalized,
+// computed to at least `precision` bits.
+fn product_approx(mantissa: u64, exp10: i64, precision: u32) -> (u64, u64) {
+    let mask = u64::MAX >> precision;
+    let (hi5, lo5) = POWERS_OF_FIVE[(exp10 - SMALLEST_POWER_OF_FIVE) as usize];
+
+    let (mut first_lo, mut first_hi) = full_multiplication(mantissa, hi5);
+    if first_hi & mask == mask {
+        // The low bits may carry into those that matter, so the second
+        // half of the power is needed.
+        let (_, second_hi) = full_multiplication(mantissa, lo5);
+        first_lo = first_lo.wrapping_add(second_hi);
+        if second_hi > first_lo {
+            first_hi += 1;
+        }
+    }
+
+    (first_lo, first_hi)
+}
+
+fn full_multiplication(a: u64, b: u64) -> (u64, u64) {
+    let product = u128::from(a) * u128::from(b);
+
+    (product as u64, (product >> 64) as u64)
+}
+
+// floor(log2(5^q)) + 63, good for the whole table.
+fn power(q: i32) -> i32 {
+    (q.wrapping_mul(152_170 + 65536) >> 16) + 63
+}
+
+fn from_parts(mantissa: u64, power2: i32) -> f64 {
+    let fraction = mantissa & ((1 << MANTISSA_BITS) - 1);
+
+    f64::from_bits(((power2 as u64) << MANTISSA_BITS) | fraction)
+}
+
+// The double nearest the literal, found by moving from `guess`, which is
+// at most an ulp or so away, while the literal lies past a halfway point.
+fn nearest(integer: &[u8], fraction: &[u8], exponent: i64, guess: f64) -> f64 {
+    let mut digits = Big::default();
+    let mut count = 0;
+    let mut exp10 = exponent - fraction.len() as i64;
+    let mut sticky = false;
+
+    let mut chunk = 0;
+    let mut chunk_len = 0;
+    for &b in integer.iter().chain(fraction) {
+        if count == 0 && b == b'0' {
+            continue;
+        }
+        if count < MAX_DIGITS {
+            chunk = chunk * 10 + u32::from(b - b'0');
+            chunk_len += 1;
+            if chunk_len == 9 {
+                digits.mul_small(1_000_000_000);
+                digits.add_small(chunk);
+                chunk = 0;
+                chunk_len = 0;
+            }
+        } else {
+            sticky |= b != b'0';
+            exp10 += 1;
+        }
+        count += 1;
+    }
+    digits.mul_small(10u32.pow(chunk_len));
+    digits.add_small(chunk);
+
+    // The literal is `value * 2^exp2`, and halfway points are multiplied
+    // by `5^scale` to match.
+    let (value, exp2, scale) = if exp10 >= 0 {
+        digits.mul_pow5(exp10 as u32);
+        (digits, exp10, 0)
+    } else {
+        (digits, exp10, -exp10 as u32)
+    };
+    let compare = |bits: u64| {
+        let ordering = compare_halfway(&value, exp2, scale, bits);
+        if ordering == Ordering::Equal && sticky {
+            Ordering::Greater
+        } else {
+            ordering
+        }
+    };
+
+    let infinity = f64::INFINITY.to_bits();
+    let mut bits = guess.to_bits();
+
+    while bits < infinity {
+        match compare(bits) {
+            Ordering::Greater => bits += 1,
+            Ordering::Equal => {
+                bits += bits & 1;
+                return f64::from_bits(bits);
+            }
+            Ordering::Less => break,
+        }
+    }
+    while bits > 0 {
+        match compare(bits - 1) {
+            Ordering::Less => bits -= 1,
+            Ordering::Equal => {
+                bits -= bits & 1;
+                break;
+            }
+            Ordering::Greater => break,
+        }
+    }
+
+    f64::from_bits(bits)
+}
+
+// How `value * 2^exp2` compares with the point halfway between the
+// positive double with the given bits and the next one up.
+fn compare_halfway(value: &Big, exp2: i64, scale: u32, bits: u64) -> Ordering {
+    let fraction = bits & ((1 << MANTISSA_BITS) - 1);
+    let power2 = (bits >> MANTISSA_BITS) as i64;
+    let (mantissa, power2) = if power2 == 0 {
+        (fraction, -1074)
+    } else {
+        (fraction | (1 << MANTISSA_BITS), power2 - 1075)
+    };
+
+    // The halfway point is `(2 * mantissa + 1) * 2^(power2 - 1)`.
+    let mut halfway = Big::from_u64(2 * mantissa + 1);
+    halfway.mul_pow5(scale);
+    let halfway_exp2 = power2 - 1;
+
+    let mut value = value.clone();
+    if exp2 > halfway_exp2 {
+        value.shl((exp2 - halfway_exp2) as usize);
+    } else {
+        halfway.shl((halfway_exp2 - exp2) as usize);
+    }
+
+    value.cmp(&halfway)
+}
+
+// An unsigned big integer, as little-endian 32-bit limbs with no zero limbs
+// at the top.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Big {
+    limbs: Vec<u32>,
+}
+
+impl Big {
+    fn from_u64(x: u64) -> Self {
+        let mut big = Big {
+            limbs: vec![x as u32, (x >> 32) as u32],
+        };
+        big.normalize();
+
+        big
+    }
+
+    fn normalize(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+
+    fn mul_small(&mut self, factor: u32) {
+        let mut carry = 0;
+        for limb in self.limbs.iter_mut() {
+            let product = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+        self.normalize();
+    }
+
+    fn add_small(&mut self, addend: u32) {
+        let mut carry = u64::from(addend);
+        for limb in self.limbs.iter_mut() {
+            if carry == 0 {
+                break;
+            }
+            let sum = u64::from(*limb) + carry;
+            *limb = sum as u32;
+            carry = sum >> 32;
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+
+    fn mul_pow5(&mut self, mut n: u32) {
+        // 5^13 is the largest power of five in a limb.
+        while n >= 13 {
+            self.mul_small(1_220_703_125);
+            n -= 13;
+        }
+        self.mul_small(5u32.pow(n));
+    }
+
+    fn shl(&mut self, n: usize) {
+        if self.limbs.is_empty() {
+            return;
+        }
+
+        let (limbs, bits) = (n / 32, n % 32);
+        if bits > 0 {
+            let mut carry = 0;
+            for limb in self.limbs.iter_mut() {
+                let shifted = (*limb << bits) | carry;
+                carry = *limb >> (32 - bits);
+                *limb = shifted;
+            }
+            if carry > 0 {
+                self.limbs.push(carry);
+            }
+        }
+        self.limbs.splice(0..0, vec![0; limbs]);
+    }
+}
+
+impl PartialOrd for Big {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Big {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
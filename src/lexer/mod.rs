@@ -1,4 +1,5 @@
-use std::str::Chars;
+mod float;
+mod pow5;
 
 #[cfg(test)]
 mod tests;
@@ -17,55 +18,203 @@ pub enum TokenVal {
     Null,
     JString(String),
     JNumber(f64),
+    Ident(String),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub value: TokenVal,
     pub line_no: u64,
+    /// Byte offsets of the token's text in the input, `start..end`.
+    pub start: usize,
+    pub end: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct LexError {
     pub err_msg: String,
     pub line_no: u64,
+    pub column: u64,
 }
 
 impl LexError {
-    fn new(err_msg: String, line_no: u64) -> LexError {
-        LexError { err_msg, line_no }
+    fn new(err_msg: String, line_no: u64, column: u64) -> LexError {
+        LexError {
+            err_msg,
+            line_no,
+            column,
+        }
     }
 }
 
 type LexResult = Result<Token, LexError>;
 
+/// The flavour of JSON accepted by the lexer and parser.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dialect {
+    /// Strict RFC 8259 JSON.
+    Json,
+    /// JSON with `//` and `/* */` comments and a trailing comma allowed in
+    /// arrays and objects, as used by editor and tsconfig-style settings.
+    Jsonc,
+    /// [JSON5](https://spec.json5.org/): everything in `Jsonc`, plus
+    /// identifier keys, single-quoted and multi-line strings, hexadecimal
+    /// numbers, leading or trailing decimal points, explicit `+`,
+    /// `Infinity`, `NaN` and extra whitespace characters.
+    Json5,
+}
+
+impl Dialect {
+    pub fn allows_comments(self) -> bool {
+        self != Dialect::Json
+    }
+
+    pub fn allows_trailing_commas(self) -> bool {
+        self != Dialect::Json
+    }
+}
+
+// The Unicode Zs characters outside U+2000 to U+200A.
+const SPACE_SEPARATORS: [char; 6] = [
+    ' ', '\u{a0}', '\u{1680}', '\u{202f}', '\u{205f}', '\u{3000}',
+];
+
+// The WhiteSpace and LineTerminator productions of ECMAScript 5.1, which
+// JSON5 uses as is.
+fn is_json5_space(c: char) -> bool {
+    match c {
+        '\t' | '\n' | '\x0b' | '\x0c' | '\r' | '\u{2028}' | '\u{2029}' | '\u{feff}' => true,
+        '\u{2000}'..='\u{200a}' => true,
+        _ => SPACE_SEPARATORS.contains(&c),
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '$' || c == '_'
+}
+
+fn is_ident_part(c: char) -> bool {
+    is_ident_start(c) || c.is_numeric() || c == '\u{200c}' || c == '\u{200d}'
+}
+
+// Character classes of the ASCII bytes the lexer branches on, so that each
+// test is a single table lookup. Bytes of multi-byte characters are in no
+// class.
+const WHITESPACE: u8 = 1;
+const DIGIT: u8 = 2;
+const HEX: u8 = 4;
+const PUNCT: u8 = 8;
+// Bytes that end a run of characters that can be copied out of a string
+// as they are.
+const STRING_STOP: u8 = 16;
+
+static CLASSES: [u8; 256] = classes();
+
+const fn classes() -> [u8; 256] {
+    let mut table = [0; 256];
+
+    let mut i = 0;
+    while i < 256 {
+        let b = i as u8;
+        let mut class = 0;
+        if b == b' ' || b == b'\t' || b == b'\n' || b == b'\r' {
+            class |= WHITESPACE;
+        }
+        if b.is_ascii_digit() {
+            class |= DIGIT;
+        }
+        if b.is_ascii_hexdigit() {
+            class |= HEX;
+        }
+        if b == b'{' || b == b'}' || b == b'[' || b == b']' || b == b',' || b == b':' {
+            class |= PUNCT;
+        }
+        if b < 0x20 || b == b'"' || b == b'\'' || b == b'\\' {
+            class |= STRING_STOP;
+        }
+        table[i] = class;
+        i += 1;
+    }
+
+    table
+}
+
+const ONES: u64 = 0x0101_0101_0101_0101;
+const HIGHS: u64 = 0x8080_8080_8080_8080;
+
+// Whether any of the eight bytes packed in `x` is zero.
+fn has_zero_byte(x: u64) -> bool {
+    x.wrapping_sub(ONES) & !x & HIGHS != 0
+}
+
+// Whether any of the eight bytes packed in `x` is a `STRING_STOP` byte.
+fn has_string_stop(x: u64) -> bool {
+    let below_space = x.wrapping_sub(ONES * 0x20) & !x & HIGHS != 0;
+
+    below_space
+        || has_zero_byte(x ^ (ONES * u64::from(b'"')))
+        || has_zero_byte(x ^ (ONES * u64::from(b'\'')))
+        || has_zero_byte(x ^ (ONES * u64::from(b'\\')))
+}
+
 pub struct Lexer<'a> {
-    input: Chars<'a>,
-    curr_char: Option<char>,
+    input: &'a str,
+    bytes: &'a [u8],
     line_no: u64,
+    dialect: Dialect,
+    // Byte offset of the next character, and of the first character of the
+    // token being read.
+    pos: usize,
+    start: usize,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
-        let mut iter = input.chars();
-        let c = iter.next();
+        Lexer::with_dialect(input, Dialect::Json)
+    }
 
+    pub fn with_dialect(input: &'a str, dialect: Dialect) -> Self {
         Lexer {
-            input: iter,
-            curr_char: c,
+            input,
+            bytes: input.as_bytes(),
             line_no: 1,
+            dialect,
+            pos: 0,
+            start: 0,
         }
     }
 
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
     fn new_token(&self, value: TokenVal) -> Token {
         Token {
-            value: value,
+            value,
             line_no: self.line_no,
+            start: self.start,
+            end: self.pos,
         }
     }
 
+    // The 1-based column of the character at byte `offset`, counting
+    // characters rather than bytes from the start of its line.
+    pub fn column(&self, offset: usize) -> u64 {
+        let before = &self.bytes[..offset.min(self.bytes.len())];
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        let chars = before[line_start..]
+            .iter()
+            .filter(|&&b| b & 0xc0 != 0x80)
+            .count();
+
+        chars as u64 + 1
+    }
+
     fn error(&self, err_msg: String) -> Result<(), LexError> {
-        Err(LexError::new(err_msg, self.line_no))
+        Err(LexError::new(err_msg, self.line_no, self.column(self.pos)))
     }
 
     fn throw(&self, err_msg: String) -> LexResult {
@@ -76,71 +225,181 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    fn peek_byte(&self) -> Option<u8> {
+        self.bytes.get(self.pos).cloned()
+    }
+
     fn peek(&self) -> Option<char> {
-        self.curr_char
+        match self.peek_byte() {
+            Some(b) if b < 0x80 => Some(b as char),
+            Some(_) => self.input[self.pos..].chars().next(),
+            None => None,
+        }
+    }
+
+    fn peek_next(&self) -> Option<char> {
+        let c = self.peek()?;
+        self.input[self.pos + c.len_utf8()..].chars().next()
+    }
+
+    fn is(&self, class: u8) -> bool {
+        self.peek_byte()
+            .is_some_and(|b| CLASSES[b as usize] & class != 0)
     }
 
     fn cont(&mut self) {
-        if let Some(c) = self.peek() {
-            if c == '\n' {
+        match self.peek_byte() {
+            Some(b'\n') => {
                 self.line_no += 1;
+                self.pos += 1;
             }
+            Some(b) if b < 0x80 => self.pos += 1,
+            Some(_) => self.pos += self.peek().unwrap().len_utf8(),
+            None => (),
         }
+    }
 
-        self.curr_char = self.input.next();
+    fn skip_line_comment(&mut self) {
+        while let Some(b) = self.peek_byte() {
+            if b == b'\n' {
+                break;
+            }
+            self.pos += 1;
+        }
     }
 
-    fn eat(&mut self, string: &mut String) {
-        let c = self.peek();
+    fn skip_block_comment(&mut self) -> Result<(), LexError> {
+        self.pos += 2; // eat the slash and the star
 
-        match c {
-            None => return,
-            Some(x) => {
-                string.push(x);
-                self.cont();
+        while let Some(b) = self.peek_byte() {
+            self.cont();
+
+            if b == b'*' && self.peek_byte() == Some(b'/') {
+                self.pos += 1;
+                return Ok(());
             }
         }
+
+        self.error("Unterminated block comment".to_string())
     }
 
-    fn match_any(&self, chars: &str) -> bool {
-        let c = self.peek();
+    fn skip_spaces(&mut self) -> Result<(), LexError> {
+        loop {
+            while let Some(b) = self.peek_byte() {
+                if CLASSES[b as usize] & WHITESPACE == 0 {
+                    break;
+                }
+                if b == b'\n' {
+                    self.line_no += 1;
+                }
+                self.pos += 1;
+            }
 
-        if let Some(c) = c {
-            for ch in chars.chars() {
-                if c == ch {
-                    return true;
+            match self.peek_byte() {
+                Some(b'/') if self.dialect.allows_comments() => match self.peek_next() {
+                    Some('/') => self.skip_line_comment(),
+                    Some('*') => self.skip_block_comment()?,
+                    _ => return Ok(()),
+                },
+                Some(_)
+                    if self.dialect == Dialect::Json5
+                        && self.peek().is_some_and(is_json5_space) =>
+                {
+                    self.cont()
                 }
+                _ => return Ok(()),
             }
+        }
+    }
 
-            false
-        } else {
-            false
+    fn get_punct(&mut self) -> LexResult {
+        let b = self.peek_byte().unwrap();
+        self.pos += 1;
+
+        match b {
+            b'{' => Ok(self.new_token(TokenVal::LBrace)),
+            b'}' => Ok(self.new_token(TokenVal::RBrace)),
+            b'[' => Ok(self.new_token(TokenVal::LBrack)),
+            b']' => Ok(self.new_token(TokenVal::RBrack)),
+            b':' => Ok(self.new_token(TokenVal::Colon)),
+            b',' => Ok(self.new_token(TokenVal::Comma)),
+            _ => self.throw("Invalid punctuation".to_string()),
         }
     }
 
-    fn skip_spaces(&mut self) {
-        while self.match_any(" \t\n\r") {
-            self.cont();
+    fn get_hex(&mut self, digits: usize) -> Result<u32, LexError> {
+        let mut code_pt = 0;
+
+        for _ in 0..digits {
+            match self.peek_byte().and_then(|b| (b as char).to_digit(16)) {
+                Some(digit) => code_pt = code_pt * 16 + digit,
+                None => self.error("Invalid escape character".to_string())?,
+            }
+            self.pos += 1;
         }
+
+        Ok(code_pt)
     }
 
-    fn get_punct(&mut self) -> LexResult {
-        let c = self.peek().unwrap();
-        self.cont();
+    // The code point of a `\u` escape whose `u` has been read, combining a
+    // surrogate pair written as two escapes. A lone surrogate can't be
+    // stored in a `String`, so it is an error.
+    fn get_code_point(&mut self) -> Result<char, LexError> {
+        let mut code_pt = self.get_hex(4)?;
+
+        if (0xd800..0xdc00).contains(&code_pt) {
+            if self.peek_byte() != Some(b'\\') {
+                self.error("Lone surrogate in string".to_string())?;
+            }
+            self.pos += 1;
+            if self.peek_byte() != Some(b'u') {
+                self.error("Lone surrogate in string".to_string())?;
+            }
+            self.pos += 1;
 
+            let low = self.get_hex(4)?;
+            if !(0xdc00..0xe000).contains(&low) {
+                self.error("Lone surrogate in string".to_string())?;
+            }
+            code_pt = 0x10000 + ((code_pt - 0xd800) << 10) + (low - 0xdc00);
+        }
+
+        match std::char::from_u32(code_pt) {
+            Some(c) => Ok(c),
+            None => Err(LexError::new(
+                "Lone surrogate in string".to_string(),
+                self.line_no,
+                self.column(self.pos),
+            )),
+        }
+    }
+
+    // JSON5 follows ECMAScript 5 here: a few more single-letter escapes,
+    // `\xHH`, line continuations, and any other character escaping itself.
+    fn get_json5_escape(&mut self, c: char, string: &mut String) -> Result<(), LexError> {
         match c {
-            '{' => Ok(self.new_token(TokenVal::LBrace)),
-            '}' => Ok(self.new_token(TokenVal::RBrace)),
-            '[' => Ok(self.new_token(TokenVal::LBrack)),
-            ']' => Ok(self.new_token(TokenVal::RBrack)),
-            ':' => Ok(self.new_token(TokenVal::Colon)),
-            ',' => Ok(self.new_token(TokenVal::Comma)),
-            _ => self.throw("Invalid punctuation".to_string()),
+            '\'' => string.push('\''),
+            'v' => string.push('\x0b'),
+            '0' if !self.is(DIGIT) => string.push('\0'),
+            'x' => {
+                let code_pt = self.get_hex(2)?;
+                string.push(std::char::from_u32(code_pt).unwrap());
+            }
+            '\r' => {
+                if self.peek_byte() == Some(b'\n') {
+                    self.cont();
+                }
+            }
+            '\n' | '\u{2028}' | '\u{2029}' => (),
+            '0'..='9' => self.error("Invalid escape character".to_string())?,
+            _ => string.push(c),
         }
+
+        Ok(())
     }
 
     fn get_escape_char(&mut self, string: &mut String) -> Result<(), LexError> {
-        self.cont(); // eat the escape char
+        self.pos += 1; // eat the escape char
 
         let err_msg = "Invalid escape character".to_string();
 
@@ -158,31 +417,10 @@ impl<'a> Lexer<'a> {
                 'r' => string.push('\r'),
                 't' => string.push('\t'),
                 'u' => {
-                    let mut code_pt = String::new();
-
-                    for _ in 0..4 {
-                        if !self.match_any("0123456789abcdefABCDEF") {
-                            self.error(err_msg.clone())?;
-                        } else {
-                            code_pt.push(self.peek().unwrap());
-                            self.cont();
-                        }
-                    }
-
-                    let num: Result<u16, std::num::ParseIntError> =
-                        u16::from_str_radix(&code_pt, 16);
-                    match num {
-                        Ok(x) => {
-                            let code_pt = std::char::from_u32(x as u32);
-                            if let Some(x) = code_pt {
-                                string.push(x);
-                            } else {
-                                self.error(err_msg)?;
-                            }
-                        }
-                        Err(_) => self.error(err_msg)?,
-                    }
+                    let code_pt = self.get_code_point()?;
+                    string.push(code_pt);
                 }
+                _ if self.dialect == Dialect::Json5 => self.get_json5_escape(c, string)?,
                 _ => self.error(err_msg)?,
             }
 
@@ -192,23 +430,59 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // Steps over characters that stand for themselves in a string, eight
+    // bytes at a time while none of them is a `STRING_STOP` byte. Those are
+    // all ASCII, so the run ends on a character boundary.
+    fn skip_unescaped(&mut self) {
+        let bytes = self.bytes;
+        let mut pos = self.pos;
+
+        while pos + 8 <= bytes.len() {
+            let mut chunk = [0; 8];
+            chunk.copy_from_slice(&bytes[pos..pos + 8]);
+            if has_string_stop(u64::from_le_bytes(chunk)) {
+                break;
+            }
+            pos += 8;
+        }
+        while pos < bytes.len() && CLASSES[bytes[pos] as usize] & STRING_STOP == 0 {
+            pos += 1;
+        }
+
+        self.pos = pos;
+    }
+
     fn get_string(&mut self) -> LexResult {
-        self.cont(); // eat the opening quote
+        let quote = self.peek_byte().unwrap();
+        self.pos += 1; // eat the opening quote
 
         let mut val = String::new();
 
-        while let Some(c) = self.peek() {
-            match c {
-                '\x00'...'\x1f' => {
-                    self.error(format!("Invalid character in string: {}", c as u8))?
-                }
-                '\\' => self.get_escape_char(&mut val)?,
-                '\"' => {
-                    self.cont();
+        loop {
+            let run = self.pos;
+            self.skip_unescaped();
+            val.push_str(&self.input[run..self.pos]);
+
+            match self.peek_byte() {
+                None => break,
+                Some(b) if b == quote => {
+                    self.pos += 1;
 
                     return Ok(self.new_token(TokenVal::JString(val)));
                 }
-                _ => self.eat(&mut val),
+                Some(b'\\') => self.get_escape_char(&mut val)?,
+                Some(b) if b == b'\n' || b == b'\r' => {
+                    self.error(format!("Invalid character in string: {}", b))?
+                }
+                Some(b) if b < 0x20 && self.dialect != Dialect::Json5 => {
+                    self.error(format!("Invalid character in string: {}", b))?
+                }
+                // The other quote character, or a control character that
+                // JSON5 allows.
+                Some(b) => {
+                    val.push(b as char);
+                    self.pos += 1;
+                }
             }
         }
 
@@ -216,107 +490,222 @@ impl<'a> Lexer<'a> {
     }
 
     fn match_letters(&mut self, letters: &str, value: TokenVal) -> LexResult {
-        let err = self.error("Invalid keyword".to_string());
+        for x in letters.bytes() {
+            if self.peek_byte() != Some(x) {
+                return self.throw("Invalid keyword".to_string());
+            }
+            self.pos += 1;
+        }
 
-        for x in letters.chars() {
-            let c = self.peek();
+        Ok(self.new_token(value))
+    }
 
-            if let Some(c) = c {
-                if c != x {
-                    err?;
-                    break;
-                } else {
+    fn get_keyword(&mut self) -> LexResult {
+        match self.peek_byte() {
+            Some(b't') => self.match_letters("true", TokenVal::True),
+            Some(b'f') => self.match_letters("false", TokenVal::False),
+            Some(b'n') => self.match_letters("null", TokenVal::Null),
+            _ => self.throw("Invalid keyword".to_string()),
+        }
+    }
+
+    fn get_ident(&mut self) -> LexResult {
+        let mut val = String::new();
+
+        loop {
+            let c = match self.peek() {
+                Some('\\') => {
+                    self.cont();
+                    if self.peek() != Some('u') {
+                        return self.throw("Invalid escape in identifier".to_string());
+                    }
+                    self.cont();
+
+                    let code_pt = self.get_hex(4)?;
+                    match std::char::from_u32(code_pt) {
+                        Some(c) => c,
+                        None => return self.throw("Invalid escape in identifier".to_string()),
+                    }
+                }
+                Some(c) if is_ident_part(c) => {
                     self.cont();
+                    c
                 }
+                _ => break,
+            };
+
+            let valid = if val.is_empty() {
+                is_ident_start(c)
             } else {
-                err?;
-                break;
+                is_ident_part(c)
+            };
+            if !valid {
+                return self.throw("Invalid character in identifier".to_string());
             }
+            val.push(c);
         }
 
-        Ok(self.new_token(value))
+        Ok(self.new_token(TokenVal::Ident(val)))
     }
 
-    fn get_keyword(&mut self) -> LexResult {
-        let c = self.peek().unwrap();
-        self.cont();
+    fn get_json5_number(&mut self) -> LexResult {
+        let negative = self.peek_byte() == Some(b'-');
+        if let Some(b'+') | Some(b'-') = self.peek_byte() {
+            self.pos += 1;
+        }
 
-        match c {
-            't' => self.match_letters("rue", TokenVal::True),
-            'f' => self.match_letters("alse", TokenVal::False),
-            'n' => self.match_letters("ull", TokenVal::Null),
-            _ => self.throw("Invalid keyword".to_string()),
+        match self.peek_byte() {
+            Some(b'I') => {
+                let inf = if negative {
+                    f64::NEG_INFINITY
+                } else {
+                    f64::INFINITY
+                };
+                return self.match_letters("Infinity", TokenVal::JNumber(inf));
+            }
+            Some(b'N') => {
+                return self.match_letters("NaN", TokenVal::JNumber(f64::NAN));
+            }
+            Some(b'0') if matches!(self.bytes.get(self.pos + 1), Some(b'x') | Some(b'X')) => {
+                self.pos += 2;
+
+                let mut num = 0f64;
+                let mut digits = 0;
+                while self.is(HEX) {
+                    let digit = (self.bytes[self.pos] as char).to_digit(16).unwrap();
+                    num = num * 16.0 + f64::from(digit);
+                    digits += 1;
+                    self.pos += 1;
+                }
+                if digits == 0 {
+                    return self.throw("Need at least one hex digit".to_string());
+                }
+
+                let num = if negative { -num } else { num };
+                return Ok(self.new_token(TokenVal::JNumber(num)));
+            }
+            _ => (),
         }
-    }
 
-    fn need_digit(&mut self, string: &mut String) -> Result<(), LexError> {
-        if !self.match_any("0123456789") {
-            self.error("Need at least one digit".to_string())?;
+        let integer = self.skip_digits();
+        if integer.len() > 1 && integer[0] == b'0' {
+            return self.throw("Leading zeros are not allowed".to_string());
         }
 
-        while self.match_any("0123456789") {
-            self.eat(string);
+        let mut fraction: &[u8] = &[];
+        if self.peek_byte() == Some(b'.') {
+            self.pos += 1;
+            fraction = self.skip_digits();
         }
 
-        Ok(())
-    }
+        if integer.is_empty() && fraction.is_empty() {
+            return self.throw("Invalid number".to_string());
+        }
 
-    fn get_number(&mut self) -> LexResult {
-        let mut val = String::new();
+        let exponent = self.get_exponent()?;
+        let val = float::to_f64(integer, fraction, exponent);
+        let val = if negative { -val } else { val };
 
-        if self.peek() == Some('-') {
-            self.eat(&mut val);
+        Ok(self.new_token(TokenVal::JNumber(val)))
+    }
+
+    fn skip_digits(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while self.is(DIGIT) {
+            self.pos += 1;
         }
 
-        if self.peek() == Some('0') {
-            self.eat(&mut val);
-        } else if self.match_any("123456789") {
-            self.eat(&mut val);
+        &self.bytes[start..self.pos]
+    }
 
-            while self.match_any("0123456789") {
-                self.eat(&mut val);
-            }
-        } else {
-            return self.throw("Invalid number".to_string());
+    fn need_digit(&mut self) -> Result<&'a [u8], LexError> {
+        if !self.is(DIGIT) {
+            self.error("Need at least one digit".to_string())?;
         }
 
-        if self.peek() == Some('.') {
-            self.eat(&mut val);
+        Ok(self.skip_digits())
+    }
 
-            self.need_digit(&mut val)?;
+    // The value of an exponent part, if there is one. Exponents too large
+    // to matter are clamped rather than allowed to overflow.
+    fn get_exponent(&mut self) -> Result<i64, LexError> {
+        if let Some(b'e') | Some(b'E') = self.peek_byte() {
+            self.pos += 1;
+        } else {
+            return Ok(0);
         }
 
-        if self.match_any("eE") {
-            self.eat(&mut val);
+        let negative = self.peek_byte() == Some(b'-');
+        if let Some(b'+') | Some(b'-') = self.peek_byte() {
+            self.pos += 1;
+        }
 
-            if self.match_any("-+") {
-                self.eat(&mut val);
+        let mut exponent: i64 = 0;
+        for &b in self.need_digit()? {
+            if exponent < 1_000_000 {
+                exponent = exponent * 10 + i64::from(b - b'0');
             }
+        }
+
+        Ok(if negative { -exponent } else { exponent })
+    }
 
-            self.need_digit(&mut val)?;
+    fn get_number(&mut self) -> LexResult {
+        let negative = self.peek_byte() == Some(b'-');
+        if negative {
+            self.pos += 1;
         }
 
-        let val: Result<f64, std::num::ParseFloatError> = val.parse();
-        match val {
-            Ok(val) => Ok(self.new_token(TokenVal::JNumber(val))),
-            Err(_) => self.throw("Invalid floating-point literal".to_string()),
+        let integer = match self.peek_byte() {
+            Some(b'0') => {
+                self.pos += 1;
+                &self.bytes[self.pos - 1..self.pos]
+            }
+            Some(b'1'..=b'9') => self.skip_digits(),
+            _ => return self.throw("Invalid number".to_string()),
+        };
+
+        let mut fraction: &[u8] = &[];
+        if self.peek_byte() == Some(b'.') {
+            self.pos += 1;
+            fraction = self.need_digit()?;
         }
+
+        let exponent = self.get_exponent()?;
+        let val = float::to_f64(integer, fraction, exponent);
+        let val = if negative { -val } else { val };
+
+        Ok(self.new_token(TokenVal::JNumber(val)))
     }
 
     pub fn next_token(&mut self) -> LexResult {
-        self.skip_spaces();
+        self.skip_spaces()?;
+        self.start = self.pos;
+
+        if let Some(b) = self.peek_byte() {
+            let json5 = self.dialect == Dialect::Json5;
+            let class = CLASSES[b as usize];
 
-        if let Some(c) = self.peek() {
-            if self.match_any("{}[],:") {
+            if class & PUNCT != 0 {
                 self.get_punct()
-            } else if self.match_any("tfn") {
+            } else if json5 && (b == b'\\' || self.peek().is_some_and(is_ident_start)) {
+                self.get_ident()
+            } else if json5 && (class & DIGIT != 0 || b == b'+' || b == b'-' || b == b'.') {
+                self.get_json5_number()
+            } else if json5 && b == b'\'' {
+                self.get_string()
+            } else if b == b't' || b == b'f' || b == b'n' {
                 self.get_keyword()
-            } else if self.match_any("-0123456789") {
+            } else if class & DIGIT != 0 || b == b'-' {
                 self.get_number()
-            } else if c == '\"' {
+            } else if b == b'"' {
                 self.get_string()
             } else {
-                self.throw("Invalid character".to_string())
+                // Step over the character so that a caller recovering from
+                // the error doesn't see it again.
+                let err = self.throw("Invalid character".to_string());
+                self.cont();
+                err
             }
         } else {
             Ok(self.new_token(TokenVal::Eof))
@@ -1,4 +1,4 @@
-use crate::lexer::{LexError, Lexer, TokenVal};
+use crate::lexer::{Dialect, LexError, Lexer, TokenVal};
 
 #[test]
 fn test_eof() {
@@ -63,11 +63,60 @@ fn test_string_eof() {
     let expected = Err(LexError::new(
         "Unexpected EOF while parsing string".to_string(),
         1,
+        13,
     ));
 
     assert_eq!(actual, expected);
 }
 
+#[test]
+fn test_long_strings() {
+    // Runs longer than a word, with the stop characters at every offset.
+    for i in 0..17 {
+        let text = "abcdéfghijklmnop";
+        let prefix: String = text.chars().take(i).collect();
+        let input = format!("\"{}'\\t{}\"", prefix, text);
+        let actual = Lexer::new(&input).next_token().unwrap();
+        let expected = TokenVal::JString(format!("{}'\t{}", prefix, text));
+
+        assert_eq!(actual.value, expected);
+    }
+
+    let actual = Lexer::new("\"0123456789\u{1}\"").next_token();
+    let expected = Err(LexError::new(
+        "Invalid character in string: 1".to_string(),
+        1,
+        12,
+    ));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_surrogate_pairs() {
+    let mut lexer = Lexer::new(r#""\ud83d\ude00 \u00e9""#);
+    let actual = lexer.next_token().unwrap();
+    let expected = TokenVal::JString("\u{1f600} \u{e9}".to_string());
+
+    assert_eq!(actual.value, expected);
+
+    for &(input, column) in &[
+        (r#""\ud83d""#, 8),
+        (r#""\ud83d\n""#, 9),
+        (r#""\ud83d\u0041""#, 14),
+        (r#""\ude00""#, 8),
+    ] {
+        let actual = Lexer::new(input).next_token();
+        let expected = Err(LexError::new(
+            "Lone surrogate in string".to_string(),
+            1,
+            column,
+        ));
+
+        assert_eq!(actual, expected);
+    }
+}
+
 #[test]
 fn test_simple_float() {
     let mut lexer = Lexer::new("4.5");
@@ -86,11 +135,72 @@ fn test_complex_float() {
     assert_eq!(actual.value, expected);
 }
 
+#[test]
+fn test_long_numbers() {
+    let input = "123456789012345678901234567890 0.1e-5 9007199254740993 2.5e-400 1e400";
+    let mut lexer = Lexer::new(input);
+    let expected = [
+        1.2345678901234568e29,
+        1e-6,
+        9007199254740992.0,
+        0.0,
+        f64::INFINITY,
+    ];
+
+    for x in expected.iter() {
+        let actual = lexer.next_token().unwrap();
+        assert_eq!(actual.value, TokenVal::JNumber(*x));
+    }
+}
+
+#[test]
+fn test_rounding() {
+    let number = |input: &str| match Lexer::new(input).next_token().unwrap().value {
+        TokenVal::JNumber(x) => x,
+        other => panic!("expected a number, got {:?}", other),
+    };
+    // Halfway between 1 and the next double, and just past it.
+    let halfway = "1.00000000000000011102230246251565404236316680908203125";
+
+    assert_eq!(number(halfway), 1.0);
+    assert_eq!(
+        number(&format!("{}{}1", halfway, "0".repeat(800))),
+        1.0000000000000002
+    );
+    assert_eq!(number("9007199254740993"), 9007199254740992.0);
+    assert_eq!(number("9007199254740995"), 9007199254740996.0);
+    assert_eq!(number("1e23"), 1e23);
+    assert_eq!(number("8.988465674311579e307"), 8.988465674311579e307);
+    assert_eq!(number("1.7976931348623158e308"), f64::MAX);
+    assert_eq!(number("1.7976931348623159e308"), f64::INFINITY);
+    assert_eq!(number("-0"), 0.0);
+    assert!(number("-0").is_sign_negative());
+}
+
+#[test]
+fn test_subnormals() {
+    let number = |input: &str| match Lexer::new(input).next_token().unwrap().value {
+        TokenVal::JNumber(x) => x,
+        other => panic!("expected a number, got {:?}", other),
+    };
+    let min = f64::from_bits(1);
+
+    assert_eq!(number("4.9406564584124654e-324"), min);
+    assert_eq!(number("2.4703282292062328e-324"), min);
+    assert_eq!(number("2.4703282292062327e-324"), 0.0);
+    assert_eq!(
+        number("2.2250738585072011e-308"),
+        f64::from_bits(0x000f_ffff_ffff_ffff)
+    );
+    assert_eq!(number("2.2250738585072012e-308"), f64::MIN_POSITIVE);
+    assert_eq!(number(&format!("0.{}1e-300", "0".repeat(500))), 0.0);
+}
+
 #[test]
 fn test_bad_float() {
     let mut lexer = Lexer::new("33.");
     let actual = lexer.next_token();
-    let expected = Err(LexError::new("Need at least one digit".to_string(), 1));
+    let expected = Err(LexError::new("Need at least one digit".to_string(), 1, 4));
 
     assert_eq!(actual, expected);
 }
@@ -112,7 +222,7 @@ fn test_bools() {
 fn test_invalid_keyword() {
     let mut lexer = Lexer::new("hello");
     let actual = lexer.next_token();
-    let expected = Err(LexError::new("Invalid character".to_string(), 1));
+    let expected = Err(LexError::new("Invalid character".to_string(), 1, 1));
 
     assert_eq!(actual, expected);
 }
@@ -134,3 +244,136 @@ fn test_line_no() {
 
     assert_eq!(actual.line_no, expected);
 }
+
+#[test]
+fn test_comments() {
+    let mut lexer = Lexer::with_dialect("// line\n/* block\n */ true /**/", Dialect::Jsonc);
+
+    let actual = lexer.next_token().unwrap();
+    assert_eq!(actual.value, TokenVal::True);
+    assert_eq!(actual.line_no, 3);
+
+    let actual = lexer.next_token().unwrap();
+    assert_eq!(actual.value, TokenVal::Eof);
+}
+
+#[test]
+fn test_comments_strict() {
+    let mut lexer = Lexer::new("// comment\ntrue");
+    let actual = lexer.next_token();
+    let expected = Err(LexError::new("Invalid character".to_string(), 1, 1));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_unterminated_comment() {
+    let mut lexer = Lexer::with_dialect("true /* never\nclosed", Dialect::Jsonc);
+    lexer.next_token().unwrap();
+    let actual = lexer.next_token();
+    let expected = Err(LexError::new(
+        "Unterminated block comment".to_string(),
+        2,
+        7,
+    ));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_json5_identifiers() {
+    let mut lexer = Lexer::with_dialect("$key_1 \\u0061b Infinity", Dialect::Json5);
+
+    let actual = lexer.next_token().unwrap();
+    assert_eq!(actual.value, TokenVal::Ident("$key_1".to_string()));
+
+    let actual = lexer.next_token().unwrap();
+    assert_eq!(actual.value, TokenVal::Ident("ab".to_string()));
+
+    let actual = lexer.next_token().unwrap();
+    assert_eq!(actual.value, TokenVal::Ident("Infinity".to_string()));
+}
+
+#[test]
+fn test_json5_strings() {
+    let mut lexer = Lexer::with_dialect(
+        "'it\\'s \"quoted\"' 'line \\\n continued\\x21'",
+        Dialect::Json5,
+    );
+
+    let actual = lexer.next_token().unwrap();
+    let expected = TokenVal::JString("it's \"quoted\"".to_string());
+    assert_eq!(actual.value, expected);
+
+    let actual = lexer.next_token().unwrap();
+    let expected = TokenVal::JString("line  continued!".to_string());
+    assert_eq!(actual.value, expected);
+    assert_eq!(actual.line_no, 2);
+}
+
+#[test]
+fn test_json5_numbers() {
+    let mut lexer = Lexer::with_dialect("0xFF -0x10 .5 5. +1e2 -Infinity", Dialect::Json5);
+    let expected = [255.0, -16.0, 0.5, 5.0, 100.0, f64::NEG_INFINITY];
+
+    for x in expected.iter() {
+        let actual = lexer.next_token().unwrap();
+        assert_eq!(actual.value, TokenVal::JNumber(*x));
+    }
+
+    let mut lexer = Lexer::with_dialect("+NaN", Dialect::Json5);
+    match lexer.next_token().unwrap().value {
+        TokenVal::JNumber(x) => assert!(x.is_nan()),
+        other => panic!("expected NaN, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_json5_bad_number() {
+    let mut lexer = Lexer::with_dialect("+.", Dialect::Json5);
+    let actual = lexer.next_token();
+    let expected = Err(LexError::new("Invalid number".to_string(), 1, 3));
+
+    assert_eq!(actual, expected);
+
+    let mut lexer = Lexer::with_dialect("-01", Dialect::Json5);
+    let actual = lexer.next_token();
+    let expected = Err(LexError::new(
+        "Leading zeros are not allowed".to_string(),
+        1,
+        4,
+    ));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_json5_whitespace() {
+    let mut lexer = Lexer::with_dialect("\u{2003}\u{feff}1", Dialect::Json5);
+    assert_eq!(lexer.next_token().unwrap().value, TokenVal::JNumber(1.0));
+
+    // Not in Zs, so not whitespace in JSON5 even though Rust thinks it is
+    let mut lexer = Lexer::with_dialect("\u{85}1", Dialect::Json5);
+    assert!(lexer.next_token().is_err());
+}
+
+#[test]
+fn test_spans() {
+    let input = "{ \"é\": [1.5, true] }";
+    let mut lexer = Lexer::new(input);
+    let mut texts = Vec::new();
+
+    loop {
+        let token = lexer.next_token().unwrap();
+        if token.value == TokenVal::Eof {
+            assert_eq!((token.start, token.end), (input.len(), input.len()));
+            break;
+        }
+        texts.push(&input[token.start..token.end]);
+    }
+
+    assert_eq!(
+        texts,
+        vec!["{", "\"é\"", ":", "[", "1.5", ",", "true", "]", "}"]
+    );
+}
@@ -1,15 +1,56 @@
 #![allow(dead_code)]
 #![allow(unused_variables)]
 
+pub mod codegen;
+pub mod convert;
+pub mod cst;
+pub mod edit;
+pub mod encoding;
+pub mod flatten;
+pub mod gron;
+pub mod hjson;
+pub mod ijson;
+pub mod infer;
+pub mod jmespath;
+pub mod jq;
 pub mod json;
+pub mod jtd;
 mod lexer;
 mod parser;
+pub mod partial;
+pub mod path;
+pub mod recover;
+pub mod repair;
+pub mod schema;
+pub mod seq;
+pub mod serializer;
 
-use crate::parser::{ParseResult, Parser};
+pub use crate::lexer::Dialect;
+use crate::parser::Parser;
+pub use crate::parser::{ParseError, ParseResult};
 
 pub fn parse(input: &str) -> ParseResult {
     let mut parser = Parser::new(input);
-    let result = parser.parse();
+    parser.parse()
+}
+
+pub fn parse_with(input: &str, dialect: Dialect) -> ParseResult {
+    let mut parser = Parser::with_dialect(input, dialect);
+    parser.parse()
+}
+
+/// Parses JSON text in any of the encodings `encoding::decode` detects,
+/// ignoring a byte-order mark. Invalid bytes are reported with their offset.
+pub fn parse_bytes(input: &[u8]) -> ParseResult {
+    parse_bytes_with(input, Dialect::Json)
+}
 
-    result
+pub fn parse_bytes_with(input: &[u8], dialect: Dialect) -> ParseResult {
+    match encoding::decode(input) {
+        Ok(decoded) => parse_with(&decoded.text, dialect),
+        Err(err) => Err(ParseError::new(
+            format!("{} at byte {}", err.err_msg, err.offset),
+            err.line_no,
+        )),
+    }
 }
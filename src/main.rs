@@ -1,17 +1,590 @@
-use jsonparser;
+use jsonparser::codegen::Model;
+use jsonparser::encoding::{self, Encoding};
+use jsonparser::json::JSON;
+use jsonparser::serializer::{self, NonFinite, SerializeError};
+use jsonparser::{cst, gron, hjson, ijson, infer, path, Dialect, ParseError};
+
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::process;
+
+const USAGE: &str = "\
+usage: jsonparser <command> [options] [file...]
+       jsonparser query [options] <expression> [file...]
+
+Reads each file, or standard input when no file (or `-`) is given.
+
+commands:
+  fmt        pretty-print each document
+  minify     print each document without insignificant whitespace
+  validate   report every document that fails to parse
+  query      print the values selected by a JSON Pointer (`/a/0`) or
+             JSONPath (`$.a[0]`) expression
+  gron       print one `json.path = value;` assignment per line
+  codegen    print Rust or TypeScript types for the documents, inferred
+             from all of them together, or for a JSON Schema given with
+             --schema
+
+options:
+  --dialect <name>   input syntax: json (default), jsonc, json5 or hjson
+  --indent <n>       spaces per indentation level for fmt (default 2)
+  -w, --write        fmt: rewrite files in place instead of printing them
+  --check            fmt: print files that are not formatted and fail;
+                     both need the json dialect, which fmt reindents
+                     without reordering keys or rewriting numbers
+  --non-finite <p>   fmt, minify: what to write for NaN and infinite numbers,
+                     such as 1e400 or json5's Infinity: null (default),
+                     string or error; fmt keeps json's own literals
+  --ijson            validate: also reject documents that are not I-JSON
+                     (RFC 7493), such as ones with duplicate keys
+  --ndjson           query, codegen: read one document per line
+  --raw              query: print strings without quotes
+  --paths            query: print `path<TAB>value` lines
+  -u, --ungron       gron: rebuild documents from assignment lines
+  --lang <name>      codegen: rust (default) or typescript
+  --name <name>      codegen: name of the root type (default Root)
+  --schema           codegen: read a JSON Schema instead of samples";
+
+// Exit statuses: invalid input and usage errors are told apart so hooks can
+// distinguish a bad file from a bad invocation.
+const EXIT_INVALID: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Output {
+    Json,
+    Raw,
+    Paths,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Lang {
+    Rust,
+    TypeScript,
+}
+
+#[derive(Clone, Copy)]
+enum Syntax {
+    Dialect(Dialect),
+    Hjson,
+}
+
+struct Options {
+    syntax: Syntax,
+    indent: usize,
+    non_finite: NonFinite,
+    write: bool,
+    check: bool,
+    ndjson: bool,
+    output: Output,
+    expression: Option<String>,
+    ungron: bool,
+    ijson: bool,
+    lang: Lang,
+    name: String,
+    schema: bool,
+    files: Vec<String>,
+}
+
+struct Input {
+    name: String,
+    path: Option<String>,
+    text: String,
+    /// How the file was encoded, so that `fmt -w` can write it back the
+    /// same way.
+    encoding: Encoding,
+    bom: bool,
+}
 
 fn main() {
-    let x = jsonparser::parse(
-        r#"[
-        4.5, null, "hello world", true, { "key1": false },
-        [ false, true, "hello", null],
-        {
-            "key1": true,
-            "key2": false,
-            "key3": [0, 1, 2, 3]
-        }
-    ]"#,
-    )
-    .unwrap();
-    println!("{}", x);
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    process::exit(run(&args));
+}
+
+fn run(args: &[String]) -> i32 {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return usage_error("missing command"),
+    };
+
+    if let "help" | "-h" | "--help" = command {
+        println!("{}", USAGE);
+        return 0;
+    }
+    if !["fmt", "minify", "validate", "query", "gron", "codegen"].contains(&command) {
+        return usage_error(&format!("unknown command `{}`", command));
+    }
+
+    let options = match parse_options(command, rest) {
+        Ok(options) => options,
+        Err(err_msg) => return usage_error(&err_msg),
+    };
+
+    match command {
+        "fmt" => fmt(&options),
+        "minify" => minify(&options),
+        "query" => query(&options),
+        "gron" => gron(&options),
+        "codegen" => codegen(&options),
+        _ => validate(&options),
+    }
+}
+
+fn usage_error(err_msg: &str) -> i32 {
+    eprintln!("jsonparser: {}\n\n{}", err_msg, USAGE);
+
+    EXIT_USAGE
+}
+
+fn parse_options(command: &str, args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        syntax: Syntax::Dialect(Dialect::Json),
+        indent: 2,
+        non_finite: NonFinite::Null,
+        write: false,
+        check: false,
+        ndjson: false,
+        output: Output::Json,
+        expression: None,
+        ungron: false,
+        ijson: false,
+        lang: Lang::Rust,
+        name: "Root".to_string(),
+        schema: false,
+        files: Vec::new(),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => (&arg[..i], Some(arg[i + 1..].to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next().cloned())
+                .ok_or(format!("option `{}` needs a value", flag))
+        };
+
+        match flag {
+            "--dialect" => {
+                options.syntax = match value()?.as_str() {
+                    "json" => Syntax::Dialect(Dialect::Json),
+                    "jsonc" => Syntax::Dialect(Dialect::Jsonc),
+                    "json5" => Syntax::Dialect(Dialect::Json5),
+                    "hjson" => Syntax::Hjson,
+                    other => return Err(format!("unknown dialect `{}`", other)),
+                }
+            }
+            "--indent" if command == "fmt" => {
+                let indent = value()?;
+                options.indent = indent
+                    .parse()
+                    .map_err(|_| format!("invalid indent `{}`", indent))?;
+            }
+            "--non-finite" if command == "fmt" || command == "minify" => {
+                options.non_finite = match value()?.as_str() {
+                    "null" => NonFinite::Null,
+                    "string" => NonFinite::String,
+                    "error" => NonFinite::Error,
+                    other => return Err(format!("unknown non-finite policy `{}`", other)),
+                }
+            }
+            "-w" | "--write" if command == "fmt" => options.write = true,
+            "--check" if command == "fmt" => options.check = true,
+            "--ijson" if command == "validate" => options.ijson = true,
+            "--ndjson" if command == "query" || command == "codegen" => options.ndjson = true,
+            "--raw" if command == "query" => options.output = Output::Raw,
+            "--paths" if command == "query" => options.output = Output::Paths,
+            "-u" | "--ungron" if command == "gron" => options.ungron = true,
+            "--lang" if command == "codegen" => {
+                options.lang = match value()?.as_str() {
+                    "rust" => Lang::Rust,
+                    "typescript" | "ts" => Lang::TypeScript,
+                    other => return Err(format!("unknown language `{}`", other)),
+                }
+            }
+            "--name" if command == "codegen" => options.name = value()?,
+            "--schema" if command == "codegen" => options.schema = true,
+            "--" => {
+                options.files.extend(args.cloned());
+                break;
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option `{}` for `{}`", arg, command))
+            }
+            _ => options.files.push(arg.clone()),
+        }
+    }
+
+    if command == "query" {
+        if options.files.is_empty() {
+            return Err("missing query expression".to_string());
+        }
+        options.expression = Some(options.files.remove(0));
+    }
+    let name = &options.name;
+    if name.is_empty()
+        || name.starts_with(|c: char| c.is_ascii_digit())
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(format!("invalid type name `{}`", name));
+    }
+    if options.ijson && !matches!(options.syntax, Syntax::Dialect(Dialect::Json)) {
+        return Err("`--ijson` only applies to the json dialect".to_string());
+    }
+    if options.schema && options.ndjson {
+        return Err("`--schema` and `--ndjson` cannot be combined".to_string());
+    }
+    if (options.write || options.check) && !matches!(options.syntax, Syntax::Dialect(Dialect::Json))
+    {
+        return Err("`--write` and `--check` only apply to the json dialect".to_string());
+    }
+    if options.write && options.check {
+        return Err("`--write` and `--check` cannot be combined".to_string());
+    }
+    if options.write && (options.files.is_empty() || options.files.iter().any(|f| f == "-")) {
+        return Err("`--write` needs files to rewrite, not standard input".to_string());
+    }
+
+    Ok(options)
+}
+
+// Runs `action` on every input, reporting unreadable files along the way,
+// and returns the exit status for the whole command.
+fn for_each_input<F>(options: &Options, mut action: F) -> i32
+where
+    F: FnMut(&Input) -> bool,
+{
+    let mut status = 0;
+
+    for input in read_inputs(&options.files) {
+        let ok = match input {
+            Ok(input) => action(&input),
+            Err((name, err_msg)) => {
+                eprintln!("{}: {}", name, err_msg);
+                false
+            }
+        };
+
+        if !ok {
+            status = EXIT_INVALID;
+        }
+    }
+
+    status
+}
+
+fn read_inputs(files: &[String]) -> Vec<Result<Input, (String, String)>> {
+    if files.is_empty() {
+        return vec![read_input("-")];
+    }
+
+    files.iter().map(|file| read_input(file)).collect()
+}
+
+// Reads the whole input as bytes so that UTF-16 and UTF-32 text, with or
+// without a byte-order mark, is transcoded rather than rejected.
+fn read_input(file: &str) -> Result<Input, (String, String)> {
+    let (name, path, bytes) = if file == "-" {
+        let mut bytes = Vec::new();
+        let result = io::stdin().read_to_end(&mut bytes);
+        ("<stdin>".to_string(), None, result.map(|_| bytes))
+    } else {
+        (file.to_string(), Some(file.to_string()), fs::read(file))
+    };
+
+    let bytes = match bytes {
+        Ok(bytes) => bytes,
+        Err(err) => return Err((name, err.to_string())),
+    };
+
+    match encoding::decode(&bytes) {
+        Ok(decoded) => Ok(Input {
+            name,
+            path,
+            text: decoded.text,
+            encoding: decoded.encoding,
+            bom: decoded.bom,
+        }),
+        Err(err) => Err((
+            format!("{}:{}", name, err.line_no),
+            format!("{} at byte {}", err.err_msg, err.offset),
+        )),
+    }
+}
+
+fn parse_input(options: &Options, input: &Input) -> Option<JSON> {
+    let result = match options.syntax {
+        Syntax::Dialect(dialect) => jsonparser::parse_with(&input.text, dialect),
+        Syntax::Hjson => hjson::parse(&input.text),
+    };
+
+    match result {
+        Ok(json) => Some(json),
+        Err(err) => {
+            report(&input.name, &err);
+            None
+        }
+    }
+}
+
+// Parses every line of NDJSON input as its own document, skipping blank
+// lines. Diagnostics carry the line number within the whole input, and the
+// flag returned alongside the documents is false if any line was invalid.
+fn parse_ndjson(options: &Options, input: &Input) -> (Vec<JSON>, bool) {
+    let mut docs = Vec::new();
+    let mut ok = true;
+
+    for (i, line) in input.text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let result = match options.syntax {
+            Syntax::Dialect(dialect) => jsonparser::parse_with(line, dialect),
+            Syntax::Hjson => hjson::parse(line),
+        };
+
+        match result {
+            Ok(json) => docs.push(json),
+            Err(mut err) => {
+                err.line_no += i as u64;
+                report(&input.name, &err);
+                ok = false;
+            }
+        }
+    }
+
+    (docs, ok)
+}
+
+fn report(name: &str, err: &ParseError) {
+    if err.column == 0 {
+        eprintln!("{}:{}: {}", name, err.line_no, err.err_msg);
+    } else {
+        eprintln!("{}:{}:{}: {}", name, err.line_no, err.column, err.err_msg);
+    }
+}
+
+fn report_serialize(name: &str, err: &SerializeError) {
+    eprintln!("{}: {} (at `{}`)", name, err.err_msg, err.path);
+}
+
+fn fmt(options: &Options) -> i32 {
+    let serialize_options = serializer::Options {
+        non_finite: options.non_finite,
+    };
+
+    for_each_input(options, |input| {
+        let mut formatted = match options.syntax {
+            // Going through the syntax tree keeps key order, duplicate keys
+            // and number literals, which `JSON` would lose.
+            Syntax::Dialect(Dialect::Json) => match cst::parse(&input.text) {
+                Ok(doc) => doc.to_string_pretty(options.indent),
+                Err(err) => {
+                    report(&input.name, &err);
+                    return false;
+                }
+            },
+            _ => {
+                let json = match parse_input(options, input) {
+                    Some(json) => json,
+                    None => return false,
+                };
+                match serializer::to_string_pretty_with(&json, options.indent, &serialize_options) {
+                    Ok(formatted) => formatted,
+                    Err(err) => {
+                        report_serialize(&input.name, &err);
+                        return false;
+                    }
+                }
+            }
+        };
+        formatted.push('\n');
+
+        if options.check {
+            if formatted != input.text {
+                println!("{}", input.name);
+                return false;
+            }
+        } else if options.write {
+            let path = input.path.as_ref().unwrap();
+            if formatted != input.text {
+                let bytes = encoding::encode(&formatted, input.encoding, input.bom);
+                if let Err(err) = fs::write(path, bytes) {
+                    eprintln!("{}: {}", input.name, err);
+                    return false;
+                }
+            }
+        } else {
+            print!("{}", formatted);
+        }
+
+        true
+    })
+}
+
+fn minify(options: &Options) -> i32 {
+    let serialize_options = serializer::Options {
+        non_finite: options.non_finite,
+    };
+
+    for_each_input(options, |input| {
+        let json = match parse_input(options, input) {
+            Some(json) => json,
+            None => return false,
+        };
+
+        match serializer::to_string_with(&json, &serialize_options) {
+            Ok(minified) => {
+                println!("{}", minified);
+                true
+            }
+            Err(err) => {
+                report_serialize(&input.name, &err);
+                false
+            }
+        }
+    })
+}
+
+fn validate(options: &Options) -> i32 {
+    if !options.ijson {
+        return for_each_input(options, |input| parse_input(options, input).is_some());
+    }
+
+    for_each_input(options, |input| match ijson::check(&input.text) {
+        Ok(violations) => {
+            for violation in &violations {
+                eprintln!(
+                    "{}:{}: {} (at `{}`)",
+                    input.name, violation.line_no, violation.err_msg, violation.path
+                );
+            }
+            violations.is_empty()
+        }
+        Err(err) => {
+            report(&input.name, &err);
+            false
+        }
+    })
+}
+
+fn query(options: &Options) -> i32 {
+    let expression = options.expression.as_ref().unwrap();
+    let query = match path::compile(expression) {
+        Ok(query) => query,
+        Err(err) => {
+            eprintln!(
+                "jsonparser: invalid expression at offset {}: {}",
+                err.pos, err.err_msg
+            );
+            return EXIT_USAGE;
+        }
+    };
+
+    for_each_input(options, |input| {
+        let (docs, ok) = if options.ndjson {
+            parse_ndjson(options, input)
+        } else {
+            match parse_input(options, input) {
+                Some(json) => (vec![json], true),
+                None => (Vec::new(), false),
+            }
+        };
+
+        for doc in &docs {
+            for found in query.select(doc) {
+                match (options.output, found.value) {
+                    (Output::Raw, JSON::JSONString(x)) => println!("{}", x),
+                    (Output::Paths, value) => println!(
+                        "{}\t{}",
+                        query.format_path(&found.path),
+                        serializer::to_string(value)
+                    ),
+                    (_, value) => println!("{}", serializer::to_string(value)),
+                }
+            }
+        }
+
+        ok
+    })
+}
+
+fn gron(options: &Options) -> i32 {
+    for_each_input(options, |input| {
+        if options.ungron {
+            return match gron::ungron(&input.text) {
+                Ok(json) => {
+                    println!("{}", serializer::to_string_pretty(&json, options.indent));
+                    true
+                }
+                Err(err) => {
+                    report(&input.name, &err);
+                    false
+                }
+            };
+        }
+
+        match parse_input(options, input) {
+            Some(json) => {
+                print!("{}", gron::gron(&json));
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+// Reads every document before writing anything, since the types describe
+// all of them at once.
+fn codegen(options: &Options) -> i32 {
+    let mut docs = Vec::new();
+    let status = for_each_input(options, |input| {
+        if options.ndjson {
+            let (found, ok) = parse_ndjson(options, input);
+            docs.extend(found);
+            return ok;
+        }
+
+        match parse_input(options, input) {
+            Some(json) => {
+                docs.push(json);
+                true
+            }
+            None => false,
+        }
+    });
+    if status != 0 {
+        return status;
+    }
+
+    let model = if options.schema {
+        if docs.len() != 1 {
+            return usage_error("`--schema` takes a single schema document");
+        }
+        match Model::from_json_schema(&docs[0], &options.name) {
+            Ok(model) => model,
+            Err(err) => {
+                eprintln!(
+                    "jsonparser: invalid schema at `{}`: {}",
+                    err.keyword_location, err.err_msg
+                );
+                return EXIT_INVALID;
+            }
+        }
+    } else {
+        Model::from_shape(&infer::infer(&docs), &options.name)
+    };
+
+    match options.lang {
+        Lang::Rust => print!("{}", model.to_rust()),
+        Lang::TypeScript => print!("{}", model.to_typescript()),
+    }
+
+    0
 }
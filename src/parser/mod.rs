@@ -1,5 +1,5 @@
 use crate::json::JSON;
-use crate::lexer::{Lexer, Token, TokenVal};
+use crate::lexer::{Dialect, Lexer, Token, TokenVal};
 
 use std::collections::HashMap;
 
@@ -8,13 +8,23 @@ mod tests;
 
 #[derive(Debug, PartialEq)]
 pub struct ParseError {
-    err_msg: String,
-    line_no: u64,
+    pub err_msg: String,
+    pub line_no: u64,
+    /// 1-based, in characters; 0 when the error is only known by its line.
+    pub column: u64,
 }
 
 impl ParseError {
-    fn new(err_msg: String, line_no: u64) -> Self {
-        ParseError { err_msg, line_no }
+    pub(crate) fn new(err_msg: String, line_no: u64) -> Self {
+        ParseError::at(err_msg, line_no, 0)
+    }
+
+    pub(crate) fn at(err_msg: String, line_no: u64, column: u64) -> Self {
+        ParseError {
+            err_msg,
+            line_no,
+            column,
+        }
     }
 }
 
@@ -27,7 +37,11 @@ pub struct Parser<'a> {
 
 impl<'a> Parser<'a> {
     pub fn new(input: &'a str) -> Self {
-        let lexer = Lexer::new(input);
+        Parser::with_dialect(input, Dialect::Json)
+    }
+
+    pub fn with_dialect(input: &'a str, dialect: Dialect) -> Self {
+        let lexer = Lexer::with_dialect(input, dialect);
 
         Parser {
             lexer,
@@ -41,7 +55,7 @@ impl<'a> Parser<'a> {
                 self.curr_token = Some(token);
                 Ok(())
             }
-            Err(err) => self.error(err.err_msg, err.line_no),
+            Err(err) => Err(ParseError::at(err.err_msg, err.line_no, err.column)),
         }
     }
 
@@ -61,18 +75,26 @@ impl<'a> Parser<'a> {
         let token = self.curr_token.take().unwrap();
 
         if token.value != value {
-            self.error(err_msg, token.line_no)
+            self.error(err_msg, token.line_no, token.start)
         } else {
             self.cont()
         }
     }
 
-    fn error(&self, err_msg: String, line_no: u64) -> Result<(), ParseError> {
-        Err(ParseError::new(err_msg, line_no))
+    // In dialects that allow it, a comma may be followed directly by the
+    // closing bracket or brace.
+    fn trailing_comma(&self, close: TokenVal) -> bool {
+        let token = self.curr_token.as_ref().unwrap();
+
+        self.lexer.dialect().allows_trailing_commas() && token.value == close
+    }
+
+    fn error(&self, err_msg: String, line_no: u64, offset: usize) -> Result<(), ParseError> {
+        Err(ParseError::at(err_msg, line_no, self.lexer.column(offset)))
     }
 
-    fn throw(&self, err_msg: String, line_no: u64) -> ParseResult {
-        Err(ParseError::new(err_msg, line_no))
+    fn throw(&self, err_msg: String, line_no: u64, offset: usize) -> ParseResult {
+        Err(ParseError::at(err_msg, line_no, self.lexer.column(offset)))
     }
 
     fn get_string(&mut self) -> Result<String, ParseError> {
@@ -80,10 +102,11 @@ impl<'a> Parser<'a> {
         self.cont()?;
 
         match token.value {
-            TokenVal::JString(x) => Ok(x),
-            _ => Err(ParseError::new(
+            TokenVal::JString(x) | TokenVal::Ident(x) => Ok(x),
+            _ => Err(ParseError::at(
                 "Expecting string".to_string(),
                 token.line_no,
+                self.lexer.column(token.start),
             )),
         }
     }
@@ -101,6 +124,10 @@ impl<'a> Parser<'a> {
         obj.insert(key, val);
 
         while self.matches(TokenVal::Comma)? {
+            if self.trailing_comma(TokenVal::RBrace) {
+                break;
+            }
+
             let key = self.get_string()?;
             self.expect(TokenVal::Colon, "Expecting colon after key".to_string())?;
             let val = self.parse_elem()?;
@@ -126,6 +153,10 @@ impl<'a> Parser<'a> {
         arr.push(elem);
 
         while self.matches(TokenVal::Comma)? {
+            if self.trailing_comma(TokenVal::RBrack) {
+                break;
+            }
+
             let elem = self.parse_elem()?;
             arr.push(elem);
         }
@@ -150,7 +181,19 @@ impl<'a> Parser<'a> {
             TokenVal::Null => Ok(JSON::JSONNull),
             TokenVal::JString(x) => Ok(JSON::JSONString(x)),
             TokenVal::JNumber(x) => Ok(JSON::JSONNum(x)),
-            _ => self.throw("Unexpected token".to_string(), token.line_no),
+            TokenVal::Ident(x) => match x.as_str() {
+                "true" => Ok(JSON::JSONBool(true)),
+                "false" => Ok(JSON::JSONBool(false)),
+                "null" => Ok(JSON::JSONNull),
+                "Infinity" => Ok(JSON::JSONNum(f64::INFINITY)),
+                "NaN" => Ok(JSON::JSONNum(f64::NAN)),
+                _ => self.throw(
+                    format!("Unexpected identifier: {}", x),
+                    token.line_no,
+                    token.start,
+                ),
+            },
+            _ => self.throw("Unexpected token".to_string(), token.line_no, token.start),
         }
     }
 
@@ -163,4 +206,21 @@ impl<'a> Parser<'a> {
 
         Ok(elem)
     }
+
+    // Reads the next of several values written one after another, or
+    // `None` once only whitespace is left. After an error the parser is in
+    // no state to go on.
+    pub fn parse_next(&mut self) -> Option<ParseResult> {
+        if self.curr_token.is_none() {
+            if let Err(err) = self.cont() {
+                return Some(Err(err));
+            }
+        }
+
+        if self.curr_token.as_ref().unwrap().value == TokenVal::Eof {
+            return None;
+        }
+
+        Some(self.parse_elem())
+    }
 }
@@ -1,4 +1,5 @@
 use crate::json::JSON;
+use crate::lexer::Dialect;
 use crate::parser::{ParseError, Parser};
 
 use std::collections::HashMap;
@@ -66,9 +67,10 @@ fn test_simple_array() {
 fn test_arr_missing_comma() {
     let mut parser = Parser::new("[true, false null, 1.2]");
     let actual = parser.parse();
-    let expected = Err(ParseError::new(
+    let expected = Err(ParseError::at(
         "Expecting right bracket at end of array".to_string(),
         1,
+        14,
     ));
 
     assert_eq!(actual, expected);
@@ -93,7 +95,7 @@ fn test_simple_obj() {
 fn test_extra_comma() {
     let mut parser = Parser::new("{ \"abc\":1.1, \"def\":2.2, \"xyz\":3.3, }");
     let actual = parser.parse();
-    let expected = Err(ParseError::new("Expecting string".to_string(), 1));
+    let expected = Err(ParseError::at("Expecting string".to_string(), 1, 36));
 
     assert_eq!(actual, expected);
 }
@@ -102,7 +104,11 @@ fn test_extra_comma() {
 fn test_obj_missing_colon() {
     let mut parser = Parser::new("{ \"abc\" 1.1, \"def\":2.2, \"xyz\":3.3 }");
     let actual = parser.parse();
-    let expected = Err(ParseError::new("Expecting colon after key".to_string(), 1));
+    let expected = Err(ParseError::at(
+        "Expecting colon after key".to_string(),
+        1,
+        9,
+    ));
 
     assert_eq!(actual, expected);
 }
@@ -111,7 +117,87 @@ fn test_obj_missing_colon() {
 fn test_invalid_obj_key() {
     let mut parser = Parser::new("{ 15 : false }");
     let actual = parser.parse();
-    let expected = Err(ParseError::new("Expecting string".to_string(), 1));
+    let expected = Err(ParseError::at("Expecting string".to_string(), 1, 3));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_trailing_commas() {
+    let input = "{ \"abc\": [1.1, 2.2,], // note\n \"def\": null, }";
+    let mut parser = Parser::with_dialect(input, Dialect::Jsonc);
+    let actual = parser.parse().unwrap();
+
+    let mut hmap = HashMap::new();
+    hmap.insert(
+        "abc".to_string(),
+        JSON::JSONArray(vec![JSON::JSONNum(1.1), JSON::JSONNum(2.2)]),
+    );
+    hmap.insert("def".to_string(), JSON::JSONNull);
+
+    let expected = JSON::JSONObject(hmap);
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_trailing_comma_strict() {
+    let mut parser = Parser::new("[1.1, 2.2,]");
+    let actual = parser.parse();
+    let expected = Err(ParseError::at("Unexpected token".to_string(), 1, 11));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_single_trailing_comma() {
+    let mut parser = Parser::with_dialect("[1.1,,]", Dialect::Jsonc);
+    let actual = parser.parse();
+    let expected = Err(ParseError::at("Unexpected token".to_string(), 1, 6));
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_json5() {
+    let input = "// vendor config
+    {
+        unquoted: 'single',
+        \"quoted\": [0x10, +.5, Infinity,],
+        nested: { null: null, yes: true },
+    }";
+    let mut parser = Parser::with_dialect(input, Dialect::Json5);
+    let actual = parser.parse().unwrap();
+
+    let mut nested = HashMap::new();
+    nested.insert("null".to_string(), JSON::JSONNull);
+    nested.insert("yes".to_string(), JSON::JSONBool(true));
+
+    let mut hmap = HashMap::new();
+    hmap.insert(
+        "unquoted".to_string(),
+        JSON::JSONString("single".to_string()),
+    );
+    hmap.insert(
+        "quoted".to_string(),
+        JSON::JSONArray(vec![
+            JSON::JSONNum(16.0),
+            JSON::JSONNum(0.5),
+            JSON::JSONNum(f64::INFINITY),
+        ]),
+    );
+    hmap.insert("nested".to_string(), JSON::JSONObject(nested));
+
+    let expected = JSON::JSONObject(hmap);
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_json5_bare_word_value() {
+    let mut parser = Parser::with_dialect("{ a: b }", Dialect::Json5);
+    let actual = parser.parse();
+    let expected = Err(ParseError::at("Unexpected identifier: b".to_string(), 1, 6));
 
     assert_eq!(actual, expected);
 }
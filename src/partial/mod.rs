@@ -0,0 +1,335 @@
+//! Parsing of truncated JSON.
+//!
+//! `parse` takes a prefix of a JSON document, such as a response that is
+//! still being received, and returns the value it implies so far: open
+//! strings, arrays and objects are closed, a literal cut short is completed
+//! and a number, key or escape that may still go on is left out. Every node
+//! says whether it was complete in the input. Text that can't be the start
+//! of a JSON document is an error.
+
+use crate::json::JSON;
+use crate::parser::ParseError;
+
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Partial>),
+    /// Members in input order.
+    Object(Vec<(String, Partial)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Partial {
+    pub value: Value,
+    /// Whether the whole value was in the input, closing quote, bracket or
+    /// brace included.
+    pub complete: bool,
+}
+
+impl Partial {
+    fn new(value: Value, complete: bool) -> Self {
+        Partial { value, complete }
+    }
+
+    pub fn to_json(&self) -> JSON {
+        match self.value {
+            Value::Null => JSON::JSONNull,
+            Value::Bool(x) => JSON::JSONBool(x),
+            Value::Number(x) => JSON::JSONNum(x),
+            Value::String(ref x) => JSON::JSONString(x.clone()),
+            Value::Array(ref x) => JSON::JSONArray(x.iter().map(Partial::to_json).collect()),
+            Value::Object(ref x) => {
+                let obj: HashMap<String, JSON> = x
+                    .iter()
+                    .map(|(key, val)| (key.clone(), val.to_json()))
+                    .collect();
+                JSON::JSONObject(obj)
+            }
+        }
+    }
+}
+
+/// Returns `None` while the input doesn't hold any part of a value yet,
+/// e.g. when it is empty or ends in the middle of the first number.
+pub fn parse(input: &str) -> Result<Option<Partial>, ParseError> {
+    let mut reader = Reader {
+        chars: input.chars().collect(),
+        pos: 0,
+        line_no: 1,
+    };
+
+    reader.parse()
+}
+
+struct Reader {
+    chars: Vec<char>,
+    pos: usize,
+    line_no: u64,
+}
+
+impl Reader {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn cont(&mut self) {
+        if self.peek() == Some('\n') {
+            self.line_no += 1;
+        }
+        self.pos += 1;
+    }
+
+    fn throw<T>(&self, err_msg: String) -> Result<T, ParseError> {
+        Err(ParseError::new(err_msg, self.line_no))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = self.peek() {
+            self.cont();
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.peek().is_none()
+    }
+
+    fn parse(&mut self) -> Result<Option<Partial>, ParseError> {
+        if self.at_end() {
+            return Ok(None);
+        }
+
+        let value = self.parse_value()?;
+
+        if !self.at_end() {
+            return self.throw("Expecting EOF".to_string());
+        }
+
+        Ok(value)
+    }
+
+    // `None` is a value that was cut off too early to tell what it is.
+    fn parse_value(&mut self) -> Result<Option<Partial>, ParseError> {
+        match self.peek() {
+            Some('{') => {
+                self.cont();
+                self.parse_object().map(Some)
+            }
+            Some('[') => {
+                self.cont();
+                self.parse_array().map(Some)
+            }
+            Some('"') => {
+                let (x, complete) = self.parse_string()?;
+                Ok(Some(Partial::new(Value::String(x), complete)))
+            }
+            Some('t') => self.parse_literal("true", Value::Bool(true)),
+            Some('f') => self.parse_literal("false", Value::Bool(false)),
+            Some('n') => self.parse_literal("null", Value::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            Some(c) => self.throw(format!("Unexpected '{}' where a value was expected", c)),
+            None => Ok(None),
+        }
+    }
+
+    // A keyword cut short can only end one way.
+    fn parse_literal(&mut self, word: &str, value: Value) -> Result<Option<Partial>, ParseError> {
+        for expected in word.chars() {
+            match self.peek() {
+                Some(c) if c == expected => self.cont(),
+                Some(_) => return self.throw("Invalid keyword".to_string()),
+                None => return Ok(Some(Partial::new(value, false))),
+            }
+        }
+
+        Ok(Some(Partial::new(value, true)))
+    }
+
+    // A number is only known once something follows it.
+    fn parse_number(&mut self) -> Result<Option<Partial>, ParseError> {
+        let mut literal = String::new();
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || "+-.eE".contains(c)) {
+                break;
+            }
+            literal.push(c);
+            self.cont();
+        }
+
+        if self.peek().is_none() {
+            return Ok(None);
+        }
+
+        match crate::parse(&literal) {
+            Ok(JSON::JSONNum(x)) => Ok(Some(Partial::new(Value::Number(x), true))),
+            _ => self.throw("Invalid number".to_string()),
+        }
+    }
+
+    // Returns the string so far and whether its closing quote was seen.
+    fn parse_string(&mut self) -> Result<(String, bool), ParseError> {
+        self.cont(); // eat the opening quote
+
+        let mut val = String::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                '"' => {
+                    self.cont();
+                    return Ok((val, true));
+                }
+                '\\' => {
+                    if !self.parse_escape(&mut val)? {
+                        break;
+                    }
+                }
+                '\x00'..='\x1f' => {
+                    return self.throw(format!("Invalid character in string: {}", c as u8))
+                }
+                _ => {
+                    val.push(c);
+                    self.cont();
+                }
+            }
+        }
+
+        Ok((val, false))
+    }
+
+    // Tells whether the escape was complete; one cut short is dropped.
+    fn parse_escape(&mut self, string: &mut String) -> Result<bool, ParseError> {
+        self.cont(); // eat the backslash
+
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Ok(false),
+        };
+        self.cont();
+
+        match c {
+            '\\' | '"' | '/' => string.push(c),
+            'b' => string.push('\x08'),
+            'f' => string.push('\x0c'),
+            'n' => string.push('\n'),
+            'r' => string.push('\r'),
+            't' => string.push('\t'),
+            'u' => {
+                let mut code_pt = 0;
+                for _ in 0..4 {
+                    match self.peek().map(|c| c.to_digit(16)) {
+                        Some(Some(digit)) => code_pt = code_pt * 16 + digit,
+                        Some(None) => return self.throw("Invalid escape character".to_string()),
+                        None => return Ok(false),
+                    }
+                    self.cont();
+                }
+
+                match std::char::from_u32(code_pt) {
+                    Some(x) => string.push(x),
+                    None => return self.throw("Invalid escape character".to_string()),
+                }
+            }
+            _ => return self.throw("Invalid escape character".to_string()),
+        }
+
+        Ok(true)
+    }
+
+    fn parse_array(&mut self) -> Result<Partial, ParseError> {
+        let mut arr = Vec::new();
+
+        if self.at_end() {
+            return Ok(Partial::new(Value::Array(arr), false));
+        }
+        if self.peek() == Some(']') {
+            self.cont();
+            return Ok(Partial::new(Value::Array(arr), true));
+        }
+
+        loop {
+            if self.at_end() {
+                break;
+            }
+            if let Some(elem) = self.parse_value()? {
+                arr.push(elem);
+            }
+
+            if self.at_end() {
+                break;
+            }
+            match self.peek() {
+                Some(',') => self.cont(),
+                Some(']') => {
+                    self.cont();
+                    return Ok(Partial::new(Value::Array(arr), true));
+                }
+                _ => return self.throw("Expecting comma or right bracket".to_string()),
+            }
+        }
+
+        Ok(Partial::new(Value::Array(arr), false))
+    }
+
+    fn parse_object(&mut self) -> Result<Partial, ParseError> {
+        let mut obj = Vec::new();
+
+        if self.at_end() {
+            return Ok(Partial::new(Value::Object(obj), false));
+        }
+        if self.peek() == Some('}') {
+            self.cont();
+            return Ok(Partial::new(Value::Object(obj), true));
+        }
+
+        loop {
+            if self.at_end() {
+                break;
+            }
+            if self.peek() != Some('"') {
+                return self.throw("Expecting string".to_string());
+            }
+
+            // A member is kept once its key is complete and some of its
+            // value has arrived.
+            let key = match self.parse_string()? {
+                (key, true) => key,
+                _ => break,
+            };
+            if self.at_end() {
+                break;
+            }
+            if self.peek() != Some(':') {
+                return self.throw("Expecting colon after key".to_string());
+            }
+            self.cont();
+            if self.at_end() {
+                break;
+            }
+            if let Some(val) = self.parse_value()? {
+                obj.push((key, val));
+            }
+
+            if self.at_end() {
+                break;
+            }
+            match self.peek() {
+                Some(',') => self.cont(),
+                Some('}') => {
+                    self.cont();
+                    return Ok(Partial::new(Value::Object(obj), true));
+                }
+                _ => return self.throw("Expecting comma or right brace".to_string()),
+            }
+        }
+
+        Ok(Partial::new(Value::Object(obj), false))
+    }
+}
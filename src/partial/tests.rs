@@ -0,0 +1,88 @@
+use crate::json::JSON;
+use crate::partial::{parse, Partial, Value};
+
+fn json(input: &str) -> Option<JSON> {
+    parse(input).unwrap().map(|x| x.to_json())
+}
+
+fn expected(input: &str) -> Option<JSON> {
+    Some(crate::parse(input).unwrap())
+}
+
+#[test]
+fn test_complete_document() {
+    let input = r#"{"a": [1, 2.5, "x"], "b": {"c": null}, "d": true}"#;
+    let result = parse(input).unwrap().unwrap();
+
+    assert!(result.complete);
+    assert_eq!(Some(result.to_json()), expected(input));
+}
+
+#[test]
+fn test_prefixes() {
+    assert_eq!(json(""), None);
+    assert_eq!(json("  "), None);
+    assert_eq!(json("12"), None);
+    assert_eq!(json("\""), Some(JSON::JSONString(String::new())));
+    assert_eq!(json("["), expected("[]"));
+    assert_eq!(json("[1, 2"), expected("[1]"));
+    assert_eq!(json("[1, 2,"), expected("[1, 2]"));
+    assert_eq!(json("[1, tr"), expected("[1, true]"));
+    assert_eq!(json(r#"{"msg": "Hel"#), expected(r#"{"msg": "Hel"}"#));
+    assert_eq!(json(r#"{"msg": "a\"#), expected(r#"{"msg": "a"}"#));
+    assert_eq!(json(r#"{"msg": "a\u00"#), expected(r#"{"msg": "a"}"#));
+    assert_eq!(json(r#"{"a": 1, "ke"#), expected(r#"{"a": 1}"#));
+    assert_eq!(json(r#"{"a": 1, "key""#), expected(r#"{"a": 1}"#));
+    assert_eq!(json(r#"{"a": 1, "key": "#), expected(r#"{"a": 1}"#));
+    assert_eq!(
+        json(r#"{"a": [{"b": [1, {"#),
+        expected(r#"{"a": [{"b": [1, {}]}]}"#)
+    );
+}
+
+#[test]
+fn test_complete_flags() {
+    let result = parse(r#"[{"a": "x"}, {"b": "y"#).unwrap().unwrap();
+
+    let elems = match result.value {
+        Value::Array(ref x) => x,
+        _ => panic!("expected an array"),
+    };
+    assert!(!result.complete);
+    assert!(elems[0].complete);
+    assert!(!elems[1].complete);
+    assert_eq!(
+        elems[1].value,
+        Value::Object(vec![(
+            "b".to_string(),
+            Partial {
+                value: Value::String("y".to_string()),
+                complete: false,
+            }
+        )])
+    );
+
+    let result = parse("[nul").unwrap().unwrap();
+    match result.value {
+        Value::Array(ref x) => assert_eq!(
+            x[0],
+            Partial {
+                value: Value::Null,
+                complete: false,
+            }
+        ),
+        _ => panic!("expected an array"),
+    }
+}
+
+#[test]
+fn test_errors() {
+    assert!(parse("[1 2").is_err());
+    assert!(parse("{1").is_err());
+    assert!(parse(r#"{"a" 1"#).is_err());
+    assert!(parse("[tx").is_err());
+    assert!(parse("[01,").is_err());
+    assert!(parse(r#"["\q"#).is_err());
+    assert!(parse("[1] [").is_err());
+    assert_eq!(parse("[\n1,\n]").unwrap_err().line_no, 3);
+}
@@ -0,0 +1,672 @@
+use super::{Match, PathElem, PathError};
+use crate::json::JSON;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Filter(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    descendant: bool,
+    selectors: Vec<Selector>,
+}
+
+// A query inside a filter, relative to the current node (`@`) or the root
+// (`$`).
+#[derive(Debug, Clone, PartialEq)]
+struct SubQuery {
+    absolute: bool,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Comparable {
+    Literal(JSON),
+    Query(SubQuery),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Comparator, Comparable, Comparable),
+    Exists(SubQuery),
+}
+
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    pub fn compile(input: &str) -> Result<Self, PathError> {
+        let mut parser = Parser {
+            chars: input.chars().collect(),
+            pos: 0,
+        };
+
+        parser.expect('$')?;
+        let segments = parser.segments()?;
+        if parser.pos < parser.chars.len() {
+            return parser.error(format!(
+                "Unexpected character `{}`",
+                parser.chars[parser.pos]
+            ));
+        }
+
+        Ok(JsonPath { segments })
+    }
+
+    pub fn select<'a>(&self, input: &'a JSON) -> Vec<Match<'a>> {
+        let start = vec![Match {
+            path: Vec::new(),
+            value: input,
+        }];
+
+        eval_segments(&self.segments, start, input)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn looking_at(&self, text: &str) -> bool {
+        text.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn error<T>(&self, err_msg: String) -> Result<T, PathError> {
+        Err(PathError::new(err_msg, self.pos))
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), PathError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.error(format!("Expecting `{}`", c))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>, PathError> {
+        let mut segments = Vec::new();
+
+        loop {
+            let descendant = if self.looking_at("..") {
+                self.pos += 2;
+                true
+            } else {
+                false
+            };
+
+            let selectors = match self.peek() {
+                Some('[') => self.bracketed()?,
+                Some('*') if descendant => {
+                    self.pos += 1;
+                    vec![Selector::Wildcard]
+                }
+                _ if descendant => vec![Selector::Name(self.member_name()?)],
+                Some('.') => {
+                    self.pos += 1;
+                    if self.peek() == Some('*') {
+                        self.pos += 1;
+                        vec![Selector::Wildcard]
+                    } else {
+                        vec![Selector::Name(self.member_name()?)]
+                    }
+                }
+                _ => return Ok(segments),
+            };
+
+            segments.push(Segment {
+                descendant,
+                selectors,
+            });
+        }
+    }
+
+    fn member_name(&mut self) -> Result<String, PathError> {
+        let start = self.pos;
+
+        while let Some(c) = self.peek() {
+            let valid = c.is_ascii_alphabetic()
+                || c == '_'
+                || !c.is_ascii()
+                || (self.pos > start && c.is_ascii_digit());
+            if !valid {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        if self.pos == start {
+            return self.error("Expecting member name".to_string());
+        }
+
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn bracketed(&mut self) -> Result<Vec<Selector>, PathError> {
+        self.expect('[')?;
+
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.selector()?);
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(selectors);
+                }
+                _ => return self.error("Expecting `,` or `]` in selector".to_string()),
+            }
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, PathError> {
+        match self.peek() {
+            Some('\'') | Some('"') => Ok(Selector::Name(self.string()?)),
+            Some('*') => {
+                self.pos += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some('?') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                Ok(Selector::Filter(self.logical_or()?))
+            }
+            Some(c) if c == ':' || c == '-' || c.is_ascii_digit() => self.index_or_slice(),
+            _ => self.error("Expecting selector".to_string()),
+        }
+    }
+
+    fn index_or_slice(&mut self) -> Result<Selector, PathError> {
+        let mut parts = [None, None, None];
+        let mut colons = 0;
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(c) if c == '-' || c.is_ascii_digit() => {
+                    if parts[colons].is_some() {
+                        return self.error("Expecting `:` in slice".to_string());
+                    }
+                    parts[colons] = Some(self.integer()?);
+                }
+                Some(':') if colons < 2 => {
+                    self.pos += 1;
+                    colons += 1;
+                }
+                _ => break,
+            }
+        }
+
+        match (colons, parts[0]) {
+            (0, Some(i)) => Ok(Selector::Index(i)),
+            (0, None) => self.error("Expecting index".to_string()),
+            _ => Ok(Selector::Slice(parts[0], parts[1], parts[2])),
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64, PathError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        while let Some('0'..='9') = self.peek() {
+            self.pos += 1;
+        }
+
+        let text: String = self.chars[start..self.pos].iter().collect();
+        let digits = text.trim_start_matches('-');
+        if digits.is_empty() || (digits.len() > 1 && digits.starts_with('0')) || text == "-0" {
+            self.pos = start;
+            return self.error(format!("Invalid integer `{}`", text));
+        }
+
+        match text.parse() {
+            Ok(x) => Ok(x),
+            Err(_) => {
+                self.pos = start;
+                self.error(format!("Integer `{}` out of range", text))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, PathError> {
+        let quote = self.peek().unwrap();
+        self.pos += 1;
+
+        let mut val = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+
+            match c {
+                '\\' => {
+                    let escaped = match self.peek() {
+                        Some('b') => '\x08',
+                        Some('f') => '\x0c',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('/') => '/',
+                        Some('\\') => '\\',
+                        Some('u') => {
+                            self.pos += 1;
+                            let code_pt = self.hex_escape()?;
+                            val.push(code_pt);
+                            continue;
+                        }
+                        Some(c) if c == quote => c,
+                        _ => return self.error("Invalid escape character".to_string()),
+                    };
+                    self.pos += 1;
+                    val.push(escaped);
+                }
+                _ if c == quote => return Ok(val),
+                '\x00'..='\x1f' => {
+                    return self.error("Control characters must be escaped".to_string())
+                }
+                _ => val.push(c),
+            }
+        }
+
+        self.error("Unterminated string".to_string())
+    }
+
+    fn hex_escape(&mut self) -> Result<char, PathError> {
+        let mut code_pt = self.hex4()?;
+
+        if (0xd800..0xdc00).contains(&code_pt) && self.looking_at("\\u") {
+            self.pos += 2;
+            let low = self.hex4()?;
+            if (0xdc00..0xe000).contains(&low) {
+                code_pt = 0x10000 + ((code_pt - 0xd800) << 10) + (low - 0xdc00);
+            }
+        }
+
+        match std::char::from_u32(code_pt) {
+            Some(c) => Ok(c),
+            None => self.error("Invalid escape character".to_string()),
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, PathError> {
+        let mut code_pt = 0;
+
+        for _ in 0..4 {
+            match self.peek().and_then(|c| c.to_digit(16)) {
+                Some(digit) => code_pt = code_pt * 16 + digit,
+                None => return self.error("Invalid escape character".to_string()),
+            }
+            self.pos += 1;
+        }
+
+        Ok(code_pt)
+    }
+
+    fn logical_or(&mut self) -> Result<Expr, PathError> {
+        let mut left = self.logical_and()?;
+
+        while self.looking_at("||") {
+            self.pos += 2;
+            self.skip_whitespace();
+            let right = self.logical_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn logical_and(&mut self) -> Result<Expr, PathError> {
+        let mut left = self.basic()?;
+
+        while self.looking_at("&&") {
+            self.pos += 2;
+            self.skip_whitespace();
+            let right = self.basic()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn basic(&mut self) -> Result<Expr, PathError> {
+        let expr = match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                let inner = self.logical_or()?;
+                self.expect(')')?;
+                inner
+            }
+            Some('!') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                Expr::Not(Box::new(self.basic()?))
+            }
+            _ => {
+                let start = self.pos;
+                let left = self.comparable()?;
+                self.skip_whitespace();
+
+                match self.comparator() {
+                    Some(op) => {
+                        self.skip_whitespace();
+                        let right = self.comparable()?;
+                        Expr::Compare(op, left, right)
+                    }
+                    None => match left {
+                        Comparable::Query(query) => Expr::Exists(query),
+                        Comparable::Literal(_) => {
+                            self.pos = start;
+                            return self.error("A literal must be compared".to_string());
+                        }
+                    },
+                }
+            }
+        };
+        self.skip_whitespace();
+
+        Ok(expr)
+    }
+
+    fn comparator(&mut self) -> Option<Comparator> {
+        let ops = [
+            ("==", Comparator::Eq),
+            ("!=", Comparator::Ne),
+            ("<=", Comparator::Le),
+            (">=", Comparator::Ge),
+            ("<", Comparator::Lt),
+            (">", Comparator::Gt),
+        ];
+
+        for &(text, op) in &ops {
+            if self.looking_at(text) {
+                self.pos += text.len();
+                return Some(op);
+            }
+        }
+
+        None
+    }
+
+    fn comparable(&mut self) -> Result<Comparable, PathError> {
+        match self.peek() {
+            Some('@') | Some('$') => {
+                let absolute = self.peek() == Some('$');
+                self.pos += 1;
+                let segments = self.segments()?;
+                Ok(Comparable::Query(SubQuery { absolute, segments }))
+            }
+            Some('\'') | Some('"') => Ok(Comparable::Literal(JSON::JSONString(self.string()?))),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            _ => {
+                for &(text, ref value) in &[
+                    ("true", JSON::JSONBool(true)),
+                    ("false", JSON::JSONBool(false)),
+                    ("null", JSON::JSONNull),
+                ] {
+                    if self.looking_at(text) {
+                        self.pos += text.len();
+                        return Ok(Comparable::Literal(value.clone()));
+                    }
+                }
+
+                self.error("Expecting a query or a literal".to_string())
+            }
+        }
+    }
+
+    // Number literals follow JSON's grammar, so they are handed to the
+    // crate's own parser once their extent is known.
+    fn number(&mut self) -> Result<Comparable, PathError> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || "+-.eE".contains(c)) {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        let text: String = self.chars[start..self.pos].iter().collect();
+        match crate::parse(&text) {
+            Ok(JSON::JSONNum(x)) => Ok(Comparable::Literal(JSON::JSONNum(x))),
+            _ => {
+                self.pos = start;
+                self.error(format!("Invalid number `{}`", text))
+            }
+        }
+    }
+}
+
+fn eval_segments<'a>(
+    segments: &[Segment],
+    start: Vec<Match<'a>>,
+    root: &'a JSON,
+) -> Vec<Match<'a>> {
+    let mut nodes = start;
+
+    for segment in segments {
+        let mut next = Vec::new();
+
+        for node in nodes {
+            if segment.descendant {
+                for desc in descendants(node) {
+                    apply_selectors(&segment.selectors, &desc, root, &mut next);
+                }
+            } else {
+                apply_selectors(&segment.selectors, &node, root, &mut next);
+            }
+        }
+
+        nodes = next;
+    }
+
+    nodes
+}
+
+// A node followed by all of its descendants, in document order.
+fn descendants(node: Match) -> Vec<Match> {
+    let mut out = Vec::new();
+    let mut stack = vec![node];
+
+    while let Some(node) = stack.pop() {
+        let mut kids = children(&node);
+        kids.reverse();
+        out.push(node);
+        stack.extend(kids);
+    }
+
+    out
+}
+
+fn child<'a>(node: &Match<'a>, elem: PathElem, value: &'a JSON) -> Match<'a> {
+    let mut path = node.path.clone();
+    path.push(elem);
+
+    Match { path, value }
+}
+
+// Object members come out sorted by key so results are stable from one
+// run to the next.
+fn children<'a>(node: &Match<'a>) -> Vec<Match<'a>> {
+    match *node.value {
+        JSON::JSONArray(ref x) => x
+            .iter()
+            .enumerate()
+            .map(|(i, val)| child(node, PathElem::Index(i), val))
+            .collect(),
+        JSON::JSONObject(ref x) => crate::serializer::sorted_entries(x)
+            .into_iter()
+            .map(|(key, val)| child(node, PathElem::Key(key.clone()), val))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn apply_selectors<'a>(
+    selectors: &[Selector],
+    node: &Match<'a>,
+    root: &'a JSON,
+    out: &mut Vec<Match<'a>>,
+) {
+    for selector in selectors {
+        match *selector {
+            Selector::Name(ref name) => {
+                if let JSON::JSONObject(ref x) = *node.value {
+                    if let Some(val) = x.get(name) {
+                        out.push(child(node, PathElem::Key(name.clone()), val));
+                    }
+                }
+            }
+            Selector::Wildcard => out.extend(children(node)),
+            Selector::Index(i) => {
+                if let JSON::JSONArray(ref x) = *node.value {
+                    let i = if i < 0 { i + x.len() as i64 } else { i };
+                    if i >= 0 && (i as usize) < x.len() {
+                        let i = i as usize;
+                        out.push(child(node, PathElem::Index(i), &x[i]));
+                    }
+                }
+            }
+            Selector::Slice(start, end, step) => {
+                if let JSON::JSONArray(ref x) = *node.value {
+                    for i in slice_indices(x.len() as i64, start, end, step) {
+                        out.push(child(node, PathElem::Index(i), &x[i]));
+                    }
+                }
+            }
+            Selector::Filter(ref expr) => {
+                for kid in children(node) {
+                    if test(expr, kid.value, root) {
+                        out.push(kid);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn slice_indices(len: i64, start: Option<i64>, end: Option<i64>, step: Option<i64>) -> Vec<usize> {
+    let step = step.unwrap_or(1);
+    let normalize = |i: i64| if i < 0 { len + i } else { i };
+    let mut indices = Vec::new();
+
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        let mut i = lower;
+        while i < upper {
+            indices.push(i as usize);
+            i = match i.checked_add(step) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = normalize(end.unwrap_or(-len - 1)).clamp(-1, len - 1);
+        let mut i = upper;
+        while lower < i {
+            indices.push(i as usize);
+            i = match i.checked_add(step) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+    }
+
+    indices
+}
+
+fn eval_query<'a>(query: &SubQuery, current: &'a JSON, root: &'a JSON) -> Vec<Match<'a>> {
+    let start = Match {
+        path: Vec::new(),
+        value: if query.absolute { root } else { current },
+    };
+
+    eval_segments(&query.segments, vec![start], root)
+}
+
+fn test(expr: &Expr, current: &JSON, root: &JSON) -> bool {
+    match *expr {
+        Expr::Or(ref a, ref b) => test(a, current, root) || test(b, current, root),
+        Expr::And(ref a, ref b) => test(a, current, root) && test(b, current, root),
+        Expr::Not(ref a) => !test(a, current, root),
+        Expr::Exists(ref query) => !eval_query(query, current, root).is_empty(),
+        Expr::Compare(op, ref a, ref b) => {
+            let a = comparable_value(a, current, root);
+            let b = comparable_value(b, current, root);
+            compare(op, a.as_ref(), b.as_ref())
+        }
+    }
+}
+
+// A query used in a comparison stands for its single result; one that
+// selects nothing (or more than one value) is "Nothing".
+fn comparable_value(comparable: &Comparable, current: &JSON, root: &JSON) -> Option<JSON> {
+    match *comparable {
+        Comparable::Literal(ref x) => Some(x.clone()),
+        Comparable::Query(ref query) => {
+            let matches = eval_query(query, current, root);
+            if matches.len() == 1 {
+                Some(matches[0].value.clone())
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn compare(op: Comparator, a: Option<&JSON>, b: Option<&JSON>) -> bool {
+    match op {
+        Comparator::Eq => a == b,
+        Comparator::Ne => a != b,
+        Comparator::Lt => less_than(a, b),
+        Comparator::Le => less_than(a, b) || a == b,
+        Comparator::Gt => less_than(b, a),
+        Comparator::Ge => less_than(b, a) || a == b,
+    }
+}
+
+// Only numbers and strings are ordered, and only against their own kind.
+fn less_than(a: Option<&JSON>, b: Option<&JSON>) -> bool {
+    match (a, b) {
+        (Some(JSON::JSONNum(x)), Some(JSON::JSONNum(y))) => x < y,
+        (Some(JSON::JSONString(x)), Some(JSON::JSONString(y))) => x < y,
+        _ => false,
+    }
+}
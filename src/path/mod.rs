@@ -0,0 +1,205 @@
+//! Selecting parts of a `JSON` value by path.
+//!
+//! Two syntaxes are understood: [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901)
+//! (`/items/0/name`), which names at most one value, and
+//! [JSONPath](https://www.rfc-editor.org/rfc/rfc9535) (`$.items[?@.price < 10].name`),
+//! which can select any number of them. `compile` tells the two apart by
+//! the leading `$`.
+//!
+//! JSONPath support covers names, wildcards, indices, slices, unions,
+//! descendant segments and filters with comparisons, `&&`, `||`, `!` and
+//! existence tests. Filter functions are not supported.
+
+use crate::json::JSON;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+mod jsonpath;
+mod pointer;
+
+#[cfg(test)]
+mod tests;
+
+use self::jsonpath::JsonPath;
+use self::pointer::Pointer;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathError {
+    pub err_msg: String,
+    pub pos: usize,
+}
+
+impl PathError {
+    fn new(err_msg: String, pos: usize) -> Self {
+        PathError { err_msg, pos }
+    }
+}
+
+/// One step from a value to one of its children.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathElem {
+    Key(String),
+    Index(usize),
+}
+
+/// A selected value together with its location in the document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match<'a> {
+    pub path: Vec<PathElem>,
+    pub value: &'a JSON,
+}
+
+pub enum Query {
+    Pointer(Pointer),
+    JsonPath(JsonPath),
+}
+
+impl Query {
+    /// Returns every value the query selects, in document order.
+    pub fn select<'a>(&self, input: &'a JSON) -> Vec<Match<'a>> {
+        match *self {
+            Query::Pointer(ref pointer) => pointer.select(input).into_iter().collect(),
+            Query::JsonPath(ref path) => path.select(input),
+        }
+    }
+
+    /// Formats a path in the query's own syntax: a JSON Pointer for pointer
+    /// queries, a normalized path (`$['a'][0]`) for JSONPath ones.
+    pub fn format_path(&self, path: &[PathElem]) -> String {
+        match *self {
+            Query::Pointer(_) => to_pointer(path),
+            Query::JsonPath(_) => to_normalized_path(path),
+        }
+    }
+}
+
+pub fn compile(query: &str) -> Result<Query, PathError> {
+    if query.starts_with('$') {
+        Ok(Query::JsonPath(JsonPath::compile(query)?))
+    } else {
+        Ok(Query::Pointer(Pointer::compile(query)?))
+    }
+}
+
+// The reference tokens of a JSON Pointer, unescaped.
+pub(crate) fn pointer_tokens(pointer: &str) -> Result<Vec<String>, PathError> {
+    Ok(Pointer::compile(pointer)?.tokens().to_vec())
+}
+
+pub(crate) use self::pointer::parse_index;
+
+pub fn select<'a>(query: &str, input: &'a JSON) -> Result<Vec<Match<'a>>, PathError> {
+    Ok(compile(query)?.select(input))
+}
+
+pub fn to_pointer(path: &[PathElem]) -> String {
+    let mut out = String::new();
+
+    for elem in path {
+        out.push('/');
+        match *elem {
+            PathElem::Key(ref key) => out.push_str(&key.replace('~', "~0").replace('/', "~1")),
+            PathElem::Index(i) => write!(out, "{}", i).unwrap(),
+        }
+    }
+
+    out
+}
+
+pub fn to_normalized_path(path: &[PathElem]) -> String {
+    let mut out = String::from("$");
+
+    for elem in path {
+        match *elem {
+            PathElem::Key(ref key) => {
+                out.push_str("['");
+                for c in key.chars() {
+                    match c {
+                        '\'' => out.push_str("\\'"),
+                        '\\' => out.push_str("\\\\"),
+                        '\x08' => out.push_str("\\b"),
+                        '\x0c' => out.push_str("\\f"),
+                        '\n' => out.push_str("\\n"),
+                        '\r' => out.push_str("\\r"),
+                        '\t' => out.push_str("\\t"),
+                        '\x00'..='\x1f' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+                        _ => out.push(c),
+                    }
+                }
+                out.push_str("']");
+            }
+            PathElem::Index(i) => write!(out, "[{}]", i).unwrap(),
+        }
+    }
+
+    out
+}
+
+// The largest array index `assign` accepts. Arrays are padded with `null` up
+// to the index, so without a bound one short line of input could ask for
+// terabytes.
+pub(crate) const MAX_INDEX: usize = 1_000_000;
+
+// Stores `value` at `path` below `slot`, creating objects and arrays as the
+// path needs them and padding arrays with `null`.
+pub(crate) fn assign(
+    slot: &mut Option<JSON>,
+    path: &[PathElem],
+    value: JSON,
+) -> Result<(), String> {
+    let (first, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            // `{}` and `[]` only declare a container; they must not wipe out
+            // members that an earlier assignment already filled in.
+            let keep = matches!(
+                (&*slot, &value),
+                (Some(JSON::JSONObject(_)), JSON::JSONObject(x)) if x.is_empty()
+            ) || matches!(
+                (&*slot, &value),
+                (Some(JSON::JSONArray(_)), JSON::JSONArray(x)) if x.is_empty()
+            );
+            if !keep {
+                *slot = Some(value);
+            }
+            return Ok(());
+        }
+    };
+
+    if slot.is_none() {
+        *slot = Some(match *first {
+            PathElem::Key(_) => JSON::JSONObject(HashMap::new()),
+            PathElem::Index(_) => JSON::JSONArray(Vec::new()),
+        });
+    }
+
+    match (slot.as_mut().unwrap(), first) {
+        (JSON::JSONObject(obj), PathElem::Key(key)) => {
+            let mut child = obj.remove(key);
+            let result = assign(&mut child, rest, value);
+            if let Some(child) = child {
+                obj.insert(key.clone(), child);
+            }
+            result
+        }
+        (JSON::JSONArray(_), PathElem::Index(i)) if *i > MAX_INDEX => Err(format!(
+            "Index {} is too large, the limit is {}",
+            i, MAX_INDEX
+        )),
+        (JSON::JSONArray(arr), PathElem::Index(i)) => {
+            if arr.len() <= *i {
+                arr.resize(*i + 1, JSON::JSONNull);
+            }
+            let mut child = Some(std::mem::replace(&mut arr[*i], JSON::JSONNull));
+            if child == Some(JSON::JSONNull) {
+                child = None;
+            }
+            let result = assign(&mut child, rest, value);
+            arr[*i] = child.unwrap_or(JSON::JSONNull);
+            result
+        }
+        (_, PathElem::Key(key)) => Err(format!("Cannot set key `{}` on a non-object", key)),
+        (_, PathElem::Index(i)) => Err(format!("Cannot set index {} on a non-array", i)),
+    }
+}
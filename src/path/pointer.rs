@@ -0,0 +1,96 @@
+use super::{Match, PathElem, PathError};
+use crate::json::JSON;
+
+pub struct Pointer {
+    tokens: Vec<String>,
+}
+
+impl Pointer {
+    pub fn compile(input: &str) -> Result<Self, PathError> {
+        if input.is_empty() {
+            return Ok(Pointer { tokens: Vec::new() });
+        }
+        if !input.starts_with('/') {
+            return Err(PathError::new(
+                "A JSON Pointer must be empty or start with `/`".to_string(),
+                0,
+            ));
+        }
+
+        let mut tokens = Vec::new();
+        let mut pos = 1;
+
+        for raw in input[1..].split('/') {
+            tokens.push(unescape(raw, pos)?);
+            pos += raw.len() + 1;
+        }
+
+        Ok(Pointer { tokens })
+    }
+
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    pub fn select<'a>(&self, input: &'a JSON) -> Option<Match<'a>> {
+        let mut path = Vec::new();
+        let mut value = input;
+
+        for token in &self.tokens {
+            value = match *value {
+                JSON::JSONObject(ref x) => {
+                    path.push(PathElem::Key(token.clone()));
+                    x.get(token)?
+                }
+                JSON::JSONArray(ref x) => {
+                    let i = parse_index(token)?;
+                    path.push(PathElem::Index(i));
+                    x.get(i)?
+                }
+                _ => return None,
+            };
+        }
+
+        Some(Match { path, value })
+    }
+}
+
+// `~1` stands for `/` and `~0` for `~`; no other escapes exist.
+fn unescape(raw: &str, pos: usize) -> Result<String, PathError> {
+    let mut token = String::new();
+    let mut chars = raw.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if c != '~' {
+            token.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some((_, '0')) => token.push('~'),
+            Some((_, '1')) => token.push('/'),
+            _ => {
+                return Err(PathError::new(
+                    "`~` must be followed by `0` or `1`".to_string(),
+                    pos + i,
+                ))
+            }
+        }
+    }
+
+    Ok(token)
+}
+
+// Array indices are plain decimal without leading zeros; `-` (the element
+// after the last) never names an existing value.
+pub fn parse_index(token: &str) -> Option<usize> {
+    let valid = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+
+    if valid {
+        token.parse().ok()
+    } else {
+        None
+    }
+}
@@ -0,0 +1,150 @@
+use crate::json::JSON;
+use crate::path::{compile, select, to_normalized_path, to_pointer, PathElem};
+
+fn doc() -> JSON {
+    crate::parse(
+        r#"{
+            "store": {
+                "book": [
+                    { "title": "Sayings", "price": 8.95, "tags": ["old"] },
+                    { "title": "Sword", "price": 12.99 },
+                    { "title": "Moby Dick", "price": 8.99, "isbn": "0-553" },
+                    { "title": "Rings", "price": 22.99, "isbn": "0-395" }
+                ],
+                "bicycle": { "color": "red", "price": 399 }
+            },
+            "a/b": { "m~n": 1 },
+            "limit": 10
+        }"#,
+    )
+    .unwrap()
+}
+
+fn values(query: &str) -> Vec<JSON> {
+    let doc = doc();
+
+    select(query, &doc)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.value.clone())
+        .collect()
+}
+
+fn titles(query: &str) -> Vec<String> {
+    values(query)
+        .into_iter()
+        .map(|x| match x {
+            JSON::JSONString(x) => x,
+            other => panic!("expected a string, got {}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn test_pointer() {
+    assert_eq!(values("/store/book/1/price"), vec![JSON::JSONNum(12.99)]);
+    assert_eq!(values("/a~1b/m~0n"), vec![JSON::JSONNum(1.0)]);
+    assert_eq!(values("").len(), 1);
+    assert!(values("/store/book/01").is_empty());
+    assert!(values("/store/book/-").is_empty());
+    assert!(values("/missing").is_empty());
+    assert!(compile("store").is_err());
+    assert!(compile("/a~2").is_err());
+}
+
+#[test]
+fn test_names_and_indices() {
+    assert_eq!(titles("$.store.book[0].title"), vec!["Sayings"]);
+    assert_eq!(titles("$['store']['book'][-1]['title']"), vec!["Rings"]);
+    assert_eq!(
+        titles("$.store.book[0,2].title"),
+        vec!["Sayings", "Moby Dick"]
+    );
+    assert_eq!(values("$.store.bicycle.*").len(), 2);
+}
+
+#[test]
+fn test_slices() {
+    assert_eq!(
+        titles("$.store.book[1:3].title"),
+        vec!["Sword", "Moby Dick"]
+    );
+    assert_eq!(
+        titles("$.store.book[::2].title"),
+        vec!["Sayings", "Moby Dick"]
+    );
+    assert_eq!(
+        titles("$.store.book[::-1].title"),
+        vec!["Rings", "Moby Dick", "Sword", "Sayings"]
+    );
+    assert!(values("$.store.book[0:4:0]").is_empty());
+    assert_eq!(
+        titles("$.store.book[1:4:9223372036854775807].title"),
+        vec!["Sword"]
+    );
+    assert_eq!(
+        titles("$.store.book[2:0:-9223372036854775808].title"),
+        vec!["Moby Dick"]
+    );
+}
+
+#[test]
+fn test_descendants() {
+    assert_eq!(
+        titles("$..title"),
+        vec!["Sayings", "Sword", "Moby Dick", "Rings"]
+    );
+    assert_eq!(values("$..price").len(), 5);
+    assert_eq!(values("$..[0]").len(), 2);
+}
+
+#[test]
+fn test_filters() {
+    assert_eq!(
+        titles("$.store.book[?@.price < 10].title"),
+        vec!["Sayings", "Moby Dick"]
+    );
+    assert_eq!(titles("$..book[?@.isbn].title"), vec!["Moby Dick", "Rings"]);
+    assert_eq!(
+        titles("$..book[?!@.isbn && @.price > $.limit].title"),
+        vec!["Sword"]
+    );
+    assert_eq!(
+        titles("$..book[?(@.title == 'Rings' || @.tags[0] == \"old\")].title"),
+        vec!["Sayings", "Rings"]
+    );
+    assert!(values("$..book[?@.price == 'cheap']").is_empty());
+}
+
+#[test]
+fn test_syntax_errors() {
+    assert!(compile("$.").is_err());
+    assert!(compile("$[").is_err());
+    assert!(compile("$[01]").is_err());
+    assert!(compile("$[?1]").is_err());
+    assert!(compile("$['unterminated]").is_err());
+    assert!(compile("$.a b").is_err());
+}
+
+#[test]
+fn test_paths() {
+    let doc = doc();
+    let query = compile("$..book[?@.isbn].isbn").unwrap();
+    let paths: Vec<String> = query
+        .select(&doc)
+        .iter()
+        .map(|m| query.format_path(&m.path))
+        .collect();
+
+    assert_eq!(
+        paths,
+        vec![
+            "$['store']['book'][2]['isbn']",
+            "$['store']['book'][3]['isbn']"
+        ]
+    );
+
+    let path = vec![PathElem::Key("a/b'".to_string()), PathElem::Index(0)];
+    assert_eq!(to_pointer(&path), "/a~1b'/0");
+    assert_eq!(to_normalized_path(&path), "$['a/b\\''][0]");
+}
@@ -0,0 +1,346 @@
+//! An error-tolerant parser.
+//!
+//! Where `Parser` stops at the first error, this one records it, puts a
+//! `Missing` or `Error` node in the tree and resynchronizes at the next
+//! comma or closing bracket or brace, so a single pass reports every
+//! problem in the input along with a best-effort tree.
+
+use crate::json::JSON;
+use crate::lexer::{Dialect, Lexer, Token, TokenVal};
+use crate::parser::ParseError;
+
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Node>),
+    /// Members in source order; a key that couldn't be read is `Missing`.
+    Object(Vec<(Node, Node)>),
+    /// A value that should have been there but wasn't.
+    Missing,
+    /// Tokens that don't form a value.
+    Error,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Recovered {
+    pub node: Node,
+    pub errors: Vec<ParseError>,
+}
+
+impl Recovered {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl Node {
+    /// The tree as a plain value: `Missing` and `Error` nodes become
+    /// `null`, and members without a key are left out.
+    pub fn to_json(&self) -> JSON {
+        match *self {
+            Node::Null | Node::Missing | Node::Error => JSON::JSONNull,
+            Node::Bool(x) => JSON::JSONBool(x),
+            Node::Number(x) => JSON::JSONNum(x),
+            Node::String(ref x) => JSON::JSONString(x.clone()),
+            Node::Array(ref x) => JSON::JSONArray(x.iter().map(Node::to_json).collect()),
+            Node::Object(ref x) => {
+                let mut obj = HashMap::new();
+                for (key, val) in x {
+                    if let Node::String(ref key) = *key {
+                        obj.insert(key.clone(), val.to_json());
+                    }
+                }
+                JSON::JSONObject(obj)
+            }
+        }
+    }
+}
+
+pub fn parse(input: &str) -> Recovered {
+    parse_with(input, Dialect::Json)
+}
+
+pub fn parse_with(input: &str, dialect: Dialect) -> Recovered {
+    let mut parser = RecoveringParser {
+        lexer: Lexer::with_dialect(input, dialect),
+        curr_token: None,
+        closers: Vec::new(),
+        skipped: false,
+        errors: Vec::new(),
+    };
+
+    let node = parser.parse();
+
+    Recovered {
+        node,
+        errors: parser.errors,
+    }
+}
+
+enum Next {
+    Item,
+    Closed,
+    Unclosed,
+}
+
+struct RecoveringParser<'a> {
+    lexer: Lexer<'a>,
+    curr_token: Option<Token>,
+    // Closing tokens of the arrays and objects being parsed, innermost last.
+    closers: Vec<TokenVal>,
+    // Whether the lexer rejected any text before the current token.
+    skipped: bool,
+    errors: Vec<ParseError>,
+}
+
+impl<'a> RecoveringParser<'a> {
+    // Lexical errors are recorded and skipped; the lexer always moves past
+    // the text it rejects. Only the first of a run of them is kept, since
+    // the rest usually come from the same bad text.
+    fn cont(&mut self) {
+        self.skipped = false;
+        loop {
+            match self.lexer.next_token() {
+                Ok(token) => {
+                    self.curr_token = Some(token);
+                    return;
+                }
+                Err(err) => {
+                    if !self.skipped {
+                        self.errors
+                            .push(ParseError::at(err.err_msg, err.line_no, err.column));
+                    }
+                    self.skipped = true;
+                }
+            }
+        }
+    }
+
+    fn peek(&self) -> &TokenVal {
+        &self.curr_token.as_ref().unwrap().value
+    }
+
+    fn error(&mut self, err_msg: &str) {
+        let token = self.curr_token.as_ref().unwrap();
+        let column = self.lexer.column(token.start);
+        self.errors
+            .push(ParseError::at(err_msg.to_string(), token.line_no, column));
+    }
+
+    fn closes_outer(&self) -> bool {
+        self.closers.contains(self.peek())
+    }
+
+    fn parse(&mut self) -> Node {
+        self.cont();
+
+        let node = self.parse_value();
+
+        if *self.peek() != TokenVal::Eof {
+            self.error("Expecting EOF");
+            while *self.peek() != TokenVal::Eof {
+                self.skip_token();
+            }
+        }
+
+        node
+    }
+
+    fn parse_value(&mut self) -> Node {
+        let node = match self.peek().clone() {
+            TokenVal::LBrace => return self.parse_object(),
+            TokenVal::LBrack => return self.parse_array(),
+            TokenVal::True => Node::Bool(true),
+            TokenVal::False => Node::Bool(false),
+            TokenVal::Null => Node::Null,
+            TokenVal::JString(x) => Node::String(x),
+            TokenVal::JNumber(x) => Node::Number(x),
+            TokenVal::Ident(x) => match x.as_str() {
+                "true" => Node::Bool(true),
+                "false" => Node::Bool(false),
+                "null" => Node::Null,
+                "Infinity" => Node::Number(f64::INFINITY),
+                "NaN" => Node::Number(f64::NAN),
+                _ => {
+                    self.error(&format!("Unexpected identifier: {}", x));
+                    Node::Error
+                }
+            },
+            TokenVal::Colon => {
+                self.error("Unexpected token");
+                Node::Error
+            }
+            TokenVal::Comma | TokenVal::RBrace | TokenVal::RBrack | TokenVal::Eof => {
+                // Text the lexer rejected already stood where the value
+                // should be, and has been reported.
+                if self.skipped {
+                    return Node::Error;
+                }
+                self.error("Expecting value");
+                return Node::Missing;
+            }
+        };
+        self.cont();
+
+        node
+    }
+
+    // Skips one token, or a whole array or object, while resynchronizing.
+    fn skip_token(&mut self) {
+        match self.peek() {
+            TokenVal::LBrace | TokenVal::LBrack => {
+                self.parse_value();
+            }
+            _ => self.cont(),
+        }
+    }
+
+    // Skips ahead to the next comma, the container's own closer, or a
+    // closer of an enclosing container.
+    fn resync(&mut self, close: &TokenVal) {
+        loop {
+            let token = self.peek();
+            if *token == TokenVal::Comma
+                || token == close
+                || *token == TokenVal::Eof
+                || self.closes_outer()
+            {
+                return;
+            }
+            self.skip_token();
+        }
+    }
+
+    // Handles what follows an item: a comma, the closer, or something to
+    // skip over first.
+    fn after_item(&mut self, close: TokenVal, err_msg: &str) -> Next {
+        if *self.peek() != close && self.unclosed() {
+            return Next::Unclosed;
+        }
+        if *self.peek() != TokenVal::Comma && *self.peek() != close {
+            self.error(err_msg);
+            self.resync(&close);
+        }
+
+        if *self.peek() == TokenVal::Comma {
+            self.cont();
+            // After rejected text the comma isn't trailing; the value it
+            // separates was there but unreadable, and is already reported.
+            if *self.peek() == close
+                && !self.skipped
+                && !self.lexer.dialect().allows_trailing_commas()
+            {
+                self.error("Trailing comma");
+            }
+        }
+
+        if *self.peek() == close {
+            self.cont();
+            Next::Closed
+        } else if *self.peek() == TokenVal::Eof || self.closes_outer() {
+            Next::Unclosed
+        } else {
+            Next::Item
+        }
+    }
+
+    // An array or object that ends at EOF, or at the closer of a container
+    // around it, is reported and left without consuming anything more.
+    fn unclosed(&self) -> bool {
+        *self.peek() == TokenVal::Eof || self.closes_outer()
+    }
+
+    fn parse_array(&mut self) -> Node {
+        let err_msg = "Expecting right bracket at end of array";
+        let mut arr = Vec::new();
+        self.cont();
+
+        if *self.peek() == TokenVal::RBrack {
+            self.cont();
+            return Node::Array(arr);
+        }
+        if self.unclosed() {
+            self.error(err_msg);
+            return Node::Array(arr);
+        }
+
+        self.closers.push(TokenVal::RBrack);
+        loop {
+            arr.push(self.parse_value());
+
+            match self.after_item(TokenVal::RBrack, "Expecting comma or right bracket") {
+                Next::Item => (),
+                Next::Closed => break,
+                Next::Unclosed => {
+                    self.error(err_msg);
+                    break;
+                }
+            }
+        }
+        self.closers.pop();
+
+        Node::Array(arr)
+    }
+
+    fn parse_object(&mut self) -> Node {
+        let err_msg = "Expecting right brace at end of object";
+        let mut obj = Vec::new();
+        self.cont();
+
+        if *self.peek() == TokenVal::RBrace {
+            self.cont();
+            return Node::Object(obj);
+        }
+        if self.unclosed() {
+            self.error(err_msg);
+            return Node::Object(obj);
+        }
+
+        self.closers.push(TokenVal::RBrace);
+        loop {
+            let key = match self.peek().clone() {
+                TokenVal::JString(x) | TokenVal::Ident(x) => {
+                    self.cont();
+                    Node::String(x)
+                }
+                _ => {
+                    self.error("Expecting string");
+                    Node::Missing
+                }
+            };
+
+            let val = if *self.peek() == TokenVal::Colon {
+                self.cont();
+                self.parse_value()
+            } else if key == Node::Missing {
+                // Neither key nor colon: whatever is here isn't a member.
+                self.resync(&TokenVal::RBrace);
+                Node::Missing
+            } else {
+                self.error("Expecting colon after key");
+                Node::Missing
+            };
+            obj.push((key, val));
+
+            match self.after_item(TokenVal::RBrace, "Expecting comma or right brace") {
+                Next::Item => (),
+                Next::Closed => break,
+                Next::Unclosed => {
+                    self.error(err_msg);
+                    break;
+                }
+            }
+        }
+        self.closers.pop();
+
+        Node::Object(obj)
+    }
+}
@@ -0,0 +1,124 @@
+use crate::json::JSON;
+use crate::lexer::Dialect;
+use crate::recover::{parse, parse_with, Node};
+
+fn errors(input: &str) -> Vec<(String, u64)> {
+    parse(input)
+        .errors
+        .into_iter()
+        .map(|err| (err.err_msg, err.line_no))
+        .collect()
+}
+
+#[test]
+fn test_valid() {
+    let result = parse(r#"{"a": [1, true, null], "b": "x"}"#);
+
+    assert!(result.is_ok());
+    assert_eq!(
+        result.node.to_json(),
+        crate::parse(r#"{"a": [1, true, null], "b": "x"}"#).unwrap()
+    );
+}
+
+#[test]
+fn test_missing_values() {
+    let result = parse("[1,,2,]");
+
+    assert_eq!(
+        result.node,
+        Node::Array(vec![Node::Number(1.0), Node::Missing, Node::Number(2.0)])
+    );
+    assert_eq!(
+        errors("[1,,2,]"),
+        vec![
+            ("Expecting value".to_string(), 1),
+            ("Trailing comma".to_string(), 1),
+        ]
+    );
+    assert!(parse_with("[1,2,]", Dialect::Json5).is_ok());
+}
+
+#[test]
+fn test_all_errors() {
+    let input = "{\n  \"a\": 1 2,\n  \"b\" 3,\n  4: \"c\",\n  \"d\": nope\n}";
+
+    assert_eq!(
+        errors(input),
+        vec![
+            ("Expecting comma or right brace".to_string(), 2),
+            ("Expecting colon after key".to_string(), 3),
+            ("Expecting comma or right brace".to_string(), 3),
+            ("Expecting string".to_string(), 4),
+            ("Invalid keyword".to_string(), 5),
+        ]
+    );
+
+    let mut expected = std::collections::HashMap::new();
+    expected.insert("a".to_string(), JSON::JSONNum(1.0));
+    expected.insert("b".to_string(), JSON::JSONNull);
+    expected.insert("d".to_string(), JSON::JSONNull);
+    assert_eq!(parse(input).node.to_json(), JSON::JSONObject(expected));
+}
+
+#[test]
+fn test_unclosed() {
+    let result = parse("[{\"a\": [1, 2}, {\"b\": 3");
+
+    assert_eq!(
+        result.node,
+        Node::Array(vec![
+            Node::Object(vec![(
+                Node::String("a".to_string()),
+                Node::Array(vec![Node::Number(1.0), Node::Number(2.0)])
+            )]),
+            Node::Object(vec![(Node::String("b".to_string()), Node::Number(3.0))]),
+        ])
+    );
+    assert_eq!(
+        errors("[{\"a\": [1, 2}, {\"b\": 3"),
+        vec![
+            ("Expecting right bracket at end of array".to_string(), 1),
+            ("Expecting right brace at end of object".to_string(), 1),
+            ("Expecting right bracket at end of array".to_string(), 1),
+        ]
+    );
+    assert_eq!(errors("[1] ]"), vec![("Expecting EOF".to_string(), 1)]);
+}
+
+#[test]
+fn test_lexical_errors() {
+    let result = parse("[1, @, 2,\n \"x]");
+
+    assert_eq!(result.errors[0].err_msg, "Invalid character");
+    assert_eq!(result.errors[0].line_no, 1);
+    assert!(result.errors.len() > 1);
+    assert_eq!(result.node.to_json(), crate::parse("[1, null, 2]").unwrap());
+
+    for input in ["@@@", "[\"\\q\", 1", "{\"a\" \"b\" ::: ]]", "1 2 3", ""] {
+        assert!(!parse(input).is_ok());
+    }
+}
+
+#[test]
+fn test_columns() {
+    let columns = |input: &str| -> Vec<(String, u64, u64)> {
+        parse(input)
+            .errors
+            .into_iter()
+            .map(|err| (err.err_msg, err.line_no, err.column))
+            .collect()
+    };
+
+    assert_eq!(
+        columns("[1, x]"),
+        vec![("Invalid character".to_string(), 1, 5)]
+    );
+    assert_eq!(
+        columns("{\"é\": 1 2,\n \"b\": [1,]}"),
+        vec![
+            ("Expecting comma or right brace".to_string(), 1, 9),
+            ("Trailing comma".to_string(), 2, 10),
+        ]
+    );
+}
@@ -0,0 +1,448 @@
+//! Repair of almost-JSON.
+//!
+//! `repair` reads text that is nearly JSON, such as a log line or model
+//! output, and turns it into a `JSON` value. It accepts single-quoted
+//! strings, unquoted keys, Python's `True`/`False`/`None`, comments,
+//! trailing and missing commas, raw newlines in strings and input cut off
+//! before its strings, arrays and objects are closed. Every change it had
+//! to make is listed in the result; input it can't make sense of is still
+//! an error.
+
+use crate::json::JSON;
+use crate::parser::ParseError;
+
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    pub msg: String,
+    pub line_no: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Repaired {
+    pub value: JSON,
+    pub fixes: Vec<Fix>,
+}
+
+impl Repaired {
+    /// Whether the input was valid JSON to begin with.
+    pub fn is_unchanged(&self) -> bool {
+        self.fixes.is_empty()
+    }
+}
+
+pub fn repair(input: &str) -> Result<Repaired, ParseError> {
+    let mut repairer = Repairer {
+        chars: input.chars().collect(),
+        pos: 0,
+        line_no: 1,
+        fixes: Vec::new(),
+    };
+
+    let value = repairer.parse()?;
+
+    Ok(Repaired {
+        value,
+        fixes: repairer.fixes,
+    })
+}
+
+struct Repairer {
+    chars: Vec<char>,
+    pos: usize,
+    line_no: u64,
+    fixes: Vec<Fix>,
+}
+
+impl Repairer {
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).cloned()
+    }
+
+    fn cont(&mut self) {
+        if self.peek() == Some('\n') {
+            self.line_no += 1;
+        }
+        self.pos += 1;
+    }
+
+    fn throw<T>(&self, err_msg: String) -> Result<T, ParseError> {
+        Err(ParseError::new(err_msg, self.line_no))
+    }
+
+    fn fix(&mut self, msg: String) {
+        self.fixes.push(Fix {
+            msg,
+            line_no: self.line_no,
+        });
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => self.cont(),
+                Some('/') if self.peek_at(1) == Some('/') => {
+                    self.fix("Removed comment".to_string());
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.cont();
+                    }
+                }
+                Some('/') if self.peek_at(1) == Some('*') => {
+                    self.fix("Removed comment".to_string());
+                    self.pos += 2;
+                    while self.peek().is_some()
+                        && !(self.peek() == Some('*') && self.peek_at(1) == Some('/'))
+                    {
+                        self.cont();
+                    }
+                    self.pos = (self.pos + 2).min(self.chars.len());
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn parse(&mut self) -> Result<JSON, ParseError> {
+        self.skip_whitespace();
+        if self.peek().is_none() {
+            return self.throw("Unexpected EOF while parsing value".to_string());
+        }
+
+        let value = self.parse_value()?;
+
+        self.skip_whitespace();
+        if self.peek().is_some() {
+            return self.throw("Expecting EOF".to_string());
+        }
+
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> Result<JSON, ParseError> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('{') => {
+                self.cont();
+                self.parse_object()
+            }
+            Some('[') => {
+                self.cont();
+                self.parse_array()
+            }
+            Some('"') | Some('\'') => Ok(JSON::JSONString(self.parse_string())),
+            Some(c) if c == '-' || c == '+' || c == '.' || c.is_ascii_digit() => {
+                self.parse_number()
+            }
+            Some(c) if is_word_char(c) => {
+                let word = self.parse_word();
+                Ok(match word.as_str() {
+                    "true" => JSON::JSONBool(true),
+                    "false" => JSON::JSONBool(false),
+                    "null" => JSON::JSONNull,
+                    "True" | "False" | "None" => {
+                        let value = match word.as_str() {
+                            "True" => JSON::JSONBool(true),
+                            "False" => JSON::JSONBool(false),
+                            _ => JSON::JSONNull,
+                        };
+                        self.fix(format!("Replaced `{}` with `{}`", word, value));
+                        value
+                    }
+                    _ => {
+                        self.fix(format!("Quoted bare word `{}`", word));
+                        JSON::JSONString(word)
+                    }
+                })
+            }
+            Some(c) => self.throw(format!("Unexpected '{}' where a value was expected", c)),
+            None => {
+                self.fix("Added `null` for missing value".to_string());
+                Ok(JSON::JSONNull)
+            }
+        }
+    }
+
+    // Skips the comma after a member or element, if any, and tells whether
+    // the container goes on. A missing comma is inserted when another item
+    // follows; a trailing one is dropped.
+    fn separator(&mut self, close: char, name: &str) -> Result<bool, ParseError> {
+        self.skip_whitespace();
+
+        let mut comma = false;
+        while self.peek() == Some(',') {
+            if comma {
+                self.fix("Removed extra comma".to_string());
+            }
+            comma = true;
+            self.cont();
+            self.skip_whitespace();
+        }
+
+        match self.peek() {
+            Some(c) if c == close => {
+                if comma {
+                    self.fix("Removed trailing comma".to_string());
+                }
+                self.cont();
+                Ok(false)
+            }
+            None => {
+                if comma {
+                    self.fix("Removed trailing comma".to_string());
+                }
+                self.fix(format!("Closed unterminated {}", name));
+                Ok(false)
+            }
+            Some(_) if comma => Ok(true),
+            Some(c) if starts_value(c) => {
+                self.fix("Inserted missing comma".to_string());
+                Ok(true)
+            }
+            Some(c) => self.throw(format!("Unexpected '{}' in {}", c, name)),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JSON, ParseError> {
+        let mut obj = HashMap::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.cont();
+            return Ok(JSON::JSONObject(obj));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek().is_none() {
+                self.fix("Closed unterminated object".to_string());
+                break;
+            }
+
+            let key = self.parse_key()?;
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(':') => self.cont(),
+                None => {
+                    self.fix(format!("Dropped key `{}` without a value", key));
+                    self.fix("Closed unterminated object".to_string());
+                    break;
+                }
+                Some(_) => return self.throw("Expecting colon after key".to_string()),
+            }
+
+            let val = self.parse_value()?;
+            obj.insert(key, val);
+
+            if !self.separator('}', "object")? {
+                break;
+            }
+        }
+
+        Ok(JSON::JSONObject(obj))
+    }
+
+    fn parse_array(&mut self) -> Result<JSON, ParseError> {
+        let mut arr = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.cont();
+            return Ok(JSON::JSONArray(arr));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek().is_none() {
+                self.fix("Closed unterminated array".to_string());
+                break;
+            }
+
+            arr.push(self.parse_value()?);
+
+            if !self.separator(']', "array")? {
+                break;
+            }
+        }
+
+        Ok(JSON::JSONArray(arr))
+    }
+
+    fn parse_key(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some('"') | Some('\'') => Ok(self.parse_string()),
+            Some(c) if is_word_char(c) => {
+                let key = self.parse_word();
+                self.fix(format!("Quoted key `{}`", key));
+                Ok(key)
+            }
+            Some(c) => self.throw(format!("Unexpected '{}' where a key was expected", c)),
+            None => self.throw("Unexpected EOF while parsing key".to_string()),
+        }
+    }
+
+    fn parse_word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            if !is_word_char(c) {
+                break;
+            }
+            word.push(c);
+            self.cont();
+        }
+
+        word
+    }
+
+    // Strings never fail: raw control characters are kept (and escaped on
+    // output), a backslash that doesn't start an escape is kept as is and
+    // a missing closing quote is supplied.
+    fn parse_string(&mut self) -> String {
+        let quote = self.peek().unwrap();
+        if quote == '\'' {
+            self.fix("Replaced single quotes with double quotes".to_string());
+        }
+        self.cont();
+
+        let mut val = String::new();
+        let mut raw_newline = false;
+
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => {
+                    self.fix("Closed unterminated string".to_string());
+                    break;
+                }
+            };
+
+            if c == quote {
+                self.cont();
+                break;
+            }
+            if (c == '\n' || c == '\r') && !raw_newline {
+                self.fix("Escaped newline in string".to_string());
+                raw_newline = true;
+            }
+            self.cont();
+
+            if c == '\\' {
+                self.parse_escape(&mut val);
+            } else {
+                val.push(c);
+            }
+        }
+
+        val
+    }
+
+    fn parse_escape(&mut self, string: &mut String) {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return,
+        };
+        self.cont();
+
+        match c {
+            'b' => string.push('\x08'),
+            'f' => string.push('\x0c'),
+            'n' => string.push('\n'),
+            'r' => string.push('\r'),
+            't' => string.push('\t'),
+            'u' => match self.parse_code_point() {
+                Some(x) => string.push(x),
+                None => {
+                    self.fix("Escaped stray backslash".to_string());
+                    string.push_str("\\u");
+                }
+            },
+            '\\' | '"' | '/' | '\'' => string.push(c),
+            _ => {
+                self.fix("Escaped stray backslash".to_string());
+                string.push('\\');
+                string.push(c);
+            }
+        }
+    }
+
+    // A high surrogate takes the escaped low one after it along.
+    fn parse_code_point(&mut self) -> Option<char> {
+        let mut code_pt = self.parse_hex()?;
+
+        if (0xd800..0xdc00).contains(&code_pt)
+            && self.peek() == Some('\\')
+            && self.peek_at(1) == Some('u')
+        {
+            self.pos += 2;
+            match self.parse_hex() {
+                Some(low) if (0xdc00..0xe000).contains(&low) => {
+                    code_pt = 0x10000 + ((code_pt - 0xd800) << 10) + (low - 0xdc00);
+                }
+                _ => self.pos -= 2,
+            }
+        }
+
+        std::char::from_u32(code_pt)
+    }
+
+    fn parse_hex(&mut self) -> Option<u32> {
+        let digits: String = (0..4).filter_map(|i| self.peek_at(i)).collect();
+        if digits.len() != 4 {
+            return None;
+        }
+
+        let code_pt = u32::from_str_radix(&digits, 16).ok()?;
+        self.pos += 4;
+
+        Some(code_pt)
+    }
+
+    // Anything Rust reads as a number is accepted, so `+1`, `.5` and `1.`
+    // are normalized; input cut off inside an exponent loses it.
+    fn parse_number(&mut self) -> Result<JSON, ParseError> {
+        let mut literal = String::new();
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || "+-.eE".contains(c)) {
+                break;
+            }
+            literal.push(c);
+            self.cont();
+        }
+
+        if let Ok(value) = crate::parse(&literal) {
+            return Ok(value);
+        }
+
+        let trimmed = if self.peek().is_none() {
+            literal.trim_end_matches(['e', 'E', '+', '-'])
+        } else {
+            &literal
+        };
+
+        match trimmed.parse::<f64>() {
+            Ok(x) if x.is_finite() => {
+                let value = JSON::JSONNum(x);
+                self.fix(format!("Replaced number `{}` with `{}`", literal, value));
+                Ok(value)
+            }
+            _ => self.throw(format!("Invalid number: {}", literal)),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn starts_value(c: char) -> bool {
+    "{[\"'-+.".contains(c) || is_word_char(c)
+}
@@ -0,0 +1,132 @@
+use crate::json::JSON;
+use crate::repair::repair;
+
+fn fixes(input: &str) -> Vec<String> {
+    repair(input)
+        .unwrap()
+        .fixes
+        .into_iter()
+        .map(|fix| fix.msg)
+        .collect()
+}
+
+fn value(input: &str) -> JSON {
+    repair(input).unwrap().value
+}
+
+#[test]
+fn test_valid_input_unchanged() {
+    let input = r#"{"a": [1, -2.5e3, "x\ny"], "b": {"c": null, "d": true}}"#;
+    let result = repair(input).unwrap();
+
+    assert!(result.is_unchanged());
+    assert_eq!(result.value, crate::parse(input).unwrap());
+    assert_eq!(
+        value(r#""\ud83d\ude00""#),
+        JSON::JSONString("\u{1f600}".to_string())
+    );
+}
+
+#[test]
+fn test_python_style() {
+    let input = "{'name': 'O\\'Brien', active: True, 'tags': None, count: +3,}";
+
+    assert_eq!(
+        value(input),
+        crate::parse(r#"{"name": "O'Brien", "active": true, "tags": null, "count": 3}"#).unwrap()
+    );
+    assert_eq!(
+        fixes(input),
+        vec![
+            "Replaced single quotes with double quotes",
+            "Replaced single quotes with double quotes",
+            "Quoted key `active`",
+            "Replaced `True` with `true`",
+            "Replaced single quotes with double quotes",
+            "Replaced `None` with `null`",
+            "Quoted key `count`",
+            "Replaced number `+3` with `3`",
+            "Removed trailing comma",
+        ]
+    );
+}
+
+#[test]
+fn test_missing_commas() {
+    let input = "[\n  1\n  2\n  {\"a\": 1 \"b\": 2}\n  \"x\",, \"y\"\n]";
+
+    assert_eq!(
+        value(input),
+        crate::parse(r#"[1, 2, {"a": 1, "b": 2}, "x", "y"]"#).unwrap()
+    );
+
+    let result = repair(input).unwrap();
+    let lines: Vec<(&str, u64)> = result
+        .fixes
+        .iter()
+        .map(|fix| (fix.msg.as_str(), fix.line_no))
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            ("Inserted missing comma", 3),
+            ("Inserted missing comma", 4),
+            ("Inserted missing comma", 4),
+            ("Inserted missing comma", 5),
+            ("Removed extra comma", 5),
+        ]
+    );
+}
+
+#[test]
+fn test_strings() {
+    let input = "{\"text\": \"line one\nline two\", \"path\": \"C:\\temp\\x\" // note\n}";
+
+    assert_eq!(
+        value(input),
+        crate::parse(r#"{"text": "line one\nline two", "path": "C:\temp\\x"}"#).unwrap()
+    );
+    assert_eq!(
+        fixes(input),
+        vec![
+            "Escaped newline in string",
+            "Escaped stray backslash",
+            "Removed comment",
+        ]
+    );
+}
+
+#[test]
+fn test_truncated() {
+    assert_eq!(
+        value(r#"{"items": [{"id": 1, "name": "wid"#),
+        crate::parse(r#"{"items": [{"id": 1, "name": "wid"}]}"#).unwrap()
+    );
+    assert_eq!(
+        fixes(r#"{"items": [{"id": 1, "name": "wid"#),
+        vec![
+            "Closed unterminated string",
+            "Closed unterminated object",
+            "Closed unterminated array",
+            "Closed unterminated object",
+        ]
+    );
+
+    assert_eq!(value("[1, 2,"), crate::parse("[1, 2]").unwrap());
+    assert_eq!(value("[1.5e"), crate::parse("[1.5]").unwrap());
+    assert_eq!(
+        value(r#"{"a": 1, "b""#),
+        crate::parse(r#"{"a": 1}"#).unwrap()
+    );
+    assert_eq!(value(r#"{"a":"#), crate::parse(r#"{"a": null}"#).unwrap());
+}
+
+#[test]
+fn test_errors() {
+    assert!(repair("").is_err());
+    assert!(repair("[1] 2").is_err());
+    assert!(repair("{: 1}").is_err());
+    assert!(repair("{\"a\" 1}").is_err());
+    assert!(repair("[1 : 2]").is_err());
+    assert!(repair("[1-2]").is_err());
+}
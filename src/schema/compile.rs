@@ -0,0 +1,599 @@
+use super::regex::Regex;
+use super::SchemaError;
+use crate::json::JSON;
+use crate::path::{to_pointer, PathElem};
+
+use std::collections::HashMap;
+
+pub type SchemaId = usize;
+
+const TYPE_NAMES: [&str; 7] = [
+    "null", "boolean", "object", "array", "number", "string", "integer",
+];
+
+pub enum Node {
+    Bool(bool),
+    Keywords(Vec<Keyword>),
+}
+
+pub enum Keyword {
+    Type(Vec<String>),
+    Enum(Vec<JSON>),
+    Const(JSON),
+    MultipleOf(f64),
+    Maximum(f64),
+    ExclusiveMaximum(f64),
+    Minimum(f64),
+    ExclusiveMinimum(f64),
+    MaxLength(usize),
+    MinLength(usize),
+    Pattern(String, Regex),
+    MaxItems(usize),
+    MinItems(usize),
+    UniqueItems,
+    PrefixItems(Vec<SchemaId>),
+    // `items` only applies past the elements `prefixItems` covers.
+    Items {
+        schema: SchemaId,
+        start: usize,
+    },
+    Contains {
+        schema: SchemaId,
+        min: usize,
+        max: Option<usize>,
+    },
+    MaxProperties(usize),
+    MinProperties(usize),
+    Required(Vec<String>),
+    DependentRequired(Vec<(String, Vec<String>)>),
+    Properties(Vec<(String, SchemaId)>),
+    PatternProperties(Vec<(String, Regex, SchemaId)>),
+    // The names and patterns of the sibling `properties` and
+    // `patternProperties`, whose members this doesn't apply to.
+    AdditionalProperties {
+        schema: SchemaId,
+        properties: Vec<String>,
+        patterns: Vec<Regex>,
+    },
+    DependentSchemas(Vec<(String, SchemaId)>),
+    PropertyNames(SchemaId),
+    AllOf(Vec<SchemaId>),
+    AnyOf(Vec<SchemaId>),
+    OneOf(Vec<SchemaId>),
+    Not(SchemaId),
+    If {
+        condition: SchemaId,
+        then: Option<SchemaId>,
+        otherwise: Option<SchemaId>,
+    },
+    // An absolute URI, looked up when validating.
+    Ref(String),
+    UnevaluatedItems(SchemaId),
+    UnevaluatedProperties(SchemaId),
+}
+
+impl Keyword {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Keyword::Type(_) => "type",
+            Keyword::Enum(_) => "enum",
+            Keyword::Const(_) => "const",
+            Keyword::MultipleOf(_) => "multipleOf",
+            Keyword::Maximum(_) => "maximum",
+            Keyword::ExclusiveMaximum(_) => "exclusiveMaximum",
+            Keyword::Minimum(_) => "minimum",
+            Keyword::ExclusiveMinimum(_) => "exclusiveMinimum",
+            Keyword::MaxLength(_) => "maxLength",
+            Keyword::MinLength(_) => "minLength",
+            Keyword::Pattern(..) => "pattern",
+            Keyword::MaxItems(_) => "maxItems",
+            Keyword::MinItems(_) => "minItems",
+            Keyword::UniqueItems => "uniqueItems",
+            Keyword::PrefixItems(_) => "prefixItems",
+            Keyword::Items { .. } => "items",
+            Keyword::Contains { .. } => "contains",
+            Keyword::MaxProperties(_) => "maxProperties",
+            Keyword::MinProperties(_) => "minProperties",
+            Keyword::Required(_) => "required",
+            Keyword::DependentRequired(_) => "dependentRequired",
+            Keyword::Properties(_) => "properties",
+            Keyword::PatternProperties(_) => "patternProperties",
+            Keyword::AdditionalProperties { .. } => "additionalProperties",
+            Keyword::DependentSchemas(_) => "dependentSchemas",
+            Keyword::PropertyNames(_) => "propertyNames",
+            Keyword::AllOf(_) => "allOf",
+            Keyword::AnyOf(_) => "anyOf",
+            Keyword::OneOf(_) => "oneOf",
+            Keyword::Not(_) => "not",
+            Keyword::If { .. } => "if",
+            Keyword::Ref(_) => "$ref",
+            Keyword::UnevaluatedItems(_) => "unevaluatedItems",
+            Keyword::UnevaluatedProperties(_) => "unevaluatedProperties",
+        }
+    }
+}
+
+pub struct Compiler {
+    pub nodes: Vec<Node>,
+    // Every schema by absolute URI: the base URI of its resource plus a
+    // JSON Pointer or anchor fragment.
+    pub index: HashMap<String, SchemaId>,
+    // Each `$ref` target with the location of the `$ref`, checked once the
+    // whole document is compiled.
+    refs: Vec<(String, String)>,
+}
+
+// A schema's position: the resource it belongs to and where it is both
+// within that resource and within the whole document.
+#[derive(Clone)]
+struct Place {
+    base: String,
+    pointer: String,
+    location: String,
+}
+
+impl Place {
+    fn child(&self, name: &str) -> Place {
+        let segment = to_pointer(&[PathElem::Key(name.to_string())]);
+
+        Place {
+            base: self.base.clone(),
+            pointer: format!("{}{}", self.pointer, segment),
+            location: format!("{}{}", self.location, segment),
+        }
+    }
+
+    fn error<T>(&self, err_msg: String) -> Result<T, SchemaError> {
+        Err(SchemaError::new(err_msg, self.location.clone()))
+    }
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Compiler {
+            nodes: Vec::new(),
+            index: HashMap::new(),
+            refs: Vec::new(),
+        }
+    }
+
+    pub fn compile_root(&mut self, schema: &JSON) -> Result<SchemaId, SchemaError> {
+        let place = Place {
+            base: String::new(),
+            pointer: String::new(),
+            location: String::new(),
+        };
+        let root = self.compile(schema, &place)?;
+
+        for (uri, location) in &self.refs {
+            if !self.index.contains_key(uri) {
+                return Err(SchemaError::new(
+                    format!("Unresolvable reference `{}`", uri),
+                    location.clone(),
+                ));
+            }
+        }
+
+        Ok(root)
+    }
+
+    fn register(&mut self, base: &str, fragment: &str, id: SchemaId) {
+        self.index
+            .entry(format!("{}#{}", base, fragment))
+            .or_insert(id);
+    }
+
+    fn compile(&mut self, schema: &JSON, place: &Place) -> Result<SchemaId, SchemaError> {
+        let id = self.nodes.len();
+        self.nodes.push(Node::Bool(true));
+
+        let obj = match *schema {
+            JSON::JSONBool(x) => {
+                self.nodes[id] = Node::Bool(x);
+                self.register(&place.base, &place.pointer, id);
+                return Ok(id);
+            }
+            JSON::JSONObject(ref x) => x,
+            _ => return place.error("A schema must be an object or a boolean".to_string()),
+        };
+
+        self.register(&place.base, &place.pointer, id);
+
+        // `$id` starts a new resource that fragments are relative to.
+        let mut place = place.clone();
+        if let Some(JSON::JSONString(uri)) = obj.get("$id") {
+            let uri = resolve(&place.base, uri);
+            place.base = split_fragment(&uri).0.to_string();
+            place.pointer = String::new();
+            self.register(&place.base, "", id);
+        }
+        for anchor in ["$anchor", "$dynamicAnchor"] {
+            if let Some(JSON::JSONString(name)) = obj.get(anchor) {
+                self.register(&place.base, name, id);
+            }
+        }
+
+        for defs in ["$defs", "definitions"] {
+            if let Some(JSON::JSONObject(x)) = obj.get(defs) {
+                let defs_place = place.child(defs);
+                for (name, schema) in x {
+                    self.compile(schema, &defs_place.child(name))?;
+                }
+            }
+        }
+
+        let keywords = self.compile_keywords(obj, &place)?;
+        self.nodes[id] = Node::Keywords(keywords);
+
+        Ok(id)
+    }
+
+    fn compile_keywords(
+        &mut self,
+        obj: &HashMap<String, JSON>,
+        place: &Place,
+    ) -> Result<Vec<Keyword>, SchemaError> {
+        let mut keywords = Vec::new();
+
+        if let Some(value) = obj.get("type") {
+            let names = match *value {
+                JSON::JSONString(ref x) => vec![x.clone()],
+                JSON::JSONArray(ref x) => x
+                    .iter()
+                    .map(|name| match *name {
+                        JSON::JSONString(ref x) => Ok(x.clone()),
+                        _ => place
+                            .child("type")
+                            .error("Type names must be strings".to_string()),
+                    })
+                    .collect::<Result<_, _>>()?,
+                _ => {
+                    return place
+                        .child("type")
+                        .error("Expecting a string or an array".to_string())
+                }
+            };
+            if let Some(name) = names
+                .iter()
+                .find(|name| !TYPE_NAMES.contains(&name.as_str()))
+            {
+                return place
+                    .child("type")
+                    .error(format!("Unknown type `{}`", name));
+            }
+            keywords.push(Keyword::Type(names));
+        }
+        if let Some(value) = obj.get("enum") {
+            match *value {
+                JSON::JSONArray(ref x) => keywords.push(Keyword::Enum(x.clone())),
+                _ => return place.child("enum").error("Expecting an array".to_string()),
+            }
+        }
+        if let Some(value) = obj.get("const") {
+            keywords.push(Keyword::Const(value.clone()));
+        }
+
+        if let Some(x) = number(obj, "multipleOf", place)? {
+            if x <= 0.0 {
+                return place
+                    .child("multipleOf")
+                    .error("Expecting a positive number".to_string());
+            }
+            keywords.push(Keyword::MultipleOf(x));
+        }
+        if let Some(x) = number(obj, "maximum", place)? {
+            keywords.push(Keyword::Maximum(x));
+        }
+        if let Some(x) = number(obj, "exclusiveMaximum", place)? {
+            keywords.push(Keyword::ExclusiveMaximum(x));
+        }
+        if let Some(x) = number(obj, "minimum", place)? {
+            keywords.push(Keyword::Minimum(x));
+        }
+        if let Some(x) = number(obj, "exclusiveMinimum", place)? {
+            keywords.push(Keyword::ExclusiveMinimum(x));
+        }
+
+        if let Some(x) = count(obj, "maxLength", place)? {
+            keywords.push(Keyword::MaxLength(x));
+        }
+        if let Some(x) = count(obj, "minLength", place)? {
+            keywords.push(Keyword::MinLength(x));
+        }
+        if let Some(value) = obj.get("pattern") {
+            let pattern = match *value {
+                JSON::JSONString(ref x) => x,
+                _ => {
+                    return place
+                        .child("pattern")
+                        .error("Expecting a string".to_string())
+                }
+            };
+            let regex = compile_regex(pattern, &place.child("pattern"))?;
+            keywords.push(Keyword::Pattern(pattern.clone(), regex));
+        }
+
+        if let Some(x) = count(obj, "maxItems", place)? {
+            keywords.push(Keyword::MaxItems(x));
+        }
+        if let Some(x) = count(obj, "minItems", place)? {
+            keywords.push(Keyword::MinItems(x));
+        }
+        if let Some(JSON::JSONBool(true)) = obj.get("uniqueItems") {
+            keywords.push(Keyword::UniqueItems);
+        }
+        let mut prefix_len = 0;
+        if let Some(schemas) = self.schema_list(obj, "prefixItems", place)? {
+            prefix_len = schemas.len();
+            keywords.push(Keyword::PrefixItems(schemas));
+        }
+        if let Some(schema) = self.schema(obj, "items", place)? {
+            keywords.push(Keyword::Items {
+                schema,
+                start: prefix_len,
+            });
+        }
+        if let Some(schema) = self.schema(obj, "contains", place)? {
+            keywords.push(Keyword::Contains {
+                schema,
+                min: count(obj, "minContains", place)?.unwrap_or(1),
+                max: count(obj, "maxContains", place)?,
+            });
+        }
+
+        if let Some(x) = count(obj, "maxProperties", place)? {
+            keywords.push(Keyword::MaxProperties(x));
+        }
+        if let Some(x) = count(obj, "minProperties", place)? {
+            keywords.push(Keyword::MinProperties(x));
+        }
+        if let Some(value) = obj.get("required") {
+            keywords.push(Keyword::Required(strings(value, &place.child("required"))?));
+        }
+        if let Some(value) = obj.get("dependentRequired") {
+            let place = place.child("dependentRequired");
+            let deps = match *value {
+                JSON::JSONObject(ref x) => sorted(x)
+                    .into_iter()
+                    .map(|(name, names)| Ok((name.clone(), strings(names, &place.child(name))?)))
+                    .collect::<Result<_, SchemaError>>()?,
+                _ => return place.error("Expecting an object".to_string()),
+            };
+            keywords.push(Keyword::DependentRequired(deps));
+        }
+
+        let properties = self.schema_map(obj, "properties", place)?;
+        let mut patterns = Vec::new();
+        if let Some(JSON::JSONObject(x)) = obj.get("patternProperties") {
+            let place = place.child("patternProperties");
+            for (pattern, schema) in sorted(x) {
+                let regex = compile_regex(pattern, &place.child(pattern))?;
+                let id = self.compile(schema, &place.child(pattern))?;
+                patterns.push((pattern.clone(), regex, id));
+            }
+        }
+        if let Some(schema) = self.schema(obj, "additionalProperties", place)? {
+            keywords.push(Keyword::AdditionalProperties {
+                schema,
+                properties: properties
+                    .iter()
+                    .flatten()
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+                patterns: patterns.iter().map(|(_, regex, _)| regex.clone()).collect(),
+            });
+        }
+        if let Some(properties) = properties {
+            keywords.push(Keyword::Properties(properties));
+        }
+        if !patterns.is_empty() {
+            keywords.push(Keyword::PatternProperties(patterns));
+        }
+        if let Some(schemas) = self.schema_map(obj, "dependentSchemas", place)? {
+            keywords.push(Keyword::DependentSchemas(schemas));
+        }
+        if let Some(schema) = self.schema(obj, "propertyNames", place)? {
+            keywords.push(Keyword::PropertyNames(schema));
+        }
+
+        if let Some(schemas) = self.schema_list(obj, "allOf", place)? {
+            keywords.push(Keyword::AllOf(schemas));
+        }
+        if let Some(schemas) = self.schema_list(obj, "anyOf", place)? {
+            keywords.push(Keyword::AnyOf(schemas));
+        }
+        if let Some(schemas) = self.schema_list(obj, "oneOf", place)? {
+            keywords.push(Keyword::OneOf(schemas));
+        }
+        if let Some(schema) = self.schema(obj, "not", place)? {
+            keywords.push(Keyword::Not(schema));
+        }
+        if let Some(condition) = self.schema(obj, "if", place)? {
+            keywords.push(Keyword::If {
+                condition,
+                then: self.schema(obj, "then", place)?,
+                otherwise: self.schema(obj, "else", place)?,
+            });
+        }
+        for name in ["$ref", "$dynamicRef"] {
+            if let Some(value) = obj.get(name) {
+                let uri = match *value {
+                    JSON::JSONString(ref x) => resolve(&place.base, x),
+                    _ => return place.child(name).error("Expecting a string".to_string()),
+                };
+                self.refs.push((uri.clone(), place.child(name).location));
+                keywords.push(Keyword::Ref(uri));
+            }
+        }
+
+        // These look at what every other keyword evaluated, so they go last.
+        if let Some(schema) = self.schema(obj, "unevaluatedItems", place)? {
+            keywords.push(Keyword::UnevaluatedItems(schema));
+        }
+        if let Some(schema) = self.schema(obj, "unevaluatedProperties", place)? {
+            keywords.push(Keyword::UnevaluatedProperties(schema));
+        }
+
+        Ok(keywords)
+    }
+
+    fn schema(
+        &mut self,
+        obj: &HashMap<String, JSON>,
+        name: &str,
+        place: &Place,
+    ) -> Result<Option<SchemaId>, SchemaError> {
+        match obj.get(name) {
+            Some(schema) => Ok(Some(self.compile(schema, &place.child(name))?)),
+            None => Ok(None),
+        }
+    }
+
+    fn schema_list(
+        &mut self,
+        obj: &HashMap<String, JSON>,
+        name: &str,
+        place: &Place,
+    ) -> Result<Option<Vec<SchemaId>>, SchemaError> {
+        let place = place.child(name);
+
+        match obj.get(name) {
+            Some(JSON::JSONArray(x)) => x
+                .iter()
+                .enumerate()
+                .map(|(i, schema)| self.compile(schema, &place.child(&i.to_string())))
+                .collect::<Result<_, _>>()
+                .map(Some),
+            Some(_) => place.error("Expecting an array of schemas".to_string()),
+            None => Ok(None),
+        }
+    }
+
+    fn schema_map(
+        &mut self,
+        obj: &HashMap<String, JSON>,
+        name: &str,
+        place: &Place,
+    ) -> Result<Option<Vec<(String, SchemaId)>>, SchemaError> {
+        let place = place.child(name);
+
+        match obj.get(name) {
+            Some(JSON::JSONObject(x)) => sorted(x)
+                .into_iter()
+                .map(|(key, schema)| Ok((key.clone(), self.compile(schema, &place.child(key))?)))
+                .collect::<Result<_, _>>()
+                .map(Some),
+            Some(_) => place.error("Expecting an object of schemas".to_string()),
+            None => Ok(None),
+        }
+    }
+}
+
+// Members in key order, so that errors come out in a stable order.
+fn sorted(obj: &HashMap<String, JSON>) -> Vec<(&String, &JSON)> {
+    let mut entries: Vec<_> = obj.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+fn number(
+    obj: &HashMap<String, JSON>,
+    name: &str,
+    place: &Place,
+) -> Result<Option<f64>, SchemaError> {
+    match obj.get(name) {
+        Some(JSON::JSONNum(x)) => Ok(Some(*x)),
+        Some(_) => place.child(name).error("Expecting a number".to_string()),
+        None => Ok(None),
+    }
+}
+
+fn count(
+    obj: &HashMap<String, JSON>,
+    name: &str,
+    place: &Place,
+) -> Result<Option<usize>, SchemaError> {
+    match obj.get(name) {
+        Some(JSON::JSONNum(x)) if *x >= 0.0 && x.fract() == 0.0 => Ok(Some(*x as usize)),
+        Some(_) => place
+            .child(name)
+            .error("Expecting a non-negative integer".to_string()),
+        None => Ok(None),
+    }
+}
+
+fn strings(value: &JSON, place: &Place) -> Result<Vec<String>, SchemaError> {
+    let err_msg = "Expecting an array of strings".to_string();
+
+    match *value {
+        JSON::JSONArray(ref x) => x
+            .iter()
+            .map(|name| match *name {
+                JSON::JSONString(ref x) => Ok(x.clone()),
+                _ => place.error(err_msg.clone()),
+            })
+            .collect(),
+        _ => place.error(err_msg),
+    }
+}
+
+fn compile_regex(pattern: &str, place: &Place) -> Result<Regex, SchemaError> {
+    Regex::new(pattern)
+        .or_else(|err| place.error(format!("Invalid pattern `{}`: {}", pattern, err)))
+}
+
+fn split_fragment(uri: &str) -> (&str, &str) {
+    match uri.find('#') {
+        Some(i) => (&uri[..i], &uri[i + 1..]),
+        None => (uri, ""),
+    }
+}
+
+/// Resolves `reference` against `base` and returns it as `base#fragment`,
+/// with the fragment percent-decoded.
+pub fn resolve(base: &str, reference: &str) -> String {
+    let (path, fragment) = split_fragment(reference);
+
+    let absolute = if path.is_empty() {
+        base.to_string()
+    } else if path.contains("://") || path.starts_with("urn:") {
+        path.to_string()
+    } else if path.starts_with('/') {
+        // Keep the scheme and authority of the base.
+        let root = base
+            .find("://")
+            .map(|i| i + 3)
+            .and_then(|start| base[start..].find('/').map(|i| &base[..start + i]))
+            .unwrap_or(base);
+        format!("{}{}", root, path)
+    } else {
+        let dir = base.rfind('/').map_or("", |i| &base[..i + 1]);
+        format!("{}{}", dir, path)
+    };
+
+    format!("{}#{}", absolute, percent_decode(fragment))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = text
+            .get(i + 1..i + 3)
+            .and_then(|x| u8::from_str_radix(x, 16).ok());
+        match hex {
+            Some(x) if bytes[i] == b'%' => {
+                decoded.push(x);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,156 @@
+//! [JSON Schema](https://json-schema.org/draft/2020-12/json-schema-core)
+//! (draft 2020-12) validation.
+//!
+//! `Schema::compile` checks a schema document once; `Schema::validate`
+//! then applies it to any number of instances. Results can be written in
+//! the specification's `basic` (a flat list of errors) and `detailed` (a
+//! tree that follows the schema) output formats.
+//!
+//! Supported: `type`, `enum`, `const`, the numeric, string, array and
+//! object assertions, `pattern` and `patternProperties` (ECMA-262 syntax
+//! without backreferences), the `allOf`/`anyOf`/`oneOf`/`not` and
+//! `if`/`then`/`else` applicators, `$defs`, `$id`, `$anchor`, `$ref` within
+//! the document and `unevaluatedItems`/`unevaluatedProperties`. `format`
+//! and other annotations are ignored; `$dynamicRef` is treated as `$ref`.
+
+use crate::json::JSON;
+
+use std::collections::HashMap;
+
+mod compile;
+mod regex;
+mod validate;
+
+#[cfg(test)]
+mod tests;
+
+use self::compile::{Compiler, Node, SchemaId};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub err_msg: String,
+    /// JSON Pointer to the offending part of the schema.
+    pub keyword_location: String,
+}
+
+impl SchemaError {
+    fn new(err_msg: String, keyword_location: String) -> Self {
+        SchemaError {
+            err_msg,
+            keyword_location,
+        }
+    }
+}
+
+pub struct Schema {
+    nodes: Vec<Node>,
+    index: HashMap<String, SchemaId>,
+    root: SchemaId,
+}
+
+/// A failed keyword, with the failures of the subschemas it applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputUnit {
+    /// The path through the schema to the keyword, `$ref`s included.
+    pub keyword_location: String,
+    pub instance_location: String,
+    pub error: String,
+    pub errors: Vec<OutputUnit>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validation {
+    pub errors: Vec<OutputUnit>,
+}
+
+impl Schema {
+    pub fn compile(schema: &JSON) -> Result<Schema, SchemaError> {
+        let mut compiler = Compiler::new();
+        let root = compiler.compile_root(schema)?;
+
+        Ok(Schema {
+            nodes: compiler.nodes,
+            index: compiler.index,
+            root,
+        })
+    }
+
+    pub fn validate(&self, instance: &JSON) -> Validation {
+        let outcome = self.check(self.root, instance, "", "", 0);
+
+        Validation {
+            errors: outcome.errors,
+        }
+    }
+
+    pub fn is_valid(&self, instance: &JSON) -> bool {
+        self.validate(instance).is_valid()
+    }
+}
+
+impl OutputUnit {
+    fn to_json(&self, nested: bool) -> JSON {
+        let mut obj = HashMap::new();
+        obj.insert(
+            "keywordLocation".to_string(),
+            JSON::JSONString(self.keyword_location.clone()),
+        );
+        obj.insert(
+            "instanceLocation".to_string(),
+            JSON::JSONString(self.instance_location.clone()),
+        );
+        obj.insert("error".to_string(), JSON::JSONString(self.error.clone()));
+
+        if nested && !self.errors.is_empty() {
+            let errors = self.errors.iter().map(|x| x.to_json(true)).collect();
+            obj.insert("errors".to_string(), JSON::JSONArray(errors));
+        }
+
+        JSON::JSONObject(obj)
+    }
+
+    fn flatten<'a>(&'a self, units: &mut Vec<&'a OutputUnit>) {
+        units.push(self);
+        for unit in &self.errors {
+            unit.flatten(units);
+        }
+    }
+}
+
+impl Validation {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Every failed keyword, parents before the subschemas they applied.
+    pub fn units(&self) -> Vec<&OutputUnit> {
+        let mut units = Vec::new();
+        for unit in &self.errors {
+            unit.flatten(&mut units);
+        }
+
+        units
+    }
+
+    /// The `basic` output format: `valid` and a flat list of errors.
+    pub fn basic(&self) -> JSON {
+        let errors = self.units().iter().map(|x| x.to_json(false)).collect();
+        self.output(errors)
+    }
+
+    /// The `detailed` output format: errors nested as the schema is.
+    pub fn detailed(&self) -> JSON {
+        let errors = self.errors.iter().map(|x| x.to_json(true)).collect();
+        self.output(errors)
+    }
+
+    fn output(&self, errors: Vec<JSON>) -> JSON {
+        let mut obj = HashMap::new();
+        obj.insert("valid".to_string(), JSON::JSONBool(self.is_valid()));
+        if !self.is_valid() {
+            obj.insert("errors".to_string(), JSON::JSONArray(errors));
+        }
+
+        JSON::JSONObject(obj)
+    }
+}
@@ -0,0 +1,610 @@
+// A matcher for the ECMA-262 regular expressions that JSON Schema's
+// `pattern` and `patternProperties` use. Backreferences, flags and most
+// Unicode property escapes are not supported.
+//
+// Patterns come from schemas and are run against documents, either of which
+// may be hostile, so a pattern is compiled to a program for a Pike VM rather
+// than matched by backtracking. Every thread of the VM sits at a distinct
+// instruction, which bounds the work per character by the program's size;
+// that size is in turn capped when compiling. Lookarounds run as anchored
+// sub-searches at the position they are reached.
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    prog: Vec<Inst>,
+}
+
+// Counted repeats are compiled by copying their body, so `(a{100}){100}`
+// would already take ten thousand instructions.
+const MAX_PROGRAM: usize = 10_000;
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    // `.`: anything but a line terminator.
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    Look {
+        node: Box<Node>,
+        negated: bool,
+    },
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+}
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    Look { prog: Vec<Inst>, negated: bool },
+    // Continue at both targets.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+#[derive(Debug, Clone)]
+struct Class {
+    negated: bool,
+    items: Vec<ClassItem>,
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+    Letter(bool),
+    Number(bool),
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            ClassItem::Range(lo, hi) => lo <= c && c <= hi,
+            ClassItem::Digit(negated) => c.is_ascii_digit() != negated,
+            ClassItem::Word(negated) => is_word(c) != negated,
+            ClassItem::Space(negated) => is_space(c) != negated,
+            ClassItem::Letter(negated) => c.is_alphabetic() != negated,
+            ClassItem::Number(negated) => c.is_numeric() != negated,
+        }
+    }
+}
+
+impl Class {
+    fn matches(&self, c: char) -> bool {
+        self.items.iter().any(|item| item.matches(c)) != self.negated
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_space(c: char) -> bool {
+    c.is_whitespace() || c == '\u{feff}'
+}
+
+fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let mut parser = RegexParser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+        };
+
+        let node = parser.parse_alt()?;
+        if parser.pos < parser.chars.len() {
+            return Err("Unmatched `)`".to_string());
+        }
+
+        Ok(Regex {
+            prog: compile(&node)?,
+        })
+    }
+
+    /// Whether the pattern matches anywhere in `text`; patterns are not
+    /// implicitly anchored.
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+
+        search(&self.prog, &chars, 0, false)
+    }
+}
+
+struct RegexParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl RegexParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next(&mut self) -> Result<char, String> {
+        let c = self.peek().ok_or("Unexpected end of pattern")?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn parse_alt(&mut self) -> Result<Node, String> {
+        let mut alts = vec![self.parse_concat()?];
+        while self.eat('|') {
+            alts.push(self.parse_concat()?);
+        }
+
+        Ok(if alts.len() == 1 {
+            alts.pop().unwrap()
+        } else {
+            Node::Alt(alts)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+
+        Ok(Node::Concat(nodes))
+    }
+
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.parse_braces() {
+                Some(bounds) => bounds,
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        self.pos += 1; // the quantifier or closing brace
+
+        if let Node::Start | Node::End | Node::WordBoundary(_) = atom {
+            return Err("Nothing to repeat".to_string());
+        }
+        if max.is_some_and(|max| max < min) {
+            return Err("Numbers out of order in quantifier".to_string());
+        }
+        // Laziness changes which match is found, not whether there is one.
+        self.eat('?');
+
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+        })
+    }
+
+    // Reads `{n}`, `{n,}` or `{n,m}` up to the closing brace, which is left
+    // for the caller. Anything else leaves the brace as a literal.
+    fn parse_braces(&mut self) -> Option<(usize, Option<usize>)> {
+        let start = self.pos;
+        self.pos += 1;
+
+        let bounds = (|| {
+            let min = self.parse_decimal()?;
+            let max = if self.eat(',') {
+                if self.peek() == Some('}') {
+                    None
+                } else {
+                    Some(self.parse_decimal()?)
+                }
+            } else {
+                Some(min)
+            };
+            if self.peek() == Some('}') {
+                Some((min, max))
+            } else {
+                None
+            }
+        })();
+
+        if bounds.is_none() {
+            self.pos = start;
+        }
+        bounds
+    }
+
+    fn parse_decimal(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, String> {
+        match self.next()? {
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '(' => self.parse_group(),
+            '[' => Ok(Node::Class(self.parse_class()?)),
+            '\\' => self.parse_escape(),
+            c @ ('*' | '+' | '?') => Err(format!("Nothing to repeat before `{}`", c)),
+            c => Ok(Node::Char(c)),
+        }
+    }
+
+    fn parse_group(&mut self) -> Result<Node, String> {
+        let look = if self.eat('?') {
+            match self.next()? {
+                ':' => None,
+                '=' => Some(false),
+                '!' => Some(true),
+                // Named groups match like any other group.
+                '<' if !matches!(self.peek(), Some('=') | Some('!')) => {
+                    while self.next()? != '>' {}
+                    None
+                }
+                _ => return Err("Unsupported group".to_string()),
+            }
+        } else {
+            None
+        };
+
+        let node = self.parse_alt()?;
+        if !self.eat(')') {
+            return Err("Unterminated group".to_string());
+        }
+
+        Ok(match look {
+            Some(negated) => Node::Look {
+                node: Box::new(node),
+                negated,
+            },
+            None => node,
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<Class, String> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+
+        loop {
+            let lo = match self.next().map_err(|_| "Unterminated character class")? {
+                ']' => break,
+                '\\' => match self.parse_class_escape()? {
+                    Ok(c) => c,
+                    Err(item) => {
+                        items.push(item);
+                        continue;
+                    }
+                },
+                c => c,
+            };
+
+            // A `-` that can't form a range is a literal.
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']') {
+                self.pos += 1;
+                let hi = match self.next()? {
+                    '\\' => match self.parse_class_escape()? {
+                        Ok(c) => c,
+                        Err(_) => return Err("Invalid character class range".to_string()),
+                    },
+                    c => c,
+                };
+                if hi < lo {
+                    return Err("Range out of order in character class".to_string());
+                }
+                items.push(ClassItem::Range(lo, hi));
+            } else {
+                items.push(ClassItem::Range(lo, lo));
+            }
+        }
+
+        Ok(Class { negated, items })
+    }
+
+    // Inside a class, `\b` is a backspace; shorthand classes come back as
+    // items.
+    fn parse_class_escape(&mut self) -> Result<Result<char, ClassItem>, String> {
+        if self.peek() == Some('b') {
+            self.pos += 1;
+            return Ok(Ok('\x08'));
+        }
+
+        match self.parse_escape()? {
+            Node::Char(c) => Ok(Ok(c)),
+            Node::Class(mut class) if class.items.len() == 1 && !class.negated => {
+                Ok(Err(class.items.pop().unwrap()))
+            }
+            _ => Err("Invalid escape in character class".to_string()),
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<Node, String> {
+        let item = |item| {
+            Ok(Node::Class(Class {
+                negated: false,
+                items: vec![item],
+            }))
+        };
+
+        let c = self.next()?;
+        match c {
+            'd' | 'D' => item(ClassItem::Digit(c == 'D')),
+            'w' | 'W' => item(ClassItem::Word(c == 'W')),
+            's' | 'S' => item(ClassItem::Space(c == 'S')),
+            'p' | 'P' => item(self.parse_property(c == 'P')?),
+            'b' | 'B' => Ok(Node::WordBoundary(c == 'b')),
+            'n' => Ok(Node::Char('\n')),
+            'r' => Ok(Node::Char('\r')),
+            't' => Ok(Node::Char('\t')),
+            'f' => Ok(Node::Char('\x0c')),
+            'v' => Ok(Node::Char('\x0b')),
+            '0' => Ok(Node::Char('\0')),
+            'c' => match self.next()? {
+                x if x.is_ascii_alphabetic() => Ok(Node::Char((x as u8 % 32) as char)),
+                _ => Err("Invalid control escape".to_string()),
+            },
+            'x' => self.parse_hex(2).map(Node::Char),
+            'u' if self.eat('{') => {
+                let start = self.pos;
+                while self.next()? != '}' {}
+                let hex: String = self.chars[start..self.pos - 1].iter().collect();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(std::char::from_u32)
+                    .map(Node::Char)
+                    .ok_or_else(|| "Invalid Unicode escape".to_string())
+            }
+            'u' => self.parse_hex(4).map(Node::Char),
+            '1'..='9' => Err("Backreferences are not supported".to_string()),
+            c => Ok(Node::Char(c)),
+        }
+    }
+
+    fn parse_hex(&mut self, digits: usize) -> Result<char, String> {
+        let mut code_pt = 0;
+        for _ in 0..digits {
+            let digit = self.next()?.to_digit(16).ok_or("Invalid hex escape")?;
+            code_pt = code_pt * 16 + digit;
+        }
+
+        std::char::from_u32(code_pt).ok_or_else(|| "Invalid hex escape".to_string())
+    }
+
+    fn parse_property(&mut self, negated: bool) -> Result<ClassItem, String> {
+        if !self.eat('{') {
+            return Err("Invalid property escape".to_string());
+        }
+        let start = self.pos;
+        while self.next()? != '}' {}
+        let name: String = self.chars[start..self.pos - 1].iter().collect();
+
+        match name.as_str() {
+            "L" | "Letter" => Ok(ClassItem::Letter(negated)),
+            "N" | "Number" => Ok(ClassItem::Number(negated)),
+            "Nd" | "digit" | "Decimal_Number" => Ok(ClassItem::Digit(negated)),
+            _ => Err(format!("Unsupported Unicode property `{}`", name)),
+        }
+    }
+}
+
+fn compile(node: &Node) -> Result<Vec<Inst>, String> {
+    let mut prog = Vec::new();
+    emit(node, &mut prog)?;
+    prog.push(Inst::Match);
+
+    Ok(prog)
+}
+
+fn emit(node: &Node, prog: &mut Vec<Inst>) -> Result<(), String> {
+    if prog.len() > MAX_PROGRAM {
+        return Err("Pattern is too large".to_string());
+    }
+
+    match *node {
+        Node::Char(c) => prog.push(Inst::Char(c)),
+        Node::Any => prog.push(Inst::Any),
+        Node::Class(ref class) => prog.push(Inst::Class(class.clone())),
+        Node::Start => prog.push(Inst::Start),
+        Node::End => prog.push(Inst::End),
+        Node::WordBoundary(expected) => prog.push(Inst::WordBoundary(expected)),
+        Node::Look { ref node, negated } => {
+            let sub = compile(node)?;
+            if prog.len() + sub.len() > MAX_PROGRAM {
+                return Err("Pattern is too large".to_string());
+            }
+            prog.push(Inst::Look { prog: sub, negated });
+        }
+        Node::Concat(ref nodes) => {
+            for node in nodes {
+                emit(node, prog)?;
+            }
+        }
+        Node::Alt(ref alts) => {
+            let (last, init) = alts.split_last().unwrap();
+            let mut jumps = Vec::new();
+            for alt in init {
+                let split = prog.len();
+                prog.push(Inst::Split(split + 1, 0));
+                emit(alt, prog)?;
+                jumps.push(prog.len());
+                prog.push(Inst::Jump(0));
+                prog[split] = Inst::Split(split + 1, prog.len());
+            }
+            emit(last, prog)?;
+            for jump in jumps {
+                prog[jump] = Inst::Jump(prog.len());
+            }
+        }
+        Node::Repeat {
+            ref node, min, max, ..
+        } => {
+            for _ in 0..min {
+                emit(node, prog)?;
+            }
+            match max {
+                None => {
+                    let split = prog.len();
+                    prog.push(Inst::Split(split + 1, 0));
+                    emit(node, prog)?;
+                    prog.push(Inst::Jump(split));
+                    prog[split] = Inst::Split(split + 1, prog.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in min..max {
+                        splits.push(prog.len());
+                        prog.push(Inst::Split(0, 0));
+                        emit(node, prog)?;
+                    }
+                    for split in splits {
+                        prog[split] = Inst::Split(split + 1, prog.len());
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// The instructions the VM has reached at one position, each at most once.
+struct Threads {
+    pcs: Vec<usize>,
+    seen: Vec<bool>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Threads {
+            pcs: Vec::new(),
+            seen: vec![false; len],
+        }
+    }
+
+    fn insert(&mut self, pc: usize) -> bool {
+        if self.seen[pc] {
+            return false;
+        }
+        self.seen[pc] = true;
+        self.pcs.push(pc);
+        true
+    }
+
+    fn clear(&mut self) {
+        for &pc in &self.pcs {
+            self.seen[pc] = false;
+        }
+        self.pcs.clear();
+    }
+}
+
+// Runs `prog` over `chars` from `start`, trying every later start too
+// unless `anchored`, and reports whether it reaches `Match`.
+fn search(prog: &[Inst], chars: &[char], start: usize, anchored: bool) -> bool {
+    let mut curr = Threads::new(prog.len());
+    let mut next = Threads::new(prog.len());
+    let mut pos = start;
+
+    loop {
+        if (!anchored || pos == start) && follow(prog, chars, &mut curr, 0, pos) {
+            return true;
+        }
+        if anchored && curr.pcs.is_empty() {
+            return false;
+        }
+        let c = match chars.get(pos) {
+            Some(&c) => c,
+            None => return false,
+        };
+
+        next.clear();
+        for &pc in &curr.pcs {
+            let step = match prog[pc] {
+                Inst::Char(x) => x == c,
+                Inst::Any => !is_line_terminator(c),
+                Inst::Class(ref class) => class.matches(c),
+                _ => false,
+            };
+            if step && follow(prog, chars, &mut next, pc + 1, pos + 1) {
+                return true;
+            }
+        }
+        std::mem::swap(&mut curr, &mut next);
+        pos += 1;
+    }
+}
+
+// Adds the thread at `pc` to `threads`, following jumps and assertions that
+// hold at `pos` without consuming input. Returns whether one of them
+// reaches `Match`.
+fn follow(prog: &[Inst], chars: &[char], threads: &mut Threads, pc: usize, pos: usize) -> bool {
+    let mut stack = vec![pc];
+
+    while let Some(pc) = stack.pop() {
+        if !threads.insert(pc) {
+            continue;
+        }
+
+        let holds = match prog[pc] {
+            Inst::Match => return true,
+            Inst::Jump(to) => {
+                stack.push(to);
+                continue;
+            }
+            Inst::Split(a, b) => {
+                stack.push(b);
+                stack.push(a);
+                continue;
+            }
+            Inst::Start => pos == 0,
+            Inst::End => pos == chars.len(),
+            Inst::WordBoundary(expected) => {
+                let before = pos > 0 && is_word(chars[pos - 1]);
+                let after = pos < chars.len() && is_word(chars[pos]);
+                (before != after) == expected
+            }
+            Inst::Look { ref prog, negated } => search(prog, chars, pos, true) != negated,
+            // Instructions that consume a character wait for the next step.
+            Inst::Char(_) | Inst::Any | Inst::Class(_) => false,
+        };
+        if holds {
+            stack.push(pc + 1);
+        }
+    }
+
+    false
+}
@@ -0,0 +1,303 @@
+use crate::json::JSON;
+use crate::schema::regex::Regex;
+use crate::schema::Schema;
+
+fn schema(input: &str) -> Schema {
+    Schema::compile(&crate::parse(input).unwrap()).unwrap()
+}
+
+fn valid(schema: &Schema, instance: &str) -> bool {
+    schema.is_valid(&crate::parse(instance).unwrap())
+}
+
+#[test]
+fn test_regex() {
+    let cases = [
+        ("^a*$", "aaa", true),
+        ("^a*$", "aab", false),
+        ("b+", "abbc", true),
+        ("^\\d{3}-\\d{4}$", "555-1234", true),
+        ("^\\d{3}-\\d{4}$", "555-123", false),
+        ("^[a-z_][a-z0-9_]*$", "snake_case1", true),
+        ("^[^0-9]+$", "abc1", false),
+        ("^(foo|bar)+$", "foobarfoo", true),
+        ("^(?:ab)?c$", "c", true),
+        ("^a.c$", "a\nc", false),
+        ("^x{2,3}$", "xxxx", false),
+        ("a{,2}", "a{,2}", true),
+        ("^\\p{L}+$", "héllo", true),
+        ("\\bcat\\b", "a cat!", true),
+        ("\\bcat\\b", "concat", false),
+        ("^(?!un)\\w+$", "undo", false),
+        ("^a+?b$", "aaab", true),
+        ("^[\\w.-]+@[\\w-]+\\.[a-z]{2,}$", "me@ex-ample.org", true),
+    ];
+
+    for (pattern, text, expected) in cases {
+        let regex = Regex::new(pattern).unwrap();
+        assert_eq!(regex.is_match(text), expected, "{} on {:?}", pattern, text);
+    }
+
+    for pattern in [
+        "(a",
+        "a)",
+        "[a",
+        "*a",
+        "a{3,1}",
+        "(\\1)",
+        "[z-a]",
+        "a{100000}",
+    ] {
+        assert!(Regex::new(pattern).is_err(), "{}", pattern);
+    }
+}
+
+#[test]
+fn test_regex_nested_repeats() {
+    // These take exponential time with backtracking.
+    let text = format!("{}b", "a".repeat(5000));
+    for pattern in ["^(a*)*$", "^(a|a)*$", "^(a+)+$", "^((a?){2,5})*$"] {
+        let regex = Regex::new(pattern).unwrap();
+        assert!(!regex.is_match(&text), "{}", pattern);
+        assert!(regex.is_match(&text[..5000]), "{}", pattern);
+    }
+}
+
+#[test]
+fn test_assertions() {
+    let s = schema(
+        r#"{
+            "type": "object",
+            "required": ["id", "tags"],
+            "properties": {
+                "id": {"type": "integer", "minimum": 1, "multipleOf": 1},
+                "name": {"type": "string", "minLength": 1, "maxLength": 5, "pattern": "^[A-Z]"},
+                "tags": {"type": "array", "items": {"enum": ["a", "b"]}, "uniqueItems": true, "maxItems": 2},
+                "ratio": {"type": ["number", "null"], "exclusiveMaximum": 1, "multipleOf": 0.01},
+                "kind": {"const": "widget"}
+            },
+            "additionalProperties": false
+        }"#,
+    );
+
+    assert!(valid(
+        &s,
+        r#"{"id": 3, "tags": ["a"], "name": "Bob", "ratio": 0.57}"#
+    ));
+    assert!(valid(
+        &s,
+        r#"{"id": 3, "tags": [], "ratio": null, "kind": "widget"}"#
+    ));
+    assert!(!valid(&s, r#"{"id": 3.5, "tags": []}"#));
+    assert!(!valid(&s, r#"{"id": 0, "tags": []}"#));
+    assert!(!valid(&s, r#"{"id": 1}"#));
+    assert!(!valid(&s, r#"{"id": 1, "tags": ["a", "a"]}"#));
+    assert!(!valid(&s, r#"{"id": 1, "tags": ["c"]}"#));
+    assert!(!valid(&s, r#"{"id": 1, "tags": [], "name": "bob"}"#));
+    assert!(!valid(&s, r#"{"id": 1, "tags": [], "name": "Robert"}"#));
+    assert!(!valid(&s, r#"{"id": 1, "tags": [], "ratio": 1}"#));
+    assert!(!valid(&s, r#"{"id": 1, "tags": [], "ratio": 0.125}"#));
+    assert!(!valid(&s, r#"{"id": 1, "tags": [], "kind": "gadget"}"#));
+    assert!(!valid(&s, r#"{"id": 1, "tags": [], "extra": true}"#));
+    assert!(!valid(&s, "[]"));
+}
+
+#[test]
+fn test_arrays() {
+    let s = schema(
+        r#"{
+            "prefixItems": [{"type": "string"}, {"type": "number"}],
+            "items": {"type": "boolean"},
+            "contains": {"const": true},
+            "maxContains": 2,
+            "minItems": 2
+        }"#,
+    );
+
+    assert!(valid(&s, r#"["a", 1, true, false]"#));
+    assert!(!valid(&s, r#"["a", 1]"#));
+    assert!(!valid(&s, r#"["a", 1, true, true, true]"#));
+    assert!(!valid(&s, r#"[1, "a", true]"#));
+    assert!(!valid(&s, r#"["a", 1, true, 2]"#));
+}
+
+#[test]
+fn test_combinators_and_conditionals() {
+    let s = schema(
+        r#"{
+            "anyOf": [{"type": "string"}, {"type": "number"}],
+            "oneOf": [{"type": "string"}, {"type": "number", "maximum": 100}],
+            "not": {"const": "forbidden"},
+            "if": {"type": "string"},
+            "then": {"minLength": 3},
+            "else": {"multipleOf": 2}
+        }"#,
+    );
+
+    assert!(valid(&s, "\"abc\""));
+    assert!(valid(&s, "12"));
+    assert!(valid(&s, "-2"));
+    assert!(!valid(&s, "\"ab\""));
+    assert!(!valid(&s, "\"forbidden\""));
+    assert!(!valid(&s, "102"));
+    assert!(!valid(&s, "13"));
+    assert!(!valid(&s, "true"));
+
+    let s = schema(
+        r#"{"allOf": [{"required": ["a"]}, {"required": ["b"]}], "dependentRequired": {"a": ["c"]}}"#,
+    );
+    assert!(valid(&s, r#"{"a": 1, "b": 2, "c": 3}"#));
+    assert!(!valid(&s, r#"{"a": 1, "b": 2}"#));
+    assert!(!valid(&s, r#"{"b": 2}"#));
+}
+
+#[test]
+fn test_refs() {
+    let s = schema(
+        r##"{
+            "$id": "https://example.com/schemas/tree",
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {
+                        "value": {"$ref": "#leaf"},
+                        "children": {"type": "array", "items": {"$ref": "#/$defs/node"}}
+                    }
+                },
+                "leaf": {"$anchor": "leaf", "type": "number"},
+                "other": {"$id": "other", "type": "string"}
+            },
+            "properties": {
+                "root": {"$ref": "tree#/$defs/node"},
+                "label": {"$ref": "/schemas/other"}
+            }
+        }"##,
+    );
+
+    assert!(valid(
+        &s,
+        r#"{"root": {"value": 1, "children": [{"value": 2, "children": []}]}, "label": "x"}"#
+    ));
+    assert!(!valid(&s, r#"{"root": {"children": [{"value": "2"}]}}"#));
+    assert!(!valid(&s, r#"{"label": 1}"#));
+
+    let err = Schema::compile(&crate::parse(r##"{"$ref": "#/$defs/missing"}"##).unwrap());
+    assert_eq!(err.err().unwrap().keyword_location, "/$ref");
+
+    let s = schema(r##"{"$ref": "#"}"##);
+    assert!(!valid(&s, "1"));
+}
+
+#[test]
+fn test_unevaluated() {
+    let s = schema(
+        r#"{
+            "properties": {"a": true},
+            "allOf": [{"properties": {"b": true}}],
+            "anyOf": [{"properties": {"c": true}, "required": ["c"]}, true],
+            "if": {"required": ["d"]},
+            "then": {"properties": {"d": true}},
+            "unevaluatedProperties": false
+        }"#,
+    );
+
+    assert!(valid(&s, r#"{"a": 1, "b": 2, "c": 3, "d": 4}"#));
+    assert!(!valid(&s, r#"{"a": 1, "e": 5}"#));
+
+    let s = schema(
+        r#"{
+            "prefixItems": [true],
+            "contains": {"type": "string"},
+            "unevaluatedItems": {"type": "number"}
+        }"#,
+    );
+    assert!(valid(&s, r#"[null, "x", 2, "y"]"#));
+    assert!(!valid(&s, r#"[null, "x", true]"#));
+}
+
+#[test]
+fn test_output_formats() {
+    let s = schema(
+        r##"{
+            "properties": {
+                "a": {"type": "string"},
+                "b": {"$ref": "#/$defs/positive"}
+            },
+            "$defs": {"positive": {"exclusiveMinimum": 0}}
+        }"##,
+    );
+
+    assert_eq!(
+        s.validate(&crate::parse("{}").unwrap()).basic(),
+        crate::parse(r#"{"valid": true}"#).unwrap()
+    );
+
+    let result = s.validate(&crate::parse(r#"{"a": 1, "b": 0}"#).unwrap());
+    let expected = r#"{
+        "valid": false,
+        "errors": [
+            {
+                "keywordLocation": "/properties",
+                "instanceLocation": "",
+                "error": "Properties do not match the schema"
+            },
+            {
+                "keywordLocation": "/properties/a/type",
+                "instanceLocation": "/a",
+                "error": "Value is not of type `string`"
+            },
+            {
+                "keywordLocation": "/properties/b/$ref",
+                "instanceLocation": "/b",
+                "error": "Value does not match the referenced schema"
+            },
+            {
+                "keywordLocation": "/properties/b/$ref/exclusiveMinimum",
+                "instanceLocation": "/b",
+                "error": "Value must be greater than 0"
+            }
+        ]
+    }"#;
+    assert_eq!(result.basic(), crate::parse(expected).unwrap());
+
+    let detailed = result.detailed();
+    let errors = match detailed {
+        JSON::JSONObject(ref x) => &x["errors"],
+        _ => panic!("expected an object"),
+    };
+    match *errors {
+        JSON::JSONArray(ref x) => {
+            assert_eq!(x.len(), 1);
+            assert_eq!(
+                crate::path::select("/0/errors/1/errors/0/keywordLocation", errors).unwrap()[0]
+                    .value,
+                &JSON::JSONString("/properties/b/$ref/exclusiveMinimum".to_string())
+            );
+        }
+        _ => panic!("expected an array"),
+    }
+}
+
+#[test]
+fn test_invalid_schemas() {
+    for input in [
+        r#"{"type": 1}"#,
+        r#"{"type": "strin"}"#,
+        r#"{"type": ["string", "int"]}"#,
+        r#"{"minLength": -1}"#,
+        r#"{"pattern": "(unclosed"}"#,
+        r#"{"properties": {"a": 1}}"#,
+        r#"{"allOf": {}}"#,
+        r#"{"required": [1]}"#,
+        "3",
+    ] {
+        assert!(
+            Schema::compile(&crate::parse(input).unwrap()).is_err(),
+            "{}",
+            input
+        );
+    }
+
+    let err = Schema::compile(&crate::parse(r#"{"items": {"pattern": "["}}"#).unwrap());
+    assert_eq!(err.err().unwrap().keyword_location, "/items/pattern");
+}
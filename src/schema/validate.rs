@@ -0,0 +1,560 @@
+use super::compile::{Keyword, Node, SchemaId};
+use super::{OutputUnit, Schema};
+use crate::json::JSON;
+use crate::path::{to_pointer, PathElem};
+
+use std::collections::{HashMap, HashSet};
+
+// Deeper than this, a `$ref` is taken to be going round in circles.
+const MAX_DEPTH: usize = 256;
+
+// What validating a value against one schema found: the errors, and which
+// properties and items of the value were evaluated.
+#[derive(Default)]
+pub struct Outcome {
+    pub errors: Vec<OutputUnit>,
+    properties: HashSet<String>,
+    items: HashSet<usize>,
+}
+
+impl Outcome {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    // Annotations only count from subschemas that passed.
+    fn merge(&mut self, other: Outcome) {
+        if other.is_valid() {
+            self.properties.extend(other.properties);
+            self.items.extend(other.items);
+        }
+    }
+
+    fn error(&mut self, keyword_location: &str, instance_location: &str, error: String) {
+        self.nested(keyword_location, instance_location, error, Vec::new());
+    }
+
+    fn nested(
+        &mut self,
+        keyword_location: &str,
+        instance_location: &str,
+        error: String,
+        errors: Vec<OutputUnit>,
+    ) {
+        self.errors.push(OutputUnit {
+            keyword_location: keyword_location.to_string(),
+            instance_location: instance_location.to_string(),
+            error,
+            errors,
+        });
+    }
+}
+
+fn child_location(location: &str, elem: PathElem) -> String {
+    format!("{}{}", location, to_pointer(&[elem]))
+}
+
+fn type_matches(name: &str, instance: &JSON) -> bool {
+    match (name, instance) {
+        ("null", JSON::JSONNull) => true,
+        ("boolean", JSON::JSONBool(_)) => true,
+        ("number", JSON::JSONNum(_)) => true,
+        ("integer", JSON::JSONNum(x)) => x.is_finite() && x.fract() == 0.0,
+        ("string", JSON::JSONString(_)) => true,
+        ("array", JSON::JSONArray(_)) => true,
+        ("object", JSON::JSONObject(_)) => true,
+        _ => false,
+    }
+}
+
+fn is_multiple(x: f64, divisor: f64) -> bool {
+    let quotient = x / divisor;
+
+    quotient.is_finite() && (quotient - quotient.round()).abs() <= 1e-9 * quotient.abs().max(1.0)
+}
+
+fn format_number(x: f64) -> String {
+    JSON::JSONNum(x).to_string()
+}
+
+impl Schema {
+    pub(super) fn check(
+        &self,
+        id: SchemaId,
+        instance: &JSON,
+        keyword_location: &str,
+        instance_location: &str,
+        depth: usize,
+    ) -> Outcome {
+        let mut out = Outcome::default();
+
+        match self.nodes[id] {
+            Node::Bool(true) => (),
+            Node::Bool(false) => out.error(
+                keyword_location,
+                instance_location,
+                "False schema allows no value".to_string(),
+            ),
+            Node::Keywords(ref keywords) => {
+                for keyword in keywords {
+                    let location = format!("{}/{}", keyword_location, keyword.name());
+                    let scope = Scope {
+                        schema: self,
+                        instance,
+                        keyword_location: &location,
+                        parent_location: keyword_location,
+                        instance_location,
+                        depth,
+                    };
+                    scope.keyword(keyword, &mut out);
+                }
+            }
+        }
+
+        out
+    }
+}
+
+// One keyword being applied to one value.
+struct Scope<'a> {
+    schema: &'a Schema,
+    instance: &'a JSON,
+    keyword_location: &'a str,
+    // The location of the schema object the keyword is in.
+    parent_location: &'a str,
+    instance_location: &'a str,
+    depth: usize,
+}
+
+impl<'a> Scope<'a> {
+    fn error(&self, out: &mut Outcome, error: String) {
+        out.error(self.keyword_location, self.instance_location, error);
+    }
+
+    // Applies a subschema to the same value.
+    fn in_place(&self, id: SchemaId, location: &str) -> Outcome {
+        self.schema.check(
+            id,
+            self.instance,
+            location,
+            self.instance_location,
+            self.depth,
+        )
+    }
+
+    // Applies a subschema to a child of the value.
+    fn child(&self, id: SchemaId, instance: &JSON, elem: PathElem) -> Outcome {
+        let location = child_location(self.instance_location, elem);
+        self.schema
+            .check(id, instance, self.keyword_location, &location, self.depth)
+    }
+
+    fn indexed(&self, i: usize) -> String {
+        format!("{}/{}", self.keyword_location, i)
+    }
+
+    fn keyword(&self, keyword: &Keyword, out: &mut Outcome) {
+        match *self.instance {
+            JSON::JSONNum(x) => self.number_keyword(keyword, x, out),
+            JSON::JSONString(ref x) => self.string_keyword(keyword, x, out),
+            JSON::JSONArray(ref x) => self.array_keyword(keyword, x, out),
+            JSON::JSONObject(ref x) => {
+                let mut keys: Vec<&String> = x.keys().collect();
+                keys.sort();
+                self.object_keyword(keyword, x, &keys, out)
+            }
+            _ => (),
+        }
+        self.any_keyword(keyword, out);
+    }
+
+    fn any_keyword(&self, keyword: &Keyword, out: &mut Outcome) {
+        match *keyword {
+            Keyword::Type(ref names)
+                if !names.iter().any(|name| type_matches(name, self.instance)) =>
+            {
+                let names: Vec<String> = names.iter().map(|x| format!("`{}`", x)).collect();
+                self.error(out, format!("Value is not of type {}", names.join(" or ")));
+            }
+            Keyword::Enum(ref values) if !values.contains(self.instance) => {
+                self.error(out, "Value is not one of the allowed values".to_string());
+            }
+            Keyword::Const(ref value) if value != self.instance => {
+                self.error(out, "Value does not equal the constant".to_string());
+            }
+            Keyword::AllOf(ref ids) => {
+                let mut errors = Vec::new();
+                for (i, &id) in ids.iter().enumerate() {
+                    let sub = self.in_place(id, &self.indexed(i));
+                    errors.extend(sub.errors.iter().cloned());
+                    out.merge(sub);
+                }
+                if !errors.is_empty() {
+                    out.nested(
+                        self.keyword_location,
+                        self.instance_location,
+                        "Value does not match all of the `allOf` schemas".to_string(),
+                        errors,
+                    );
+                }
+            }
+            Keyword::AnyOf(ref ids) => {
+                let mut errors = Vec::new();
+                let mut matched = false;
+                for (i, &id) in ids.iter().enumerate() {
+                    let sub = self.in_place(id, &self.indexed(i));
+                    matched |= sub.is_valid();
+                    errors.extend(sub.errors.iter().cloned());
+                    out.merge(sub);
+                }
+                if !matched {
+                    out.nested(
+                        self.keyword_location,
+                        self.instance_location,
+                        "Value does not match any of the `anyOf` schemas".to_string(),
+                        errors,
+                    );
+                }
+            }
+            Keyword::OneOf(ref ids) => {
+                let mut errors = Vec::new();
+                let mut passed = Vec::new();
+                for (i, &id) in ids.iter().enumerate() {
+                    let sub = self.in_place(id, &self.indexed(i));
+                    if sub.is_valid() {
+                        passed.push(sub);
+                    } else {
+                        errors.extend(sub.errors);
+                    }
+                }
+                match passed.len() {
+                    0 => out.nested(
+                        self.keyword_location,
+                        self.instance_location,
+                        "Value does not match any of the `oneOf` schemas".to_string(),
+                        errors,
+                    ),
+                    1 => out.merge(passed.pop().unwrap()),
+                    _ => self.error(
+                        out,
+                        "Value matches more than one of the `oneOf` schemas".to_string(),
+                    ),
+                }
+            }
+            Keyword::Not(id) if self.in_place(id, self.keyword_location).is_valid() => {
+                self.error(out, "Value must not match the `not` schema".to_string());
+            }
+            Keyword::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let sub = self.in_place(condition, self.keyword_location);
+                let (branch, name) = if sub.is_valid() {
+                    out.merge(sub);
+                    (then, "then")
+                } else {
+                    (otherwise, "else")
+                };
+
+                if let Some(id) = branch {
+                    let location = format!("{}/{}", self.parent_location, name);
+                    let sub = self.in_place(id, &location);
+                    if !sub.is_valid() {
+                        out.nested(
+                            &location,
+                            self.instance_location,
+                            format!("Value does not match the `{}` schema", name),
+                            sub.errors,
+                        );
+                    } else {
+                        out.merge(sub);
+                    }
+                }
+            }
+            Keyword::Ref(ref uri) => {
+                if self.depth >= MAX_DEPTH {
+                    self.error(out, "Too many nested references".to_string());
+                    return;
+                }
+
+                let id = self.schema.index[uri];
+                let sub = self.schema.check(
+                    id,
+                    self.instance,
+                    self.keyword_location,
+                    self.instance_location,
+                    self.depth + 1,
+                );
+                if !sub.is_valid() {
+                    let errors = sub.errors.clone();
+                    out.nested(
+                        self.keyword_location,
+                        self.instance_location,
+                        "Value does not match the referenced schema".to_string(),
+                        errors,
+                    );
+                }
+                out.merge(sub);
+            }
+            _ => (),
+        }
+    }
+
+    fn number_keyword(&self, keyword: &Keyword, x: f64, out: &mut Outcome) {
+        let (ok, relation, limit) = match *keyword {
+            Keyword::MultipleOf(y) => (is_multiple(x, y), "a multiple of", y),
+            Keyword::Maximum(y) => (x <= y, "at most", y),
+            Keyword::ExclusiveMaximum(y) => (x < y, "less than", y),
+            Keyword::Minimum(y) => (x >= y, "at least", y),
+            Keyword::ExclusiveMinimum(y) => (x > y, "greater than", y),
+            _ => return,
+        };
+
+        if !ok {
+            self.error(
+                out,
+                format!("Value must be {} {}", relation, format_number(limit)),
+            );
+        }
+    }
+
+    fn string_keyword(&self, keyword: &Keyword, x: &str, out: &mut Outcome) {
+        match *keyword {
+            Keyword::MaxLength(n) if x.chars().count() > n => {
+                self.error(out, format!("String is longer than {} characters", n))
+            }
+            Keyword::MinLength(n) if x.chars().count() < n => {
+                self.error(out, format!("String is shorter than {} characters", n))
+            }
+            Keyword::Pattern(ref pattern, ref regex) if !regex.is_match(x) => {
+                self.error(out, format!("String does not match pattern `{}`", pattern))
+            }
+            _ => (),
+        }
+    }
+
+    fn array_keyword(&self, keyword: &Keyword, arr: &[JSON], out: &mut Outcome) {
+        match *keyword {
+            Keyword::MaxItems(n) if arr.len() > n => {
+                self.error(out, format!("Array has more than {} items", n))
+            }
+            Keyword::MinItems(n) if arr.len() < n => {
+                self.error(out, format!("Array has fewer than {} items", n))
+            }
+            Keyword::UniqueItems => {
+                let repeated = (1..arr.len()).any(|i| arr[..i].contains(&arr[i]));
+                if repeated {
+                    self.error(out, "Array items are not unique".to_string());
+                }
+            }
+            Keyword::PrefixItems(ref ids) => {
+                let mut errors = Vec::new();
+                for (i, (&id, elem)) in ids.iter().zip(arr).enumerate() {
+                    let location = child_location(self.instance_location, PathElem::Index(i));
+                    let sub = self
+                        .schema
+                        .check(id, elem, &self.indexed(i), &location, self.depth);
+                    errors.extend(sub.errors);
+                    out.items.insert(i);
+                }
+                self.items_error(out, errors);
+            }
+            Keyword::Items { schema, start } => {
+                let mut errors = Vec::new();
+                for (i, elem) in arr.iter().enumerate().skip(start) {
+                    errors.extend(self.child(schema, elem, PathElem::Index(i)).errors);
+                    out.items.insert(i);
+                }
+                self.items_error(out, errors);
+            }
+            Keyword::UnevaluatedItems(schema) => {
+                let mut errors = Vec::new();
+                for (i, elem) in arr.iter().enumerate() {
+                    if !out.items.contains(&i) {
+                        errors.extend(self.child(schema, elem, PathElem::Index(i)).errors);
+                    }
+                }
+                out.items.extend(0..arr.len());
+                self.items_error(out, errors);
+            }
+            Keyword::Contains { schema, min, max } => {
+                let mut matched = 0;
+                for (i, elem) in arr.iter().enumerate() {
+                    if self.child(schema, elem, PathElem::Index(i)).is_valid() {
+                        out.items.insert(i);
+                        matched += 1;
+                    }
+                }
+
+                if matched < min {
+                    let err_msg = if min == 1 {
+                        "Array contains no matching item".to_string()
+                    } else {
+                        format!("Array contains fewer than {} matching items", min)
+                    };
+                    self.error(out, err_msg);
+                } else if max.is_some_and(|max| matched > max) {
+                    self.error(
+                        out,
+                        format!("Array contains more than {} matching items", max.unwrap()),
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn items_error(&self, out: &mut Outcome, errors: Vec<OutputUnit>) {
+        if !errors.is_empty() {
+            out.nested(
+                self.keyword_location,
+                self.instance_location,
+                "Items do not match the schema".to_string(),
+                errors,
+            );
+        }
+    }
+
+    fn object_keyword(
+        &self,
+        keyword: &Keyword,
+        obj: &HashMap<String, JSON>,
+        keys: &[&String],
+        out: &mut Outcome,
+    ) {
+        let mut errors = Vec::new();
+
+        match *keyword {
+            Keyword::MaxProperties(n) if obj.len() > n => {
+                self.error(out, format!("Object has more than {} properties", n))
+            }
+            Keyword::MinProperties(n) if obj.len() < n => {
+                self.error(out, format!("Object has fewer than {} properties", n))
+            }
+            Keyword::Required(ref names) => {
+                for name in names {
+                    if !obj.contains_key(name) {
+                        self.error(out, format!("Missing required property `{}`", name));
+                    }
+                }
+            }
+            Keyword::DependentRequired(ref deps) => {
+                for (name, required) in deps {
+                    if !obj.contains_key(name) {
+                        continue;
+                    }
+                    for other in required {
+                        if !obj.contains_key(other) {
+                            self.error(
+                                out,
+                                format!("Property `{}` requires property `{}`", name, other),
+                            );
+                        }
+                    }
+                }
+            }
+            Keyword::Properties(ref props) => {
+                for (name, id) in props {
+                    if let Some(value) = obj.get(name) {
+                        let location = format!(
+                            "{}{}",
+                            self.keyword_location,
+                            to_pointer(&[PathElem::Key(name.clone())])
+                        );
+                        let instance_location =
+                            child_location(self.instance_location, PathElem::Key(name.clone()));
+                        let sub = self.schema.check(
+                            *id,
+                            value,
+                            &location,
+                            &instance_location,
+                            self.depth,
+                        );
+                        errors.extend(sub.errors);
+                        out.properties.insert(name.clone());
+                    }
+                }
+            }
+            Keyword::PatternProperties(ref patterns) => {
+                for (pattern, regex, id) in patterns {
+                    let location = format!(
+                        "{}{}",
+                        self.keyword_location,
+                        to_pointer(&[PathElem::Key(pattern.clone())])
+                    );
+                    for &key in keys.iter().filter(|key| regex.is_match(key)) {
+                        let instance_location =
+                            child_location(self.instance_location, PathElem::Key(key.clone()));
+                        let sub = self.schema.check(
+                            *id,
+                            &obj[key],
+                            &location,
+                            &instance_location,
+                            self.depth,
+                        );
+                        errors.extend(sub.errors);
+                        out.properties.insert(key.clone());
+                    }
+                }
+            }
+            Keyword::AdditionalProperties {
+                schema,
+                ref properties,
+                ref patterns,
+            } => {
+                for &key in keys {
+                    if properties.contains(key) || patterns.iter().any(|x| x.is_match(key)) {
+                        continue;
+                    }
+                    errors.extend(
+                        self.child(schema, &obj[key], PathElem::Key(key.clone()))
+                            .errors,
+                    );
+                    out.properties.insert(key.clone());
+                }
+            }
+            Keyword::UnevaluatedProperties(schema) => {
+                for &key in keys {
+                    if !out.properties.contains(key) {
+                        errors.extend(
+                            self.child(schema, &obj[key], PathElem::Key(key.clone()))
+                                .errors,
+                        );
+                    }
+                }
+                out.properties.extend(keys.iter().map(|&key| key.clone()));
+            }
+            Keyword::PropertyNames(schema) => {
+                for &key in keys {
+                    let name = JSON::JSONString(key.clone());
+                    errors.extend(self.child(schema, &name, PathElem::Key(key.clone())).errors);
+                }
+            }
+            Keyword::DependentSchemas(ref deps) => {
+                for (name, id) in deps {
+                    if obj.contains_key(name) {
+                        let location = format!(
+                            "{}{}",
+                            self.keyword_location,
+                            to_pointer(&[PathElem::Key(name.clone())])
+                        );
+                        let sub = self.in_place(*id, &location);
+                        errors.extend(sub.errors.iter().cloned());
+                        out.merge(sub);
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        if !errors.is_empty() {
+            out.nested(
+                self.keyword_location,
+                self.instance_location,
+                "Properties do not match the schema".to_string(),
+                errors,
+            );
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Streams of several JSON texts.
+//!
+//! `values` reads values written one after another, with or without
+//! whitespace between them (`{"a":1}{"a":2}` or one value per line).
+//! `json_seq` reads [RFC 7464](https://www.rfc-editor.org/rfc/rfc7464)
+//! `application/json-seq` text, where each value is preceded by an ASCII
+//! record separator; a record that fails to parse is reported and reading
+//! resumes at the next separator. Text before the first separator is
+//! ignored.
+
+use crate::json::JSON;
+use crate::lexer::Dialect;
+use crate::parser::{ParseError, ParseResult, Parser};
+
+#[cfg(test)]
+mod tests;
+
+/// The record separator that starts every element of a JSON text sequence.
+pub const RS: char = '\x1e';
+
+pub struct Values<'a> {
+    parser: Parser<'a>,
+    done: bool,
+}
+
+impl<'a> Iterator for Values<'a> {
+    type Item = ParseResult;
+
+    // Nothing after an error can be trusted, so it is the last item.
+    fn next(&mut self) -> Option<ParseResult> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.parser.parse_next();
+        self.done = !matches!(result, Some(Ok(_)));
+
+        result
+    }
+}
+
+pub fn values(input: &str) -> Values<'_> {
+    values_with(input, Dialect::Json)
+}
+
+pub fn values_with(input: &str, dialect: Dialect) -> Values<'_> {
+    Values {
+        parser: Parser::with_dialect(input, dialect),
+        done: false,
+    }
+}
+
+pub struct JsonSeq<'a> {
+    rest: &'a str,
+    // Line of the input that `rest` starts on, and the number of characters
+    // of that line before it.
+    line_no: u64,
+    column: u64,
+}
+
+impl<'a> Iterator for JsonSeq<'a> {
+    type Item = ParseResult;
+
+    fn next(&mut self) -> Option<ParseResult> {
+        loop {
+            let start = self.rest.find(RS)?;
+            let after = &self.rest[start + RS.len_utf8()..];
+            let end = after.find(RS).unwrap_or(after.len());
+            let record = &after[..end];
+
+            let line_no = self.line_no + count_lines(&self.rest[..start]);
+            let column = advance(self.column, &self.rest[..start + RS.len_utf8()]);
+            self.line_no = line_no + count_lines(record);
+            self.column = advance(column, record);
+            self.rest = &after[end..];
+
+            // Consecutive separators don't make empty elements.
+            if record.trim().is_empty() {
+                continue;
+            }
+
+            return Some(parse_record(record, line_no, column));
+        }
+    }
+}
+
+pub fn json_seq(input: &str) -> JsonSeq<'_> {
+    JsonSeq {
+        rest: input,
+        line_no: 1,
+        column: 0,
+    }
+}
+
+fn count_lines(text: &str) -> u64 {
+    text.matches('\n').count() as u64
+}
+
+// The number of characters of the current line before the end of `text`,
+// given that many before its start.
+fn advance(column: u64, text: &str) -> u64 {
+    match text.rfind('\n') {
+        Some(i) => text[i + 1..].chars().count() as u64,
+        None => column + text.chars().count() as u64,
+    }
+}
+
+// A number, `true`, `false` or `null` without whitespace after it may have
+// lost its end, so it is rejected as truncated (RFC 7464, section 2.4).
+// `column` counts the characters before the record on its first line.
+fn parse_record(record: &str, line_no: u64, column: u64) -> ParseResult {
+    let value = crate::parse(record).map_err(|err| {
+        let shift = if err.line_no == 1 && err.column != 0 {
+            column
+        } else {
+            0
+        };
+        ParseError::at(err.err_msg, line_no + err.line_no - 1, err.column + shift)
+    })?;
+
+    let scalar = matches!(value, JSON::JSONNum(_) | JSON::JSONBool(_) | JSON::JSONNull);
+    if scalar && !record.ends_with(char::is_whitespace) {
+        let line_no = line_no + count_lines(record);
+        return Err(ParseError::new("Truncated record".to_string(), line_no));
+    }
+
+    Ok(value)
+}
@@ -0,0 +1,80 @@
+use crate::json::JSON;
+use crate::lexer::Dialect;
+use crate::seq::{json_seq, values, values_with};
+
+fn parse(input: &str) -> JSON {
+    crate::parse(input).unwrap()
+}
+
+#[test]
+fn test_concatenated() {
+    let actual: Vec<JSON> = values("{\"a\":1}{\"a\":2}[3]\"x\"true 4\n  null\n")
+        .map(Result::unwrap)
+        .collect();
+
+    assert_eq!(
+        actual,
+        vec![
+            parse("{\"a\": 1}"),
+            parse("{\"a\": 2}"),
+            parse("[3]"),
+            parse("\"x\""),
+            JSON::JSONBool(true),
+            JSON::JSONNum(4.0),
+            JSON::JSONNull,
+        ]
+    );
+    assert_eq!(values("").count(), 0);
+    assert_eq!(values(" \n ").count(), 0);
+
+    let json5: Vec<JSON> = values_with("{a: 1,} // one\n{a: 2}", Dialect::Json5)
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(json5, vec![parse("{\"a\": 1}"), parse("{\"a\": 2}")]);
+}
+
+#[test]
+fn test_concatenated_error() {
+    let mut iter = values("[1]\n{\"a\" 2}\n[3]");
+
+    assert_eq!(iter.next(), Some(Ok(parse("[1]"))));
+    let err = iter.next().unwrap().unwrap_err();
+    assert_eq!(err.err_msg, "Expecting colon after key");
+    assert_eq!(err.line_no, 2);
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn test_json_seq() {
+    let input = "\x1e{\"a\":1}\n\x1e\x1e[2,\n3]\n\x1e\"x\"\n";
+    let actual: Vec<JSON> = json_seq(input).map(Result::unwrap).collect();
+
+    assert_eq!(
+        actual,
+        vec![parse("{\"a\": 1}"), parse("[2, 3]"), parse("\"x\"")]
+    );
+}
+
+#[test]
+fn test_json_seq_recovery() {
+    let input = "\x1e{\"a\":1}\n\x1e{\"a\":\n\x1e123\x1etrue\n\x1e[4]\n";
+    let results: Vec<_> = json_seq(input).collect();
+
+    assert_eq!(results.len(), 5);
+    assert_eq!(results[0], Ok(parse("{\"a\": 1}")));
+    assert_eq!(results[1].as_ref().unwrap_err().line_no, 3);
+    assert_eq!(results[2].as_ref().unwrap_err().err_msg, "Truncated record");
+    assert_eq!(results[3], Ok(JSON::JSONBool(true)));
+    assert_eq!(results[4], Ok(parse("[4]")));
+}
+
+#[test]
+fn test_json_seq_columns() {
+    let input = "\x1e1 \x1e[1 2]\n\x1e[\n 1 2]\n";
+    let errors: Vec<(u64, u64)> = json_seq(input)
+        .filter_map(Result::err)
+        .map(|err| (err.line_no, err.column))
+        .collect();
+
+    assert_eq!(errors, vec![(1, 8), (3, 4)]);
+}
@@ -0,0 +1,398 @@
+use crate::json::JSON;
+use crate::path::{to_pointer, PathElem};
+
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
+#[cfg(test)]
+mod tests;
+
+/// What to write for a NaN or infinite number, which JSON has no syntax
+/// for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NonFinite {
+    /// Fail with a `SerializeError`.
+    Error,
+    /// Write `null`, as `JSON.stringify` does.
+    Null,
+    /// Write the string `"NaN"`, `"Infinity"` or `"-Infinity"`.
+    String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub non_finite: NonFinite,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            non_finite: NonFinite::Null,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerializeError {
+    pub err_msg: String,
+    /// JSON Pointer to the value that couldn't be written.
+    pub path: String,
+}
+
+impl SerializeError {
+    fn new(err_msg: String, path: &[PathElem]) -> Self {
+        SerializeError {
+            err_msg,
+            path: to_pointer(path),
+        }
+    }
+}
+
+pub fn to_string(json: &JSON) -> String {
+    let mut out = String::new();
+    write_value(&mut out, json);
+
+    out
+}
+
+pub fn to_string_with(json: &JSON, options: &Options) -> Result<String, SerializeError> {
+    check_finite(json, options)?;
+
+    let mut out = String::new();
+    write_compact(&mut out, json, options);
+
+    Ok(out)
+}
+
+pub fn write_value(out: &mut String, json: &JSON) {
+    write_compact(out, json, &Options::default());
+}
+
+fn write_compact(out: &mut String, json: &JSON, options: &Options) {
+    match *json {
+        JSON::JSONNum(x) => write_number_with(out, x, options),
+        JSON::JSONString(ref x) => write_string(out, x),
+        JSON::JSONNull => out.push_str("null"),
+        JSON::JSONBool(x) => out.push_str(if x { "true" } else { "false" }),
+        JSON::JSONArray(ref x) => {
+            out.push('[');
+            for (i, elem) in x.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                write_compact(out, elem, options);
+            }
+            out.push(']');
+        }
+        JSON::JSONObject(ref x) => {
+            out.push('{');
+            for (i, (key, val)) in sorted_entries(x).into_iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_compact(out, val, options);
+            }
+            out.push('}');
+        }
+    }
+}
+
+pub fn to_string_pretty(json: &JSON, indent: usize) -> String {
+    let mut out = String::new();
+    write_pretty(&mut out, json, indent, 0, &Options::default());
+
+    out
+}
+
+pub fn to_string_pretty_with(
+    json: &JSON,
+    indent: usize,
+    options: &Options,
+) -> Result<String, SerializeError> {
+    check_finite(json, options)?;
+
+    let mut out = String::new();
+    write_pretty(&mut out, json, indent, 0, options);
+
+    Ok(out)
+}
+
+// With `NonFinite::Error`, the document is checked before anything is
+// written, so the writers themselves can't fail. The first offending value
+// in output order is reported.
+fn check_finite(json: &JSON, options: &Options) -> Result<(), SerializeError> {
+    if options.non_finite != NonFinite::Error {
+        return Ok(());
+    }
+
+    match find_non_finite(json, &mut Vec::new()) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+fn find_non_finite(json: &JSON, path: &mut Vec<PathElem>) -> Option<SerializeError> {
+    match *json {
+        JSON::JSONNum(x) if !x.is_finite() => Some(SerializeError::new(
+            format!("{} is not a valid JSON number", non_finite_name(x)),
+            path,
+        )),
+        JSON::JSONArray(ref x) => x.iter().enumerate().find_map(|(i, elem)| {
+            path.push(PathElem::Index(i));
+            let err = find_non_finite(elem, path);
+            path.pop();
+            err
+        }),
+        JSON::JSONObject(ref x) => sorted_entries(x).into_iter().find_map(|(key, val)| {
+            path.push(PathElem::Key(key.clone()));
+            let err = find_non_finite(val, path);
+            path.pop();
+            err
+        }),
+        _ => None,
+    }
+}
+
+// Empty arrays and objects stay on one line; everything else puts one
+// element or member per line, indented `indent` spaces per level.
+fn write_pretty(out: &mut String, json: &JSON, indent: usize, level: usize, options: &Options) {
+    match *json {
+        JSON::JSONArray(ref x) if !x.is_empty() => {
+            out.push('[');
+            for (i, elem) in x.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                write_newline(out, indent, level + 1);
+                write_pretty(out, elem, indent, level + 1, options);
+            }
+            write_newline(out, indent, level);
+            out.push(']');
+        }
+        JSON::JSONObject(ref x) if !x.is_empty() => {
+            out.push('{');
+            for (i, (key, val)) in sorted_entries(x).into_iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                write_newline(out, indent, level + 1);
+                write_string(out, key);
+                out.push_str(": ");
+                write_pretty(out, val, indent, level + 1, options);
+            }
+            write_newline(out, indent, level);
+            out.push('}');
+        }
+        _ => write_compact(out, json, options),
+    }
+}
+
+fn write_newline(out: &mut String, indent: usize, level: usize) {
+    out.push('\n');
+    for _ in 0..indent * level {
+        out.push(' ');
+    }
+}
+
+// Objects are backed by a HashMap, so keys are sorted to keep output stable
+// from one run to the next.
+pub fn sorted_entries(obj: &HashMap<String, JSON>) -> Vec<(&String, &JSON)> {
+    let mut entries: Vec<(&String, &JSON)> = obj.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    entries
+}
+
+/// Writes a number in its shortest form that reads back as the same
+/// double, as `Number.prototype.toString` does in ECMAScript and RFC 8785
+/// requires: plain decimals from `1e-7` up to `1e21`, and exponent notation
+/// with an explicit sign outside that, as in `1e+21` and `1.5e-7`. Negative
+/// zero is written as `0`. Non-finite numbers are written as `null`.
+pub fn write_number(out: &mut String, x: f64) {
+    write_number_with(out, x, &Options::default());
+}
+
+fn write_number_with(out: &mut String, x: f64, options: &Options) {
+    if x.is_finite() {
+        write_finite(out, x);
+    } else if options.non_finite == NonFinite::String {
+        write_string(out, non_finite_name(x));
+    } else {
+        out.push_str("null");
+    }
+}
+
+fn non_finite_name(x: f64) -> &'static str {
+    if x.is_nan() {
+        "NaN"
+    } else if x > 0.0 {
+        "Infinity"
+    } else {
+        "-Infinity"
+    }
+}
+
+fn write_finite(out: &mut String, x: f64) {
+    if x == 0.0 {
+        out.push('0');
+        return;
+    }
+
+    let (digits, exponent) = shortest(x.abs());
+    let mut text = Buffer::default();
+    write!(text, "{}", digits).unwrap();
+    let digits = text.as_str();
+
+    // The decimal point goes after the first `point` digits.
+    let len = digits.len() as i32;
+    let point = len + exponent;
+
+    if x < 0.0 {
+        out.push('-');
+    }
+    if len <= point && point <= 21 {
+        out.push_str(digits);
+        for _ in len..point {
+            out.push('0');
+        }
+    } else if 0 < point && point <= 21 {
+        out.push_str(&digits[..point as usize]);
+        out.push('.');
+        out.push_str(&digits[point as usize..]);
+    } else if -6 < point && point <= 0 {
+        out.push_str("0.");
+        for _ in point..0 {
+            out.push('0');
+        }
+        out.push_str(digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if len > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        let sign = if point > 0 { '+' } else { '-' };
+        write!(out, "e{}{}", sign, (point - 1).abs()).unwrap();
+    }
+}
+
+// The shortest `digits * 10^exponent` that reads back as `x`, which is
+// positive, with no trailing zeros in `digits`.
+fn shortest(x: f64) -> (u64, i32) {
+    // The standard library's `{:e}` finds the shortest digits, with Grisu
+    // and Dragon4 as a fallback. Where two candidates are equally close it
+    // rounds up, though, and ECMAScript takes the even one.
+    let mut text = Buffer::default();
+    write!(text, "{:e}", x).unwrap();
+    let text = text.as_str();
+
+    let e = text.find('e').unwrap();
+    let mut digits: u64 = 0;
+    let mut len = 0;
+    for c in text[..e].chars().filter(|c| *c != '.') {
+        digits = digits * 10 + u64::from(c.to_digit(10).unwrap());
+        len += 1;
+    }
+    let mut exponent = text[e + 1..].parse::<i32>().unwrap() - (len - 1);
+
+    if digits % 2 == 1 {
+        let even = if is_midpoint(x, 2 * digits - 1, exponent) {
+            digits - 1
+        } else if is_midpoint(x, 2 * digits + 1, exponent) {
+            digits + 1
+        } else {
+            digits
+        };
+        // Next to a power of two the gap below is half the one above, so
+        // the even neighbour may not read back as `x`.
+        let mut candidate = Buffer::default();
+        write!(candidate, "{}e{}", even, exponent).unwrap();
+        if even != digits && candidate.as_str().parse() == Ok(x) {
+            digits = even;
+            while digits % 10 == 0 {
+                digits /= 10;
+                exponent += 1;
+            }
+        }
+    }
+
+    (digits, exponent)
+}
+
+// Whether `x` is exactly `twice / 2 * 10^exponent`, for an odd `twice`.
+// With `x = m * 2^e`, that is `m * 2^(e + 1 - exponent) * 5^-exponent`
+// equal to an odd number, so the powers of two must cancel out.
+fn is_midpoint(x: f64, twice: u64, exponent: i32) -> bool {
+    let bits = x.to_bits();
+    let fraction = bits & ((1 << 52) - 1);
+    let power2 = (bits >> 52) as i32;
+    let (m, e) = if power2 == 0 {
+        (fraction, -1074)
+    } else {
+        (fraction | (1 << 52), power2 - 1075)
+    };
+
+    let zeros = m.trailing_zeros() as i32;
+    if e + 1 - exponent + zeros != 0 {
+        return false;
+    }
+    let odd = u128::from(m >> zeros);
+
+    // 5^27 is the largest power of five below 2^64.
+    if exponent.abs() > 27 {
+        return false;
+    }
+    let pow5 = 5u128.pow(exponent.unsigned_abs());
+    if exponent <= 0 {
+        odd * pow5 == u128::from(twice)
+    } else {
+        odd == u128::from(twice) * pow5
+    }
+}
+
+// Room for the longest `{:e}` form of a double, `2.2250738585072014e-308`,
+// so formatting a number doesn't allocate.
+#[derive(Default)]
+struct Buffer {
+    bytes: [u8; 32],
+    len: usize,
+}
+
+impl Buffer {
+    fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len]).unwrap()
+    }
+}
+
+impl fmt::Write for Buffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > self.bytes.len() {
+            return Err(fmt::Error);
+        }
+        self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+
+        Ok(())
+    }
+}
+
+pub fn write_string(out: &mut String, string: &str) {
+    out.push('"');
+    for c in string.chars() {
+        match c {
+            '\"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\x08' => out.push_str("\\b"),
+            '\x0c' => out.push_str("\\f"),
+            '\x00'..='\x1f' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+}
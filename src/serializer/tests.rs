@@ -0,0 +1,121 @@
+use crate::json::JSON;
+use crate::serializer::{
+    to_string, to_string_pretty, to_string_pretty_with, to_string_with, NonFinite, Options,
+    SerializeError,
+};
+
+use std::collections::HashMap;
+
+#[test]
+fn test_scalars() {
+    assert_eq!(to_string(&JSON::JSONNull), "null");
+    assert_eq!(to_string(&JSON::JSONBool(true)), "true");
+    assert_eq!(to_string(&JSON::JSONNum(4.5)), "4.5");
+    assert_eq!(to_string(&JSON::JSONNum(-3.0)), "-3");
+}
+
+#[test]
+fn test_non_finite() {
+    let actual = to_string(&JSON::JSONNum(f64::NAN));
+    let expected = "null";
+
+    assert_eq!(actual, expected);
+    assert_eq!(JSON::JSONNum(f64::INFINITY).to_string(), "null");
+}
+
+#[test]
+fn test_numbers() {
+    let number = |x: f64| to_string(&JSON::JSONNum(x));
+
+    assert_eq!(number(0.1), "0.1");
+    assert_eq!(number(-0.0), "0");
+    assert_eq!(number(1e20), "100000000000000000000");
+    assert_eq!(number(1e21), "1e+21");
+    assert_eq!(number(1.5e300), "1.5e+300");
+    assert_eq!(number(0.000001), "0.000001");
+    assert_eq!(number(1e-7), "1e-7");
+    assert_eq!(number(-1.25e-10), "-1.25e-10");
+    assert_eq!(number(f64::MAX), "1.7976931348623157e+308");
+    assert_eq!(number(f64::from_bits(1)), "5e-324");
+    assert_eq!(number(1.0 / 3.0), "0.3333333333333333");
+    // Halfway between two 17-digit candidates, which rounds to the even one.
+    assert_eq!(number(1512439563889119.0 + 0.25), "1512439563889119.2");
+    assert_eq!(number(5.960464477539063e-8), "5.960464477539063e-8");
+    assert_eq!(JSON::JSONNum(1e21).to_string(), "1e+21");
+}
+
+#[test]
+fn test_non_finite_policies() {
+    let input = JSON::JSONArray(vec![
+        JSON::JSONNum(1.0),
+        JSON::JSONNum(f64::NEG_INFINITY),
+        JSON::JSONNum(f64::NAN),
+    ]);
+    let options = |non_finite| Options { non_finite };
+
+    assert_eq!(
+        to_string_with(&input, &options(NonFinite::Null)),
+        Ok("[1,null,null]".to_string())
+    );
+    assert_eq!(
+        to_string_with(&input, &options(NonFinite::String)),
+        Ok(r#"[1,"-Infinity","NaN"]"#.to_string())
+    );
+    assert_eq!(
+        to_string_pretty_with(&input, 2, &options(NonFinite::Error)),
+        Err(SerializeError {
+            err_msg: "-Infinity is not a valid JSON number".to_string(),
+            path: "/1".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_escapes() {
+    let json = JSON::JSONString("a\"b\\c\nd\u{1}".to_string());
+    let actual = to_string(&json);
+    let expected = r#""a\"b\\c\nd\u0001""#;
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_sorted_keys() {
+    let mut hmap = HashMap::new();
+    hmap.insert("b".to_string(), JSON::JSONNum(2.0));
+    hmap.insert("a".to_string(), JSON::JSONArray(vec![JSON::JSONNull]));
+    hmap.insert("c".to_string(), JSON::JSONObject(HashMap::new()));
+
+    let actual = to_string(&JSON::JSONObject(hmap));
+    let expected = r#"{"a":[null],"b":2,"c":{}}"#;
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_round_trip() {
+    let input = r#"{"key":[1,2.5,"x\ty",true,false,null,{}]}"#;
+    let json = crate::parse(input).unwrap();
+    let actual = to_string(&json);
+
+    assert_eq!(actual, input);
+}
+
+#[test]
+fn test_pretty() {
+    let input = r#"{"b":[1,{"c":null}],"a":[],"d":{}}"#;
+    let json = crate::parse(input).unwrap();
+    let actual = to_string_pretty(&json, 2);
+    let expected = r#"{
+  "a": [],
+  "b": [
+    1,
+    {
+      "c": null
+    }
+  ],
+  "d": {}
+}"#;
+
+    assert_eq!(actual, expected);
+}